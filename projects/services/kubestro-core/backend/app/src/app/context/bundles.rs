@@ -0,0 +1,27 @@
+use kubestro_core_infra::services::{bundle_store::BundleStore, bundles_service::BUNDLE_MAX_SIZE};
+
+use super::outbound::get_env_number;
+
+/// Configuration of the bundles mirroring repositories
+pub struct BundlesConfig {
+    pub store: BundleStore,
+    /// Maximum size of a bundle, uploaded and unpacked, in bytes
+    pub max_size: u64,
+}
+
+/// Read the environment variables and build the bundles configuration
+///
+///   - `REPOSITORIES_BUNDLE_DIR`: directory holding the uploaded bundles, a temporary directory
+///     by default
+///   - `REPOSITORIES_BUNDLE_MAX_SIZE`: maximum size of a bundle, in bytes
+pub fn init_bundles_config() -> BundlesConfig {
+    let mut store = BundleStore::default();
+    if let Ok(dir) = std::env::var("REPOSITORIES_BUNDLE_DIR") {
+        store = store.with_dir(dir.into());
+    }
+
+    BundlesConfig {
+        store,
+        max_size: get_env_number("REPOSITORIES_BUNDLE_MAX_SIZE", BUNDLE_MAX_SIZE),
+    }
+}
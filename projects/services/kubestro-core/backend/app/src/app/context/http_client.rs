@@ -0,0 +1,26 @@
+use std::path::PathBuf;
+
+use kubestro_core_infra::services::http_client::HttpClientConfig;
+
+use super::refresh::get_env_duration;
+
+/// Read the environment variables and build the outgoing HTTP client configuration
+///
+///   - `HTTP_CLIENT_TIMEOUT`: maximum duration of a request, in seconds
+///   - `HTTP_CLIENT_CONNECT_TIMEOUT`: maximum duration to establish a connection, in seconds
+///   - `HTTP_CLIENT_USER_AGENT`: `User-Agent` header sent with every request
+///   - `HTTP_CLIENT_PROXY`: proxy url used for every request
+///   - `HTTP_CLIENT_CA_BUNDLE`: path to a PEM bundle of additional trusted certificate authorities
+pub fn init_http_client_config() -> HttpClientConfig {
+    let default = HttpClientConfig::default();
+
+    HttpClientConfig {
+        timeout: get_env_duration("HTTP_CLIENT_TIMEOUT", default.timeout),
+        connect_timeout: get_env_duration("HTTP_CLIENT_CONNECT_TIMEOUT", default.connect_timeout),
+        user_agent: std::env::var("HTTP_CLIENT_USER_AGENT").unwrap_or(default.user_agent),
+        proxy: std::env::var("HTTP_CLIENT_PROXY").ok(),
+        ca_bundle: std::env::var("HTTP_CLIENT_CA_BUNDLE")
+            .ok()
+            .map(PathBuf::from),
+    }
+}
@@ -0,0 +1,81 @@
+use std::time::Duration;
+
+use kubestro_core_domain::services::game_managers::{
+    DEFAULT_MIGRATION_TIMEOUT, DEFAULT_ROLLOUT_TIMEOUT,
+};
+use kubestro_core_infra::{k8s::controller::ControllerBackoff, services::k8s_client::K8sClient};
+
+use super::refresh::get_env_duration;
+
+/// Namespace of the game managers when no client could be configured
+const DEFAULT_NAMESPACE: &str = "default";
+
+/// Configuration of the Kubernetes cluster managed by Kubestro Core
+#[derive(Clone)]
+pub struct KubernetesConfig {
+    /// Client of the cluster, `None` when no cluster could be configured
+    pub client: Option<K8sClient>,
+    /// Whether the custom resource definitions are applied at startup
+    pub apply_crds: bool,
+    /// Namespace the game managers are installed in
+    pub namespace: String,
+    /// Delays between the retries of a failing game manager
+    pub backoff: ControllerBackoff,
+    /// Time given to an upgraded game manager to become available before it is rolled back
+    pub rollout_timeout: Duration,
+    /// Time given to the migration of a game manager to succeed
+    pub migration_timeout: Duration,
+}
+
+/// Read the environment variables and build the Kubernetes configuration
+///
+/// The client is configured from the kubeconfig file, or from the service account when running
+/// inside the cluster.
+///   - `KUBERNETES_APPLY_CRDS`: set to `false` when the custom resource definitions are
+///     installed separately, such as when Kubestro Core cannot manage them
+///   - `GAME_MANAGERS_NAMESPACE`: namespace the game managers are installed in, the default
+///     namespace of the client by default
+///   - `GAME_MANAGERS_RECONCILE_BACKOFF_BASE`: first retry delay of a failing game manager, in
+///     seconds
+///   - `GAME_MANAGERS_RECONCILE_BACKOFF_MAX`: maximum retry delay of a failing game manager, in
+///     seconds
+///   - `GAME_MANAGERS_ROLLOUT_TIMEOUT`: time given to an upgraded game manager to become
+///     available before the previous revision is restored, in seconds
+///   - `GAME_MANAGERS_MIGRATION_TIMEOUT`: time given to the migration job of an upgrade to
+///     succeed, in seconds
+pub async fn init_kubernetes_config() -> KubernetesConfig {
+    let client = match K8sClient::try_new().await {
+        Ok(client) => Some(client),
+        Err(e) => {
+            warn!(
+                "No Kubernetes cluster could be configured, game managers cannot be installed: {}",
+                e
+            );
+            None
+        }
+    };
+
+    let namespace = std::env::var("GAME_MANAGERS_NAMESPACE").unwrap_or_else(|_| {
+        client
+            .as_ref()
+            .map_or(DEFAULT_NAMESPACE.to_string(), |client| {
+                client.client().default_namespace().to_string()
+            })
+    });
+
+    let default = ControllerBackoff::default();
+    KubernetesConfig {
+        client,
+        apply_crds: std::env::var("KUBERNETES_APPLY_CRDS").map_or(true, |value| value != "false"),
+        namespace,
+        backoff: ControllerBackoff {
+            base: get_env_duration("GAME_MANAGERS_RECONCILE_BACKOFF_BASE", default.base),
+            max: get_env_duration("GAME_MANAGERS_RECONCILE_BACKOFF_MAX", default.max),
+        },
+        rollout_timeout: get_env_duration("GAME_MANAGERS_ROLLOUT_TIMEOUT", DEFAULT_ROLLOUT_TIMEOUT),
+        migration_timeout: get_env_duration(
+            "GAME_MANAGERS_MIGRATION_TIMEOUT",
+            DEFAULT_MIGRATION_TIMEOUT,
+        ),
+    }
+}
@@ -1,17 +1,32 @@
 use std::sync::Arc;
 
+use anyhow::Context;
+
 use kubestro_core_domain::{
     ports::{
         repositories::user_repository::UserRepository,
-        services::repositories_service::RepositoriesService,
+        services::{bundles_service::BundlesService, repositories_service::RepositoriesService},
+    },
+    services::{
+        auth::local_auth::LocalAuthService, catalog::CatalogService,
+        game_managers::GameManagersService, refresh_jobs::RefreshJobsService,
+        repositories_import::RepositoriesImportService,
+        repositories_refresh::RepositoriesRefreshScheduler,
     },
-    services::auth::local_auth::LocalAuthService,
 };
 use kubestro_core_infra::{
-    repositories::{repositories_repo::RepositoriesPgRepo, user_repo::UserPgRepo},
+    repositories::{
+        packages_repo::PackagesPgRepo, repositories_repo::RepositoriesPgRepo, user_repo::UserPgRepo,
+    },
     services::{
-        argon_hasher::Argon2Hasher, password_validator::InfraPasswordValidator,
+        argon_hasher::Argon2Hasher,
+        bundles_service::InfraBundlesService,
+        cluster_service::K8sClusterService,
+        credentials_cipher::CredentialsCipher,
+        http_client::{build_credentials_http_client, build_http_client},
+        password_validator::InfraPasswordValidator,
         repositories_service::InfraRepositoriesService,
+        values_validator::JsonSchemaValuesValidator,
     },
 };
 use redis_pool::SingleRedisPool;
@@ -20,9 +35,16 @@ use std::sync::RwLock;
 use utoipa::ToSchema;
 
 use super::services::oidc_auth::OidcAuthService;
+use kubernetes::KubernetesConfig;
 
+mod bundles;
 mod db;
+mod http_client;
+pub mod kubernetes;
 pub mod oidc;
+mod outbound;
+mod refresh;
+pub mod seed;
 
 #[derive(Debug, Clone, Serialize, ToSchema, Eq, PartialEq)]
 #[serde(rename_all = "snake_case")]
@@ -51,6 +73,17 @@ pub struct AppContext {
     pub(crate) local_auth: Arc<LocalAuthService>,
     pub(crate) oidc_auth: Option<Arc<OidcAuthService>>,
     pub(crate) repository_service: Arc<dyn RepositoriesService>,
+    pub(crate) catalog_service: Arc<CatalogService>,
+    pub(crate) refresh_scheduler: Arc<RepositoriesRefreshScheduler>,
+    pub(crate) refresh_jobs: Arc<RefreshJobsService>,
+    pub(crate) repositories_import: Arc<RepositoriesImportService>,
+    pub(crate) bundles_service: Arc<dyn BundlesService>,
+    /// Maximum size of an uploaded bundle, in bytes
+    pub(crate) bundle_max_size: u64,
+    pub(crate) game_managers: Arc<GameManagersService>,
+
+    // Kubernetes
+    pub(crate) kubernetes: KubernetesConfig,
 
     // Redis pool
     pub(crate) cache_pool: SingleRedisPool,
@@ -66,6 +99,22 @@ pub async fn create_app_context() -> anyhow::Result<AppContext> {
     // Initialize OIDC configuration
     let oidc_config = oidc::init_oidc_config().await;
 
+    // Initialize repositories refresh configuration
+    let refresh_config = refresh::init_refresh_config();
+
+    // Initialize the store of the bundles uploaded for the clusters without network access
+    let bundles_config = bundles::init_bundles_config();
+
+    // Initialize the HTTP client shared by outgoing requests, restricted by the outbound policy
+    let outbound_policy = outbound::init_outbound_policy();
+    let http_client_config = http_client::init_http_client_config();
+    let http_client = build_http_client(&http_client_config, &outbound_policy)?;
+    let credentials_http_client =
+        build_credentials_http_client(&http_client_config, &outbound_policy)?;
+
+    // Initialize the client of the cluster the game managers are installed on
+    let kubernetes = kubernetes::init_kubernetes_config().await;
+
     // Infrastructure Services
     let hasher = Arc::new(Argon2Hasher::default());
     let password_validator = Arc::new(InfraPasswordValidator::default());
@@ -79,12 +128,90 @@ pub async fn create_app_context() -> anyhow::Result<AppContext> {
     ));
     let oidc_auth =
         oidc_config.map(|config| Arc::new(OidcAuthService::new(user_repo.clone(), config)));
-    let repository_repo = Arc::new(RepositoriesPgRepo::new(db.clone()));
-
-    let repository_service = Arc::new(InfraRepositoriesService::new(
+    let mut repository_repo = RepositoriesPgRepo::new(db.clone());
+    match std::env::var("REPOSITORIES_ENCRYPTION_KEY") {
+        Ok(key) => {
+            let cipher = CredentialsCipher::from_base64(&key)
+                .context("Invalid `REPOSITORIES_ENCRYPTION_KEY`")?;
+            repository_repo = repository_repo.with_credentials_cipher(cipher);
+        }
+        Err(_) => {
+            warn!("`REPOSITORIES_ENCRYPTION_KEY` is not set, repositories credentials cannot be stored")
+        }
+    }
+    let repository_repo = Arc::new(repository_repo);
+    let packages_repo = Arc::new(PackagesPgRepo::new(db.clone()));
+
+    // Keep the validators of the stored indexes alive across a missed refresh
+    let cache_ttl = refresh_config
+        .interval
+        .saturating_add(refresh_config.jitter)
+        .saturating_mul(2);
+    // Only for development purposes, every production repository should be signed
+    let allow_unsigned =
+        std::env::var("REPOSITORIES_ALLOW_UNSIGNED").is_ok_and(|value| value == "true");
+    if allow_unsigned {
+        warn!("`REPOSITORIES_ALLOW_UNSIGNED` is set, repositories without trusted keys will not be verified");
+    }
+    let mut repository_service =
+        InfraRepositoriesService::new(repository_repo.clone(), packages_repo.clone(), pool.clone())
+            .with_cache_ttl(cache_ttl)
+            .with_http_client(http_client)
+            .with_credentials_http_client(credentials_http_client)
+            .with_outbound_policy(outbound_policy)
+            .with_bundle_store(bundles_config.store.clone())
+            .with_unsigned_indexes(allow_unsigned);
+    // Clones of the Git repositories, kept in a temporary directory by default
+    if let Ok(git_dir) = std::env::var("REPOSITORIES_GIT_DIR") {
+        repository_service = repository_service.with_git_work_dir(git_dir.into());
+    }
+    let bundles_service = Arc::new(
+        InfraBundlesService::new(
+            repository_repo.clone(),
+            repository_service.clone(),
+            bundles_config.store,
+        )
+        .with_max_size(bundles_config.max_size),
+    );
+    let repository_service = Arc::new(repository_service);
+    // The conflicts are found after the packages are stored, find them for the ones already stored
+    let conflicts_service = repository_service.clone();
+    tokio::spawn(async move {
+        if let Err(e) = conflicts_service.update_conflicts().await {
+            warn!("Failed to update the conflicts of the catalog: {}", e);
+        }
+    });
+    let catalog_service = Arc::new(
+        CatalogService::new(repository_repo.clone(), packages_repo)
+            .with_core_version(semver::Version::parse(env!("CARGO_PKG_VERSION"))?),
+    );
+    let mut game_managers = GameManagersService::new(
+        catalog_service.clone(),
+        Arc::new(JsonSchemaValuesValidator::default()),
+    )
+    .with_rollout_timeout(kubernetes.rollout_timeout)
+    .with_migration_timeout(kubernetes.migration_timeout);
+    if let Some(client) = &kubernetes.client {
+        game_managers = game_managers.with_cluster(Arc::new(K8sClusterService::new(
+            client,
+            kubernetes.namespace.clone(),
+        )));
+    }
+    let game_managers = Arc::new(game_managers);
+    let refresh_scheduler = Arc::new(RepositoriesRefreshScheduler::new(
+        refresh_config,
+        repository_repo.clone(),
+        repository_service.clone(),
+    ));
+    let refresh_jobs = Arc::new(RefreshJobsService::new(
         repository_repo.clone(),
-        pool.clone(),
+        repository_service.clone(),
     ));
+    let repositories_import = Arc::new(RepositoriesImportService::new(
+        repository_repo.clone(),
+        repository_service.clone(),
+    ));
+
     // Shared states
     let shared_state = Arc::new(RwLock::new(SharedState {
         status: ServiceStatus::NotReady,
@@ -98,6 +225,14 @@ pub async fn create_app_context() -> anyhow::Result<AppContext> {
         user_repo,
         repository_repo,
         repository_service,
+        catalog_service,
+        refresh_scheduler,
+        refresh_jobs,
+        repositories_import,
+        bundles_service,
+        bundle_max_size: bundles_config.max_size,
+        game_managers,
+        kubernetes,
     };
 
     Ok(api_context)
@@ -0,0 +1,73 @@
+use std::str::FromStr;
+
+use kubestro_core_infra::services::outbound_policy::OutboundPolicy;
+
+/// Read a number from an environment variable
+///
+/// Falls back to `default` when the variable is not set or invalid.
+pub(super) fn get_env_number<T: FromStr + std::fmt::Display>(name: &str, default: T) -> T
+where
+    T::Err: std::fmt::Display,
+{
+    let Ok(value) = std::env::var(name) else {
+        return default;
+    };
+
+    value.parse().unwrap_or_else(|e| {
+        warn!(
+            "Invalid value `{}` for `{}` ({}), using the default of {}",
+            value, name, e, default
+        );
+        default
+    })
+}
+
+/// Read a comma separated list from an environment variable
+fn get_env_list(name: &str) -> Option<Vec<String>> {
+    let value = std::env::var(name).ok()?;
+
+    Some(
+        value
+            .split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(String::from)
+            .collect(),
+    )
+}
+
+/// Read the environment variables and build the policy restricting the requests made to the
+/// repositories
+///
+///   - `REPOSITORIES_ALLOWED_SCHEMES`: url schemes the repositories can use, separated by commas
+///   - `REPOSITORIES_ALLOWED_HOSTS`: hosts the repositories can use, separated by commas, a `*.`
+///     prefix allows every subdomain
+///   - `REPOSITORIES_ALLOW_PRIVATE_NETWORKS`: `true` to reach the loopback, private and
+///     link-local addresses, e.g. for a repository served inside the cluster
+///   - `REPOSITORIES_MAX_RESPONSE_SIZE`: maximum size of a response, in bytes
+///   - `REPOSITORIES_MAX_REDIRECTS`: maximum number of redirects followed by a request
+///   - `REPOSITORIES_STRICT_CONTENT_TYPE`: `false` to accept indexes served with any content type
+pub fn init_outbound_policy() -> OutboundPolicy {
+    let default = OutboundPolicy::default();
+
+    let policy = OutboundPolicy {
+        allowed_schemes: get_env_list("REPOSITORIES_ALLOWED_SCHEMES")
+            .unwrap_or(default.allowed_schemes),
+        allowed_hosts: get_env_list("REPOSITORIES_ALLOWED_HOSTS").unwrap_or(default.allowed_hosts),
+        allow_private_networks: std::env::var("REPOSITORIES_ALLOW_PRIVATE_NETWORKS")
+            .is_ok_and(|value| value == "true"),
+        max_response_size: get_env_number(
+            "REPOSITORIES_MAX_RESPONSE_SIZE",
+            default.max_response_size,
+        ),
+        max_redirects: get_env_number("REPOSITORIES_MAX_REDIRECTS", default.max_redirects),
+        strict_content_type: !std::env::var("REPOSITORIES_STRICT_CONTENT_TYPE")
+            .is_ok_and(|value| value == "false"),
+    };
+
+    if policy.allow_private_networks {
+        warn!("`REPOSITORIES_ALLOW_PRIVATE_NETWORKS` is set, repositories can reach the internal services");
+    }
+
+    policy
+}
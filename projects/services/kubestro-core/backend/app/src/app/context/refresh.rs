@@ -0,0 +1,67 @@
+use std::time::Duration;
+
+use kubestro_core_domain::services::repositories_refresh::{
+    RefreshSchedulerConfig, MAX_REFRESH_DELAY,
+};
+
+/// Read a duration in seconds from an environment variable
+///
+/// Falls back to `default` when the variable is not set or invalid.
+pub(super) fn get_env_duration(name: &str, default: Duration) -> Duration {
+    let Ok(value) = std::env::var(name) else {
+        return default;
+    };
+
+    match value.parse::<u64>() {
+        Ok(seconds) => Duration::from_secs(seconds),
+        Err(e) => {
+            warn!(
+                "Invalid value `{}` for `{}` ({}), using the default of {}s",
+                value,
+                name,
+                e,
+                default.as_secs()
+            );
+            default
+        }
+    }
+}
+
+/// Read a refresh delay from an environment variable, capped to [`MAX_REFRESH_DELAY`]
+fn get_env_refresh_delay(name: &str, default: Duration) -> Duration {
+    let delay = get_env_duration(name, default);
+    if delay > MAX_REFRESH_DELAY {
+        warn!(
+            "`{}` cannot exceed {}s, using the maximum",
+            name,
+            MAX_REFRESH_DELAY.as_secs()
+        );
+        return MAX_REFRESH_DELAY;
+    }
+
+    delay
+}
+
+/// Read the environment variables and build the repositories refresh configuration
+///
+/// Every value is a number of seconds, of at most 30 days:
+///   - `REPOSITORIES_REFRESH_INTERVAL`: delay between two refreshes of a repository
+///   - `REPOSITORIES_REFRESH_JITTER`: maximum random delay added to each refresh
+///   - `REPOSITORIES_REFRESH_BACKOFF_BASE`: first retry delay of a failing repository
+///   - `REPOSITORIES_REFRESH_BACKOFF_MAX`: maximum retry delay of a failing repository
+///
+/// The interval and the backoff base cannot be 0, and the maximum backoff is at least the base.
+pub fn init_refresh_config() -> RefreshSchedulerConfig {
+    let default = RefreshSchedulerConfig::default();
+
+    RefreshSchedulerConfig {
+        interval: get_env_refresh_delay("REPOSITORIES_REFRESH_INTERVAL", default.interval),
+        jitter: get_env_refresh_delay("REPOSITORIES_REFRESH_JITTER", default.jitter),
+        backoff_base: get_env_refresh_delay(
+            "REPOSITORIES_REFRESH_BACKOFF_BASE",
+            default.backoff_base,
+        ),
+        backoff_max: get_env_refresh_delay("REPOSITORIES_REFRESH_BACKOFF_MAX", default.backoff_max),
+    }
+    .normalized()
+}
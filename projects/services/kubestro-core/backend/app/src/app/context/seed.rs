@@ -0,0 +1,38 @@
+use anyhow::Context;
+use kubestro_core_domain::services::repositories_import::RepositoryDeclaration;
+
+use crate::app::http::dto::repositories_file_dto::RepositoriesFileDto;
+
+/// Repositories reconciled at boot
+#[derive(Debug, Default)]
+pub struct RepositoriesSeedConfig {
+    pub declarations: Vec<RepositoryDeclaration>,
+    /// Whether the seeded repositories are protected from the API
+    pub managed: bool,
+}
+
+/// Read the environment variables and load the repositories seeded at boot
+///
+///   - `REPOSITORIES_SEED_FILE`: path to a repositories file, in the layout of the exported ones
+///   - `REPOSITORIES_SEED_MANAGED`: `true` to prevent the seeded repositories from being updated
+///     or deleted through the API
+pub fn init_seed_config() -> anyhow::Result<RepositoriesSeedConfig> {
+    let managed = std::env::var("REPOSITORIES_SEED_MANAGED").is_ok_and(|value| value == "true");
+
+    let Ok(path) = std::env::var("REPOSITORIES_SEED_FILE") else {
+        return Ok(RepositoriesSeedConfig {
+            managed,
+            ..Default::default()
+        });
+    };
+
+    let data = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read `REPOSITORIES_SEED_FILE` ({})", path))?;
+    let declarations = RepositoriesFileDto::parse(&data)
+        .with_context(|| format!("Invalid `REPOSITORIES_SEED_FILE` ({})", path))?;
+
+    Ok(RepositoriesSeedConfig {
+        declarations,
+        managed,
+    })
+}
@@ -0,0 +1,202 @@
+use chrono::{DateTime, Utc};
+use kubestro_core_domain::{
+    models::game_manager::{
+        GameManagerCondition, GameManagerPhase, GameManagerRevision, GameManagerRevisionCause,
+        GameManagerState, GameManagerWorkload,
+    },
+    services::game_managers::GameManagerDetail,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::package_dto::{CrdReferenceDto, PackageDto, PermissionRuleDto};
+
+/// Summary of the state of a game manager
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum GameManagerPhaseDto {
+    Pending,
+    Progressing,
+    Available,
+    Degraded,
+    Deleting,
+}
+
+impl From<GameManagerPhase> for GameManagerPhaseDto {
+    fn from(phase: GameManagerPhase) -> Self {
+        match phase {
+            GameManagerPhase::Pending => Self::Pending,
+            GameManagerPhase::Progressing => Self::Progressing,
+            GameManagerPhase::Available => Self::Available,
+            GameManagerPhase::Degraded => Self::Degraded,
+            GameManagerPhase::Deleting => Self::Deleting,
+        }
+    }
+}
+
+/// An observation of the state of a game manager, such as `Available`
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct GameManagerConditionDto {
+    #[serde(rename = "type")]
+    pub type_: String,
+    /// Whether the condition holds, `null` when unknown
+    pub status: Option<bool>,
+    pub reason: Option<String>,
+    pub message: Option<String>,
+    pub last_transition_time: Option<DateTime<Utc>>,
+}
+
+impl From<GameManagerCondition> for GameManagerConditionDto {
+    fn from(condition: GameManagerCondition) -> Self {
+        Self {
+            type_: condition.type_,
+            status: condition.status,
+            reason: condition.reason,
+            message: condition.message,
+            last_transition_time: condition.last_transition_time,
+        }
+    }
+}
+
+/// Live state of a game manager, as reported by the controller
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct GameManagerStatusDto {
+    pub phase: GameManagerPhaseDto,
+    pub conditions: Vec<GameManagerConditionDto>,
+}
+
+impl From<GameManagerState> for GameManagerStatusDto {
+    fn from(state: GameManagerState) -> Self {
+        Self {
+            phase: state.phase.into(),
+            conditions: state.conditions.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+/// Pods running a game manager
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct GameManagerWorkloadDto {
+    pub replicas: u32,
+    /// Number of pods ready to serve requests
+    pub ready_replicas: u32,
+    /// Image the pods are running, which differs from the requested one during a rollout
+    pub image: Option<String>,
+    /// Number of restarts of the containers of the pods
+    pub restarts: u32,
+}
+
+impl From<GameManagerWorkload> for GameManagerWorkloadDto {
+    fn from(workload: GameManagerWorkload) -> Self {
+        Self {
+            replicas: workload.replicas,
+            ready_replicas: workload.ready_replicas,
+            image: workload.image,
+            restarts: workload.restarts,
+        }
+    }
+}
+
+/// A game manager installed on the cluster
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct GameManagerDto {
+    /// Name of the game manager, unique on the cluster
+    pub id: String,
+    /// Repository the package was installed from
+    pub repository_id: String,
+    pub package: String,
+    pub version: String,
+    pub image: String,
+    /// Configuration values of the game manager
+    #[schema(value_type = Object)]
+    pub values: serde_json::Value,
+    pub created_at: Option<DateTime<Utc>>,
+    /// Kinds of the game servers managed by the game manager
+    pub game_servers: Vec<CrdReferenceDto>,
+    pub status: GameManagerStatusDto,
+    /// Pods running the game manager, `null` until they are deployed
+    pub workload: Option<GameManagerWorkloadDto>,
+    /// Installed version of the package, `null` when the catalog does not offer it anymore
+    pub catalog: Option<PackageDto>,
+}
+
+impl From<GameManagerDetail> for GameManagerDto {
+    fn from(detail: GameManagerDetail) -> Self {
+        let installation = detail.installation;
+
+        Self {
+            id: installation.name,
+            repository_id: installation.repository_id,
+            package: installation.package,
+            version: installation.version,
+            image: installation.image,
+            values: installation.values,
+            created_at: installation.created_at,
+            game_servers: installation
+                .game_servers
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+            status: installation.state.into(),
+            workload: installation.workload.map(Into::into),
+            catalog: detail.package.map(|version| version.package.into()),
+        }
+    }
+}
+
+/// Reason a revision of a game manager was deployed
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum GameManagerRevisionCauseDto {
+    Install,
+    Upgrade,
+    Rollback,
+    /// The previous revision was restored after an upgrade failed to become available
+    AutomaticRollback,
+}
+
+impl From<GameManagerRevisionCause> for GameManagerRevisionCauseDto {
+    fn from(cause: GameManagerRevisionCause) -> Self {
+        match cause {
+            GameManagerRevisionCause::Install => Self::Install,
+            GameManagerRevisionCause::Upgrade => Self::Upgrade,
+            GameManagerRevisionCause::Rollback => Self::Rollback,
+            GameManagerRevisionCause::AutomaticRollback => Self::AutomaticRollback,
+        }
+    }
+}
+
+/// A revision of a game manager, as recorded in its history
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct GameManagerRevisionDto {
+    /// Number of the revision, increased by every upgrade and rollback
+    pub revision: u32,
+    pub version: String,
+    pub image: String,
+    /// Configuration values the revision was deployed with
+    #[schema(value_type = Object)]
+    pub values: serde_json::Value,
+    pub permissions: Vec<PermissionRuleDto>,
+    pub game_servers: Vec<CrdReferenceDto>,
+    /// `null` for the game managers installed outside of Kubestro
+    pub cause: Option<GameManagerRevisionCauseDto>,
+    pub created_at: Option<DateTime<Utc>>,
+    /// User who deployed the revision, `null` when deployed by Kubestro itself
+    pub created_by: Option<String>,
+}
+
+impl From<GameManagerRevision> for GameManagerRevisionDto {
+    fn from(revision: GameManagerRevision) -> Self {
+        Self {
+            revision: revision.revision,
+            version: revision.version,
+            image: revision.image,
+            values: revision.values,
+            permissions: revision.permissions.into_iter().map(Into::into).collect(),
+            game_servers: revision.game_servers.into_iter().map(Into::into).collect(),
+            cause: revision.cause.map(Into::into),
+            created_at: revision.created_at,
+            created_by: revision.created_by,
+        }
+    }
+}
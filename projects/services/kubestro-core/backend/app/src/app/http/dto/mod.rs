@@ -1,3 +1,6 @@
+pub mod game_manager_dto;
 pub mod package_dto;
+pub mod refresh_job_dto;
 pub mod repositories_dto;
+pub mod repositories_file_dto;
 pub mod user_dto;
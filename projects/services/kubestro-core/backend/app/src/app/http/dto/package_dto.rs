@@ -1,10 +1,383 @@
+use kubestro_core_domain::{
+    models::index::{
+        CrdReference, IndexPackage, PackageChannel, PackageMaintainer, PackageMigration,
+        PermissionRule,
+    },
+    services::{
+        catalog::{CatalogFacets, FacetCount, PackageDetail, PackageVersion, PackageVersions},
+        package_graph::CatalogConflict,
+    },
+};
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
+/// Release channel of a package version
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PackageChannelDto {
+    #[default]
+    Stable,
+    Beta,
+    Nightly,
+}
+
+impl From<PackageChannel> for PackageChannelDto {
+    fn from(channel: PackageChannel) -> Self {
+        match channel {
+            PackageChannel::Stable => Self::Stable,
+            PackageChannel::Beta => Self::Beta,
+            PackageChannel::Nightly => Self::Nightly,
+        }
+    }
+}
+
+impl From<PackageChannelDto> for PackageChannel {
+    fn from(channel: PackageChannelDto) -> Self {
+        match channel {
+            PackageChannelDto::Stable => Self::Stable,
+            PackageChannelDto::Beta => Self::Beta,
+            PackageChannelDto::Nightly => Self::Nightly,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
 pub struct PackageDto {
     pub id: String,
     pub name: String,
     pub version: String,
+    pub channel: PackageChannelDto,
     pub description: String,
+    /// Game managed by the package
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub game: Option<String>,
+    /// Keywords the package can be searched by
+    pub tags: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+}
+
+impl From<IndexPackage> for PackageDto {
+    fn from(package: IndexPackage) -> Self {
+        Self {
+            id: format!("{}@{}", package.name, package.version),
+            name: package.name,
+            version: package.version.to_string(),
+            channel: package.channel.into(),
+            description: package.description,
+            game: package.game,
+            tags: package.tags,
+            icon: package.icon,
+        }
+    }
+}
+
+/// A version of a package
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct PackageVersionDto {
+    pub version: String,
+    pub channel: PackageChannelDto,
+    /// Versions of Kubestro core the package can be installed on
+    pub kubestro_version: Option<String>,
+    /// Whether the version can be installed on the running Kubestro core
+    pub compatible: bool,
+}
+
+impl From<PackageVersion> for PackageVersionDto {
+    fn from(version: PackageVersion) -> Self {
+        Self {
+            version: version.package.version.to_string(),
+            channel: version.package.channel.into(),
+            kubestro_version: version
+                .package
+                .kubestro_version
+                .map(|requirement| requirement.to_string()),
+            compatible: version.compatible,
+        }
+    }
+}
+
+/// The versions of a package published by a repository
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct PackageVersionsDto {
+    pub repository_id: String,
+    pub name: String,
+    /// The newest compatible version of the channel
+    pub latest: Option<String>,
+    /// The versions of the channel, newest first
+    pub versions: Vec<PackageVersionDto>,
+}
+
+impl From<PackageVersions> for PackageVersionsDto {
+    fn from(versions: PackageVersions) -> Self {
+        Self {
+            repository_id: versions.repository_id.to_string(),
+            name: versions.name,
+            latest: versions.latest.map(|version| version.to_string()),
+            versions: versions.versions.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct PackageMaintainerDto {
+    pub name: String,
+    pub email: Option<String>,
+    pub url: Option<String>,
+}
+
+impl From<PackageMaintainer> for PackageMaintainerDto {
+    fn from(maintainer: PackageMaintainer) -> Self {
+        Self {
+            name: maintainer.name,
+            email: maintainer.email,
+            url: maintainer.url,
+        }
+    }
+}
+
+/// Kubernetes permissions requested by a game manager
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct PermissionRuleDto {
+    /// API groups of the resources, the empty string being the core group
+    pub api_groups: Vec<String>,
+    pub resources: Vec<String>,
+    pub verbs: Vec<String>,
+    /// Whether the rule applies to the whole cluster, instead of the game manager namespace
+    pub cluster_wide: bool,
+}
+
+impl From<PermissionRule> for PermissionRuleDto {
+    fn from(rule: PermissionRule) -> Self {
+        Self {
+            api_groups: rule.api_groups,
+            resources: rule.resources,
+            verbs: rule.verbs,
+            cluster_wide: rule.cluster_wide,
+        }
+    }
+}
+
+/// Job migrating the custom resources of a game manager when upgrading to the package version
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct PackageMigrationDto {
+    /// Image of the job, the image of the package when `null`
+    pub image: Option<String>,
+    pub command: Vec<String>,
+    /// Versions the migration upgrades from, any previous version when `null`
+    pub from: Option<String>,
+}
+
+impl From<PackageMigration> for PackageMigrationDto {
+    fn from(migration: PackageMigration) -> Self {
+        Self {
+            image: migration.image,
+            command: migration.command,
+            from: migration.from.map(|requirement| requirement.to_string()),
+        }
+    }
+}
+
+/// Custom resource definition installed by a package
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct CrdReferenceDto {
+    pub group: String,
+    pub version: String,
+    pub kind: String,
+}
+
+impl From<CrdReference> for CrdReferenceDto {
+    fn from(crd: CrdReference) -> Self {
+        Self {
+            group: crd.group,
+            version: crd.version,
+            kind: crd.kind,
+        }
+    }
+}
+
+/// Changes brought by a version of a package
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct PackageChangelogDto {
+    pub version: String,
+    pub channel: PackageChannelDto,
+    /// The changes, in markdown
+    pub changelog: Option<String>,
+}
+
+impl From<PackageVersion> for PackageChangelogDto {
+    fn from(version: PackageVersion) -> Self {
+        Self {
+            version: version.package.version.to_string(),
+            channel: version.package.channel.into(),
+            changelog: version.package.changelog,
+        }
+    }
+}
+
+/// A version of a package, with everything to review before installing it
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct PackageDetailDto {
+    pub id: String,
+    pub repository_id: String,
+    pub name: String,
+    pub version: String,
+    pub channel: PackageChannelDto,
+    /// Versions of Kubestro core the package can be installed on
+    pub kubestro_version: Option<String>,
+    /// Whether the version can be installed on the running Kubestro core
+    pub compatible: bool,
+    pub description: String,
+    /// Game managed by the package
+    pub game: Option<String>,
+    pub tags: Vec<String>,
+    /// Long description, in markdown
+    pub readme: Option<String>,
+    pub icon: Option<String>,
+    pub screenshots: Vec<String>,
+    pub maintainers: Vec<PackageMaintainerDto>,
+    pub license: Option<String>,
+    pub source_url: Option<String>,
+    /// Container image of the game manager, the package cannot be installed without it
+    pub image: Option<String>,
+    /// JSON schema of the configuration values accepted by the game manager
+    #[schema(value_type = Option<Object>)]
+    pub values_schema: Option<serde_json::Value>,
+    /// Kubernetes permissions the game manager requests once installed
+    pub permissions: Vec<PermissionRuleDto>,
+    /// Custom resource definitions installed by the package
+    pub crds: Vec<CrdReferenceDto>,
+    /// Migration run when upgrading an installed game manager to this version
+    pub migration: Option<PackageMigrationDto>,
+    /// Changelog of every version of the package, newest first
+    pub changelog: Vec<PackageChangelogDto>,
+}
+
+impl From<PackageDetail> for PackageDetailDto {
+    fn from(detail: PackageDetail) -> Self {
+        let package = detail.version.package;
+
+        Self {
+            id: format!("{}@{}", package.name, package.version),
+            repository_id: detail.repository_id.to_string(),
+            name: package.name,
+            version: package.version.to_string(),
+            channel: package.channel.into(),
+            kubestro_version: package
+                .kubestro_version
+                .map(|requirement| requirement.to_string()),
+            compatible: detail.version.compatible,
+            description: package.description,
+            game: package.game,
+            tags: package.tags,
+            readme: package.readme,
+            icon: package.icon,
+            screenshots: package.screenshots,
+            maintainers: package.maintainers.into_iter().map(Into::into).collect(),
+            license: package.license,
+            source_url: package.source_url,
+            image: package.image,
+            values_schema: package.values_schema,
+            permissions: package.permissions.into_iter().map(Into::into).collect(),
+            crds: package.crds.into_iter().map(Into::into).collect(),
+            migration: package.migration.map(Into::into),
+            changelog: detail.versions.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+/// Number of matching packages sharing a value
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct FacetCountDto {
+    pub value: String,
+    pub count: u64,
+}
+
+impl From<FacetCount> for FacetCountDto {
+    fn from(facet: FacetCount) -> Self {
+        Self {
+            value: facet.value,
+            count: facet.count,
+        }
+    }
+}
+
+/// Number of packages matching the catalog query by value, most frequent first
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct CatalogFacetsDto {
+    pub games: Vec<FacetCountDto>,
+    pub tags: Vec<FacetCountDto>,
+    /// Counts by repository id
+    pub repositories: Vec<FacetCountDto>,
+    pub channels: Vec<FacetCountDto>,
+}
+
+impl From<CatalogFacets> for CatalogFacetsDto {
+    fn from(facets: CatalogFacets) -> Self {
+        Self {
+            games: facets.games.into_iter().map(Into::into).collect(),
+            tags: facets.tags.into_iter().map(Into::into).collect(),
+            repositories: facets.repositories.into_iter().map(Into::into).collect(),
+            channels: facets.channels.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+/// Kind of conflict between the packages of several repositories
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CatalogConflictKindDto {
+    /// Several repositories publish a package with the same name
+    DuplicateName,
+    /// Several packages install the same custom resource
+    Crd,
+    /// No repository provides a dependency of a package
+    UnresolvedDependency,
+}
+
+/// A conflict between the packages of several repositories
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct CatalogConflictDto {
+    pub kind: CatalogConflictKindDto,
+    pub detail: String,
+    /// Repositories taking part in the conflict, by priority for duplicate names
+    pub repositories: Vec<String>,
+    /// Names of the packages taking part in the conflict
+    pub packages: Vec<String>,
+}
+
+impl From<&CatalogConflict> for CatalogConflictDto {
+    fn from(conflict: &CatalogConflict) -> Self {
+        let detail = conflict.to_string();
+
+        match conflict {
+            CatalogConflict::DuplicateName { name, repositories } => Self {
+                kind: CatalogConflictKindDto::DuplicateName,
+                detail,
+                repositories: repositories.iter().map(ToString::to_string).collect(),
+                packages: vec![name.clone()],
+            },
+            CatalogConflict::Crd { packages, .. } => Self {
+                kind: CatalogConflictKindDto::Crd,
+                detail,
+                repositories: packages
+                    .iter()
+                    .map(|package| package.repository_id.to_string())
+                    .collect(),
+                packages: packages
+                    .iter()
+                    .map(|package| package.name.clone())
+                    .collect(),
+            },
+            CatalogConflict::UnresolvedDependency {
+                package,
+                dependency,
+            } => Self {
+                kind: CatalogConflictKindDto::UnresolvedDependency,
+                detail,
+                repositories: vec![package.repository_id.to_string()],
+                packages: vec![package.name.clone(), dependency.name.clone()],
+            },
+        }
+    }
 }
@@ -0,0 +1,45 @@
+use chrono::{DateTime, Utc};
+use kubestro_core_domain::services::refresh_jobs::{RefreshJob, RefreshJobStatus};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::repositories_dto::RepositoryDto;
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RefreshJobStatusDto {
+    Running,
+    Completed,
+}
+
+/// A manual refresh of one or several repositories
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct RefreshJobDto {
+    pub id: String,
+    pub status: RefreshJobStatusDto,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    /// The refreshed repositories, with their sync status once the job completed
+    pub repositories: Vec<RepositoryDto>,
+}
+
+impl From<RefreshJobStatus> for RefreshJobStatusDto {
+    fn from(status: RefreshJobStatus) -> Self {
+        match status {
+            RefreshJobStatus::Running => Self::Running,
+            RefreshJobStatus::Completed => Self::Completed,
+        }
+    }
+}
+
+impl From<RefreshJob> for RefreshJobDto {
+    fn from(job: RefreshJob) -> Self {
+        Self {
+            id: job.id.to_string(),
+            status: job.status.into(),
+            started_at: job.started_at,
+            finished_at: job.finished_at,
+            repositories: job.repositories.iter().map(RepositoryDto::from).collect(),
+        }
+    }
+}
@@ -1,30 +1,175 @@
-use kubestro_core_domain::models::{package::Repository, Entity};
+use chrono::{DateTime, Utc};
+use kubestro_core_domain::{
+    models::{
+        package::{
+            GitSource, Repository, RepositoryId, RepositoryKind, RepositorySync,
+            RepositorySyncStatus,
+        },
+        Entity,
+    },
+    services::package_graph::CatalogConflict,
+};
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
+use super::package_dto::CatalogConflictDto;
+
 #[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
 pub struct RepositoryDto {
     pub id: String,
     pub name: String,
+    pub kind: RepositoryKindDto,
     pub url: String,
+    /// Location of the index, for the `git` repositories
+    pub git: Option<GitSourceDto>,
+    /// Public keys allowed to sign the repository index
+    pub trusted_keys: Vec<String>,
+    /// Kind of credentials used to access the repository, the secrets are never returned
+    #[schema(example = "bearer")]
+    pub credentials: Option<String>,
+    /// When several repositories publish the same package, the highest priority wins
+    pub priority: i32,
+    /// Seeded from the configuration, the repository cannot be updated or deleted
+    pub managed: bool,
+    pub sync: RepositorySyncDto,
 }
 
-impl From<Repository> for RepositoryDto {
-    fn from(repository: Repository) -> Self {
+impl RepositoryDto {
+    /// Attach the conflicts the repository takes part in to its synchronization state
+    pub fn with_conflicts(mut self, id: &RepositoryId, conflicts: &[CatalogConflict]) -> Self {
+        self.sync.conflicts = conflicts
+            .iter()
+            .filter(|conflict| conflict.involves(id))
+            .map(Into::into)
+            .collect();
+        self
+    }
+}
+
+/// Source of a repository index
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RepositoryKindDto {
+    #[default]
+    Http,
+    Oci,
+    Git,
+    Bundle,
+}
+
+/// Location of the index in a Git repository
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct GitSourceDto {
+    /// Branch or tag the index is read from
+    pub reference: String,
+    /// Path of the index file in the repository
+    pub path: String,
+}
+
+impl From<&RepositoryKind> for RepositoryKindDto {
+    fn from(kind: &RepositoryKind) -> Self {
+        match kind {
+            RepositoryKind::Http => Self::Http,
+            RepositoryKind::Oci => Self::Oci,
+            RepositoryKind::Git(_) => Self::Git,
+            RepositoryKind::Bundle => Self::Bundle,
+        }
+    }
+}
+
+impl From<&GitSource> for GitSourceDto {
+    fn from(source: &GitSource) -> Self {
         Self {
-            id: repository.id().to_string(),
-            name: repository.name.to_string(),
-            url: repository.url.to_string(),
+            reference: source.reference.clone(),
+            path: source.path.clone(),
+        }
+    }
+}
+
+/// Synchronization state of a repository index
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RepositorySyncStatusDto {
+    Pending,
+    Syncing,
+    Ok,
+    Failed,
+}
+
+/// Result of the last synchronizations of a repository index
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct RepositorySyncDto {
+    pub status: RepositorySyncStatusDto,
+    /// Date of the last successful synchronization
+    pub last_synced_at: Option<DateTime<Utc>>,
+    /// Error of the last synchronization
+    pub last_error: Option<String>,
+    /// Number of packages in the last synchronized index
+    pub package_count: Option<u32>,
+    /// Version of the last synchronized index
+    pub index_version: Option<String>,
+    /// Revision of the source of the last synchronized index, e.g. a Git commit SHA
+    pub revision: Option<String>,
+    /// Conflicts between the index and the ones of the other repositories, only computed when
+    /// listing the repositories or the catalog
+    #[serde(default)]
+    pub conflicts: Vec<CatalogConflictDto>,
+}
+
+impl From<RepositorySyncStatus> for RepositorySyncStatusDto {
+    fn from(status: RepositorySyncStatus) -> Self {
+        match status {
+            RepositorySyncStatus::Pending => Self::Pending,
+            RepositorySyncStatus::Syncing => Self::Syncing,
+            RepositorySyncStatus::Ok => Self::Ok,
+            RepositorySyncStatus::Failed => Self::Failed,
         }
     }
 }
 
+impl From<&RepositorySync> for RepositorySyncDto {
+    fn from(sync: &RepositorySync) -> Self {
+        Self {
+            status: sync.status.into(),
+            last_synced_at: sync.last_synced_at,
+            last_error: sync.last_error.clone(),
+            package_count: sync.package_count,
+            index_version: sync.index_version.map(|version| version.to_string()),
+            revision: sync.revision.clone(),
+            conflicts: vec![],
+        }
+    }
+}
+
+impl From<Repository> for RepositoryDto {
+    fn from(repository: Repository) -> Self {
+        Self::from(&repository)
+    }
+}
+
 impl From<&Repository> for RepositoryDto {
     fn from(repository: &Repository) -> Self {
         Self {
             id: repository.id().to_string(),
             name: repository.name.to_string(),
+            kind: (&repository.kind).into(),
             url: repository.url.to_string(),
+            git: match &repository.kind {
+                RepositoryKind::Git(source) => Some(source.into()),
+                _ => None,
+            },
+            trusted_keys: repository
+                .trusted_keys
+                .iter()
+                .map(|key| key.value().to_string())
+                .collect(),
+            credentials: repository
+                .credentials
+                .as_ref()
+                .map(|credentials| credentials.kind().to_string()),
+            priority: repository.priority,
+            managed: repository.managed,
+            sync: (&repository.sync).into(),
         }
     }
 }
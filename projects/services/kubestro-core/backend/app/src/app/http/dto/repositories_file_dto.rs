@@ -0,0 +1,140 @@
+use kubestro_core_domain::{
+    models::{
+        fields::trusted_key::{TrustedKey, TrustedKeyError},
+        package::{GitSource, RepositoryKind},
+    },
+    services::repositories_import::RepositoryDeclaration,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::repositories_dto::{GitSourceDto, RepositoryKindDto};
+
+/// Version of the repositories file layout
+pub const REPOSITORIES_FILE_VERSION: u32 = 1;
+
+/// A declarative list of repositories, as exported and imported
+///
+/// The credentials are never part of the file, they are left untouched on import.
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct RepositoriesFileDto {
+    pub version: u32,
+    pub repositories: Vec<RepositoryEntryDto>,
+}
+
+/// A repository declared in a repositories file, identified by its url
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct RepositoryEntryDto {
+    pub name: String,
+    #[serde(default)]
+    pub kind: RepositoryKindDto,
+    pub url: String,
+    /// Location of the index, for the `git` repositories
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git: Option<GitSourceDto>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub trusted_keys: Vec<String>,
+    #[serde(default)]
+    pub priority: i32,
+}
+
+impl From<Vec<RepositoryDeclaration>> for RepositoriesFileDto {
+    fn from(declarations: Vec<RepositoryDeclaration>) -> Self {
+        Self {
+            version: REPOSITORIES_FILE_VERSION,
+            repositories: declarations
+                .into_iter()
+                .map(|declaration| RepositoryEntryDto {
+                    kind: (&declaration.kind).into(),
+                    git: match &declaration.kind {
+                        RepositoryKind::Git(source) => Some(source.into()),
+                        _ => None,
+                    },
+                    name: declaration.name,
+                    url: declaration.url,
+                    trusted_keys: declaration
+                        .trusted_keys
+                        .iter()
+                        .map(|key| key.value().to_string())
+                        .collect(),
+                    priority: declaration.priority,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl RepositoriesFileDto {
+    /// Parse a repositories file, written either in YAML or in JSON
+    pub fn parse(data: &str) -> Result<Vec<RepositoryDeclaration>, RepositoriesFileError> {
+        let file = serde_yaml::from_str::<Self>(data)
+            .map_err(|e| RepositoriesFileError::Malformed(e.to_string()))?;
+
+        if file.version != REPOSITORIES_FILE_VERSION {
+            return Err(RepositoriesFileError::UnsupportedVersion(file.version));
+        }
+
+        file.repositories
+            .into_iter()
+            .map(RepositoryDeclaration::try_from)
+            .collect()
+    }
+}
+
+impl TryFrom<RepositoryEntryDto> for RepositoryDeclaration {
+    type Error = RepositoriesFileError;
+
+    fn try_from(entry: RepositoryEntryDto) -> Result<Self, Self::Error> {
+        if entry.name.len() < 3 {
+            return Err(RepositoriesFileError::InvalidName(entry.name));
+        }
+        if url::Url::parse(&entry.url).is_err() {
+            return Err(RepositoriesFileError::InvalidUrl(entry.url));
+        }
+
+        let kind = match entry.kind {
+            RepositoryKindDto::Http => RepositoryKind::Http,
+            RepositoryKindDto::Oci => RepositoryKind::Oci,
+            RepositoryKindDto::Git => RepositoryKind::Git(
+                entry
+                    .git
+                    .map(|git| GitSource {
+                        reference: git.reference,
+                        path: git.path,
+                    })
+                    .unwrap_or_default(),
+            ),
+            RepositoryKindDto::Bundle => RepositoryKind::Bundle,
+        };
+
+        Ok(Self {
+            name: entry.name,
+            kind,
+            url: entry.url,
+            trusted_keys: entry
+                .trusted_keys
+                .into_iter()
+                .map(TrustedKey::try_from)
+                .collect::<Result<_, _>>()?,
+            priority: entry.priority,
+        })
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RepositoriesFileError {
+    #[error("The repositories file is malformed: {0}")]
+    Malformed(String),
+    #[error(
+        "Unsupported repositories file version {0}, only {REPOSITORIES_FILE_VERSION} is supported"
+    )]
+    UnsupportedVersion(u32),
+    #[error("Repository name `{0}` must be at least 3 characters long")]
+    InvalidName(String),
+    #[error("Invalid repository URL `{0}`")]
+    InvalidUrl(String),
+    #[error(transparent)]
+    InvalidTrustedKey(#[from] TrustedKeyError),
+}
@@ -2,18 +2,36 @@ use std::{borrow::Cow, collections::HashMap};
 
 use axum::http::StatusCode;
 use kubestro_core_domain::{
-    models::fields::{email::EmailError, password::PasswordError, username::UsernameError},
+    models::{
+        fields::{
+            email::EmailError, password::PasswordError, trusted_key::TrustedKeyError,
+            username::UsernameError,
+        },
+        index::IndexError,
+        package::RepositoryCredentialsError,
+    },
     ports::{
         repositories::{
             repositories_repositories::RepositoryRepoError, user_repository::UserRepoError,
         },
-        services::repositories_service::RepositoriesServiceError,
+        services::{
+            bundles_service::BundlesServiceError, cluster_service::ClusterServiceError,
+            repositories_service::RepositoriesServiceError,
+        },
+        validators::ValuesValidationError,
+    },
+    services::{
+        auth::local_auth::LocalAuthServiceError, catalog::CatalogServiceError,
+        game_managers::GameManagersServiceError, refresh_jobs::RefreshJobsError,
+        repositories_import::RepositoriesImportError,
     },
-    services::auth::local_auth::LocalAuthServiceError,
 };
 use serde::{Serialize, Serializer};
 
-use crate::app::services::oidc_auth::OidcAuthServiceError;
+use crate::app::{
+    http::dto::repositories_file_dto::RepositoriesFileError,
+    services::oidc_auth::OidcAuthServiceError,
+};
 
 use super::ApiError;
 
@@ -133,6 +151,30 @@ impl From<UsernameError> for ApiError {
     }
 }
 
+impl From<TrustedKeyError> for ApiError {
+    fn from(value: TrustedKeyError) -> Self {
+        ApiError {
+            status: StatusCode::BAD_REQUEST,
+            title: "Invalid trusted key".into(),
+            detail: Some(value.to_string().into()),
+            code: "INVALID_TRUSTED_KEY".into(),
+            ..Default::default()
+        }
+    }
+}
+
+impl From<RepositoryCredentialsError> for ApiError {
+    fn from(value: RepositoryCredentialsError) -> Self {
+        ApiError {
+            status: StatusCode::BAD_REQUEST,
+            title: "Invalid repository credentials".into(),
+            detail: Some(value.to_string().into()),
+            code: "INVALID_CREDENTIALS".into(),
+            ..Default::default()
+        }
+    }
+}
+
 impl From<LocalAuthServiceError> for ApiError {
     fn from(value: LocalAuthServiceError) -> Self {
         match value {
@@ -219,14 +261,279 @@ impl From<RepositoryRepoError> for ApiError {
     }
 }
 
+impl From<IndexError> for ApiError {
+    fn from(value: IndexError) -> Self {
+        let mut extensions = HashMap::<Cow<'static, str>, serde_json::Value>::new();
+        if let IndexError::InvalidPackages(errors) = &value {
+            extensions.insert(
+                "errors".into(),
+                serde_json::to_value(errors).unwrap_or_default(),
+            );
+        }
+
+        ApiError {
+            status: StatusCode::UNPROCESSABLE_ENTITY,
+            title: "Invalid repository index".into(),
+            detail: Some(value.to_string().into()),
+            code: "INVALID_REPOSITORY_INDEX".into(),
+            extensions,
+            ..Default::default()
+        }
+    }
+}
+
 impl From<RepositoriesServiceError> for ApiError {
     fn from(value: RepositoriesServiceError) -> Self {
         match value {
             RepositoriesServiceError::RepositoryError(e) => e.into(),
+            RepositoriesServiceError::PackageError(e) => ApiError::unexpected_error(e),
             // TODO: return the proper error code instead of internal server error
             RepositoriesServiceError::RemoteDataError(e) => ApiError::unexpected_error(e),
+            RepositoriesServiceError::InvalidIndex(e) => e.into(),
+            RepositoriesServiceError::InvalidSignature(e) => ApiError {
+                status: StatusCode::UNPROCESSABLE_ENTITY,
+                title: "Invalid index signature".into(),
+                detail: Some(e.into()),
+                code: "INVALID_INDEX_SIGNATURE".into(),
+                ..Default::default()
+            },
+            RepositoriesServiceError::UrlNotAllowed(e) => ApiError {
+                status: StatusCode::UNPROCESSABLE_ENTITY,
+                title: "Validation error".into(),
+                detail: Some("The request body is invalid".into()),
+                code: "VALIDATION_ERROR".into(),
+                extensions: HashMap::from([(
+                    Cow::Borrowed("errors"),
+                    serde_json::json!({
+                        "#/url": { "code": "url_not_allowed", "detail": e }
+                    }),
+                )]),
+                ..Default::default()
+            },
+            RepositoriesServiceError::Managed(_) => ApiError {
+                status: StatusCode::FORBIDDEN,
+                title: "Managed repository".into(),
+                detail: Some(value.to_string().into()),
+                code: "REPOSITORY_MANAGED".into(),
+                ..Default::default()
+            },
             RepositoriesServiceError::UnexpectedError(e) => ApiError::unexpected_error(e),
             RepositoriesServiceError::CachingError(e) => ApiError::unexpected_error(e),
         }
     }
 }
+
+impl From<CatalogServiceError> for ApiError {
+    fn from(value: CatalogServiceError) -> Self {
+        match value {
+            CatalogServiceError::RepositoryError(e) => e.into(),
+            CatalogServiceError::PackageError(e) => ApiError::unexpected_error(e),
+            CatalogServiceError::PackageNotFound(_)
+            | CatalogServiceError::VersionNotFound { .. } => ApiError::not_found(value.to_string()),
+            CatalogServiceError::IndexUnavailable(_) => ApiError {
+                status: StatusCode::SERVICE_UNAVAILABLE,
+                title: "Repository index unavailable".into(),
+                detail: Some(value.to_string().into()),
+                code: "INDEX_UNAVAILABLE".into(),
+                ..Default::default()
+            },
+            CatalogServiceError::NoCompatibleVersion { .. } => ApiError {
+                status: StatusCode::UNPROCESSABLE_ENTITY,
+                title: "No compatible version".into(),
+                detail: Some(value.to_string().into()),
+                code: "NO_COMPATIBLE_VERSION".into(),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl From<RepositoriesImportError> for ApiError {
+    fn from(value: RepositoriesImportError) -> Self {
+        match value {
+            RepositoriesImportError::RepositoryError(e) => e.into(),
+            RepositoriesImportError::DuplicateUrl(_) => ApiError {
+                status: StatusCode::BAD_REQUEST,
+                title: "Invalid repositories file".into(),
+                detail: Some(value.to_string().into()),
+                code: "INVALID_REPOSITORIES_FILE".into(),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl From<RepositoriesFileError> for ApiError {
+    fn from(value: RepositoriesFileError) -> Self {
+        ApiError {
+            status: StatusCode::BAD_REQUEST,
+            title: "Invalid repositories file".into(),
+            detail: Some(value.to_string().into()),
+            code: "INVALID_REPOSITORIES_FILE".into(),
+            ..Default::default()
+        }
+    }
+}
+
+impl From<RefreshJobsError> for ApiError {
+    fn from(value: RefreshJobsError) -> Self {
+        match value {
+            RefreshJobsError::RepositoryError(e) => e.into(),
+            RefreshJobsError::JobsLock(e) => ApiError::unexpected_error(e),
+        }
+    }
+}
+
+impl From<BundlesServiceError> for ApiError {
+    fn from(value: BundlesServiceError) -> Self {
+        match value {
+            BundlesServiceError::RepositoryError(e) => e.into(),
+            BundlesServiceError::RepositoriesServiceError(e) => e.into(),
+            BundlesServiceError::InvalidBundle(_) => ApiError {
+                status: StatusCode::BAD_REQUEST,
+                title: "Invalid bundle".into(),
+                detail: Some(value.to_string().into()),
+                code: "INVALID_BUNDLE".into(),
+                ..Default::default()
+            },
+            BundlesServiceError::StorageError(e) => ApiError::unexpected_error(e),
+            BundlesServiceError::UnexpectedError(e) => ApiError::unexpected_error(e),
+        }
+    }
+}
+
+impl From<ClusterServiceError> for ApiError {
+    fn from(value: ClusterServiceError) -> Self {
+        match value {
+            ClusterServiceError::AlreadyExists(_) => ApiError::conflict(
+                value.to_string(),
+                "GAME_MANAGER_ALREADY_EXISTS",
+                HashMap::new(),
+            ),
+            ClusterServiceError::NotFound(_) => ApiError::not_found(value.to_string()),
+            ClusterServiceError::ApiError(e) => ApiError::unexpected_error(e),
+            ClusterServiceError::MigrationFailed(..) => ApiError {
+                status: StatusCode::UNPROCESSABLE_ENTITY,
+                title: "Migration failed".into(),
+                detail: Some(value.to_string().into()),
+                code: "MIGRATION_FAILED".into(),
+                ..Default::default()
+            },
+            ClusterServiceError::Conflict(_) => {
+                ApiError::conflict(value.to_string(), "GAME_MANAGER_CHANGED", HashMap::new())
+            }
+        }
+    }
+}
+
+impl From<ValuesValidationError> for ApiError {
+    fn from(value: ValuesValidationError) -> Self {
+        match value {
+            ValuesValidationError::InvalidValues(violations) => ApiError {
+                status: StatusCode::UNPROCESSABLE_ENTITY,
+                title: "Validation error".into(),
+                detail: Some(
+                    "The configuration values do not match the schema of the package".into(),
+                ),
+                code: "VALIDATION_ERROR".into(),
+                extensions: HashMap::from([(
+                    Cow::Borrowed("errors"),
+                    violations
+                        .into_iter()
+                        .map(|violation| {
+                            (
+                                format!("#/values{}", violation.path),
+                                serde_json::json!({ "code": "schema", "detail": violation.message }),
+                            )
+                        })
+                        .collect::<serde_json::Map<_, _>>()
+                        .into(),
+                )]),
+                ..Default::default()
+            },
+            ValuesValidationError::InvalidSchema(_) => ApiError {
+                status: StatusCode::UNPROCESSABLE_ENTITY,
+                title: "Invalid values schema".into(),
+                detail: Some(value.to_string().into()),
+                code: "INVALID_VALUES_SCHEMA".into(),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl From<GameManagersServiceError> for ApiError {
+    fn from(value: GameManagersServiceError) -> Self {
+        match value {
+            GameManagersServiceError::CatalogError(e) => e.into(),
+            GameManagersServiceError::ClusterError(e) => e.into(),
+            GameManagersServiceError::InvalidValues(e) => e.into(),
+            GameManagersServiceError::ClusterUnavailable => ApiError {
+                status: StatusCode::SERVICE_UNAVAILABLE,
+                title: "Cluster unavailable".into(),
+                detail: Some(value.to_string().into()),
+                code: "CLUSTER_UNAVAILABLE".into(),
+                ..Default::default()
+            },
+            GameManagersServiceError::InvalidName(_) => ApiError {
+                status: StatusCode::UNPROCESSABLE_ENTITY,
+                title: "Validation error".into(),
+                detail: Some("The request body is invalid".into()),
+                code: "VALIDATION_ERROR".into(),
+                extensions: HashMap::from([(
+                    Cow::Borrowed("errors"),
+                    serde_json::json!({
+                        "#/name": { "code": "name", "detail": value.to_string() }
+                    }),
+                )]),
+                ..Default::default()
+            },
+            GameManagersServiceError::IncompatibleVersion { .. } => ApiError {
+                status: StatusCode::UNPROCESSABLE_ENTITY,
+                title: "Incompatible version".into(),
+                detail: Some(value.to_string().into()),
+                code: "INCOMPATIBLE_VERSION".into(),
+                ..Default::default()
+            },
+            GameManagersServiceError::NotInstallable(..) => ApiError {
+                status: StatusCode::UNPROCESSABLE_ENTITY,
+                title: "Package not installable".into(),
+                detail: Some(value.to_string().into()),
+                code: "PACKAGE_NOT_INSTALLABLE".into(),
+                ..Default::default()
+            },
+            GameManagersServiceError::OwnsGameServers { count, .. } => ApiError::conflict(
+                value.to_string(),
+                "GAME_MANAGER_HAS_GAME_SERVERS",
+                HashMap::from([("game_servers".to_string(), count.into())]),
+            ),
+            GameManagersServiceError::NotFromCatalog(_) => ApiError {
+                status: StatusCode::UNPROCESSABLE_ENTITY,
+                title: "Game manager not from the catalog".into(),
+                detail: Some(value.to_string().into()),
+                code: "GAME_MANAGER_NOT_FROM_CATALOG".into(),
+                ..Default::default()
+            },
+            GameManagersServiceError::Downgrade { .. } => ApiError {
+                status: StatusCode::UNPROCESSABLE_ENTITY,
+                title: "Downgrade not supported".into(),
+                detail: Some(value.to_string().into()),
+                code: "DOWNGRADE_NOT_SUPPORTED".into(),
+                ..Default::default()
+            },
+            GameManagersServiceError::RevisionNotFound { .. } => {
+                ApiError::not_found(value.to_string())
+            }
+            GameManagersServiceError::NoPreviousRevision(_) => {
+                ApiError::conflict(value.to_string(), "NO_PREVIOUS_REVISION", HashMap::new())
+            }
+            GameManagersServiceError::InvalidRevision { .. } => ApiError {
+                status: StatusCode::UNPROCESSABLE_ENTITY,
+                title: "Invalid revision".into(),
+                detail: Some(value.to_string().into()),
+                code: "INVALID_REVISION".into(),
+                ..Default::default()
+            },
+        }
+    }
+}
@@ -0,0 +1,174 @@
+use axum::{
+    body::Body,
+    http::{header, StatusCode},
+    response::IntoResponse,
+    Extension, Json,
+};
+use deserr::Deserr;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+
+use crate::app::{
+    context::AppContext,
+    http::helpers::{errors::ApiError, validation::ValidatedJson},
+};
+use kubestro_core_domain::{
+    models::package::RepositoryId,
+    ports::{
+        repositories::repositories_repositories::RepositoryRepoError,
+        services::bundles_service::BundledRepository,
+    },
+};
+
+use super::GAME_MANAGER_TAG;
+
+/// File name suggested for an exported bundle
+const BUNDLE_FILE_NAME: &str = "kubestro-bundle.tar.gz";
+
+/// Export a bundle payload
+#[derive(Deserialize, Deserr, Validate, ToSchema, Debug)]
+pub(super) struct ExportBundlePayload {
+    /// Ids of the repositories to export
+    #[validate(length(min = 1, message = "At least one repository must be exported"))]
+    pub repositories: Vec<String>,
+}
+
+#[utoipa::path(
+    method(post),
+    path = "/api/v1.0/game-managers/bundles/export",
+    summary = "Export a bundle",
+    description = "Export repositories in a single gzipped tarball, to serve them on a cluster \
+        without network access. The indexes are fetched again from their source, along with \
+        their signature and every package artifact and icon they reference.",
+    tag = GAME_MANAGER_TAG,
+
+    request_body(content = ExportBundlePayload, content_type = "application/json"),
+    responses(
+        (status = OK, description = "Bundle archive", content_type = "application/gzip"),
+        (status = NOT_FOUND, description = "Repository not found", body = ApiError),
+    ),
+)]
+pub async fn handler_export_bundle(
+    Extension(ctx): Extension<AppContext>,
+    ValidatedJson(payload): ValidatedJson<ExportBundlePayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    // An id that cannot be parsed cannot match any repository either
+    let repositories = payload
+        .repositories
+        .into_iter()
+        .map(RepositoryId::try_from)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| ApiError::not_found(RepositoryRepoError::NotFound))?;
+
+    let archive = ctx.bundles_service.export(&repositories).await?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/gzip".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", BUNDLE_FILE_NAME),
+            ),
+        ],
+        archive,
+    ))
+}
+
+/// A repository of an uploaded bundle
+#[derive(Debug, Serialize, ToSchema)]
+struct BundledRepositoryDto {
+    name: String,
+    /// Url of the `bundle` repositories serving it
+    url: String,
+    /// Url of the repository the bundle was exported from
+    source: String,
+    package_count: u32,
+    index_version: String,
+    /// Whether the index signature was verified
+    signed: bool,
+    /// Number of artifacts and icons bundled along with the index
+    file_count: u32,
+}
+
+impl From<BundledRepository> for BundledRepositoryDto {
+    fn from(repository: BundledRepository) -> Self {
+        Self {
+            name: repository.name,
+            url: repository.url,
+            source: repository.source,
+            package_count: repository.package_count,
+            index_version: repository.index_version,
+            signed: repository.signed,
+            file_count: repository.file_count,
+        }
+    }
+}
+
+/// Bundle upload response
+#[derive(Serialize, ToSchema)]
+pub(super) struct UploadBundleResponse {
+    repositories: Vec<BundledRepositoryDto>,
+}
+
+#[utoipa::path(
+    method(post),
+    path = "/api/v1.0/game-managers/bundles",
+    summary = "Upload a bundle",
+    description = "Verify an exported bundle and store its repositories, replacing the ones of \
+        the same names uploaded before. The files must match their digest and the signed \
+        indexes must be signed by the keys of their source repository. A repository of the \
+        bundle is served by the repositories of kind `bundle` with its url, they are refreshed \
+        once the bundle is stored. Nothing is stored when the bundle is invalid.",
+    tag = GAME_MANAGER_TAG,
+
+    request_body(content = Vec<u8>, content_type = "application/gzip"),
+    responses(
+        (status = OK, description = "Bundle stored", body = UploadBundleResponse, example = json!({
+            "repositories": [
+                {
+                    "name": "demo",
+                    "url": "bundle://demo",
+                    "source": "https://example.com/repository",
+                    "package_count": 12,
+                    "index_version": "1.0",
+                    "signed": true,
+                    "file_count": 24
+                }
+            ]
+        })),
+        (status = BAD_REQUEST, description = "Invalid bundle", body = ApiError),
+        (status = PAYLOAD_TOO_LARGE, description = "Bundle too large", body = ApiError),
+    ),
+)]
+pub async fn handler_upload_bundle(
+    Extension(ctx): Extension<AppContext>,
+    body: Body,
+) -> Result<impl IntoResponse, ApiError> {
+    let max_size = ctx.bundle_max_size;
+    let archive = axum::body::to_bytes(body, usize::try_from(max_size).unwrap_or(usize::MAX))
+        .await
+        .map_err(|_| ApiError {
+            status: StatusCode::PAYLOAD_TOO_LARGE,
+            title: "Bundle too large".into(),
+            detail: Some(
+                format!(
+                    "The bundle could not be read, it may exceed {} bytes",
+                    max_size
+                )
+                .into(),
+            ),
+            code: "BUNDLE_TOO_LARGE".into(),
+            ..Default::default()
+        })?;
+
+    let repositories = ctx
+        .bundles_service
+        .import(archive.to_vec())
+        .await?
+        .into_iter()
+        .map(Into::into)
+        .collect();
+
+    Ok(Json(UploadBundleResponse { repositories }))
+}
@@ -1,22 +1,52 @@
-use axum::{response::IntoResponse, Extension, Json};
-use serde::Serialize;
-use utoipa::ToSchema;
+use axum::{
+    extract::{Path, Query},
+    response::IntoResponse,
+    Extension, Json,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 
 use crate::app::{
     context::AppContext,
     http::{
-        dto::{package_dto::PackageDto, repositories_dto::RepositoryDto},
+        dto::{
+            package_dto::{
+                CatalogConflictDto, CatalogFacetsDto, PackageChannelDto, PackageDetailDto,
+                PackageDto, PackageVersionsDto,
+            },
+            repositories_dto::RepositoryDto,
+        },
         helpers::errors::ApiError,
     },
 };
-use kubestro_core_domain::ports::repositories::repositories_repositories::RepositoriesRepository;
+use kubestro_core_domain::{
+    models::package::RepositoryId,
+    services::catalog::{
+        CatalogQuery, CatalogRepositoryStatus, CatalogSort, SortOrder, DEFAULT_CATALOG_PER_PAGE,
+    },
+};
 
 use super::GAME_MANAGER_TAG;
 
+/// State of the cached index of a repository
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+enum RepositoryCatalogStatus {
+    /// The repository index is cached
+    Ok,
+    /// The repository index has not been cached yet
+    Missing,
+    /// The cached repository index could not be read
+    Failed,
+}
+
 #[derive(Debug, Serialize, ToSchema)]
 struct RepositoryWithPackages {
     #[serde(flatten)]
     repository: RepositoryDto,
+    status: RepositoryCatalogStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
     packages: Vec<PackageDto>,
 }
 
@@ -24,32 +54,355 @@ struct RepositoryWithPackages {
 #[derive(Serialize, ToSchema)]
 pub(super) struct GameManagerCatalogResponse {
     packages: Vec<RepositoryWithPackages>,
+    /// Conflicts between the packages of the repositories
+    conflicts: Vec<CatalogConflictDto>,
+    /// Total number of packages matching the query
+    total: usize,
+    page: usize,
+    per_page: usize,
+    /// Counts of the packages matching the query, to refine it
+    facets: CatalogFacetsDto,
+}
+
+/// Field used to sort the catalog
+#[derive(Debug, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub(super) enum CatalogSortQuery {
+    /// Most relevant packages for the search first, by name without a search
+    #[default]
+    Relevance,
+    Name,
+    Version,
+}
+
+/// Sort direction of the catalog
+#[derive(Debug, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub(super) enum SortOrderQuery {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// Game Manager catalog queries
+#[derive(Deserialize, IntoParams)]
+pub(super) struct GameManagerCatalogQueries {
+    /// Search packages by name, game, tags or description, every word must match
+    #[serde(default)]
+    search: Option<String>,
+    /// Only return packages from this repository
+    #[serde(default)]
+    #[param(value_type = Option<String>)]
+    repository: Option<RepositoryId>,
+    /// Only return packages managing this game
+    #[serde(default)]
+    game: Option<String>,
+    /// Only return packages published on this channel
+    #[serde(default)]
+    channel: Option<PackageChannelDto>,
+    /// Only return packages having all these tags, separated by commas
+    #[serde(default)]
+    tags: Option<String>,
+    #[serde(default)]
+    sort: CatalogSortQuery,
+    #[serde(default)]
+    order: SortOrderQuery,
+    /// Page number, starting at 1
+    #[serde(default)]
+    page: Option<usize>,
+    /// Number of packages per page (max 100)
+    #[serde(default)]
+    per_page: Option<usize>,
+}
+
+impl From<GameManagerCatalogQueries> for CatalogQuery {
+    fn from(queries: GameManagerCatalogQueries) -> Self {
+        Self {
+            search: queries.search.filter(|search| !search.is_empty()),
+            repository_id: queries.repository,
+            game: queries.game.filter(|game| !game.is_empty()),
+            channel: queries.channel.map(Into::into),
+            tags: queries
+                .tags
+                .iter()
+                .flat_map(|tags| tags.split(','))
+                .map(str::trim)
+                .filter(|tag| !tag.is_empty())
+                .map(String::from)
+                .collect(),
+            sort: match queries.sort {
+                CatalogSortQuery::Relevance => CatalogSort::Relevance,
+                CatalogSortQuery::Name => CatalogSort::Name,
+                CatalogSortQuery::Version => CatalogSort::Version,
+            },
+            order: match queries.order {
+                SortOrderQuery::Asc => SortOrder::Asc,
+                SortOrderQuery::Desc => SortOrder::Desc,
+            },
+            page: queries.page.unwrap_or(1),
+            per_page: queries.per_page.unwrap_or(DEFAULT_CATALOG_PER_PAGE),
+        }
+    }
 }
 
 #[utoipa::path(
     method(get),
     path = "/api/v1.0/game-managers/catalog",
     summary = "Get game managers catalog",
-    description = "Get the game managers catalog",
+    description = "Search the game managers catalog, grouped by repository. The search tolerates \
+        typos, and the facets count the matching packages by game, tag, repository and channel",
     tag = GAME_MANAGER_TAG,
 
+    params(GameManagerCatalogQueries),
     responses(
-        (status = OK, description = "Game managers catalog", body = GameManagerCatalogResponse, example = json!({})),
+        (status = OK, description = "Game managers catalog", body = GameManagerCatalogResponse, example = json!({
+            "packages": [
+                {
+                    "id": "321a07de-7717-49a8-9b28-a6858503bef3",
+                    "name": "Demo",
+                    "url": "https://example.com/repository",
+                    "status": "ok",
+                    "packages": [
+                        {
+                            "id": "minecraft@1.2.0",
+                            "name": "minecraft",
+                            "version": "1.2.0",
+                            "channel": "stable",
+                            "description": "Minecraft game manager",
+                            "game": "minecraft",
+                            "tags": ["sandbox", "paper"]
+                        }
+                    ]
+                }
+            ],
+            "conflicts": [],
+            "total": 1,
+            "page": 1,
+            "per_page": 20,
+            "facets": {
+                "games": [{ "value": "minecraft", "count": 1 }],
+                "tags": [{ "value": "paper", "count": 1 }, { "value": "sandbox", "count": 1 }],
+                "repositories": [{ "value": "321a07de-7717-49a8-9b28-a6858503bef3", "count": 1 }],
+                "channels": [{ "value": "stable", "count": 1 }]
+            }
+        })),
     ),
 )]
 pub async fn handler_get_game_managers_catalog(
     Extension(ctx): Extension<AppContext>,
+    Query(queries): Query<GameManagerCatalogQueries>,
 ) -> Result<impl IntoResponse, ApiError> {
-    let repositories = ctx.repository_repo.find_all(None).await?;
+    let catalog = ctx.catalog_service.search(queries.into()).await?;
 
-    let packages: Vec<RepositoryWithPackages> = repositories
+    let mut packages: Vec<RepositoryWithPackages> = catalog
+        .repositories
         .into_iter()
-        .map(|repository| RepositoryWithPackages {
-            repository: repository.into(),
-            packages: vec![],
+        .map(|catalog_repository| {
+            let (status, error) = match catalog_repository.status {
+                CatalogRepositoryStatus::Ok => (RepositoryCatalogStatus::Ok, None),
+                CatalogRepositoryStatus::Missing => (RepositoryCatalogStatus::Missing, None),
+                CatalogRepositoryStatus::Failed(e) => (RepositoryCatalogStatus::Failed, Some(e)),
+            };
+
+            RepositoryWithPackages {
+                repository: RepositoryDto::from(&catalog_repository.repository)
+                    .with_conflicts(&catalog_repository.repository.id, &catalog.conflicts),
+                status,
+                error,
+                packages: vec![],
+            }
         })
-        .filter(|repository| !repository.packages.is_empty())
         .collect();
 
-    Ok(Json(GameManagerCatalogResponse { packages }))
+    // Dispatch the packages of the page in their repository, keeping their order
+    for entry in catalog.packages {
+        let repository_id = entry.repository_id.to_string();
+        if let Some(repository) = packages
+            .iter_mut()
+            .find(|repository| repository.repository.id == repository_id)
+        {
+            repository.packages.push(entry.package.into());
+        }
+    }
+
+    Ok(Json(GameManagerCatalogResponse {
+        packages,
+        conflicts: catalog.conflicts.iter().map(Into::into).collect(),
+        total: catalog.total,
+        page: catalog.page,
+        per_page: catalog.per_page,
+        facets: catalog.facets.into(),
+    }))
+}
+
+/// Package versions queries
+#[derive(Deserialize, IntoParams)]
+pub(super) struct PackageVersionsQueries {
+    /// Release channel to list the versions of, the more stable channels are included
+    #[serde(default)]
+    channel: PackageChannelDto,
+}
+
+/// Package versions response
+#[derive(Serialize, ToSchema)]
+pub(super) struct PackageVersionsResponse {
+    package: PackageVersionsDto,
+}
+
+#[utoipa::path(
+    method(get),
+    path = "/api/v1.0/game-managers/catalog/{repository}/{package}/versions",
+    summary = "List the versions of a package",
+    description = "List the versions of a package offered on a release channel, newest first, \
+        along with the newest version compatible with this Kubestro release",
+    tag = GAME_MANAGER_TAG,
+
+    params(
+        ("repository" = String, Path, description = "Repository database id"),
+        ("package" = String, Path, description = "Package name"),
+        PackageVersionsQueries,
+    ),
+    responses(
+        (status = OK, description = "Package versions", body = PackageVersionsResponse, example = json!({
+            "package": {
+                "repository_id": "321a07de-7717-49a8-9b28-a6858503bef3",
+                "name": "minecraft",
+                "latest": "1.2.0",
+                "versions": [
+                    {
+                        "version": "1.3.0",
+                        "channel": "stable",
+                        "kubestro_version": ">=0.3.0",
+                        "compatible": false
+                    },
+                    {
+                        "version": "1.2.0",
+                        "channel": "stable",
+                        "kubestro_version": ">=0.1.0",
+                        "compatible": true
+                    }
+                ]
+            }
+        })),
+        (status = NOT_FOUND, description = "Repository or package not found", body = ApiError),
+        (status = SERVICE_UNAVAILABLE, description = "Repository index not cached", body = ApiError),
+    ),
+)]
+pub async fn handler_get_package_versions(
+    Extension(ctx): Extension<AppContext>,
+    Path((repository_id, name)): Path<(RepositoryId, String)>,
+    Query(queries): Query<PackageVersionsQueries>,
+) -> Result<impl IntoResponse, ApiError> {
+    let versions = ctx
+        .catalog_service
+        .versions(&repository_id, &name, queries.channel.into())
+        .await?;
+
+    Ok(Json(PackageVersionsResponse {
+        package: versions.into(),
+    }))
+}
+
+/// Package detail queries
+#[derive(Deserialize, IntoParams)]
+pub(super) struct PackageDetailQueries {
+    /// Version to detail, the newest compatible version of the channel by default
+    #[serde(default)]
+    #[param(value_type = Option<String>)]
+    version: Option<semver::Version>,
+    /// Release channel to pick the version from when none is given
+    #[serde(default)]
+    channel: PackageChannelDto,
+}
+
+/// Package detail response
+#[derive(Serialize, ToSchema)]
+pub(super) struct PackageDetailResponse {
+    package: PackageDetailDto,
+}
+
+#[utoipa::path(
+    method(get),
+    path = "/api/v1.0/game-managers/catalog/{repository}/{package}",
+    summary = "Get the details of a package",
+    description = "Get everything to review before installing a package: its description, \
+        changelog, maintainers, and the Kubernetes permissions and CRDs it will install",
+    tag = GAME_MANAGER_TAG,
+
+    params(
+        ("repository" = String, Path, description = "Repository database id"),
+        ("package" = String, Path, description = "Package name"),
+        PackageDetailQueries,
+    ),
+    responses(
+        (status = OK, description = "Package details", body = PackageDetailResponse, example = json!({
+            "package": {
+                "id": "minecraft@1.2.0",
+                "repository_id": "321a07de-7717-49a8-9b28-a6858503bef3",
+                "name": "minecraft",
+                "version": "1.2.0",
+                "channel": "stable",
+                "kubestro_version": ">=0.1.0",
+                "compatible": true,
+                "description": "Minecraft game manager",
+                "game": "minecraft",
+                "tags": ["sandbox", "paper"],
+                "readme": "# Minecraft\n\nRun Minecraft servers on Kubernetes.",
+                "icon": "https://kubestro.io/icons/minecraft.png",
+                "screenshots": ["https://kubestro.io/screenshots/minecraft.png"],
+                "maintainers": [
+                    { "name": "Kubestro team", "email": "team@kubestro.io", "url": null }
+                ],
+                "license": "MIT",
+                "source_url": "https://github.com/kubestro/minecraft",
+                "image": "ghcr.io/kubestro/minecraft:1.2.0",
+                "values_schema": {
+                    "type": "object",
+                    "properties": {
+                        "motd": { "type": "string" },
+                    },
+                },
+                "permissions": [
+                    {
+                        "api_groups": ["apps"],
+                        "resources": ["statefulsets"],
+                        "verbs": ["get", "create"],
+                        "cluster_wide": false
+                    }
+                ],
+                "crds": [
+                    { "group": "minecraft.kubestro.io", "version": "v1", "kind": "MinecraftServer" }
+                ],
+                "migration": {
+                    "image": null,
+                    "command": ["/migrate"],
+                    "from": ">=1.0.0, <1.2.0"
+                },
+                "changelog": [
+                    { "version": "1.2.0", "channel": "stable", "changelog": "- Support Minecraft 1.21" }
+                ]
+            }
+        })),
+        (status = NOT_FOUND, description = "Repository, package or version not found", body = ApiError),
+        (status = SERVICE_UNAVAILABLE, description = "Repository index not cached", body = ApiError),
+    ),
+)]
+pub async fn handler_get_package_detail(
+    Extension(ctx): Extension<AppContext>,
+    Path((repository_id, name)): Path<(RepositoryId, String)>,
+    Query(queries): Query<PackageDetailQueries>,
+) -> Result<impl IntoResponse, ApiError> {
+    let detail = ctx
+        .catalog_service
+        .detail(
+            &repository_id,
+            &name,
+            queries.version.as_ref(),
+            queries.channel.into(),
+        )
+        .await?;
+
+    Ok(Json(PackageDetailResponse {
+        package: detail.into(),
+    }))
 }
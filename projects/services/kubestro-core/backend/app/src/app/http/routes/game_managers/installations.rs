@@ -0,0 +1,306 @@
+use axum::{
+    extract::{Path, Query},
+    http::StatusCode,
+    response::IntoResponse,
+    Extension, Json,
+};
+use deserr::Deserr;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use validator::{Validate, ValidationError};
+
+use crate::app::{
+    context::AppContext,
+    http::{
+        dto::game_manager_dto::GameManagerDto,
+        helpers::{errors::ApiError, validation::ValidatedJson},
+        middlewares::auth::RequireAuth,
+    },
+};
+use kubestro_core_domain::{
+    models::package::RepositoryId, services::game_managers::InstallGameManager,
+};
+
+use super::GAME_MANAGER_TAG;
+
+/// Game managers list response
+#[derive(Serialize, ToSchema)]
+pub(super) struct GameManagersListResponse {
+    game_managers: Vec<GameManagerDto>,
+}
+
+#[utoipa::path(
+    method(get),
+    path = "/api/v1.0/game-managers",
+    summary = "List the installed game managers",
+    description = "List the game managers installed on the cluster, with the state of their pods \
+        and the catalog package they were installed from",
+    tag = GAME_MANAGER_TAG,
+
+    responses(
+        (status = OK, description = "Installed game managers", body = GameManagersListResponse, example = json!({
+            "game_managers": [
+                {
+                    "id": "minecraft",
+                    "repository_id": "321a07de-7717-49a8-9b28-a6858503bef3",
+                    "package": "minecraft",
+                    "version": "1.2.0",
+                    "image": "ghcr.io/kubestro/minecraft:1.2.0",
+                    "values": {
+                        "motd": "Hello",
+                    },
+                    "created_at": "2025-05-18T10:00:00Z",
+                    "game_servers": [
+                        { "group": "minecraft.kubestro.io", "version": "v1", "kind": "MinecraftServer" },
+                    ],
+                    "status": {
+                        "phase": "available",
+                        "conditions": [
+                            {
+                                "type": "Available",
+                                "status": true,
+                                "reason": "Deployed",
+                                "message": "The game manager is deployed",
+                                "last_transition_time": "2025-05-18T10:01:12Z",
+                            },
+                        ],
+                    },
+                    "workload": {
+                        "replicas": 1,
+                        "ready_replicas": 1,
+                        "image": "ghcr.io/kubestro/minecraft:1.2.0",
+                        "restarts": 0,
+                    },
+                    "catalog": {
+                        "id": "minecraft@1.2.0",
+                        "name": "minecraft",
+                        "version": "1.2.0",
+                        "channel": "stable",
+                        "description": "Minecraft servers manager",
+                        "game": "minecraft",
+                        "tags": ["sandbox"],
+                    },
+                }
+            ]
+        })),
+        (status = SERVICE_UNAVAILABLE, description = "No Kubernetes cluster is available", body = ApiError),
+    ),
+)]
+pub async fn handler_get_game_managers(
+    Extension(ctx): Extension<AppContext>,
+) -> Result<impl IntoResponse, ApiError> {
+    let game_managers = ctx
+        .game_managers
+        .list()
+        .await?
+        .into_iter()
+        .map(Into::into)
+        .collect();
+
+    Ok(Json(GameManagersListResponse { game_managers }))
+}
+
+/// Game manager response
+#[derive(Serialize, ToSchema)]
+pub(super) struct GameManagerResponse {
+    game_manager: GameManagerDto,
+}
+
+#[utoipa::path(
+    method(get),
+    path = "/api/v1.0/game-managers/{id}",
+    summary = "Get an installed game manager",
+    description = "Get a game manager installed on the cluster, with the state of its pods \
+        and the catalog package it was installed from",
+    tag = GAME_MANAGER_TAG,
+
+    params(
+        ("id" = String, Path, description = "Game manager name"),
+    ),
+    responses(
+        (status = OK, description = "Game manager", body = GameManagerResponse),
+        (status = NOT_FOUND, description = "Game manager not found", body = ApiError),
+        (status = SERVICE_UNAVAILABLE, description = "No Kubernetes cluster is available", body = ApiError),
+    ),
+)]
+pub async fn handler_get_game_manager(
+    Extension(ctx): Extension<AppContext>,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    let game_manager = ctx.game_managers.get(&id).await?;
+
+    Ok(Json(GameManagerResponse {
+        game_manager: game_manager.into(),
+    }))
+}
+
+/// Uninstall a game manager queries
+#[derive(Deserialize, IntoParams)]
+pub(super) struct UninstallGameManagerQueries {
+    /// Delete the game servers of the game manager along with it
+    #[serde(default)]
+    cascade: bool,
+}
+
+#[utoipa::path(
+    method(delete),
+    path = "/api/v1.0/game-managers/{id}",
+    summary = "Uninstall a game manager",
+    description = "Uninstall a game manager from the cluster. A game manager still owning game \
+        servers is only uninstalled when `cascade` is set, its game servers being deleted first.",
+    tag = GAME_MANAGER_TAG,
+
+    params(
+        ("id" = String, Path, description = "Game manager name"),
+        UninstallGameManagerQueries,
+    ),
+    responses(
+        (status = NO_CONTENT, description = "Game manager uninstalling"),
+        (status = NOT_FOUND, description = "Game manager not found", body = ApiError),
+        (status = CONFLICT, description = "The game manager still owns game servers", body = ApiError, example = json!({
+            "status": 409,
+            "title": "Conflict",
+            "detail": "Game manager `minecraft` still owns 2 game server(s)",
+            "code": "GAME_MANAGER_HAS_GAME_SERVERS",
+            "fields": {
+                "game_servers": 2
+            }
+        })),
+        (status = SERVICE_UNAVAILABLE, description = "No Kubernetes cluster is available", body = ApiError),
+    ),
+)]
+pub async fn handler_uninstall_game_manager(
+    Extension(ctx): Extension<AppContext>,
+    Path(id): Path<String>,
+    Query(queries): Query<UninstallGameManagerQueries>,
+) -> Result<impl IntoResponse, ApiError> {
+    ctx.game_managers.uninstall(&id, queries.cascade).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+fn validate_repository_id(value: &str) -> Result<(), ValidationError> {
+    uuid::Uuid::parse_str(value)
+        .map(|_| ())
+        .map_err(|_| ValidationError::new("uuid"))
+}
+
+pub(super) fn validate_version(value: &str) -> Result<(), ValidationError> {
+    semver::Version::parse(value)
+        .map(|_| ())
+        .map_err(|_| ValidationError::new("semver"))
+}
+
+/// Install a game manager payload
+#[derive(Deserialize, Deserr, Validate, ToSchema, Debug)]
+pub(super) struct InstallGameManagerPayload {
+    /// Repository offering the package
+    #[validate(custom(function = "validate_repository_id", message = "Invalid repository id"))]
+    pub repository_id: String,
+    /// Name of the package in the repository
+    #[validate(length(min = 1, message = "Package name cannot be empty"))]
+    pub package: String,
+    /// Version of the package to install
+    #[validate(custom(function = "validate_version", message = "Invalid version"))]
+    pub version: String,
+    /// Name of the game manager, the name of the package by default
+    pub name: Option<String>,
+    /// Configuration values, checked against the schema published by the package
+    #[schema(value_type = Option<Object>)]
+    pub values: Option<serde_json::Value>,
+}
+
+#[utoipa::path(
+    method(post),
+    path = "/api/v1.0/game-managers",
+    summary = "Install a game manager",
+    description = "Install a version of a package of the catalog on the cluster. \
+        The values are checked against the schema published by the package, then the game manager \
+        is deployed in the background: follow its status to know when it is available.",
+    tag = GAME_MANAGER_TAG,
+
+    request_body(content = InstallGameManagerPayload, description = "Package to install", example = json!({
+        "repository_id": "321a07de-7717-49a8-9b28-a6858503bef3",
+        "package": "minecraft",
+        "version": "1.2.0",
+        "values": {
+            "motd": "Hello",
+        },
+    })),
+    responses(
+        (status = CREATED, description = "Game manager installed", body = GameManagerResponse, example = json!({
+            "game_manager": {
+                "id": "minecraft",
+                "repository_id": "321a07de-7717-49a8-9b28-a6858503bef3",
+                "package": "minecraft",
+                "version": "1.2.0",
+                "image": "ghcr.io/kubestro/minecraft:1.2.0",
+                "values": {
+                    "motd": "Hello",
+                },
+                "created_at": "2025-05-18T10:00:00Z",
+                "game_servers": [
+                    { "group": "minecraft.kubestro.io", "version": "v1", "kind": "MinecraftServer" },
+                ],
+                "status": {
+                    "phase": "pending",
+                    "conditions": [],
+                },
+                "workload": null,
+                "catalog": {
+                    "id": "minecraft@1.2.0",
+                    "name": "minecraft",
+                    "version": "1.2.0",
+                    "channel": "stable",
+                    "description": "Minecraft servers manager",
+                    "game": "minecraft",
+                    "tags": ["sandbox"],
+                },
+            }
+        })),
+        (status = NOT_FOUND, description = "Repository, package or version not found", body = ApiError),
+        (status = CONFLICT, description = "A game manager with the same name is already installed", body = ApiError),
+        (status = UNPROCESSABLE_ENTITY, description = "Invalid values, or the package cannot be installed", body = ApiError, example = json!({
+            "status": 422,
+            "title": "Validation error",
+            "detail": "The configuration values do not match the schema of the package",
+            "code": "VALIDATION_ERROR",
+            "errors": {
+                "#/values/motd": {
+                    "code": "schema",
+                    "detail": "42 is not of type \"string\""
+                }
+            }
+        })),
+        (status = SERVICE_UNAVAILABLE, description = "No Kubernetes cluster is available", body = ApiError),
+    ),
+)]
+pub async fn handler_install_game_manager(
+    Extension(ctx): Extension<AppContext>,
+    Extension(RequireAuth(user)): Extension<RequireAuth>,
+    ValidatedJson(payload): ValidatedJson<InstallGameManagerPayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    let repository_id = uuid::Uuid::parse_str(&payload.repository_id)
+        .map(RepositoryId::from)
+        .map_err(ApiError::unexpected_error)?;
+    let version = semver::Version::parse(&payload.version).map_err(ApiError::unexpected_error)?;
+
+    let game_manager = ctx
+        .game_managers
+        .install(InstallGameManager {
+            name: payload.name,
+            repository_id,
+            package: payload.package,
+            version,
+            values: payload.values,
+            user: Some(user.username.to_string()),
+        })
+        .await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(GameManagerResponse {
+            game_manager: game_manager.into(),
+        }),
+    ))
+}
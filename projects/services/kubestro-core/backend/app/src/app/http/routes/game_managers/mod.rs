@@ -1,8 +1,13 @@
 use utoipa::OpenApi;
 use utoipa_axum::{router::OpenApiRouter, routes};
 
+mod bundles;
 mod catalog;
+mod installations;
+mod refresh;
 mod repositories;
+mod repositories_file;
+mod revisions;
 
 pub(super) const GAME_MANAGER_TAG: &str = "game-managers";
 
@@ -18,13 +23,49 @@ pub fn get_routes() -> OpenApiRouter {
     let repositories_routes = OpenApiRouter::new().routes(routes!(
         repositories::handler_get_repositories,
         repositories::handler_add_repository,
+        repositories::handler_update_repository,
         repositories::handler_delete_repository,
     ));
 
-    let catalog_routes =
-        OpenApiRouter::new().routes(routes!(catalog::handler_get_game_managers_catalog));
+    let repositories_file_routes = OpenApiRouter::new()
+        .routes(routes!(repositories_file::handler_export_repositories))
+        .routes(routes!(repositories_file::handler_import_repositories));
+
+    let bundles_routes = OpenApiRouter::new()
+        .routes(routes!(bundles::handler_export_bundle))
+        .routes(routes!(bundles::handler_upload_bundle));
+
+    let refresh_routes = OpenApiRouter::new()
+        .routes(routes!(refresh::handler_refresh_repository))
+        .routes(routes!(refresh::handler_refresh_repositories))
+        .routes(routes!(refresh::handler_get_refresh_job));
+
+    let catalog_routes = OpenApiRouter::new()
+        .routes(routes!(catalog::handler_get_game_managers_catalog))
+        .routes(routes!(catalog::handler_get_package_detail))
+        .routes(routes!(catalog::handler_get_package_versions));
+
+    let installations_routes = OpenApiRouter::new()
+        .routes(routes!(
+            installations::handler_get_game_managers,
+            installations::handler_install_game_manager,
+        ))
+        .routes(routes!(
+            installations::handler_get_game_manager,
+            installations::handler_uninstall_game_manager,
+        ));
+
+    let revisions_routes = OpenApiRouter::new()
+        .routes(routes!(revisions::handler_get_game_manager_revisions))
+        .routes(routes!(revisions::handler_upgrade_game_manager))
+        .routes(routes!(revisions::handler_rollback_game_manager));
 
     OpenApiRouter::with_openapi(ApiDoc::openapi())
         .merge(repositories_routes)
+        .merge(repositories_file_routes)
+        .merge(bundles_routes)
+        .merge(refresh_routes)
         .merge(catalog_routes)
+        .merge(installations_routes)
+        .merge(revisions_routes)
 }
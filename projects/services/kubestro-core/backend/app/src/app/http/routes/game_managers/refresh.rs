@@ -0,0 +1,190 @@
+use std::time::Duration;
+
+use axum::{
+    extract::{Path, Query},
+    http::StatusCode,
+    response::IntoResponse,
+    Extension, Json,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::app::{
+    context::AppContext,
+    http::{dto::refresh_job_dto::RefreshJobDto, helpers::errors::ApiError},
+};
+use kubestro_core_domain::{
+    models::package::RepositoryId,
+    services::refresh_jobs::{RefreshJob, RefreshJobId, RefreshJobStatus},
+};
+
+use super::GAME_MANAGER_TAG;
+
+/// Default time to wait for a job to complete, in seconds
+const DEFAULT_WAIT_TIMEOUT: u64 = 30;
+/// Maximum time to wait for a job to complete, in seconds
+const MAX_WAIT_TIMEOUT: u64 = 120;
+
+/// Refresh job queries
+#[derive(Deserialize, IntoParams)]
+pub(super) struct RefreshJobQueries {
+    /// Wait for the job to complete before answering
+    #[serde(default)]
+    wait: bool,
+    /// Maximum time to wait for the job to complete, in seconds
+    #[param(maximum = 120)]
+    #[serde(default)]
+    timeout: Option<u64>,
+}
+
+impl RefreshJobQueries {
+    fn timeout(&self) -> Option<Duration> {
+        self.wait.then(|| {
+            Duration::from_secs(
+                self.timeout
+                    .unwrap_or(DEFAULT_WAIT_TIMEOUT)
+                    .min(MAX_WAIT_TIMEOUT),
+            )
+        })
+    }
+}
+
+/// Refresh job response
+#[derive(Serialize, ToSchema)]
+pub(super) struct RefreshJobResponse {
+    job: RefreshJobDto,
+}
+
+/// Wait for the job if requested, and answer with `202 Accepted` while it is still running
+async fn respond(
+    ctx: &AppContext,
+    job: RefreshJob,
+    queries: &RefreshJobQueries,
+) -> Result<impl IntoResponse, ApiError> {
+    let job = match queries.timeout() {
+        Some(timeout) => ctx
+            .refresh_jobs
+            .wait(&job.id, timeout)
+            .await?
+            .unwrap_or(job),
+        None => job,
+    };
+
+    let status = match job.status {
+        RefreshJobStatus::Running => StatusCode::ACCEPTED,
+        RefreshJobStatus::Completed => StatusCode::OK,
+    };
+
+    Ok((status, Json(RefreshJobResponse { job: job.into() })))
+}
+
+/// Refresh a repository handler
+#[utoipa::path(
+    method(post),
+    path = "/api/v1.0/game-managers/repositories/{id}/refresh",
+    summary = "Refresh a repository",
+    description = "Download the index of a repository again, bypassing the cache. \
+        The refresh runs in the background unless `wait` is set.",
+    tag = GAME_MANAGER_TAG,
+
+    params(
+        ("id" = String, Path, description = "Repository database id"),
+        RefreshJobQueries,
+    ),
+    responses(
+        (status = ACCEPTED, description = "Refresh started", body = RefreshJobResponse, example = json!({
+            "job": {
+                "id": "0b7e3a5e-8f0c-4a59-a3cb-1d7a8e2f6c11",
+                "status": "running",
+                "started_at": "2025-03-15T10:12:03Z",
+                "finished_at": null,
+                "repositories": [
+                    {
+                        "id": "321a07de-7717-49a8-9b28-a6858503bef3",
+                        "name": "Demo",
+                        "kind": "http",
+                        "url": "https://example.com/repository",
+                        "git": null,
+                        "priority": 0,
+                        "managed": false,
+                        "sync": {
+                            "status": "ok",
+                            "last_synced_at": "2025-03-15T09:12:03Z",
+                            "last_error": null,
+                            "package_count": 12,
+                            "index_version": "1.0",
+                            "revision": null,
+                            "conflicts": [],
+                        },
+                    }
+                ],
+            }
+        })),
+        (status = OK, description = "Refresh completed", body = RefreshJobResponse),
+        (status = NOT_FOUND, description = "Repository not found", body = ApiError),
+    ),
+)]
+pub async fn handler_refresh_repository(
+    Extension(ctx): Extension<AppContext>,
+    Path(id): Path<RepositoryId>,
+    Query(queries): Query<RefreshJobQueries>,
+) -> Result<impl IntoResponse, ApiError> {
+    let job = ctx.refresh_jobs.refresh(&id).await?;
+
+    respond(&ctx, job, &queries).await
+}
+
+/// Refresh all repositories handler
+#[utoipa::path(
+    method(post),
+    path = "/api/v1.0/game-managers/repositories/refresh",
+    summary = "Refresh all repositories",
+    description = "Download the index of every repository again, bypassing the cache. \
+        The refresh runs in the background unless `wait` is set.",
+    tag = GAME_MANAGER_TAG,
+
+    params(RefreshJobQueries),
+    responses(
+        (status = ACCEPTED, description = "Refresh started", body = RefreshJobResponse),
+        (status = OK, description = "Refresh completed", body = RefreshJobResponse),
+    ),
+)]
+pub async fn handler_refresh_repositories(
+    Extension(ctx): Extension<AppContext>,
+    Query(queries): Query<RefreshJobQueries>,
+) -> Result<impl IntoResponse, ApiError> {
+    let job = ctx.refresh_jobs.refresh_all().await?;
+
+    respond(&ctx, job, &queries).await
+}
+
+/// Get a refresh job handler
+#[utoipa::path(
+    method(get),
+    path = "/api/v1.0/game-managers/repositories/refresh-jobs/{id}",
+    summary = "Get a refresh job",
+    description = "Get the state of a repositories refresh. Set `wait` to wait for its completion.",
+    tag = GAME_MANAGER_TAG,
+
+    params(
+        ("id" = String, Path, description = "Refresh job id"),
+        RefreshJobQueries,
+    ),
+    responses(
+        (status = ACCEPTED, description = "Refresh still running", body = RefreshJobResponse),
+        (status = OK, description = "Refresh completed", body = RefreshJobResponse),
+        (status = NOT_FOUND, description = "Refresh job not found or expired", body = ApiError),
+    ),
+)]
+pub async fn handler_get_refresh_job(
+    Extension(ctx): Extension<AppContext>,
+    Path(id): Path<RefreshJobId>,
+    Query(queries): Query<RefreshJobQueries>,
+) -> Result<impl IntoResponse, ApiError> {
+    let job = ctx
+        .refresh_jobs
+        .get(&id)?
+        .ok_or_else(|| ApiError::not_found("Refresh job not found"))?;
+
+    respond(&ctx, job, &queries).await
+}
@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use axum::{
     extract::{Path, Query},
     http::StatusCode,
@@ -17,7 +19,13 @@ use crate::app::{
     },
 };
 use kubestro_core_domain::{
-    models::package::{CreateRepository, RepositoryId},
+    models::{
+        fields::trusted_key::{TrustedKey, TrustedKeyError},
+        package::{
+            CreateRepository, GitSource, RepositoryCredentials, RepositoryCredentialsError,
+            RepositoryId, RepositoryKind, UpdateRepository,
+        },
+    },
     ports::repositories::repositories_repositories::RepositoriesRepository,
 };
 
@@ -41,7 +49,8 @@ pub(super) struct RepositoriesListQueries {
     method(get),
     path = "/api/v1.0/game-managers/repositories",
     summary = "Get managers repositories list",
-    description = "Get the repositories list for game managers",
+    description = "Get the repositories list for game managers, along with the synchronization state of their index \
+        and its conflicts with the other repositories",
     tag = GAME_MANAGER_TAG,
 
     responses(
@@ -50,7 +59,22 @@ pub(super) struct RepositoriesListQueries {
                 {
                     "id": "321a07de-7717-49a8-9b28-a6858503bef3",
                     "name": "Demo",
+                    "kind": "http",
                     "url": "https://example.com/repository",
+                    "git": null,
+                    "trusted_keys": ["RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3"],
+                    "credentials": "bearer",
+                    "priority": 0,
+                    "managed": false,
+                    "sync": {
+                        "status": "failed",
+                        "last_synced_at": "2025-03-15T10:12:03Z",
+                        "last_error": "Failed to fetch remote data: 404 Not Found",
+                        "package_count": 12,
+                        "index_version": "1.0",
+                        "revision": null,
+                        "conflicts": [],
+                    },
                 }
             ]
         })),
@@ -60,17 +84,102 @@ pub async fn handler_get_repositories(
     Extension(ctx): Extension<AppContext>,
     Query(queries): Query<RepositoriesListQueries>,
 ) -> Result<impl IntoResponse, ApiError> {
+    let conflicts = ctx.catalog_service.conflicts().await?;
     let repositories = ctx
         .repository_repo
         .find_all(queries.search)
         .await?
         .iter()
-        .map(RepositoryDto::from)
+        .map(|repository| {
+            RepositoryDto::from(repository).with_conflicts(&repository.id, &conflicts)
+        })
         .collect();
 
     Ok(Json(RepositoriesListResponse { repositories }))
 }
 
+fn parse_trusted_keys(trusted_keys: Vec<String>) -> Result<Vec<TrustedKey>, TrustedKeyError> {
+    trusted_keys.into_iter().map(TrustedKey::try_from).collect()
+}
+
+/// Source of a repository index
+#[derive(Deserialize, Deserr, ToSchema, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+#[deserr(rename_all = lowercase)]
+pub(super) enum RepositoryKindPayload {
+    /// A JSON index served over HTTP
+    #[default]
+    Http,
+    /// An index artifact stored in an OCI registry, referenced as `oci://<registry>/<name>[:<tag>]`
+    Oci,
+    /// An index file committed in a Git repository, see [`GitSourcePayload`]
+    Git,
+    /// An index read from an uploaded bundle, referenced as `bundle://<name>`
+    Bundle,
+}
+
+/// Location of the index in a Git repository
+#[derive(Deserialize, Deserr, Validate, ToSchema, Debug)]
+pub(super) struct GitSourcePayload {
+    /// Branch or tag to read the index from, the default branch when omitted
+    #[validate(length(min = 1, message = "Git reference cannot be empty"))]
+    pub reference: Option<String>,
+    /// Path of the index file in the repository, `index.json` when omitted
+    #[validate(length(min = 1, message = "Index path cannot be empty"))]
+    pub path: Option<String>,
+}
+
+/// Build the repository kind, the Git source is ignored by the other kinds
+fn repository_kind(kind: RepositoryKindPayload, git: Option<GitSourcePayload>) -> RepositoryKind {
+    match kind {
+        RepositoryKindPayload::Http => RepositoryKind::Http,
+        RepositoryKindPayload::Oci => RepositoryKind::Oci,
+        RepositoryKindPayload::Git => {
+            let default = GitSource::default();
+            let git = git.unwrap_or(GitSourcePayload {
+                reference: None,
+                path: None,
+            });
+
+            RepositoryKind::Git(GitSource {
+                reference: git.reference.unwrap_or(default.reference),
+                path: git.path.unwrap_or(default.path),
+            })
+        }
+        RepositoryKindPayload::Bundle => RepositoryKind::Bundle,
+    }
+}
+
+/// Credentials of a private repository
+#[derive(Deserialize, Deserr, ToSchema, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+#[deserr(tag = "type", rename_all = lowercase)]
+pub(super) enum RepositoryCredentialsPayload {
+    /// HTTP basic authentication
+    Basic { username: String, password: String },
+    /// Bearer token, sent in the `Authorization` header
+    Bearer { token: String },
+    /// Arbitrary headers, e.g. an API key
+    Headers { headers: BTreeMap<String, String> },
+}
+
+fn parse_credentials(
+    credentials: RepositoryCredentialsPayload,
+) -> Result<RepositoryCredentials, RepositoryCredentialsError> {
+    let credentials = match credentials {
+        RepositoryCredentialsPayload::Basic { username, password } => {
+            RepositoryCredentials::Basic { username, password }
+        }
+        RepositoryCredentialsPayload::Bearer { token } => RepositoryCredentials::Bearer { token },
+        RepositoryCredentialsPayload::Headers { headers } => {
+            RepositoryCredentials::Headers { headers }
+        }
+    };
+    credentials.validate()?;
+
+    Ok(credentials)
+}
+
 /// Add a new repository payload
 #[derive(Deserialize, Deserr, Validate, ToSchema, Debug)]
 pub(super) struct AddRepositoryPayload {
@@ -79,8 +188,27 @@ pub(super) struct AddRepositoryPayload {
         message = "Repository name must be at least 3 characters long"
     ))]
     pub name: String,
+    /// Source of the repository index, `http` by default
+    #[serde(default)]
+    #[deserr(default)]
+    pub kind: RepositoryKindPayload,
+    /// Location of the index, for the `git` repositories
+    #[validate(nested)]
+    pub git: Option<GitSourcePayload>,
     #[validate(url(message = "Invalid repository URL"))]
     pub url: String,
+    /// Minisign public keys allowed to sign the repository index
+    #[serde(default)]
+    #[deserr(default)]
+    pub trusted_keys: Vec<String>,
+    /// Credentials sent along every request to the repository, they are never returned
+    #[serde(default)]
+    #[deserr(default)]
+    pub credentials: Option<RepositoryCredentialsPayload>,
+    /// When several repositories publish the same package, the highest priority wins
+    #[serde(default)]
+    #[deserr(default)]
+    pub priority: i32,
 }
 
 /// Add a new repository response
@@ -94,7 +222,9 @@ pub(super) struct AddRepositoryResponse {
     method(post),
     path = "/api/v1.0/game-managers/repositories",
     summary = "Add a new repository",
-    description = "Add a new repository to the game managers list",
+    description = "Add a new repository to the game managers list. The url must be allowed by the \
+        outbound policy: its scheme and host are checked, and its host must not resolve to a \
+        private address unless allowed.",
     tag = GAME_MANAGER_TAG,
 
     request_body(content = AddRepositoryPayload, content_type = "application/json"),
@@ -102,7 +232,22 @@ pub(super) struct AddRepositoryResponse {
         (status = CREATED, description = "Repository added", body = RepositoryDto, example = json!({
             "id": "1",
             "name": "repository",
-            "url": "https://example.com/repository",
+            "kind": "oci",
+            "url": "oci://ghcr.io/example/index:1.0",
+            "git": null,
+            "trusted_keys": ["RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3"],
+            "credentials": null,
+            "priority": 0,
+            "managed": false,
+            "sync": {
+                "status": "pending",
+                "last_synced_at": null,
+                "last_error": null,
+                "package_count": null,
+                "index_version": null,
+                "revision": null,
+                "conflicts": [],
+            },
         })),
 
         (status = UNPROCESSABLE_ENTITY, description = "Invalid input data", body = ApiError, example = json!({
@@ -132,7 +277,12 @@ pub async fn handler_add_repository(
 ) -> Result<impl IntoResponse, ApiError> {
     let repo_data = CreateRepository {
         name: payload.name,
+        kind: repository_kind(payload.kind, payload.git),
         url: payload.url,
+        trusted_keys: parse_trusted_keys(payload.trusted_keys)?,
+        credentials: payload.credentials.map(parse_credentials).transpose()?,
+        priority: payload.priority,
+        managed: false,
     };
 
     let repository: RepositoryDto = ctx.repository_service.create(repo_data).await?.into();
@@ -143,12 +293,138 @@ pub async fn handler_add_repository(
     ))
 }
 
+/// Update a repository payload
+///
+/// Only the given fields are updated.
+#[derive(Deserialize, Deserr, Validate, ToSchema, Debug)]
+pub(super) struct UpdateRepositoryPayload {
+    #[validate(length(
+        min = 3,
+        message = "Repository name must be at least 3 characters long"
+    ))]
+    pub name: Option<String>,
+    pub kind: Option<RepositoryKindPayload>,
+    /// Location of the index, for the `git` repositories, only used along with `kind`
+    #[validate(nested)]
+    pub git: Option<GitSourcePayload>,
+    #[validate(url(message = "Invalid repository URL"))]
+    pub url: Option<String>,
+    /// Minisign public keys allowed to sign the repository index, replacing the current ones
+    pub trusted_keys: Option<Vec<String>>,
+    /// Credentials replacing the current ones, `null` removes them
+    #[serde(default)]
+    #[deserr(default, from(Option<RepositoryCredentialsPayload>) = Some)]
+    #[schema(nullable)]
+    pub credentials: Option<Option<RepositoryCredentialsPayload>>,
+    /// Priority of the repository over the others publishing the same packages
+    pub priority: Option<i32>,
+}
+
+/// Update a repository response
+#[derive(Serialize, ToSchema)]
+pub(super) struct UpdateRepositoryResponse {
+    repository: RepositoryDto,
+}
+
+/// Update a repository handler
+#[utoipa::path(
+    method(patch),
+    path = "/api/v1.0/game-managers/repositories/{id}",
+    summary = "Update a repository",
+    description = "Update the name, the kind, the url, the trusted keys, the credentials or the \
+        priority of a repository. Changing anything but the name and the priority drops the cached \
+        index and fetches it again. The repositories managed by the configuration cannot be \
+        updated.",
+    tag = GAME_MANAGER_TAG,
+
+    params(
+        ("id" = String, Path, description = "Repository database id")
+    ),
+    request_body(content = UpdateRepositoryPayload, content_type = "application/json"),
+    responses(
+        (status = OK, description = "Repository updated", body = UpdateRepositoryResponse, example = json!({
+            "repository": {
+                "id": "321a07de-7717-49a8-9b28-a6858503bef3",
+                "name": "Demo",
+                "kind": "http",
+                "url": "https://example.com/new-repository",
+                "git": null,
+                "trusted_keys": ["RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3"],
+                "credentials": "basic",
+                "priority": 0,
+                "managed": false,
+                "sync": {
+                    "status": "pending",
+                    "last_synced_at": null,
+                    "last_error": null,
+                    "package_count": null,
+                    "index_version": null,
+                    "revision": null,
+                    "conflicts": [],
+                },
+            }
+        })),
+
+        (status = NOT_FOUND, description = "Repository not found", body = ApiError),
+
+        (status = FORBIDDEN, description = "Repository managed by the configuration", body = ApiError, example = json!({
+            "status": 403,
+            "title": "Managed repository",
+            "detail": "The repository `Demo` is managed by the configuration and cannot be changed",
+            "code": "REPOSITORY_MANAGED"
+        })),
+
+        (status = UNPROCESSABLE_ENTITY, description = "Invalid input data", body = ApiError, example = json!({
+            "status": 422,
+            "title": "Validation error",
+            "detail": "The request body is invalid",
+            "code": "VALIDATION_ERROR",
+            "errors": {
+                "#/url": {
+                "code": "url",
+                "detail": "Invalid repository URL"
+                }
+            }
+        })),
+
+        (status = CONFLICT, description = "Another repository already uses this url", body = ApiError, example = json!({
+            "status": 409,
+            "title": "Conflict",
+            "detail": "This repository already exists",
+            "code": "REPO_ALREADY_EXISTS"
+        })),
+    ),
+)]
+pub async fn handler_update_repository(
+    Extension(ctx): Extension<AppContext>,
+    Path(id): Path<RepositoryId>,
+    ValidatedJson(payload): ValidatedJson<UpdateRepositoryPayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    let repo_data = UpdateRepository {
+        name: payload.name,
+        kind: payload.kind.map(|kind| repository_kind(kind, payload.git)),
+        url: payload.url,
+        trusted_keys: payload.trusted_keys.map(parse_trusted_keys).transpose()?,
+        credentials: payload
+            .credentials
+            .map(|credentials| credentials.map(parse_credentials).transpose())
+            .transpose()?,
+        priority: payload.priority,
+        managed: None,
+    };
+
+    let repository: RepositoryDto = ctx.repository_service.update(&id, repo_data).await?.into();
+
+    Ok(Json(UpdateRepositoryResponse { repository }))
+}
+
 /// Delete a repository handler
 #[utoipa::path(
     method(delete),
     path = "/api/v1.0/game-managers/repositories/{id}",
     summary = "Delete a repository",
-    description = "Delete a repository from the game managers list",
+    description = "Delete a repository from the game managers list, unless it is managed by the \
+        configuration",
     tag = GAME_MANAGER_TAG,
 
     params(
@@ -156,6 +432,13 @@ pub async fn handler_add_repository(
     ),
     responses(
         (status = NO_CONTENT, description = "Repository deleted"),
+
+        (status = FORBIDDEN, description = "Repository managed by the configuration", body = ApiError, example = json!({
+            "status": 403,
+            "title": "Managed repository",
+            "detail": "The repository `Demo` is managed by the configuration and cannot be changed",
+            "code": "REPOSITORY_MANAGED"
+        })),
     ),
 )]
 pub async fn handler_delete_repository(
@@ -0,0 +1,227 @@
+use axum::{
+    extract::Query,
+    http::header,
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::app::{
+    context::AppContext,
+    http::{dto::repositories_file_dto::RepositoriesFileDto, helpers::errors::ApiError},
+};
+use kubestro_core_domain::services::repositories_import::{AppliedChange, RepositoryChange};
+
+use super::GAME_MANAGER_TAG;
+
+/// Format of an exported repositories file
+#[derive(Debug, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub(super) enum RepositoriesFileFormat {
+    #[default]
+    Yaml,
+    Json,
+}
+
+/// Repositories export queries
+#[derive(Deserialize, IntoParams)]
+pub(super) struct ExportRepositoriesQueries {
+    #[serde(default)]
+    format: RepositoriesFileFormat,
+}
+
+#[utoipa::path(
+    method(get),
+    path = "/api/v1.0/game-managers/repositories/export",
+    summary = "Export the repositories",
+    description = "Export every repository as a declarative file, to import it in another instance. \
+        The credentials are never exported.",
+    tag = GAME_MANAGER_TAG,
+
+    params(ExportRepositoriesQueries),
+    responses(
+        (status = OK, description = "Repositories file", body = RepositoriesFileDto, example = json!({
+            "version": 1,
+            "repositories": [
+                {
+                    "name": "Demo",
+                    "kind": "http",
+                    "url": "https://example.com/repository",
+                    "trusted_keys": ["RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3"],
+                    "priority": 0
+                }
+            ]
+        })),
+    ),
+)]
+pub async fn handler_export_repositories(
+    Extension(ctx): Extension<AppContext>,
+    Query(queries): Query<ExportRepositoriesQueries>,
+) -> Result<Response, ApiError> {
+    let file = RepositoriesFileDto::from(ctx.repositories_import.export().await?);
+
+    let response = match queries.format {
+        RepositoriesFileFormat::Json => Json(file).into_response(),
+        RepositoriesFileFormat::Yaml => (
+            [(header::CONTENT_TYPE, "application/yaml")],
+            serde_yaml::to_string(&file).map_err(ApiError::unexpected_error)?,
+        )
+            .into_response(),
+    };
+
+    Ok(response)
+}
+
+/// Repositories import queries
+#[derive(Deserialize, IntoParams)]
+pub(super) struct ImportRepositoriesQueries {
+    /// Only compute the changes, without applying them
+    #[serde(default)]
+    dry_run: bool,
+    /// Delete the repositories missing from the file
+    #[serde(default)]
+    prune: bool,
+}
+
+/// Action taken on a repository by an import
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+enum RepositoryChangeAction {
+    Create,
+    Update,
+    Delete,
+    Unchanged,
+}
+
+/// A change applied, or planned, by an import
+#[derive(Debug, Serialize, ToSchema)]
+struct RepositoryChangeDto {
+    action: RepositoryChangeAction,
+    /// Id of the repository, unknown for the repositories to create
+    id: Option<String>,
+    name: String,
+    url: String,
+    /// Names of the updated fields
+    fields: Vec<String>,
+    /// Why the change could not be applied
+    error: Option<String>,
+}
+
+impl From<RepositoryChange> for RepositoryChangeDto {
+    fn from(change: RepositoryChange) -> Self {
+        let (action, id, fields) = match &change {
+            RepositoryChange::Create(_) => (RepositoryChangeAction::Create, None, vec![]),
+            RepositoryChange::Update {
+                repository, fields, ..
+            } => (
+                RepositoryChangeAction::Update,
+                Some(repository.id.to_string()),
+                fields.iter().map(ToString::to_string).collect(),
+            ),
+            RepositoryChange::Delete(repository) => (
+                RepositoryChangeAction::Delete,
+                Some(repository.id.to_string()),
+                vec![],
+            ),
+            RepositoryChange::Unchanged(repository) => (
+                RepositoryChangeAction::Unchanged,
+                Some(repository.id.to_string()),
+                vec![],
+            ),
+        };
+
+        Self {
+            action,
+            id,
+            name: change.name().to_string(),
+            url: change.url().to_string(),
+            fields,
+            error: None,
+        }
+    }
+}
+
+impl From<AppliedChange> for RepositoryChangeDto {
+    fn from(applied: AppliedChange) -> Self {
+        let mut change = Self::from(applied.change);
+        change.id = applied.id.map(|id| id.to_string()).or(change.id);
+        change.error = applied.error;
+        change
+    }
+}
+
+/// Repositories import response
+#[derive(Serialize, ToSchema)]
+pub(super) struct ImportRepositoriesResponse {
+    dry_run: bool,
+    changes: Vec<RepositoryChangeDto>,
+}
+
+#[utoipa::path(
+    method(post),
+    path = "/api/v1.0/game-managers/repositories/import",
+    summary = "Import repositories",
+    description = "Create or update the repositories declared in a YAML or JSON repositories file, \
+        identified by their url. Importing the same file twice changes nothing. The repositories \
+        missing from the file are only deleted with `prune`. With `dry_run`, the changes are only \
+        listed. A change that cannot be applied is reported with its error, the others are still \
+        applied.",
+    tag = GAME_MANAGER_TAG,
+
+    params(ImportRepositoriesQueries),
+    request_body(content = RepositoriesFileDto, content_type = "application/yaml"),
+    responses(
+        (status = OK, description = "Changes applied or planned", body = ImportRepositoriesResponse, example = json!({
+            "dry_run": true,
+            "changes": [
+                {
+                    "action": "update",
+                    "id": "321a07de-7717-49a8-9b28-a6858503bef3",
+                    "name": "Demo",
+                    "url": "https://example.com/repository",
+                    "fields": ["trusted_keys"],
+                    "error": null
+                },
+                {
+                    "action": "create",
+                    "id": null,
+                    "name": "Community",
+                    "url": "https://example.com/community",
+                    "fields": [],
+                    "error": null
+                }
+            ]
+        })),
+        (status = BAD_REQUEST, description = "Invalid repositories file", body = ApiError),
+    ),
+)]
+pub async fn handler_import_repositories(
+    Extension(ctx): Extension<AppContext>,
+    Query(queries): Query<ImportRepositoriesQueries>,
+    body: String,
+) -> Result<impl IntoResponse, ApiError> {
+    let declarations = RepositoriesFileDto::parse(&body)?;
+
+    let changes = match queries.dry_run {
+        true => ctx
+            .repositories_import
+            .plan(declarations, queries.prune)
+            .await?
+            .into_iter()
+            .map(Into::into)
+            .collect(),
+        false => ctx
+            .repositories_import
+            .apply(declarations, queries.prune)
+            .await?
+            .into_iter()
+            .map(Into::into)
+            .collect(),
+    };
+
+    Ok(Json(ImportRepositoriesResponse {
+        dry_run: queries.dry_run,
+        changes,
+    }))
+}
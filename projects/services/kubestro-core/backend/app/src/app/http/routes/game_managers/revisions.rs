@@ -0,0 +1,225 @@
+use axum::{extract::Path, http::StatusCode, response::IntoResponse, Extension, Json};
+use deserr::Deserr;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+
+use crate::app::{
+    context::AppContext,
+    http::{
+        dto::game_manager_dto::{GameManagerDto, GameManagerRevisionDto},
+        helpers::{errors::ApiError, validation::ValidatedJson},
+        middlewares::auth::RequireAuth,
+    },
+};
+use kubestro_core_domain::services::game_managers::{
+    DeployedRevision, RollbackGameManager, UpgradeGameManager,
+};
+
+use super::{installations::validate_version, GAME_MANAGER_TAG};
+
+/// Game manager revisions response
+#[derive(Serialize, ToSchema)]
+pub(super) struct GameManagerRevisionsResponse {
+    /// Revisions of the game manager, oldest first
+    revisions: Vec<GameManagerRevisionDto>,
+}
+
+#[utoipa::path(
+    method(get),
+    path = "/api/v1.0/game-managers/{id}/revisions",
+    summary = "List the revisions of a game manager",
+    description = "List the revisions deployed on a game manager by its installation, upgrades and \
+        rollbacks, oldest first",
+    tag = GAME_MANAGER_TAG,
+
+    params(
+        ("id" = String, Path, description = "Game manager name"),
+    ),
+    responses(
+        (status = OK, description = "Revisions of the game manager", body = GameManagerRevisionsResponse, example = json!({
+            "revisions": [
+                {
+                    "revision": 1,
+                    "version": "1.1.0",
+                    "image": "ghcr.io/kubestro/minecraft:1.1.0",
+                    "values": { "motd": "Hello" },
+                    "permissions": [],
+                    "game_servers": [
+                        { "group": "minecraft.kubestro.io", "version": "v1", "kind": "MinecraftServer" },
+                    ],
+                    "cause": "install",
+                    "created_at": "2025-05-18T10:00:00Z",
+                    "created_by": "admin",
+                },
+                {
+                    "revision": 2,
+                    "version": "1.2.0",
+                    "image": "ghcr.io/kubestro/minecraft:1.2.0",
+                    "values": { "motd": "Hello" },
+                    "permissions": [],
+                    "game_servers": [
+                        { "group": "minecraft.kubestro.io", "version": "v1", "kind": "MinecraftServer" },
+                    ],
+                    "cause": "upgrade",
+                    "created_at": "2025-06-02T08:30:00Z",
+                    "created_by": "admin",
+                },
+            ]
+        })),
+        (status = NOT_FOUND, description = "Game manager not found", body = ApiError),
+        (status = SERVICE_UNAVAILABLE, description = "No Kubernetes cluster is available", body = ApiError),
+    ),
+)]
+pub async fn handler_get_game_manager_revisions(
+    Extension(ctx): Extension<AppContext>,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    let revisions = ctx
+        .game_managers
+        .history(&id)
+        .await?
+        .into_iter()
+        .map(Into::into)
+        .collect();
+
+    Ok(Json(GameManagerRevisionsResponse { revisions }))
+}
+
+/// Deployed revision response
+#[derive(Serialize, ToSchema)]
+pub(super) struct DeployedRevisionResponse {
+    game_manager: GameManagerDto,
+    revision: GameManagerRevisionDto,
+}
+
+impl From<DeployedRevision> for DeployedRevisionResponse {
+    fn from(deployed: DeployedRevision) -> Self {
+        Self {
+            game_manager: deployed.game_manager.into(),
+            revision: deployed.revision.into(),
+        }
+    }
+}
+
+/// Upgrade a game manager payload
+#[derive(Deserialize, Deserr, Validate, ToSchema, Debug)]
+pub(super) struct UpgradeGameManagerPayload {
+    /// Version of the package to upgrade to, the installed version to only change the values
+    #[validate(custom(function = "validate_version", message = "Invalid version"))]
+    pub version: Option<String>,
+    /// Configuration values, the current ones by default
+    #[schema(value_type = Option<Object>)]
+    pub values: Option<serde_json::Value>,
+}
+
+#[utoipa::path(
+    method(post),
+    path = "/api/v1.0/game-managers/{id}/upgrade",
+    summary = "Upgrade a game manager",
+    description = "Upgrade a game manager to a newer version offered by its repository, or change \
+        its values. The migration declared by the new version runs first, then the new revision is \
+        deployed in the background: the previous revision is restored if it does not become \
+        available in time.",
+    tag = GAME_MANAGER_TAG,
+
+    params(
+        ("id" = String, Path, description = "Game manager name"),
+    ),
+    request_body(content = UpgradeGameManagerPayload, description = "Version and values to deploy", example = json!({
+        "version": "1.2.0",
+    })),
+    responses(
+        (status = ACCEPTED, description = "Revision deploying", body = DeployedRevisionResponse),
+        (status = NOT_FOUND, description = "Game manager, or version of its package, not found", body = ApiError),
+        (status = UNPROCESSABLE_ENTITY, description = "Invalid values, the version cannot be deployed, or its migration failed", body = ApiError, example = json!({
+            "status": 422,
+            "title": "Downgrade not supported",
+            "detail": "Cannot upgrade game manager `minecraft` from version 1.2.0 to the older 1.1.0, roll it back instead",
+            "code": "DOWNGRADE_NOT_SUPPORTED",
+        })),
+        (status = SERVICE_UNAVAILABLE, description = "No Kubernetes cluster is available", body = ApiError),
+    ),
+)]
+pub async fn handler_upgrade_game_manager(
+    Extension(ctx): Extension<AppContext>,
+    Extension(RequireAuth(user)): Extension<RequireAuth>,
+    Path(id): Path<String>,
+    ValidatedJson(payload): ValidatedJson<UpgradeGameManagerPayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    let version = payload
+        .version
+        .map(|version| semver::Version::parse(&version))
+        .transpose()
+        .map_err(ApiError::unexpected_error)?;
+
+    let deployed = ctx
+        .game_managers
+        .upgrade(
+            &id,
+            UpgradeGameManager {
+                version,
+                values: payload.values,
+                user: Some(user.username.to_string()),
+            },
+        )
+        .await?;
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(DeployedRevisionResponse::from(deployed)),
+    ))
+}
+
+/// Roll a game manager back payload
+#[derive(Deserialize, Deserr, Validate, ToSchema, Debug)]
+pub(super) struct RollbackGameManagerPayload {
+    /// Revision to restore, the one preceding the current revision by default
+    #[validate(range(min = 1, message = "Revisions start at 1"))]
+    pub revision: Option<u32>,
+}
+
+#[utoipa::path(
+    method(post),
+    path = "/api/v1.0/game-managers/{id}/rollback",
+    summary = "Roll a game manager back",
+    description = "Deploy the version and the values of a previous revision of a game manager, as \
+        a new revision. The version must still be offered by the repository. No migration runs.",
+    tag = GAME_MANAGER_TAG,
+
+    params(
+        ("id" = String, Path, description = "Game manager name"),
+    ),
+    request_body(content = RollbackGameManagerPayload, description = "Revision to restore", example = json!({
+        "revision": 1,
+    })),
+    responses(
+        (status = ACCEPTED, description = "Revision deploying", body = DeployedRevisionResponse),
+        (status = NOT_FOUND, description = "Game manager, revision, or version of its package, not found", body = ApiError),
+        (status = CONFLICT, description = "The game manager has no previous revision", body = ApiError),
+        (status = UNPROCESSABLE_ENTITY, description = "The version of the revision cannot be deployed anymore", body = ApiError),
+        (status = SERVICE_UNAVAILABLE, description = "No Kubernetes cluster is available", body = ApiError),
+    ),
+)]
+pub async fn handler_rollback_game_manager(
+    Extension(ctx): Extension<AppContext>,
+    Extension(RequireAuth(user)): Extension<RequireAuth>,
+    Path(id): Path<String>,
+    ValidatedJson(payload): ValidatedJson<RollbackGameManagerPayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    let deployed = ctx
+        .game_managers
+        .rollback(
+            &id,
+            RollbackGameManager {
+                revision: payload.revision,
+                user: Some(user.username.to_string()),
+            },
+        )
+        .await?;
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(DeployedRevisionResponse::from(deployed)),
+    ))
+}
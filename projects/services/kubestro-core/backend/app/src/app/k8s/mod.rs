@@ -1,17 +1,74 @@
+use std::sync::Arc;
+
+use kubestro_core_domain::services::game_managers::{GameManagersService, ROLLOUT_POLL_INTERVAL};
+use kubestro_core_infra::k8s::controller::{self, ControllerContext};
 use tokio_util::sync::CancellationToken;
 
 use super::context::AppContext;
 
 pub async fn start_k8s_loop(
     shutdown_token: CancellationToken,
-    _app_context: AppContext,
+    app_context: AppContext,
 ) -> anyhow::Result<()> {
-    // Wait for shutdown signal
-    tokio::select! {
-        _ = shutdown_token.cancelled() => {
-            trace!("K8S loop shutdown signal received");
-        }
+    let kubernetes = app_context.kubernetes;
+
+    // Without a cluster, there is nothing to reconcile
+    let Some(client) = kubernetes.client else {
+        shutdown_token.cancelled().await;
+        trace!("K8S loop shutdown signal received");
+        return Ok(());
     };
 
+    // Install the custom resource definitions before watching the resources
+    if kubernetes.apply_crds {
+        debug!("Applying the custom resource definitions...");
+        tokio::select! {
+            result = client.apply_crds() => match result {
+                Ok(()) => info!("Custom resource definitions applied"),
+                Err(e) => error!("Failed to apply the custom resource definitions: {}", e),
+            },
+            _ = shutdown_token.cancelled() => {
+                trace!("K8S loop shutdown signal received");
+                return Ok(());
+            }
+        }
+    }
+
+    // Reconcile the game managers until the shutdown signal, letting the running
+    // reconciliations finish
+    info!("Starting the game managers controller...");
+    let ctx = ControllerContext::new(client.client()).with_backoff(kubernetes.backoff);
+    tokio::join!(
+        controller::run(ctx, shutdown_token.clone().cancelled_owned()),
+        watch_rollouts(shutdown_token.clone(), app_context.game_managers),
+    );
+    trace!("K8S loop shutdown signal received");
+
     Ok(())
 }
+
+/// Restore the previous revision of the upgraded game managers which do not become available
+/// in time, until the shutdown signal is received
+///
+/// A running check is finished before stopping, so a restored revision is always recorded in
+/// the history. The deadlines are stored along with the game managers, the next start watches
+/// them again.
+async fn watch_rollouts(
+    shutdown_token: CancellationToken,
+    game_managers: Arc<GameManagersService>,
+) {
+    loop {
+        match game_managers.check_rollouts().await {
+            Ok(restored) if !restored.is_empty() => {
+                info!("{} game manager(s) rolled back", restored.len())
+            }
+            Ok(_) => {}
+            Err(e) => error!("Failed to check the rollouts of the game managers: {}", e),
+        }
+
+        tokio::select! {
+            _ = shutdown_token.cancelled() => break,
+            _ = tokio::time::sleep(ROLLOUT_POLL_INTERVAL) => {}
+        }
+    }
+}
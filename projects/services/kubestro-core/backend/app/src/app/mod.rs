@@ -1,7 +1,7 @@
-use context::{create_app_context, AppContext, ServiceStatus};
+use context::{create_app_context, seed::init_seed_config, AppContext, ServiceStatus};
 use kubestro_core_domain::{
     models::fields::{email::Email, username::Username},
-    services::auth::local_auth::RegisterUserPayload,
+    services::{auth::local_auth::RegisterUserPayload, repositories_import::RepositoryChange},
 };
 use tokio::{signal, sync::mpsc};
 use tokio_util::sync::CancellationToken;
@@ -9,6 +9,7 @@ use tokio_util::sync::CancellationToken;
 mod context;
 mod http;
 mod k8s;
+mod scheduler;
 mod services;
 
 /// Start the Kubestro Core application
@@ -32,6 +33,19 @@ pub async fn start() -> anyhow::Result<()> {
 /// This function will try to check if the application has already been setup and if
 /// the admin user exists. Otherwise, it will do the necessary setup.
 async fn init_app(ctx: AppContext) -> anyhow::Result<()> {
+    // Restore the repositories whose synchronization was interrupted by the last shutdown,
+    // before the seeding and the scheduler start new ones
+    let interrupted = ctx.refresh_scheduler.reset_interrupted().await?;
+    if interrupted > 0 {
+        info!(
+            "Restored the status of {} interrupted repositories",
+            interrupted
+        );
+    }
+
+    // Reconcile the repositories seeded from the configuration
+    seed_repositories(&ctx).await?;
+
     // Check if the admin user exists
     let user_repo = ctx.user_repo.clone();
     let local_auth = ctx.local_auth.clone();
@@ -100,14 +114,41 @@ async fn init_app(ctx: AppContext) -> anyhow::Result<()> {
         .map_err(|e| anyhow::anyhow!("Failed to acquire shared state lock: {}", e))?;
     shared_state_lock.status = ServiceStatus::Installed;
 
-    // Updating repositories cache
-    let repo_svc_clone = ctx.repository_service.clone();
-    tokio::spawn(async move {
-        match repo_svc_clone.update_cache(false).await {
-            Ok(_) => info!("Repositories cache updated successfully"),
-            Err(e) => error!("Failed to update repositories cache: {}", e),
+    Ok(())
+}
+
+/// Create the repositories seeded from the configuration, and keep the managed ones in line
+/// with it
+async fn seed_repositories(ctx: &AppContext) -> anyhow::Result<()> {
+    let config = init_seed_config()?;
+    debug!(
+        "Seeding {} repositories from the configuration...",
+        config.declarations.len()
+    );
+
+    let applied = ctx
+        .repositories_import
+        .seed(config.declarations, config.managed)
+        .await?;
+
+    for applied in applied {
+        let (action, done) = match applied.change {
+            RepositoryChange::Create(_) => ("create", "created"),
+            RepositoryChange::Update { .. } | RepositoryChange::Unchanged(_) => {
+                ("update", "updated")
+            }
+            RepositoryChange::Delete(_) => ("release", "released"),
+        };
+        match applied.error {
+            Some(e) => warn!(
+                "Failed to {} seeded repository `{}`: {}",
+                action,
+                applied.change.url(),
+                e
+            ),
+            None => info!("Seeded repository `{}` {}", applied.change.url(), done),
         }
-    });
+    }
 
     Ok(())
 }
@@ -119,6 +160,7 @@ async fn run_app(ctx: AppContext) -> anyhow::Result<()> {
     // Clone the token for use in tasks
     let http_shutdown_token = shutdown_token.clone();
     let k8s_shutdown_token = shutdown_token.clone();
+    let scheduler_shutdown_token = shutdown_token.clone();
 
     // Create a mpsc channel to send shutdown signal
     let (_shutdown_send, mut shutdown_recv) = mpsc::unbounded_channel::<()>();
@@ -128,6 +170,10 @@ async fn run_app(ctx: AppContext) -> anyhow::Result<()> {
     let http_handle = tokio::spawn(async move {
         http::start_http_server(http_shutdown_token, app_context_http).await
     });
+    let app_context_scheduler = ctx.clone();
+    let scheduler_handle = tokio::spawn(async move {
+        scheduler::start_refresh_loop(scheduler_shutdown_token, app_context_scheduler).await
+    });
     let k8s_handle =
         tokio::spawn(async move { k8s::start_k8s_loop(k8s_shutdown_token, ctx.clone()).await });
 
@@ -147,6 +193,7 @@ async fn run_app(ctx: AppContext) -> anyhow::Result<()> {
     // Wait for all tasks to complete
     http_handle.await??;
     k8s_handle.await??;
+    scheduler_handle.await??;
 
     info!("All tasks have completed, shutting down...");
 
@@ -0,0 +1,39 @@
+use tokio_util::sync::CancellationToken;
+
+use super::context::AppContext;
+
+/// Periodically refresh the repositories cache until the shutdown signal is received
+pub async fn start_refresh_loop(
+    shutdown_token: CancellationToken,
+    app_context: AppContext,
+) -> anyhow::Result<()> {
+    let scheduler = app_context.refresh_scheduler.clone();
+
+    loop {
+        // A refresh in progress is dropped on shutdown: the stored packages are only replaced
+        // once an index has been fully fetched, and the repositories left syncing get their
+        // status back at the next start
+        let tick = tokio::select! {
+            _ = shutdown_token.cancelled() => break,
+            tick = scheduler.tick() => tick,
+        };
+
+        let wait = match tick {
+            Ok(wait) => wait,
+            Err(e) => {
+                error!("Failed to refresh repositories: {}", e);
+                scheduler.config().backoff_base
+            }
+        };
+        trace!("Next repositories refresh in {:?}", wait);
+
+        tokio::select! {
+            _ = shutdown_token.cancelled() => break,
+            _ = tokio::time::sleep(wait) => {}
+        }
+    }
+
+    trace!("Repositories refresh loop shutdown signal received");
+
+    Ok(())
+}
@@ -11,6 +11,19 @@ async fn main() {
     // since we're not going to use a `.env` file if we deploy this application.
     dotenvy::dotenv().ok();
 
+    // Print the custom resource definitions, for the clusters where they are installed
+    // separately from Kubestro Core
+    if std::env::args().nth(1).as_deref() == Some("crds") {
+        match kubestro_core_infra::k8s::game_manager::crds_manifest() {
+            Ok(manifest) => print!("{}", manifest),
+            Err(e) => {
+                eprintln!("Failed to render the custom resource definitions: {}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
     #[cfg(debug_assertions)]
     let trace_layer = fmt::layer().with_target(true).pretty();
     #[cfg(not(debug_assertions))]
@@ -1,3 +1,6 @@
 pub mod models;
 pub mod ports;
 pub mod services;
+
+#[cfg(test)]
+pub(crate) mod test_support;
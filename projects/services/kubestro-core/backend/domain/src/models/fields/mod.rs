@@ -1,3 +1,4 @@
 pub mod email;
 pub mod password;
+pub mod trusted_key;
 pub mod username;
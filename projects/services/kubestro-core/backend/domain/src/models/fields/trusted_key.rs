@@ -0,0 +1,106 @@
+use std::ops::Deref;
+
+use minisign_verify::PublicKey;
+use serde::{Deserialize, Serialize};
+
+/// The [`TrustedKey`] field represent a public key trusted to sign repository indexes.
+/// It uses the minisign format, e.g. `RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3`.
+/// When created or deserialized, it will validate the public key.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(try_from = "String")]
+pub struct TrustedKey(String);
+
+impl TrustedKey {
+    /// Return the public key value.
+    #[inline]
+    pub fn value(&self) -> &str {
+        &self.0
+    }
+
+    /// Return the decoded public key.
+    pub fn public_key(&self) -> PublicKey {
+        // The key has been validated on creation and on deserialization
+        PublicKey::from_base64(&self.0).expect("trusted key should be valid")
+    }
+}
+
+/// Implement the [`TryFrom`] trait to convert a string into a [`TrustedKey`] object.
+/// If the conversion fails, it will return a [`TrustedKeyError`].
+impl TryFrom<String> for TrustedKey {
+    type Error = TrustedKeyError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let value = value.trim().to_string();
+
+        if value.is_empty() {
+            return Err(TrustedKeyError::Empty);
+        }
+
+        PublicKey::from_base64(&value).map_err(|_| TrustedKeyError::Invalid)?;
+
+        Ok(TrustedKey(value))
+    }
+}
+
+impl TryFrom<&str> for TrustedKey {
+    type Error = TrustedKeyError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Self::try_from(value.to_string())
+    }
+}
+
+impl Deref for TrustedKey {
+    type Target = String;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum TrustedKeyError {
+    /// A trusted key must not be empty.
+    #[error("A trusted key must not be empty")]
+    Empty,
+    /// A trusted key must be a valid minisign public key.
+    #[error("A trusted key must be a valid minisign public key")]
+    Invalid,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VALID_KEY: &str = "RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3";
+
+    #[test]
+    fn empty_key_should_throw_an_error() {
+        let key = TrustedKey::try_from("  ");
+
+        assert_eq!(key.unwrap_err(), TrustedKeyError::Empty);
+    }
+
+    #[test]
+    fn invalid_key_should_throw_an_error() {
+        let key = TrustedKey::try_from("not-a-key");
+
+        assert_eq!(key.unwrap_err(), TrustedKeyError::Invalid);
+    }
+
+    #[test]
+    fn valid_key_should_be_accepted() {
+        let key = TrustedKey::try_from(format!("{}\n", VALID_KEY));
+
+        assert_eq!(key.unwrap().value(), VALID_KEY);
+    }
+
+    #[test]
+    fn deserialized_key_should_be_validated() {
+        let key = serde_json::from_str::<TrustedKey>(&format!("\"{}\"", VALID_KEY));
+        let invalid = serde_json::from_str::<TrustedKey>("\"not-a-key\"");
+
+        assert_eq!(key.unwrap().value(), VALID_KEY);
+        assert!(invalid.is_err());
+    }
+}
@@ -0,0 +1,288 @@
+use std::{fmt::Display, str::FromStr};
+
+use chrono::{DateTime, Utc};
+use semver::Version;
+
+use super::{
+    index::{CrdReference, PermissionRule},
+    package::RepositoryId,
+};
+
+/// Maximum length of a game manager name
+pub const MAX_GAME_MANAGER_NAME_LENGTH: usize = 63;
+
+/// The game manager objects are deployed and up to date
+pub const CONDITION_AVAILABLE: &str = "Available";
+/// The game manager objects are being created or rolled out
+pub const CONDITION_PROGRESSING: &str = "Progressing";
+/// The game manager failed to be deployed, or stopped working
+pub const CONDITION_DEGRADED: &str = "Degraded";
+
+/// Check whether a name can identify a game manager
+///
+/// The name also names the Kubernetes objects of the game manager, it must be a DNS label:
+/// lowercase letters, digits and dashes, starting with a letter and not ending with a dash.
+pub fn is_valid_game_manager_name(name: &str) -> bool {
+    name.len() <= MAX_GAME_MANAGER_NAME_LENGTH
+        && name.starts_with(|c: char| c.is_ascii_lowercase())
+        && !name.ends_with('-')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+
+/// A game manager to install on the cluster
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewGameManager {
+    pub name: String,
+    pub repository_id: RepositoryId,
+    pub package: String,
+    pub version: Version,
+    /// Container image of the game manager
+    pub image: String,
+    /// Configuration values, validated against the schema of the package
+    pub values: serde_json::Value,
+    /// Namespaced permissions granted to the game manager
+    pub permissions: Vec<PermissionRule>,
+    /// Kinds of the game servers managed by the game manager
+    pub game_servers: Vec<CrdReference>,
+    /// Name of the user installing the game manager
+    pub created_by: Option<String>,
+}
+
+/// Why a revision of a game manager was deployed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameManagerRevisionCause {
+    Install,
+    Upgrade,
+    Rollback,
+    /// The previous revision was restored after an upgrade failed to become available
+    AutomaticRollback,
+}
+
+impl Display for GameManagerRevisionCause {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Install => write!(f, "install"),
+            Self::Upgrade => write!(f, "upgrade"),
+            Self::Rollback => write!(f, "rollback"),
+            Self::AutomaticRollback => write!(f, "automatic-rollback"),
+        }
+    }
+}
+
+impl FromStr for GameManagerRevisionCause {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "install" => Ok(Self::Install),
+            "upgrade" => Ok(Self::Upgrade),
+            "rollback" => Ok(Self::Rollback),
+            "automatic-rollback" => Ok(Self::AutomaticRollback),
+            _ => Err(value.to_string()),
+        }
+    }
+}
+
+/// A version of a game manager and the values to deploy it with
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewGameManagerRevision {
+    pub version: String,
+    pub image: String,
+    pub values: serde_json::Value,
+    pub permissions: Vec<PermissionRule>,
+    pub game_servers: Vec<CrdReference>,
+    pub cause: GameManagerRevisionCause,
+    /// Name of the user deploying the revision, `None` when deployed by Kubestro itself
+    pub created_by: Option<String>,
+    /// Time the revision must become available by, the previous revision is restored otherwise
+    pub rollout_deadline: Option<DateTime<Utc>>,
+    /// Revision expected to be the latest one, the deployment is refused when another revision
+    /// was deployed in the meantime
+    pub replaces: Option<u32>,
+}
+
+/// A revision of a game manager, as recorded in its history
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameManagerRevision {
+    /// Number of the revision, increased by every upgrade and rollback
+    pub revision: u32,
+    pub version: String,
+    pub image: String,
+    pub values: serde_json::Value,
+    pub permissions: Vec<PermissionRule>,
+    pub game_servers: Vec<CrdReference>,
+    /// `None` for the revisions of game managers installed outside of Kubestro
+    pub cause: Option<GameManagerRevisionCause>,
+    pub created_at: Option<DateTime<Utc>>,
+    /// Name of the user who deployed the revision, `None` when deployed by Kubestro itself
+    pub created_by: Option<String>,
+}
+
+/// Job migrating the custom resources of a game manager between two versions
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameManagerMigration {
+    pub image: String,
+    pub command: Vec<String>,
+    pub from_version: String,
+    pub to_version: Version,
+}
+
+/// A game manager installed on the cluster
+///
+/// The installation mirrors the Kubernetes resource of the game manager. The resources created
+/// with `kubectl` are not checked against the catalog: the repository, the package and the
+/// version are kept as written.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameManagerInstallation {
+    /// Name of the game manager, unique on the cluster
+    pub name: String,
+    /// Id of the repository the package was installed from
+    pub repository_id: String,
+    pub package: String,
+    pub version: String,
+    pub image: String,
+    pub values: serde_json::Value,
+    pub created_at: Option<DateTime<Utc>>,
+    /// Kinds of the game servers managed by the game manager
+    pub game_servers: Vec<CrdReference>,
+    /// Time the deployed revision must become available by, `None` once it did
+    pub rollout_deadline: Option<DateTime<Utc>>,
+    /// State of the game manager, as last reported by the controller
+    pub state: GameManagerState,
+    /// Pods running the game manager, `None` until they are deployed
+    pub workload: Option<GameManagerWorkload>,
+}
+
+/// Pods running a game manager, as observed on the cluster
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct GameManagerWorkload {
+    /// Number of pods of the game manager
+    pub replicas: u32,
+    /// Number of pods ready to serve requests
+    pub ready_replicas: u32,
+    /// Image the pods are running, which differs from the requested one during a rollout
+    pub image: Option<String>,
+    /// Number of restarts of the containers of the pods
+    pub restarts: u32,
+}
+
+/// Summary of the state of a game manager
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameManagerPhase {
+    /// The controller did not report the state of the game manager yet
+    Pending,
+    /// The game manager is being deployed
+    Progressing,
+    /// The game manager is deployed and running
+    Available,
+    /// The game manager failed to be deployed, or stopped working
+    Degraded,
+    /// The game manager is being uninstalled
+    Deleting,
+}
+
+/// An observation of the state of a game manager
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameManagerCondition {
+    /// Type of the condition, such as [`CONDITION_AVAILABLE`]
+    pub type_: String,
+    /// Whether the condition holds, `None` when unknown
+    pub status: Option<bool>,
+    pub reason: Option<String>,
+    pub message: Option<String>,
+    pub last_transition_time: Option<DateTime<Utc>>,
+}
+
+/// State of a game manager, as reported by the controller
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameManagerState {
+    pub phase: GameManagerPhase,
+    pub conditions: Vec<GameManagerCondition>,
+}
+
+impl GameManagerState {
+    /// Summarize the conditions of a game manager
+    ///
+    /// The conditions are only trusted once they were computed from the latest generation of
+    /// the game manager, otherwise it is still progressing.
+    pub fn new(conditions: Vec<GameManagerCondition>, up_to_date: bool, deleting: bool) -> Self {
+        let holds = |type_: &str| {
+            conditions
+                .iter()
+                .any(|condition| condition.type_ == type_ && condition.status == Some(true))
+        };
+
+        let phase = match () {
+            _ if deleting => GameManagerPhase::Deleting,
+            _ if conditions.is_empty() => GameManagerPhase::Pending,
+            _ if !up_to_date => GameManagerPhase::Progressing,
+            _ if holds(CONDITION_DEGRADED) => GameManagerPhase::Degraded,
+            _ if holds(CONDITION_AVAILABLE) => GameManagerPhase::Available,
+            _ => GameManagerPhase::Progressing,
+        };
+
+        Self { phase, conditions }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn condition(type_: &str, status: bool) -> GameManagerCondition {
+        GameManagerCondition {
+            type_: type_.to_string(),
+            status: Some(status),
+            reason: None,
+            message: None,
+            last_transition_time: None,
+        }
+    }
+
+    #[test]
+    fn names_should_be_dns_labels() {
+        assert!(is_valid_game_manager_name("minecraft"));
+        assert!(is_valid_game_manager_name("minecraft-2"));
+        assert!(!is_valid_game_manager_name(""));
+        assert!(!is_valid_game_manager_name("2-minecraft"));
+        assert!(!is_valid_game_manager_name("minecraft-"));
+        assert!(!is_valid_game_manager_name("Minecraft"));
+        assert!(!is_valid_game_manager_name("minecraft.server"));
+        assert!(!is_valid_game_manager_name(&"a".repeat(64)));
+    }
+
+    #[test]
+    fn phase_should_summarize_the_conditions() {
+        let available = vec![
+            condition(CONDITION_AVAILABLE, true),
+            condition(CONDITION_DEGRADED, false),
+        ];
+        let degraded = vec![
+            condition(CONDITION_AVAILABLE, false),
+            condition(CONDITION_DEGRADED, true),
+        ];
+
+        assert_eq!(
+            GameManagerState::new(vec![], true, false).phase,
+            GameManagerPhase::Pending
+        );
+        assert_eq!(
+            GameManagerState::new(available.clone(), true, false).phase,
+            GameManagerPhase::Available
+        );
+        assert_eq!(
+            GameManagerState::new(available.clone(), false, false).phase,
+            GameManagerPhase::Progressing
+        );
+        assert_eq!(
+            GameManagerState::new(degraded, true, false).phase,
+            GameManagerPhase::Degraded
+        );
+        assert_eq!(
+            GameManagerState::new(available, true, true).phase,
+            GameManagerPhase::Deleting
+        );
+    }
+}
@@ -0,0 +1,814 @@
+//! Repository index format
+//!
+//! A repository exposes a single JSON document, the index, describing the repository
+//! itself and every game-manager package it publishes. The document is versioned with a
+//! `MAJOR.MINOR` string: minor versions only add optional fields, while a new major version
+//! may break the layout and is rejected until Kubestro learns how to read it.
+//!
+//! ```json
+//! {
+//!   "version": "1.0",
+//!   "repository": {
+//!     "name": "Kubestro official",
+//!     "description": "Official game managers",
+//!     "homepage": "https://kubestro.io",
+//!     "maintainer": "Kubestro team"
+//!   },
+//!   "packages": [
+//!     {
+//!       "name": "minecraft",
+//!       "version": "1.2.0",
+//!       "channel": "stable",
+//!       "kubestro_version": ">=0.1.0, <0.3.0",
+//!       "description": "Minecraft game manager",
+//!       "game": "minecraft",
+//!       "tags": ["sandbox", "paper"],
+//!       "readme": "# Minecraft\n\nRun Minecraft servers on Kubernetes.",
+//!       "changelog": "- Support Minecraft 1.21",
+//!       "icon": "https://kubestro.io/icons/minecraft.png",
+//!       "screenshots": ["https://kubestro.io/screenshots/minecraft.png"],
+//!       "maintainers": [{ "name": "Kubestro team", "email": "team@kubestro.io" }],
+//!       "license": "MIT",
+//!       "source_url": "https://github.com/kubestro/minecraft",
+//!       "permissions": [
+//!         { "api_groups": ["apps"], "resources": ["statefulsets"], "verbs": ["get", "create"] }
+//!       ],
+//!       "crds": [
+//!         { "group": "minecraft.kubestro.io", "version": "v1", "kind": "MinecraftServer" }
+//!       ],
+//!       "dependencies": [{ "name": "java-runtime", "version": "^17.0" }],
+//!       "image": "ghcr.io/kubestro/minecraft:1.2.0",
+//!       "values_schema": {
+//!         "type": "object",
+//!         "properties": { "motd": { "type": "string" } }
+//!       },
+//!       "migration": { "command": ["/migrate"], "from": ">=1.0.0, <1.2.0" },
+//!       "source": { "type": "chart", "url": "https://kubestro.io/charts/minecraft-1.2.0.tgz" }
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! A package may be listed several times, once per version. Versions follow semver, and each
+//! one is published on a release [`PackageChannel`].
+//!
+//! Unknown fields are rejected. Packages are parsed one by one so that every invalid entry
+//! is reported at once instead of stopping at the first one.
+use std::{collections::HashSet, fmt::Display, str::FromStr};
+
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
+
+/// The only index major version this release knows how to read
+pub const SUPPORTED_INDEX_MAJOR_VERSION: u64 = 1;
+
+/// Version of the index document, formatted as `MAJOR.MINOR`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct IndexVersion {
+    pub major: u64,
+    pub minor: u64,
+}
+
+impl IndexVersion {
+    /// Check whether this version can be read by the current release
+    pub fn is_supported(&self) -> bool {
+        self.major == SUPPORTED_INDEX_MAJOR_VERSION
+    }
+}
+
+impl Display for IndexVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+impl FromStr for IndexVersion {
+    type Err = IndexError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || IndexError::InvalidVersion(value.to_string());
+
+        let (major, minor) = value.split_once('.').ok_or_else(invalid)?;
+        let major = major.parse::<u64>().map_err(|_| invalid())?;
+        let minor = minor.parse::<u64>().map_err(|_| invalid())?;
+
+        Ok(Self { major, minor })
+    }
+}
+
+impl TryFrom<String> for IndexVersion {
+    type Error = IndexError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<IndexVersion> for String {
+    fn from(value: IndexVersion) -> Self {
+        value.to_string()
+    }
+}
+
+/// A parsed repository index
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RepositoryIndex {
+    /// The version of the index format
+    pub version: IndexVersion,
+    /// Information about the repository publishing the index
+    pub repository: RepositoryMetadata,
+    /// The game-manager packages published by the repository
+    pub packages: Vec<IndexPackage>,
+}
+
+/// Repository information declared by the index
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RepositoryMetadata {
+    /// Display name of the repository
+    pub name: String,
+    /// Short description of the repository
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Homepage of the repository
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub homepage: Option<String>,
+    /// Person or organization maintaining the repository
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub maintainer: Option<String>,
+}
+
+/// A game-manager package declared by the index
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct IndexPackage {
+    /// Name of the package, unique inside the repository
+    pub name: String,
+    /// Version of the package
+    pub version: Version,
+    /// Release channel the version is published on
+    #[serde(default, skip_serializing_if = "PackageChannel::is_stable")]
+    pub channel: PackageChannel,
+    /// Versions of Kubestro core the package can be installed on, any version when missing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kubestro_version: Option<VersionReq>,
+    /// Short description of the package
+    pub description: String,
+    /// Game managed by the package, e.g. `minecraft`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub game: Option<String>,
+    /// Keywords the package can be searched by
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Long description of the package, in markdown
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub readme: Option<String>,
+    /// Changes brought by this version, in markdown
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub changelog: Option<String>,
+    /// URL of the package icon
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+    /// URLs of screenshots of the game manager
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub screenshots: Vec<String>,
+    /// People or organizations maintaining the package
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub maintainers: Vec<PackageMaintainer>,
+    /// SPDX identifier of the package license
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+    /// URL of the package source code
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_url: Option<String>,
+    /// Kubernetes permissions the game manager requests once installed
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub permissions: Vec<PermissionRule>,
+    /// Custom resource definitions installed by the package
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub crds: Vec<CrdReference>,
+    /// Packages that must be installed along with this one, e.g. shared libraries or CRDs
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dependencies: Vec<PackageDependency>,
+    /// Container image of the game manager, the package cannot be installed without it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    /// JSON schema of the configuration values of the game manager, any object is accepted
+    /// when missing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub values_schema: Option<serde_json::Value>,
+    /// Job migrating the custom resources of the previous versions, run before an upgrade
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub migration: Option<PackageMigration>,
+    /// Where the package can be retrieved from
+    pub source: PackageSource,
+}
+
+impl IndexPackage {
+    /// Check the package values that cannot be enforced by the deserialization alone
+    fn validate(&self) -> Result<(), String> {
+        if self.name.is_empty() {
+            return Err("the package name must not be empty".to_string());
+        }
+
+        if !self
+            .name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        {
+            return Err(
+                "the package name must only contain lowercase letters, digits and dashes"
+                    .to_string(),
+            );
+        }
+
+        if self.source.url().is_empty() {
+            return Err("the package source url must not be empty".to_string());
+        }
+
+        if self.game.as_ref().is_some_and(String::is_empty) {
+            return Err("the game must not be empty".to_string());
+        }
+
+        if self.tags.iter().any(String::is_empty) {
+            return Err("the tags must not be empty".to_string());
+        }
+
+        if self.screenshots.iter().any(String::is_empty) {
+            return Err("the screenshot urls must not be empty".to_string());
+        }
+
+        if self
+            .maintainers
+            .iter()
+            .any(|maintainer| maintainer.name.is_empty())
+        {
+            return Err("the maintainer names must not be empty".to_string());
+        }
+
+        if let Some(rule) = self
+            .permissions
+            .iter()
+            .find(|rule| rule.resources.is_empty() || rule.verbs.is_empty())
+        {
+            return Err(format!(
+                "the permission rule `{}` must have resources and verbs",
+                rule
+            ));
+        }
+
+        if let Some(dependency) = self
+            .dependencies
+            .iter()
+            .find(|dependency| dependency.name.is_empty() || dependency.name == self.name)
+        {
+            return Err(format!(
+                "the dependency `{}` must name another package",
+                dependency
+            ));
+        }
+
+        if let Some(crd) = self
+            .crds
+            .iter()
+            .find(|crd| crd.group.is_empty() || crd.version.is_empty() || crd.kind.is_empty())
+        {
+            return Err(format!(
+                "the CRD reference `{}` must have a group, a version and a kind",
+                crd
+            ));
+        }
+
+        if self.image.as_ref().is_some_and(String::is_empty) {
+            return Err("the image must not be empty".to_string());
+        }
+
+        if self
+            .values_schema
+            .as_ref()
+            .is_some_and(|schema| !schema.is_object())
+        {
+            return Err("the values schema must be an object".to_string());
+        }
+
+        if let Some(migration) = &self.migration {
+            if migration.command.is_empty() || migration.command.iter().any(String::is_empty) {
+                return Err("the migration command must not be empty".to_string());
+            }
+
+            if migration.image.as_ref().is_some_and(String::is_empty) {
+                return Err("the migration image must not be empty".to_string());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check whether the package can be installed on the given Kubestro core version
+    pub fn is_compatible_with(&self, core_version: &Version) -> bool {
+        self.kubestro_version
+            .as_ref()
+            .is_none_or(|requirement| requirement.matches(core_version))
+    }
+}
+
+/// Release channel of a package version
+///
+/// Channels are ordered from the most to the least stable: following a channel also offers
+/// the versions of the more stable ones.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum PackageChannel {
+    #[default]
+    Stable,
+    Beta,
+    Nightly,
+}
+
+impl PackageChannel {
+    fn is_stable(&self) -> bool {
+        *self == PackageChannel::Stable
+    }
+
+    /// Check whether a version published on `channel` is offered when following this channel
+    pub fn includes(&self, channel: PackageChannel) -> bool {
+        channel <= *self
+    }
+}
+
+impl Display for PackageChannel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PackageChannel::Stable => write!(f, "stable"),
+            PackageChannel::Beta => write!(f, "beta"),
+            PackageChannel::Nightly => write!(f, "nightly"),
+        }
+    }
+}
+
+/// Person or organization maintaining a package
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PackageMaintainer {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+/// Kubernetes permissions requested by a game manager, following the layout of an RBAC rule
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PermissionRule {
+    /// API groups of the resources, the empty string being the core group
+    #[serde(default)]
+    pub api_groups: Vec<String>,
+    /// Resources the rule applies to (e.g. `pods`, `statefulsets`)
+    pub resources: Vec<String>,
+    /// Actions allowed on the resources (e.g. `get`, `create`)
+    pub verbs: Vec<String>,
+    /// Whether the rule applies to the whole cluster, instead of the game manager namespace
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub cluster_wide: bool,
+}
+
+impl Display for PermissionRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} on {}",
+            self.verbs.join(","),
+            self.resources.join(","),
+            match self.api_groups.is_empty() {
+                true => "the core group".to_string(),
+                false => self.api_groups.join(","),
+            }
+        )
+    }
+}
+
+/// Job migrating the custom resources of a game manager to the schema of a new version
+///
+/// The job runs with the permissions of the game manager, before the new version is deployed.
+/// It receives the versions it migrates between in the `KUBESTRO_FROM_VERSION` and
+/// `KUBESTRO_TO_VERSION` environment variables.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PackageMigration {
+    /// Image of the job, the image of the package when missing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    /// Command run by the job
+    pub command: Vec<String>,
+    /// Versions the migration upgrades from, any previous version when missing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from: Option<VersionReq>,
+}
+
+impl PackageMigration {
+    /// Check whether the migration must run when upgrading from a version
+    pub fn applies_from(&self, version: &Version) -> bool {
+        self.from
+            .as_ref()
+            .is_none_or(|requirement| requirement.matches(version))
+    }
+}
+
+/// Reference to a custom resource definition installed by a package
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CrdReference {
+    /// API group of the resource (e.g. `minecraft.kubestro.io`)
+    pub group: String,
+    /// API version of the resource (e.g. `v1`)
+    pub version: String,
+    /// Kind of the resource (e.g. `MinecraftServer`)
+    pub kind: String,
+}
+
+impl Display for CrdReference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}/{}", self.group, self.version, self.kind)
+    }
+}
+
+/// A package required by another one
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PackageDependency {
+    /// Name of the required package, looked up in every repository
+    pub name: String,
+    /// Versions of the required package that are accepted, any version when missing
+    #[serde(default)]
+    pub version: VersionReq,
+}
+
+impl Display for PackageDependency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.name, self.version)
+    }
+}
+
+/// Location of the deployable artifact of a package
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase", deny_unknown_fields)]
+pub enum PackageSource {
+    /// A Helm chart archive
+    Chart { url: String },
+    /// A plain Kubernetes manifest
+    Manifest { url: String },
+}
+
+impl PackageSource {
+    /// Return the URL of the artifact
+    pub fn url(&self) -> &str {
+        match self {
+            PackageSource::Chart { url } | PackageSource::Manifest { url } => url,
+        }
+    }
+}
+
+/// The index layout before the packages are parsed one by one
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawRepositoryIndex {
+    version: IndexVersion,
+    repository: RepositoryMetadata,
+    packages: Vec<serde_json::Value>,
+}
+
+impl RepositoryIndex {
+    /// Parse an index from its raw JSON representation
+    pub fn from_slice(data: &[u8]) -> Result<Self, IndexError> {
+        let value = serde_json::from_slice::<serde_json::Value>(data)
+            .map_err(|e| IndexError::Malformed(e.to_string()))?;
+
+        Self::from_value(value)
+    }
+
+    /// Parse an index from an already decoded JSON value
+    pub fn from_value(value: serde_json::Value) -> Result<Self, IndexError> {
+        // The version is checked first, an index from an unknown major version may not
+        // follow the layout below at all.
+        let version = value
+            .get("version")
+            .ok_or(IndexError::MissingVersion)?
+            .as_str()
+            .ok_or_else(|| IndexError::InvalidVersion(value["version"].to_string()))?
+            .parse::<IndexVersion>()?;
+
+        if !version.is_supported() {
+            return Err(IndexError::UnsupportedVersion(version));
+        }
+
+        let raw = serde_json::from_value::<RawRepositoryIndex>(value)
+            .map_err(|e| IndexError::Malformed(e.to_string()))?;
+
+        let mut packages = Vec::with_capacity(raw.packages.len());
+        let mut errors = Vec::new();
+        let mut seen = HashSet::new();
+
+        for (index, entry) in raw.packages.into_iter().enumerate() {
+            let name = entry
+                .get("name")
+                .and_then(|name| name.as_str())
+                .map(String::from);
+
+            let package = serde_json::from_value::<IndexPackage>(entry)
+                .map_err(|e| e.to_string())
+                .and_then(|package| package.validate().map(|_| package));
+
+            match package {
+                Ok(package) if !seen.insert((package.name.clone(), package.version.clone())) => {
+                    errors.push(IndexPackageError {
+                        index,
+                        name,
+                        reason: format!(
+                            "the version `{}` of this package is declared more than once",
+                            package.version
+                        ),
+                    });
+                }
+                Ok(package) => packages.push(package),
+                Err(reason) => errors.push(IndexPackageError {
+                    index,
+                    name,
+                    reason,
+                }),
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err(IndexError::InvalidPackages(errors));
+        }
+
+        Ok(Self {
+            version: raw.version,
+            repository: raw.repository,
+            packages,
+        })
+    }
+}
+
+/// An invalid package entry inside an index
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct IndexPackageError {
+    /// Position of the entry inside the `packages` list
+    pub index: usize,
+    /// Name of the package, if it could be read
+    pub name: Option<String>,
+    /// Why the entry was rejected
+    pub reason: String,
+}
+
+impl Display for IndexPackageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.name {
+            Some(name) => write!(f, "#{} ({}): {}", self.index, name, self.reason),
+            None => write!(f, "#{}: {}", self.index, self.reason),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum IndexError {
+    /// The document is not a valid index
+    #[error("The index is malformed: {0}")]
+    Malformed(String),
+
+    /// The document does not declare its version
+    #[error("The index version is missing")]
+    MissingVersion,
+
+    /// The version is not formatted as `MAJOR.MINOR`
+    #[error("Invalid index version: {0}")]
+    InvalidVersion(String),
+
+    /// The index major version is not supported by this release
+    #[error("Unsupported index version {0}, only {SUPPORTED_INDEX_MAJOR_VERSION}.x is supported")]
+    UnsupportedVersion(IndexVersion),
+
+    /// One or more packages are invalid
+    #[error(
+        "The index contains invalid packages: {}",
+        .0.iter().map(ToString::to_string).collect::<Vec<_>>().join(", ")
+    )]
+    InvalidPackages(Vec<IndexPackageError>),
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn valid_package(name: &str) -> serde_json::Value {
+        json!({
+            "name": name,
+            "version": "1.0.0",
+            "description": "A game manager",
+            "crds": [{ "group": "games.kubestro.io", "version": "v1", "kind": "GameServer" }],
+            "source": { "type": "chart", "url": "https://example.com/chart.tgz" }
+        })
+    }
+
+    fn index_with(version: &str, packages: Vec<serde_json::Value>) -> serde_json::Value {
+        json!({
+            "version": version,
+            "repository": { "name": "Demo" },
+            "packages": packages
+        })
+    }
+
+    #[test]
+    fn valid_index_should_be_parsed() {
+        let data = index_with("1.0", vec![valid_package("minecraft")]).to_string();
+
+        let index = RepositoryIndex::from_slice(data.as_bytes()).unwrap();
+
+        assert_eq!(index.version, IndexVersion { major: 1, minor: 0 });
+        assert_eq!(index.repository.name, "Demo");
+        assert_eq!(index.packages.len(), 1);
+        assert_eq!(index.packages[0].name, "minecraft");
+        assert_eq!(
+            index.packages[0].source,
+            PackageSource::Chart {
+                url: "https://example.com/chart.tgz".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn newer_minor_version_should_be_accepted() {
+        let index = RepositoryIndex::from_value(index_with("1.7", vec![]));
+
+        assert!(index.is_ok());
+    }
+
+    #[test]
+    fn unknown_major_version_should_throw_an_error() {
+        let index = RepositoryIndex::from_value(index_with("2.0", vec![]));
+
+        assert_eq!(
+            index.unwrap_err(),
+            IndexError::UnsupportedVersion(IndexVersion { major: 2, minor: 0 })
+        );
+    }
+
+    #[test]
+    fn missing_version_should_throw_an_error() {
+        let index = RepositoryIndex::from_value(json!({ "packages": [] }));
+
+        assert_eq!(index.unwrap_err(), IndexError::MissingVersion);
+    }
+
+    #[test]
+    fn invalid_version_should_throw_an_error() {
+        let index = RepositoryIndex::from_value(index_with("latest", vec![]));
+
+        assert_eq!(
+            index.unwrap_err(),
+            IndexError::InvalidVersion("latest".to_string())
+        );
+    }
+
+    #[test]
+    fn unknown_field_should_throw_an_error() {
+        let mut value = index_with("1.0", vec![]);
+        value["unexpected"] = json!(true);
+
+        let index = RepositoryIndex::from_value(value);
+
+        assert!(matches!(index.unwrap_err(), IndexError::Malformed(_)));
+    }
+
+    #[test]
+    fn package_versions_should_be_parsed_as_semver() {
+        let mut beta = valid_package("minecraft");
+        beta["version"] = json!("1.1.0-rc.1");
+        beta["channel"] = json!("beta");
+        beta["kubestro_version"] = json!(">=0.2");
+        let mut invalid = valid_package("terraria");
+        invalid["version"] = json!("1.0");
+
+        let index = RepositoryIndex::from_value(index_with("1.0", vec![beta.clone()])).unwrap();
+        let rejected = RepositoryIndex::from_value(index_with("1.0", vec![beta, invalid]));
+
+        let package = &index.packages[0];
+        assert_eq!(package.version, Version::parse("1.1.0-rc.1").unwrap());
+        assert_eq!(package.channel, PackageChannel::Beta);
+        assert!(package.is_compatible_with(&Version::new(0, 2, 1)));
+        assert!(!package.is_compatible_with(&Version::new(0, 1, 0)));
+        assert!(matches!(
+            rejected.unwrap_err(),
+            IndexError::InvalidPackages(errors) if errors.len() == 1 && errors[0].index == 1
+        ));
+    }
+
+    #[test]
+    fn permission_rules_should_have_resources_and_verbs() {
+        let mut package = valid_package("minecraft");
+        package["permissions"] = json!([
+            { "api_groups": ["apps"], "resources": ["statefulsets"], "verbs": ["get"] },
+            { "resources": ["pods"], "verbs": [] }
+        ]);
+
+        let index = RepositoryIndex::from_value(index_with("1.0", vec![package]));
+
+        let Err(IndexError::InvalidPackages(errors)) = index else {
+            panic!("the index should be rejected");
+        };
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].reason.ends_with("must have resources and verbs"));
+    }
+
+    #[test]
+    fn values_schema_should_be_an_object() {
+        let mut package = valid_package("minecraft");
+        package["image"] = json!("ghcr.io/kubestro/minecraft:1.0.0");
+        package["values_schema"] = json!({ "type": "object" });
+        let mut invalid = valid_package("terraria");
+        invalid["values_schema"] = json!("object");
+
+        let index = RepositoryIndex::from_value(index_with("1.0", vec![package])).unwrap();
+        let rejected = RepositoryIndex::from_value(index_with("1.0", vec![invalid]));
+
+        assert_eq!(
+            index.packages[0].image.as_deref(),
+            Some("ghcr.io/kubestro/minecraft:1.0.0")
+        );
+        assert_eq!(
+            index.packages[0].values_schema,
+            Some(json!({ "type": "object" }))
+        );
+        assert!(matches!(
+            rejected.unwrap_err(),
+            IndexError::InvalidPackages(errors) if errors[0].reason == "the values schema must be an object"
+        ));
+    }
+
+    #[test]
+    fn migration_should_have_a_command() {
+        let mut package = valid_package("minecraft");
+        package["migration"] = json!({ "command": ["/migrate"], "from": "<1.2.0" });
+        let mut invalid = valid_package("terraria");
+        invalid["migration"] = json!({ "command": [] });
+
+        let index = RepositoryIndex::from_value(index_with("1.0", vec![package])).unwrap();
+        let rejected = RepositoryIndex::from_value(index_with("1.0", vec![invalid]));
+
+        let migration = index.packages[0].migration.as_ref().unwrap();
+        assert!(migration.applies_from(&Version::new(1, 1, 0)));
+        assert!(!migration.applies_from(&Version::new(1, 2, 0)));
+        assert!(matches!(
+            rejected.unwrap_err(),
+            IndexError::InvalidPackages(errors) if errors[0].reason == "the migration command must not be empty"
+        ));
+    }
+
+    #[test]
+    fn channels_should_include_the_more_stable_ones() {
+        assert!(PackageChannel::Beta.includes(PackageChannel::Stable));
+        assert!(PackageChannel::Beta.includes(PackageChannel::Beta));
+        assert!(!PackageChannel::Beta.includes(PackageChannel::Nightly));
+        assert!(!PackageChannel::Stable.includes(PackageChannel::Beta));
+    }
+
+    #[test]
+    fn every_invalid_package_should_be_reported() {
+        let mut missing_source = valid_package("terraria");
+        missing_source.as_object_mut().unwrap().remove("source");
+
+        let value = index_with(
+            "1.0",
+            vec![
+                valid_package("minecraft"),
+                missing_source,
+                valid_package("Not Valid"),
+                valid_package("minecraft"),
+            ],
+        );
+
+        let Err(IndexError::InvalidPackages(errors)) = RepositoryIndex::from_value(value) else {
+            panic!("the index should be rejected");
+        };
+
+        let reported = errors
+            .iter()
+            .map(|e| (e.index, e.name.as_deref()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            reported,
+            vec![
+                (1, Some("terraria")),
+                (2, Some("Not Valid")),
+                (3, Some("minecraft"))
+            ]
+        );
+    }
+}
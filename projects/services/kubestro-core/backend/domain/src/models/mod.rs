@@ -4,6 +4,8 @@ mod macros;
 
 pub mod fields;
 
+pub mod game_manager;
+pub mod index;
 pub mod package;
 pub mod user;
 
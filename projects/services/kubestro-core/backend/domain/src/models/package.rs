@@ -1,6 +1,12 @@
+use std::{collections::BTreeMap, fmt};
+
+use chrono::{DateTime, Utc};
+use semver::Version;
+use serde::{Deserialize, Serialize};
+
 use crate::impl_entity_id;
 
-use super::Entity;
+use super::{fields::trusted_key::TrustedKey, index::IndexVersion, Entity};
 
 impl_entity_id!(
     /// Package Id
@@ -12,7 +18,7 @@ pub struct Package {
     pub id: PackageId,
 
     pub name: String,
-    pub version: String,
+    pub version: Version,
     pub description: String,
     pub url: String,
 }
@@ -28,12 +34,23 @@ impl_entity_id!(
     RepositoryId
 );
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Repository {
     pub id: RepositoryId,
 
     pub name: String,
+    pub kind: RepositoryKind,
     pub url: String,
+    /// Public keys allowed to sign the repository index
+    pub trusted_keys: Vec<TrustedKey>,
+    /// Credentials sent along every request to the repository
+    pub credentials: Option<RepositoryCredentials>,
+    /// When several repositories publish the same package, the highest priority wins
+    pub priority: i32,
+    /// Seeded from the configuration, it cannot be updated or deleted through the API
+    pub managed: bool,
+
+    pub sync: RepositorySync,
 }
 
 impl Entity<RepositoryId> for Repository {
@@ -42,11 +59,326 @@ impl Entity<RepositoryId> for Repository {
     }
 }
 
+/// Source the index of a repository is fetched from
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum RepositoryKind {
+    /// A JSON index served over HTTP, `url` is the index location
+    #[default]
+    Http,
+    /// An index artifact stored in an OCI registry, `url` is an `oci://<registry>/<name>[:<tag>]`
+    /// reference. Without tag, the `latest` tag or the highest version tag is used.
+    Oci,
+    /// An index file committed in a Git repository, `url` is the remote to fetch
+    Git(GitSource),
+    /// An index read from an uploaded bundle, `url` is a `bundle://<name>` reference to a
+    /// repository of the bundle. Nothing is fetched over the network.
+    Bundle,
+}
+
+/// Location of an index in a Git repository
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitSource {
+    /// Branch or tag to read the index from
+    pub reference: String,
+    /// Path of the index file in the repository
+    pub path: String,
+}
+
+impl Default for GitSource {
+    fn default() -> Self {
+        Self {
+            reference: "HEAD".to_string(),
+            path: "index.json".to_string(),
+        }
+    }
+}
+
+/// Credentials of a private repository
+///
+/// They are secrets: they are never displayed in logs nor returned by the API.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RepositoryCredentials {
+    /// HTTP basic authentication
+    Basic { username: String, password: String },
+    /// Bearer token, sent in the `Authorization` header
+    Bearer { token: String },
+    /// Arbitrary headers, e.g. an API key
+    Headers { headers: BTreeMap<String, String> },
+}
+
+impl RepositoryCredentials {
+    /// Name of the credentials kind, safe to display
+    pub fn kind(&self) -> &'static str {
+        match self {
+            RepositoryCredentials::Basic { .. } => "basic",
+            RepositoryCredentials::Bearer { .. } => "bearer",
+            RepositoryCredentials::Headers { .. } => "headers",
+        }
+    }
+
+    /// Check the credentials can be sent in HTTP requests
+    pub fn validate(&self) -> Result<(), RepositoryCredentialsError> {
+        match self {
+            RepositoryCredentials::Basic { username, .. } => {
+                if username.is_empty() || username.contains(':') {
+                    return Err(RepositoryCredentialsError::InvalidUsername);
+                }
+            }
+            RepositoryCredentials::Bearer { token } => {
+                if token.is_empty() || !is_header_value(token) {
+                    return Err(RepositoryCredentialsError::InvalidToken);
+                }
+            }
+            RepositoryCredentials::Headers { headers } => {
+                if headers.is_empty() {
+                    return Err(RepositoryCredentialsError::NoHeaders);
+                }
+
+                for (name, value) in headers {
+                    // Header names are RFC 9110 tokens
+                    let is_token = !name.is_empty()
+                        && name
+                            .bytes()
+                            .all(|c| c.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&c));
+                    if !is_token {
+                        return Err(RepositoryCredentialsError::InvalidHeaderName(name.clone()));
+                    }
+                    if !is_header_value(value) {
+                        return Err(RepositoryCredentialsError::InvalidHeaderValue(name.clone()));
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Header values are visible ASCII characters, spaces and tabs
+fn is_header_value(value: &str) -> bool {
+    value
+        .bytes()
+        .all(|c| c == b'\t' || (b' '..=b'~').contains(&c))
+}
+
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum RepositoryCredentialsError {
+    #[error("Username cannot be empty nor contain `:`")]
+    InvalidUsername,
+    #[error("Token cannot be empty and must only contain visible ASCII characters")]
+    InvalidToken,
+    #[error("At least one header is required")]
+    NoHeaders,
+    #[error("Invalid header name `{0}`")]
+    InvalidHeaderName(String),
+    #[error("Invalid value for the header `{0}`")]
+    InvalidHeaderValue(String),
+}
+
+impl fmt::Debug for RepositoryCredentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "RepositoryCredentials::{}(<redacted>)", self.kind())
+    }
+}
+
+/// Synchronization state of a repository index
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum RepositorySyncStatus {
+    /// The index has never been fetched
+    #[default]
+    Pending,
+    /// The index is being fetched
+    Syncing,
+    /// The last fetch succeeded
+    Ok,
+    /// The last fetch failed, see [`RepositorySync::last_error`]
+    Failed,
+}
+
+/// Result of the last synchronizations of a repository index
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RepositorySync {
+    pub status: RepositorySyncStatus,
+    /// Date of the last successful synchronization
+    pub last_synced_at: Option<DateTime<Utc>>,
+    /// Error of the last synchronization, cleared on success
+    pub last_error: Option<String>,
+    /// Number of packages in the last synchronized index
+    pub package_count: Option<u32>,
+    /// Version of the last synchronized index
+    pub index_version: Option<IndexVersion>,
+    /// Revision of the source the last index was read from, e.g. a Git commit SHA
+    pub revision: Option<String>,
+}
+
+impl RepositorySync {
+    /// The synchronization started
+    pub fn syncing(&self) -> Self {
+        Self {
+            status: RepositorySyncStatus::Syncing,
+            ..self.clone()
+        }
+    }
+
+    /// The synchronization succeeded without changes in the index
+    pub fn unchanged(&self, at: DateTime<Utc>) -> Self {
+        Self {
+            status: RepositorySyncStatus::Ok,
+            last_synced_at: Some(at),
+            last_error: None,
+            ..self.clone()
+        }
+    }
+
+    /// The synchronization succeeded with a new index
+    pub fn synced(
+        at: DateTime<Utc>,
+        package_count: u32,
+        index_version: IndexVersion,
+        revision: Option<String>,
+    ) -> Self {
+        Self {
+            status: RepositorySyncStatus::Ok,
+            last_synced_at: Some(at),
+            last_error: None,
+            package_count: Some(package_count),
+            index_version: Some(index_version),
+            revision,
+        }
+    }
+
+    /// The synchronization failed, the previous results are kept
+    pub fn failed(&self, error: impl ToString) -> Self {
+        Self {
+            status: RepositorySyncStatus::Failed,
+            last_error: Some(error.to_string()),
+            ..self.clone()
+        }
+    }
+
+    /// The synchronization was interrupted, e.g. by a shutdown, the status is restored from the
+    /// previous results
+    pub fn interrupted(&self) -> Self {
+        let status = if self.last_error.is_some() {
+            RepositorySyncStatus::Failed
+        } else if self.last_synced_at.is_some() {
+            RepositorySyncStatus::Ok
+        } else {
+            RepositorySyncStatus::Pending
+        };
+
+        Self {
+            status,
+            ..self.clone()
+        }
+    }
+}
+
 /// Create Repository model
 #[derive(Debug, Clone, PartialEq)]
 pub struct CreateRepository {
     /// The name of the repository
     pub name: String,
+    /// The source of the repository index
+    pub kind: RepositoryKind,
     /// The url of the repository
     pub url: String,
+    /// Public keys allowed to sign the repository index
+    pub trusted_keys: Vec<TrustedKey>,
+    /// Credentials of the repository, if it is private
+    pub credentials: Option<RepositoryCredentials>,
+    /// Priority of the repository over the others publishing the same packages
+    pub priority: i32,
+    /// Whether the repository is managed by the configuration
+    pub managed: bool,
+}
+
+/// Update Repository model, only the given fields are changed
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct UpdateRepository {
+    /// The new name of the repository
+    pub name: Option<String>,
+    /// The new source of the repository index
+    pub kind: Option<RepositoryKind>,
+    /// The new url of the repository
+    pub url: Option<String>,
+    /// The new public keys allowed to sign the repository index
+    pub trusted_keys: Option<Vec<TrustedKey>>,
+    /// The new credentials of the repository, `Some(None)` removes them
+    pub credentials: Option<Option<RepositoryCredentials>>,
+    /// The new priority of the repository
+    pub priority: Option<i32>,
+    /// Whether the repository is now managed by the configuration
+    pub managed: Option<bool>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn credentials_should_not_be_displayed() {
+        let credentials = RepositoryCredentials::Basic {
+            username: "admin".to_string(),
+            password: "secret".to_string(),
+        };
+
+        let debug = format!("{:?}", credentials);
+
+        assert!(!debug.contains("secret"));
+        assert_eq!(debug, "RepositoryCredentials::basic(<redacted>)");
+    }
+
+    #[test]
+    fn credentials_headers_should_be_valid_http_headers() {
+        let headers = |name: &str, value: &str| RepositoryCredentials::Headers {
+            headers: BTreeMap::from([(name.to_string(), value.to_string())]),
+        };
+
+        assert_eq!(headers("X-Api-Key", "k3y").validate(), Ok(()));
+        assert_eq!(
+            headers("X Api Key", "k3y").validate(),
+            Err(RepositoryCredentialsError::InvalidHeaderName(
+                "X Api Key".to_string()
+            ))
+        );
+        assert_eq!(
+            headers("X-Api-Key", "k3y\r\n").validate(),
+            Err(RepositoryCredentialsError::InvalidHeaderValue(
+                "X-Api-Key".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn failed_sync_should_keep_previous_results() {
+        let synced_at = Utc::now();
+        let sync = RepositorySync::synced(
+            synced_at,
+            3,
+            IndexVersion { major: 1, minor: 0 },
+            Some("0b7e3a5".to_string()),
+        );
+
+        let failed = sync.syncing().failed("timeout");
+
+        assert_eq!(failed.status, RepositorySyncStatus::Failed);
+        assert_eq!(failed.last_error, Some("timeout".to_string()));
+        assert_eq!(failed.last_synced_at, Some(synced_at));
+        assert_eq!(failed.package_count, Some(3));
+        assert_eq!(failed.revision, Some("0b7e3a5".to_string()));
+    }
+
+    #[test]
+    fn unchanged_sync_should_clear_the_last_error() {
+        let sync = RepositorySync::default().failed("timeout");
+        let now = Utc::now();
+
+        let unchanged = sync.unchanged(now);
+
+        assert_eq!(unchanged.status, RepositorySyncStatus::Ok);
+        assert_eq!(unchanged.last_error, None);
+        assert_eq!(unchanged.last_synced_at, Some(now));
+    }
 }
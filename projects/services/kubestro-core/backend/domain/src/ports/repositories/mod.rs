@@ -1,2 +1,3 @@
+pub mod packages_repository;
 pub mod repositories_repositories;
 pub mod user_repository;
@@ -0,0 +1,129 @@
+use crate::{
+    models::{
+        index::{IndexPackage, PackageChannel},
+        package::RepositoryId,
+    },
+    services::package_graph::CatalogConflict,
+};
+
+/// Field used to sort the catalog packages
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum CatalogSort {
+    /// Most relevant packages for the search first, by name without a search
+    #[default]
+    Relevance,
+    Name,
+    Version,
+}
+
+/// Sort direction of the catalog packages
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// A package of the catalog, along with the repository publishing it
+#[derive(Debug, Clone, PartialEq)]
+pub struct CatalogEntry {
+    pub repository_id: RepositoryId,
+    pub package: IndexPackage,
+}
+
+/// Search among the stored packages
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PackageSearch {
+    /// Only keep packages matching every word of this value, in their name, game, tags or
+    /// description
+    ///
+    /// Words match as prefixes, and the name, game and tags of the packages also match words
+    /// with a few typos.
+    pub search: Option<String>,
+    /// Only keep packages from this repository
+    pub repository_id: Option<RepositoryId>,
+    /// Only keep packages managing this game
+    pub game: Option<String>,
+    /// Only keep packages published on this channel
+    pub channel: Option<PackageChannel>,
+    /// Only keep packages having all these tags
+    pub tags: Vec<String>,
+    pub sort: CatalogSort,
+    pub order: SortOrder,
+    /// Number of matching packages to skip
+    pub offset: u64,
+    /// Maximum number of packages to return
+    pub limit: u64,
+}
+
+/// Number of matching packages sharing a value
+#[derive(Debug, Clone, PartialEq)]
+pub struct FacetCount {
+    pub value: String,
+    pub count: u64,
+}
+
+/// Number of matching packages by game, tag, repository and channel, most frequent first
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CatalogFacets {
+    pub games: Vec<FacetCount>,
+    pub tags: Vec<FacetCount>,
+    /// Counts by repository id
+    pub repositories: Vec<FacetCount>,
+    pub channels: Vec<FacetCount>,
+}
+
+/// The packages matching a search
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PackageSearchResult {
+    pub packages: Vec<CatalogEntry>,
+    /// Total number of matching packages, regardless of the offset and the limit
+    pub total: u64,
+    /// Counts of every matching package, regardless of the offset and the limit
+    pub facets: CatalogFacets,
+}
+
+/// Store of the packages parsed from the repositories indexes
+#[cfg_attr(test, mockall::automock)]
+#[async_trait::async_trait]
+pub trait PackagesRepository: Send + Sync {
+    /// Replace every package of a repository at once
+    ///
+    /// The swap is atomic: readers see either the previous packages or the new ones.
+    async fn replace(
+        &self,
+        repository_id: &RepositoryId,
+        packages: Vec<IndexPackage>,
+    ) -> Result<(), PackageRepoError>;
+    /// Remove every package of a repository
+    async fn delete(&self, repository_id: &RepositoryId) -> Result<(), PackageRepoError>;
+    /// Search the packages, sorted and paginated
+    async fn search(&self, search: PackageSearch) -> Result<PackageSearchResult, PackageRepoError>;
+    /// Get every version of a package published by a repository
+    async fn find_versions(
+        &self,
+        repository_id: &RepositoryId,
+        name: &str,
+    ) -> Result<Vec<IndexPackage>, PackageRepoError>;
+    /// Get every stored package, of every repository
+    async fn find_all(&self) -> Result<Vec<CatalogEntry>, PackageRepoError>;
+    /// Replace the conflicts found between the packages of every repository
+    async fn replace_conflicts(
+        &self,
+        conflicts: Vec<CatalogConflict>,
+    ) -> Result<(), PackageRepoError>;
+    /// Get the stored conflicts, in their order, only the ones involving a repository when
+    /// given
+    async fn find_conflicts(
+        &self,
+        repository_id: Option<RepositoryId>,
+    ) -> Result<Vec<CatalogConflict>, PackageRepoError>;
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum PackageRepoError {
+    #[error("Database error: {0}")]
+    DatabaseError(String),
+    #[error("Invalid stored package: {0}")]
+    InvalidPackage(String),
+}
@@ -1,4 +1,6 @@
-use crate::models::package::{CreateRepository, Repository, RepositoryId};
+use crate::models::package::{
+    CreateRepository, Repository, RepositoryId, RepositorySync, UpdateRepository,
+};
 
 #[cfg_attr(test, mockall::automock)]
 #[async_trait::async_trait]
@@ -10,7 +12,18 @@ pub trait RepositoriesRepository: Send + Sync {
     async fn find_one(&self, id: &RepositoryId) -> Result<Option<Repository>, RepositoryRepoError>;
     async fn create(&self, repository: CreateRepository)
         -> Result<Repository, RepositoryRepoError>;
+    async fn update(
+        &self,
+        repository_id: &RepositoryId,
+        repository: UpdateRepository,
+    ) -> Result<Repository, RepositoryRepoError>;
     async fn delete(&self, repository_id: &RepositoryId) -> Result<(), RepositoryRepoError>;
+    /// Persist the synchronization state of a repository
+    async fn update_sync(
+        &self,
+        repository_id: &RepositoryId,
+        sync: RepositorySync,
+    ) -> Result<(), RepositoryRepoError>;
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -0,0 +1,58 @@
+use crate::{
+    models::package::RepositoryId,
+    ports::{
+        repositories::repositories_repositories::RepositoryRepoError,
+        services::repositories_service::RepositoriesServiceError,
+    },
+};
+
+/// A repository of an uploaded bundle
+#[derive(Debug, Clone, PartialEq)]
+pub struct BundledRepository {
+    /// Name of the repository in the bundle
+    pub name: String,
+    /// Url of the `bundle` repositories serving it, `bundle://<name>`
+    pub url: String,
+    /// Url of the repository the bundle was exported from
+    pub source: String,
+    pub package_count: u32,
+    pub index_version: String,
+    /// Whether the index signature was verified, the indexes of repositories without trusted
+    /// keys are bundled unsigned
+    pub signed: bool,
+    /// Number of artifacts and icons bundled along with the index
+    pub file_count: u32,
+}
+
+/// Mirror repositories in self-contained archives, for the clusters without network access
+///
+/// A bundle holds the indexes of repositories, as published along with their signature, and
+/// every artifact and icon their packages reference.
+#[cfg_attr(test, mockall::automock)]
+#[async_trait::async_trait]
+pub trait BundlesService: Send + Sync {
+    /// Export repositories in a single bundle archive
+    ///
+    /// The indexes are fetched again from their source, along with every file they reference.
+    async fn export(&self, repositories: &[RepositoryId]) -> Result<Vec<u8>, BundlesServiceError>;
+    /// Verify an uploaded bundle archive and store its repositories
+    ///
+    /// The repositories of the same names uploaded before are replaced, and the `bundle`
+    /// repositories serving them are refreshed.
+    async fn import(&self, archive: Vec<u8>)
+        -> Result<Vec<BundledRepository>, BundlesServiceError>;
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum BundlesServiceError {
+    #[error(transparent)]
+    RepositoryError(#[from] RepositoryRepoError),
+    #[error(transparent)]
+    RepositoriesServiceError(#[from] RepositoriesServiceError),
+    #[error("Invalid bundle: {0}")]
+    InvalidBundle(String),
+    #[error("Failed to store the bundle: {0}")]
+    StorageError(String),
+    #[error("Unexpected error: {0}")]
+    UnexpectedError(String),
+}
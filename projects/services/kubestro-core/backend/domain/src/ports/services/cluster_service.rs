@@ -0,0 +1,94 @@
+use std::time::Duration;
+
+use crate::models::game_manager::{
+    GameManagerInstallation, GameManagerMigration, GameManagerRevision, NewGameManager,
+    NewGameManagerRevision,
+};
+
+/// Manage the game managers installed on the Kubernetes cluster
+///
+/// The game managers are declared as custom resources, the controller deploys them and reports
+/// their state.
+#[cfg_attr(test, mockall::automock)]
+#[async_trait::async_trait]
+pub trait ClusterService: Send + Sync {
+    /// Declare a game manager, returning it as created
+    ///
+    /// The game manager is recorded as the first revision of its history.
+    async fn create_game_manager(
+        &self,
+        game_manager: NewGameManager,
+    ) -> Result<GameManagerInstallation, ClusterServiceError>;
+
+    /// List the game managers, along with their pods
+    async fn list_game_managers(&self)
+        -> Result<Vec<GameManagerInstallation>, ClusterServiceError>;
+
+    /// Find a game manager by its name, along with its pods
+    async fn find_game_manager(
+        &self,
+        name: &str,
+    ) -> Result<Option<GameManagerInstallation>, ClusterServiceError>;
+
+    /// Count the game servers owned by a game manager
+    ///
+    /// The game servers are the resources of the kinds declared by the game manager, labelled
+    /// with its name. The kinds not installed on the cluster have no game servers.
+    async fn count_game_servers(
+        &self,
+        game_manager: &GameManagerInstallation,
+    ) -> Result<usize, ClusterServiceError>;
+
+    /// Delete the game servers owned by a game manager
+    async fn delete_game_servers(
+        &self,
+        game_manager: &GameManagerInstallation,
+    ) -> Result<(), ClusterServiceError>;
+
+    /// Delete a game manager, its objects are removed by the controller
+    async fn delete_game_manager(&self, name: &str) -> Result<(), ClusterServiceError>;
+
+    /// List the revisions of a game manager, oldest first
+    async fn list_revisions(
+        &self,
+        name: &str,
+    ) -> Result<Vec<GameManagerRevision>, ClusterServiceError>;
+
+    /// Deploy a new revision of a game manager and record it in its history
+    ///
+    /// The game manager is deployed in the background, as after its installation. The current
+    /// state of a game manager without history is recorded first, so that it can be rolled
+    /// back to. The rollout deadline of the revision is stored along with the game manager, and
+    /// [`ClusterServiceError::Conflict`] is returned when the revision it replaces is not the
+    /// latest one anymore.
+    async fn deploy_revision(
+        &self,
+        name: &str,
+        revision: NewGameManagerRevision,
+    ) -> Result<GameManagerRevision, ClusterServiceError>;
+
+    /// Clear the rollout deadline of a game manager, once its revision became available
+    async fn clear_rollout_deadline(&self, name: &str) -> Result<(), ClusterServiceError>;
+
+    /// Run the migration of the custom resources of a game manager, and wait for it to succeed
+    async fn run_migration(
+        &self,
+        name: &str,
+        migration: GameManagerMigration,
+        timeout: Duration,
+    ) -> Result<(), ClusterServiceError>;
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ClusterServiceError {
+    #[error("A game manager named `{0}` already exists")]
+    AlreadyExists(String),
+    #[error("Game manager `{0}` not found")]
+    NotFound(String),
+    #[error("Kubernetes API error: {0}")]
+    ApiError(String),
+    #[error("The migration of game manager `{0}` failed: {1}")]
+    MigrationFailed(String, String),
+    #[error("Game manager `{0}` was changed in the meantime")]
+    Conflict(String),
+}
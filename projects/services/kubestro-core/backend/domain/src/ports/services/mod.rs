@@ -1 +1,3 @@
+pub mod bundles_service;
+pub mod cluster_service;
 pub mod repositories_service;
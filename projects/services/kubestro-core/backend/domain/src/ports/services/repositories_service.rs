@@ -1,30 +1,72 @@
 use crate::{
-    models::package::{CreateRepository, Repository, RepositoryId},
-    ports::repositories::repositories_repositories::RepositoryRepoError,
+    models::{
+        index::IndexError,
+        package::{CreateRepository, Repository, RepositoryId, UpdateRepository},
+    },
+    ports::repositories::{
+        packages_repository::PackageRepoError, repositories_repositories::RepositoryRepoError,
+    },
 };
 
+#[cfg_attr(test, mockall::automock)]
 #[async_trait::async_trait]
 pub trait RepositoriesService: Send + Sync {
-    /// Create a new repository, cache its remote information and return it
+    /// Create a new repository, store the packages of its index and return it
+    ///
+    /// The url must be allowed by the outbound policy.
     async fn create(
         &self,
         repository: CreateRepository,
     ) -> Result<Repository, RepositoriesServiceError>;
-    /// Delete a repository and remove its data from the cache
+    /// Update a repository, its index is fetched again when its url changes
+    ///
+    /// A new url must be allowed by the outbound policy. The repositories managed by the configuration cannot be updated.
+    async fn update(
+        &self,
+        repository_id: &RepositoryId,
+        repository: UpdateRepository,
+    ) -> Result<Repository, RepositoriesServiceError>;
+    /// Delete a repository and remove its packages
+    ///
+    /// The repositories managed by the configuration cannot be deleted.
     async fn delete(&self, repository_id: &RepositoryId) -> Result<(), RepositoriesServiceError>;
 
-    /// Update the cache for all repositories
+    /// Update the package store for all repositories
     async fn update_cache(&self, force: bool) -> Result<(), RepositoriesServiceError>;
+    /// Fetch the remote index of a single repository and store its packages
+    ///
+    /// Unless `force` is set, the stored index is revalidated instead of downloaded again.
+    async fn refresh_cache(
+        &self,
+        repository: &Repository,
+        force: bool,
+    ) -> Result<(), RepositoriesServiceError>;
+    /// Find the conflicts between the packages of the repositories again, when the stored
+    /// packages changed since the last time
+    ///
+    /// Every stored package is loaded: it runs once after a batch of refreshes, not after each
+    /// of them.
+    async fn update_conflicts(&self) -> Result<(), RepositoriesServiceError>;
 }
 
 #[derive(Debug, thiserror::Error)]
 pub enum RepositoriesServiceError {
     #[error(transparent)]
     RepositoryError(#[from] RepositoryRepoError),
+    #[error(transparent)]
+    PackageError(#[from] PackageRepoError),
     #[error("Failed to use cache: {0}")]
     CachingError(String),
     #[error("Failed to fetch remote data: {0}")]
     RemoteDataError(String),
+    #[error(transparent)]
+    InvalidIndex(#[from] IndexError),
+    #[error("Invalid index signature: {0}")]
+    InvalidSignature(String),
+    #[error("The repository url is not allowed: {0}")]
+    UrlNotAllowed(String),
+    #[error("The repository `{0}` is managed by the configuration and cannot be changed")]
+    Managed(String),
     #[error("Unexpected error: {0}")]
     UnexpectedError(String),
 }
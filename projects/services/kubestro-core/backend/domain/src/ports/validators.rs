@@ -5,3 +5,32 @@ use mockall::automock;
 pub trait PasswordValidator: Send + Sync {
     fn validate(&self, password: &str) -> Result<(), validator::ValidationError>;
 }
+
+/// A configuration value not matching the schema of its package
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValueViolation {
+    /// JSON pointer to the value, empty for the root
+    pub path: String,
+    pub message: String,
+}
+
+/// Check the configuration values of a game manager against the JSON schema of its package
+#[cfg_attr(test, automock)]
+pub trait ValuesValidator: Send + Sync {
+    fn validate(
+        &self,
+        schema: &serde_json::Value,
+        values: &serde_json::Value,
+    ) -> Result<(), ValuesValidationError>;
+}
+
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum ValuesValidationError {
+    #[error("The values schema of the package is invalid: {0}")]
+    InvalidSchema(String),
+    #[error(
+        "The values do not match the schema of the package: {}",
+        .0.iter().map(|violation| format!("{} ({})", violation.message, violation.path)).collect::<Vec<_>>().join(", ")
+    )]
+    InvalidValues(Vec<ValueViolation>),
+}
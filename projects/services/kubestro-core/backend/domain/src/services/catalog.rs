@@ -0,0 +1,648 @@
+use std::{cmp::Reverse, sync::Arc};
+
+use semver::Version;
+
+pub use crate::ports::repositories::packages_repository::{
+    CatalogEntry, CatalogFacets, CatalogSort, FacetCount, SortOrder,
+};
+use crate::{
+    models::{
+        index::{IndexPackage, PackageChannel},
+        package::{Repository, RepositoryId, RepositorySyncStatus},
+    },
+    ports::repositories::{
+        packages_repository::{PackageRepoError, PackageSearch, PackagesRepository},
+        repositories_repositories::{RepositoriesRepository, RepositoryRepoError},
+    },
+    services::package_graph::CatalogConflict,
+};
+
+/// Default number of packages returned per page
+pub const DEFAULT_CATALOG_PER_PAGE: usize = 20;
+/// Maximum number of packages returned per page
+pub const MAX_CATALOG_PER_PAGE: usize = 100;
+
+/// Catalog search parameters
+#[derive(Debug, Clone, PartialEq)]
+pub struct CatalogQuery {
+    /// Full-text search over the name, game, tags and description of the packages
+    pub search: Option<String>,
+    /// Only keep packages from this repository
+    pub repository_id: Option<RepositoryId>,
+    /// Only keep packages managing this game
+    pub game: Option<String>,
+    /// Only keep packages published on this channel
+    pub channel: Option<PackageChannel>,
+    /// Only keep packages having all these tags
+    pub tags: Vec<String>,
+    pub sort: CatalogSort,
+    pub order: SortOrder,
+    /// Page number, starting at 1
+    pub page: usize,
+    pub per_page: usize,
+}
+
+impl Default for CatalogQuery {
+    fn default() -> Self {
+        Self {
+            search: None,
+            repository_id: None,
+            game: None,
+            channel: None,
+            tags: vec![],
+            sort: CatalogSort::default(),
+            order: SortOrder::default(),
+            page: 1,
+            per_page: DEFAULT_CATALOG_PER_PAGE,
+        }
+    }
+}
+
+/// State of the packages of a repository
+#[derive(Debug, Clone, PartialEq)]
+pub enum CatalogRepositoryStatus {
+    /// The index is synchronized and its packages are part of the catalog
+    Ok,
+    /// The index has not been synchronized yet
+    Missing,
+    /// The index could never be synchronized
+    Failed(String),
+}
+
+impl From<&Repository> for CatalogRepositoryStatus {
+    fn from(repository: &Repository) -> Self {
+        // The packages of the last synchronized index are kept when a synchronization fails
+        if repository.sync.package_count.is_some() {
+            return Self::Ok;
+        }
+
+        match (&repository.sync.status, &repository.sync.last_error) {
+            (RepositorySyncStatus::Failed, Some(error)) => Self::Failed(error.clone()),
+            _ => Self::Missing,
+        }
+    }
+}
+
+/// A repository taking part in the catalog
+#[derive(Debug, Clone)]
+pub struct CatalogRepository {
+    pub repository: Repository,
+    pub status: CatalogRepositoryStatus,
+}
+
+/// A page of the catalog
+#[derive(Debug, Clone)]
+pub struct CatalogPage {
+    /// Every repository matching the query, even the ones without packages in this page
+    pub repositories: Vec<CatalogRepository>,
+    /// The packages of the requested page
+    pub packages: Vec<CatalogEntry>,
+    /// Total number of packages matching the query
+    pub total: usize,
+    pub page: usize,
+    pub per_page: usize,
+    /// Counts of the packages matching the query, to refine it
+    pub facets: CatalogFacets,
+    /// Conflicts involving the repositories of the page
+    pub conflicts: Vec<CatalogConflict>,
+}
+
+/// A version of a package, along with its compatibility with the running Kubestro core
+#[derive(Debug, Clone, PartialEq)]
+pub struct PackageVersion {
+    pub package: IndexPackage,
+    /// Whether the version can be installed on the running Kubestro core
+    pub compatible: bool,
+}
+
+/// Every version of a package published by a repository
+#[derive(Debug, Clone, PartialEq)]
+pub struct PackageVersions {
+    pub repository_id: RepositoryId,
+    pub name: String,
+    /// The versions offered on the requested channel, newest first
+    pub versions: Vec<PackageVersion>,
+    /// The newest compatible version offered on the requested channel
+    pub latest: Option<Version>,
+}
+
+/// A version of a package, detailed for review before its installation
+#[derive(Debug, Clone, PartialEq)]
+pub struct PackageDetail {
+    pub repository_id: RepositoryId,
+    /// The detailed version
+    pub version: PackageVersion,
+    /// Every version of the package, newest first, to show their changelog
+    pub versions: Vec<PackageVersion>,
+}
+
+pub struct CatalogService {
+    repositories_repo: Arc<dyn RepositoriesRepository>,
+    packages_repo: Arc<dyn PackagesRepository>,
+    core_version: Version,
+}
+
+impl CatalogService {
+    pub fn new(
+        repositories_repo: Arc<dyn RepositoriesRepository>,
+        packages_repo: Arc<dyn PackagesRepository>,
+    ) -> Self {
+        Self {
+            repositories_repo,
+            packages_repo,
+            core_version: Version::new(0, 0, 0),
+        }
+    }
+
+    /// Set the version of the running Kubestro core, checked against the package requirements
+    pub fn with_core_version(mut self, core_version: Version) -> Self {
+        self.core_version = core_version;
+        self
+    }
+
+    /// Search the packages of every repository
+    ///
+    /// The filters, the sort and the pagination are run by the package store.
+    #[tracing::instrument(skip(self))]
+    pub async fn search(&self, query: CatalogQuery) -> Result<CatalogPage, CatalogServiceError> {
+        let page = query.page.max(1);
+        let per_page = query.per_page.clamp(1, MAX_CATALOG_PER_PAGE);
+
+        let result = self
+            .packages_repo
+            .search(PackageSearch {
+                search: query.search.filter(|search| !search.is_empty()),
+                repository_id: query.repository_id.clone(),
+                game: query.game,
+                channel: query.channel,
+                tags: query.tags,
+                sort: query.sort,
+                order: query.order,
+                offset: ((page - 1).saturating_mul(per_page)) as u64,
+                limit: per_page as u64,
+            })
+            .await?;
+
+        let repositories = self.repositories().await?;
+        // Found when the packages are stored, they involve every repository
+        let conflicts = self
+            .packages_repo
+            .find_conflicts(query.repository_id.clone())
+            .await?;
+
+        let catalog_repositories = repositories
+            .into_iter()
+            .filter(|repository| {
+                query
+                    .repository_id
+                    .as_ref()
+                    .is_none_or(|id| &repository.id == id)
+            })
+            .map(|repository| CatalogRepository {
+                status: (&repository).into(),
+                repository,
+            })
+            .collect::<Vec<_>>();
+
+        Ok(CatalogPage {
+            repositories: catalog_repositories,
+            packages: result.packages,
+            total: result.total.try_into().unwrap_or(usize::MAX),
+            page,
+            per_page,
+            facets: result.facets,
+            conflicts,
+        })
+    }
+
+    /// Get the conflicts between the packages of every repository, found when they were stored
+    #[tracing::instrument(skip(self))]
+    pub async fn conflicts(&self) -> Result<Vec<CatalogConflict>, CatalogServiceError> {
+        Ok(self.packages_repo.find_conflicts(None).await?)
+    }
+
+    /// Get every repository, by priority
+    async fn repositories(&self) -> Result<Vec<Repository>, CatalogServiceError> {
+        let mut repositories = self.repositories_repo.find_all(None).await?;
+        repositories.sort_by_key(|repository| Reverse(repository.priority));
+
+        Ok(repositories)
+    }
+
+    /// List the versions of a package offered on a channel
+    #[tracing::instrument(skip(self))]
+    pub async fn versions(
+        &self,
+        repository_id: &RepositoryId,
+        name: &str,
+        channel: PackageChannel,
+    ) -> Result<PackageVersions, CatalogServiceError> {
+        let mut packages = self
+            .packages(repository_id, name)
+            .await?
+            .into_iter()
+            .filter(|package| channel.includes(package.channel))
+            .collect::<Vec<_>>();
+        packages.sort_by(|a, b| b.version.cmp(&a.version));
+
+        let latest = latest_compatible(&packages, channel, &self.core_version)
+            .map(|package| package.version.clone());
+        let versions = packages
+            .into_iter()
+            .map(|package| self.package_version(package))
+            .collect();
+
+        Ok(PackageVersions {
+            repository_id: repository_id.clone(),
+            name: name.to_string(),
+            versions,
+            latest,
+        })
+    }
+
+    /// Resolve the newest version of a package offered on a channel that can be installed on
+    /// the running Kubestro core
+    #[tracing::instrument(skip(self))]
+    pub async fn resolve_latest(
+        &self,
+        repository_id: &RepositoryId,
+        name: &str,
+        channel: PackageChannel,
+    ) -> Result<IndexPackage, CatalogServiceError> {
+        let packages = self.packages(repository_id, name).await?;
+
+        latest_compatible(&packages, channel, &self.core_version)
+            .cloned()
+            .ok_or_else(|| CatalogServiceError::NoCompatibleVersion {
+                name: name.to_string(),
+                channel,
+                core_version: self.core_version.clone(),
+            })
+    }
+
+    /// Get the details of a version of a package
+    ///
+    /// Without an explicit version, the newest compatible version of the channel is detailed,
+    /// or the newest version of the channel when none is compatible.
+    #[tracing::instrument(skip(self))]
+    pub async fn detail(
+        &self,
+        repository_id: &RepositoryId,
+        name: &str,
+        version: Option<&Version>,
+        channel: PackageChannel,
+    ) -> Result<PackageDetail, CatalogServiceError> {
+        let mut packages = self.packages(repository_id, name).await?;
+        packages.sort_by(|a, b| b.version.cmp(&a.version));
+
+        let detailed = match version {
+            Some(version) => packages
+                .iter()
+                .find(|package| &package.version == version)
+                .ok_or_else(|| CatalogServiceError::VersionNotFound {
+                    name: name.to_string(),
+                    version: version.clone(),
+                })?,
+            None => latest_compatible(&packages, channel, &self.core_version)
+                .or_else(|| {
+                    packages
+                        .iter()
+                        .find(|package| channel.includes(package.channel))
+                })
+                .ok_or_else(|| CatalogServiceError::NoCompatibleVersion {
+                    name: name.to_string(),
+                    channel,
+                    core_version: self.core_version.clone(),
+                })?,
+        }
+        .clone();
+
+        Ok(PackageDetail {
+            repository_id: repository_id.clone(),
+            version: self.package_version(detailed),
+            versions: packages
+                .into_iter()
+                .map(|package| self.package_version(package))
+                .collect(),
+        })
+    }
+
+    fn package_version(&self, package: IndexPackage) -> PackageVersion {
+        PackageVersion {
+            compatible: package.is_compatible_with(&self.core_version),
+            package,
+        }
+    }
+
+    /// Get every version of a package from the package store
+    async fn packages(
+        &self,
+        repository_id: &RepositoryId,
+        name: &str,
+    ) -> Result<Vec<IndexPackage>, CatalogServiceError> {
+        let repository = self
+            .repositories_repo
+            .find_one(repository_id)
+            .await?
+            .ok_or(RepositoryRepoError::NotFound)?;
+
+        if let CatalogRepositoryStatus::Missing | CatalogRepositoryStatus::Failed(_) =
+            CatalogRepositoryStatus::from(&repository)
+        {
+            return Err(CatalogServiceError::IndexUnavailable(
+                "the index is not synchronized yet".to_string(),
+            ));
+        }
+
+        let packages = self
+            .packages_repo
+            .find_versions(repository_id, name)
+            .await?;
+        if packages.is_empty() {
+            return Err(CatalogServiceError::PackageNotFound(name.to_string()));
+        }
+
+        Ok(packages)
+    }
+}
+
+/// Pick the newest version offered on `channel` that can be installed on `core_version`
+pub fn latest_compatible<'a>(
+    packages: impl IntoIterator<Item = &'a IndexPackage>,
+    channel: PackageChannel,
+    core_version: &Version,
+) -> Option<&'a IndexPackage> {
+    packages
+        .into_iter()
+        .filter(|package| channel.includes(package.channel))
+        .filter(|package| package.is_compatible_with(core_version))
+        .max_by(|a, b| a.version.cmp(&b.version))
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum CatalogServiceError {
+    #[error(transparent)]
+    RepositoryError(#[from] RepositoryRepoError),
+    #[error(transparent)]
+    PackageError(#[from] PackageRepoError),
+    #[error("The repository index is unavailable: {0}")]
+    IndexUnavailable(String),
+    #[error("Package `{0}` not found")]
+    PackageNotFound(String),
+    #[error("Version {version} of package `{name}` not found")]
+    VersionNotFound { name: String, version: Version },
+    #[error(
+        "No version of `{name}` on the {channel} channel is compatible with Kubestro {core_version}"
+    )]
+    NoCompatibleVersion {
+        name: String,
+        channel: PackageChannel,
+        core_version: Version,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        models::package::RepositorySync,
+        ports::repositories::{
+            packages_repository::{MockPackagesRepository, PackageSearchResult},
+            repositories_repositories::MockRepositoriesRepository,
+        },
+        test_support::{package, repository, versions_service},
+    };
+
+    use super::*;
+
+    fn catalog_service(
+        repositories: Vec<Repository>,
+        packages_repo: MockPackagesRepository,
+    ) -> CatalogService {
+        let mut repositories_repo = MockRepositoriesRepository::new();
+        repositories_repo
+            .expect_find_all()
+            .returning(move |_| Ok(repositories.clone()));
+
+        CatalogService::new(Arc::new(repositories_repo), Arc::new(packages_repo))
+    }
+
+    #[tokio::test]
+    async fn catalog_should_report_repository_status() {
+        let ok = repository("ok");
+        let missing = Repository {
+            sync: RepositorySync::default(),
+            ..repository("missing")
+        };
+        let failed = Repository {
+            sync: RepositorySync::default().failed("connection refused"),
+            ..repository("failed")
+        };
+        let entry = CatalogEntry {
+            repository_id: ok.id.clone(),
+            package: package("minecraft", "1.0.0", ""),
+        };
+
+        let mut packages_repo = MockPackagesRepository::new();
+        let result = PackageSearchResult {
+            packages: vec![entry.clone()],
+            total: 1,
+            ..Default::default()
+        };
+        packages_repo
+            .expect_search()
+            .returning(move |_| Ok(result.clone()));
+        let conflict = CatalogConflict::DuplicateName {
+            name: "minecraft".to_string(),
+            repositories: vec![ok.id.clone(), failed.id.clone()],
+        };
+        let stored = conflict.clone();
+        packages_repo
+            .expect_find_conflicts()
+            .withf(|repository_id| repository_id.is_none())
+            .returning(move |_| Ok(vec![stored.clone()]));
+        let service = catalog_service(vec![ok, missing, failed], packages_repo);
+
+        let page = service.search(CatalogQuery::default()).await.unwrap();
+
+        let statuses = page
+            .repositories
+            .iter()
+            .map(|repository| repository.status.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            statuses,
+            vec![
+                CatalogRepositoryStatus::Ok,
+                CatalogRepositoryStatus::Missing,
+                CatalogRepositoryStatus::Failed("connection refused".to_string()),
+            ]
+        );
+        assert_eq!(page.total, 1);
+        assert_eq!(page.packages.len(), 1);
+        assert_eq!(page.conflicts, vec![conflict]);
+    }
+
+    #[tokio::test]
+    async fn catalog_should_be_searched_and_paginated_by_the_store() {
+        let repository = repository("demo");
+        let id = repository.id.clone();
+
+        let mut packages_repo = MockPackagesRepository::new();
+        packages_repo
+            .expect_search()
+            .withf(move |search| {
+                search
+                    == &PackageSearch {
+                        search: None,
+                        repository_id: Some(id.clone()),
+                        game: Some("minecraft".to_string()),
+                        channel: Some(PackageChannel::Beta),
+                        tags: vec!["sandbox".to_string()],
+                        sort: CatalogSort::Version,
+                        order: SortOrder::Desc,
+                        offset: 200,
+                        limit: MAX_CATALOG_PER_PAGE as u64,
+                    }
+            })
+            .returning(|_| {
+                Ok(PackageSearchResult {
+                    packages: vec![],
+                    total: 250,
+                    facets: CatalogFacets {
+                        games: vec![FacetCount {
+                            value: "minecraft".to_string(),
+                            count: 250,
+                        }],
+                        ..Default::default()
+                    },
+                })
+            });
+        let id = repository.id.clone();
+        packages_repo
+            .expect_find_conflicts()
+            .withf(move |repository_id| repository_id.as_ref() == Some(&id))
+            .returning(|_| Ok(vec![]));
+        let service = catalog_service(vec![repository.clone()], packages_repo);
+
+        let query = CatalogQuery {
+            search: Some(String::new()),
+            repository_id: Some(repository.id),
+            game: Some("minecraft".to_string()),
+            channel: Some(PackageChannel::Beta),
+            tags: vec!["sandbox".to_string()],
+            sort: CatalogSort::Version,
+            order: SortOrder::Desc,
+            page: 3,
+            per_page: 500,
+        };
+        let page = service.search(query).await.unwrap();
+
+        assert_eq!(page.total, 250);
+        assert_eq!(page.facets.games[0].count, 250);
+        assert_eq!(page.per_page, MAX_CATALOG_PER_PAGE);
+        assert_eq!(page.repositories.len(), 1);
+    }
+
+    fn release(version: &str, channel: PackageChannel, kubestro_version: &str) -> IndexPackage {
+        IndexPackage {
+            channel,
+            kubestro_version: Some(kubestro_version.parse().unwrap()),
+            ..package("minecraft", version, "")
+        }
+    }
+
+    #[tokio::test]
+    async fn versions_should_be_listed_newest_first_for_the_channel() {
+        let (service, id) = versions_service(vec![
+            release("1.0.0", PackageChannel::Stable, ">=0.1"),
+            release("1.2.0-beta.1", PackageChannel::Beta, ">=0.2"),
+            release("1.1.0", PackageChannel::Stable, ">=0.3"),
+            release("1.3.0-nightly", PackageChannel::Nightly, "*"),
+            package("terraria", "2.0.0", ""),
+        ]);
+
+        let versions = service
+            .versions(&id, "minecraft", PackageChannel::Beta)
+            .await
+            .unwrap();
+
+        let listed = versions
+            .versions
+            .iter()
+            .map(|version| (version.package.version.to_string(), version.compatible))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            listed,
+            vec![
+                ("1.2.0-beta.1".to_string(), true),
+                ("1.1.0".to_string(), false),
+                ("1.0.0".to_string(), true),
+            ]
+        );
+        assert_eq!(
+            versions.latest,
+            Some(Version::parse("1.2.0-beta.1").unwrap())
+        );
+    }
+
+    #[tokio::test]
+    async fn latest_version_should_honor_the_core_version() {
+        let (service, id) = versions_service(vec![
+            release("1.0.0", PackageChannel::Stable, ">=0.1"),
+            release("1.1.0", PackageChannel::Stable, ">=0.3"),
+            release("1.2.0-beta.1", PackageChannel::Beta, ">=0.2"),
+        ]);
+
+        let latest = service
+            .resolve_latest(&id, "minecraft", PackageChannel::Stable)
+            .await
+            .unwrap();
+        let missing = service
+            .resolve_latest(&id, "terraria", PackageChannel::Stable)
+            .await;
+
+        assert_eq!(latest.version, Version::new(1, 0, 0));
+        assert!(matches!(
+            missing,
+            Err(CatalogServiceError::PackageNotFound(name)) if name == "terraria"
+        ));
+    }
+
+    #[tokio::test]
+    async fn detail_should_default_to_the_latest_compatible_version() {
+        let (service, id) = versions_service(vec![
+            release("1.0.0", PackageChannel::Stable, ">=0.1"),
+            release("1.1.0", PackageChannel::Stable, ">=0.3"),
+        ]);
+
+        let latest = service
+            .detail(&id, "minecraft", None, PackageChannel::Stable)
+            .await
+            .unwrap();
+        let pinned = service
+            .detail(
+                &id,
+                "minecraft",
+                Some(&Version::new(1, 1, 0)),
+                PackageChannel::Stable,
+            )
+            .await
+            .unwrap();
+        let missing = service
+            .detail(
+                &id,
+                "minecraft",
+                Some(&Version::new(2, 0, 0)),
+                PackageChannel::Stable,
+            )
+            .await;
+
+        assert_eq!(latest.version.package.version, Version::new(1, 0, 0));
+        assert_eq!(latest.versions.len(), 2);
+        assert!(!pinned.version.compatible);
+        assert!(matches!(
+            missing,
+            Err(CatalogServiceError::VersionNotFound { .. })
+        ));
+    }
+}
@@ -0,0 +1,1323 @@
+use std::{sync::Arc, time::Duration};
+
+use chrono::{DateTime, Utc};
+use semver::Version;
+use tracing::warn;
+
+use crate::{
+    models::{
+        game_manager::{
+            is_valid_game_manager_name, GameManagerInstallation, GameManagerMigration,
+            GameManagerPhase, GameManagerRevision, GameManagerRevisionCause, NewGameManager,
+            NewGameManagerRevision,
+        },
+        index::PackageChannel,
+        package::RepositoryId,
+    },
+    ports::{
+        repositories::repositories_repositories::RepositoryRepoError,
+        services::cluster_service::{ClusterService, ClusterServiceError},
+        validators::{ValueViolation, ValuesValidationError, ValuesValidator},
+    },
+    services::catalog::{CatalogService, CatalogServiceError, PackageVersion},
+};
+
+/// Install a game manager from the catalog
+#[derive(Debug, Clone, PartialEq)]
+pub struct InstallGameManager {
+    /// Name of the game manager, the name of the package by default
+    pub name: Option<String>,
+    pub repository_id: RepositoryId,
+    pub package: String,
+    pub version: Version,
+    /// Configuration values, an empty object by default
+    pub values: Option<serde_json::Value>,
+    /// Name of the user installing the game manager
+    pub user: Option<String>,
+}
+
+/// Upgrade an installed game manager
+#[derive(Debug, Clone, PartialEq)]
+pub struct UpgradeGameManager {
+    /// Version to upgrade to, the installed version to only change the values
+    pub version: Option<Version>,
+    /// Configuration values, the current ones by default
+    pub values: Option<serde_json::Value>,
+    /// Name of the user upgrading the game manager
+    pub user: Option<String>,
+}
+
+/// Roll an installed game manager back to a previous revision
+#[derive(Debug, Clone, PartialEq)]
+pub struct RollbackGameManager {
+    /// Revision to restore, the one preceding the current revision by default
+    pub revision: Option<u32>,
+    /// Name of the user rolling the game manager back
+    pub user: Option<String>,
+}
+
+/// A revision deployed on a game manager
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeployedRevision {
+    pub game_manager: GameManagerDetail,
+    pub revision: GameManagerRevision,
+}
+
+/// A game manager installed on the cluster, along with the catalog package it comes from
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameManagerDetail {
+    pub installation: GameManagerInstallation,
+    /// Installed version of the package, `None` when the catalog does not offer it anymore
+    pub package: Option<PackageVersion>,
+}
+
+/// Default time given to an upgraded game manager to become available
+pub const DEFAULT_ROLLOUT_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+/// Default time given to the migration of a game manager to succeed
+pub const DEFAULT_MIGRATION_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+/// Delay between two checks of the state of the upgraded game managers
+pub const ROLLOUT_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Install the game managers published in the catalog on the cluster
+pub struct GameManagersService {
+    catalog: Arc<CatalogService>,
+    validator: Arc<dyn ValuesValidator>,
+    cluster: Option<Arc<dyn ClusterService>>,
+    rollout_timeout: Duration,
+    migration_timeout: Duration,
+}
+
+impl GameManagersService {
+    pub fn new(catalog: Arc<CatalogService>, validator: Arc<dyn ValuesValidator>) -> Self {
+        Self {
+            catalog,
+            validator,
+            cluster: None,
+            rollout_timeout: DEFAULT_ROLLOUT_TIMEOUT,
+            migration_timeout: DEFAULT_MIGRATION_TIMEOUT,
+        }
+    }
+
+    /// Set the cluster the game managers are installed on, nothing can be installed without it
+    pub fn with_cluster(mut self, cluster: Arc<dyn ClusterService>) -> Self {
+        self.cluster = Some(cluster);
+        self
+    }
+
+    /// Set the time given to an upgraded game manager to become available before it is
+    /// rolled back
+    pub fn with_rollout_timeout(mut self, timeout: Duration) -> Self {
+        self.rollout_timeout = timeout;
+        self
+    }
+
+    /// Set the time given to the migration of a game manager to succeed
+    pub fn with_migration_timeout(mut self, timeout: Duration) -> Self {
+        self.migration_timeout = timeout;
+        self
+    }
+
+    fn cluster(&self) -> Result<&Arc<dyn ClusterService>, GameManagersServiceError> {
+        self.cluster
+            .as_ref()
+            .ok_or(GameManagersServiceError::ClusterUnavailable)
+    }
+
+    /// Install a version of a package of the catalog
+    ///
+    /// The version must be compatible with the running Kubestro core, and the values must
+    /// match the schema published by the package. The game manager is declared on the cluster
+    /// and deployed in the background, its state is returned as created.
+    #[tracing::instrument(skip(self))]
+    pub async fn install(
+        &self,
+        request: InstallGameManager,
+    ) -> Result<GameManagerDetail, GameManagersServiceError> {
+        let cluster = self.cluster()?;
+
+        let name = request.name.unwrap_or_else(|| request.package.clone());
+        if !is_valid_game_manager_name(&name) {
+            return Err(GameManagersServiceError::InvalidName(name));
+        }
+
+        // The channel only matters when no version is given
+        let detail = self
+            .catalog
+            .detail(
+                &request.repository_id,
+                &request.package,
+                Some(&request.version),
+                PackageChannel::Nightly,
+            )
+            .await?;
+        let package = detail.version.package.clone();
+        let image = Self::deployable(&detail.version)?;
+
+        let values = request
+            .values
+            .unwrap_or_else(|| serde_json::Value::Object(Default::default()));
+        self.validate_values(package.values_schema.as_ref(), &values)?;
+
+        let installation = cluster
+            .create_game_manager(NewGameManager {
+                name,
+                repository_id: request.repository_id,
+                package: package.name,
+                version: package.version,
+                image,
+                values,
+                permissions: package.permissions,
+                game_servers: package.crds,
+                created_by: request.user,
+            })
+            .await?;
+
+        Ok(GameManagerDetail {
+            installation,
+            package: Some(detail.version),
+        })
+    }
+
+    /// Upgrade a game manager to a newer version offered by its repository, or change its values
+    ///
+    /// The migration declared by the new version runs first, then the new revision is deployed
+    /// in the background. The previous revision is restored by [`Self::check_rollouts`] if the
+    /// new one does not become available in time.
+    #[tracing::instrument(skip(self))]
+    pub async fn upgrade(
+        &self,
+        name: &str,
+        request: UpgradeGameManager,
+    ) -> Result<DeployedRevision, GameManagersServiceError> {
+        let cluster = self.cluster()?;
+        let installation = self.find(name).await?;
+        let (repository_id, current) = catalog_reference(&installation)
+            .ok_or_else(|| GameManagersServiceError::NotFromCatalog(name.to_string()))?;
+
+        let version = request.version.unwrap_or_else(|| current.clone());
+        if version < current {
+            return Err(GameManagersServiceError::Downgrade {
+                name: name.to_string(),
+                from: current,
+                to: version,
+            });
+        }
+
+        let detail = self
+            .catalog
+            .detail(
+                &repository_id,
+                &installation.package,
+                Some(&version),
+                PackageChannel::Nightly,
+            )
+            .await?;
+        let package = detail.version.package.clone();
+        let image = Self::deployable(&detail.version)?;
+        let values = request.values.unwrap_or(installation.values);
+        self.validate_values(package.values_schema.as_ref(), &values)?;
+
+        // The custom resources are migrated before the new version starts reading them
+        if let Some(migration) = package
+            .migration
+            .filter(|migration| current < version && migration.applies_from(&current))
+        {
+            cluster
+                .run_migration(
+                    name,
+                    GameManagerMigration {
+                        image: migration.image.unwrap_or_else(|| image.clone()),
+                        command: migration.command,
+                        from_version: current.to_string(),
+                        to_version: version.clone(),
+                    },
+                    self.migration_timeout,
+                )
+                .await?;
+        }
+
+        let rollout_deadline = chrono::Duration::from_std(self.rollout_timeout)
+            .ok()
+            .and_then(|timeout| Utc::now().checked_add_signed(timeout))
+            .unwrap_or(DateTime::<Utc>::MAX_UTC);
+        let revision = cluster
+            .deploy_revision(
+                name,
+                NewGameManagerRevision {
+                    version: package.version.to_string(),
+                    image,
+                    values,
+                    permissions: package.permissions,
+                    game_servers: package.crds,
+                    cause: GameManagerRevisionCause::Upgrade,
+                    created_by: request.user,
+                    rollout_deadline: Some(rollout_deadline),
+                    replaces: None,
+                },
+            )
+            .await?;
+
+        Ok(DeployedRevision {
+            game_manager: self.get(name).await?,
+            revision,
+        })
+    }
+
+    /// Roll a game manager back to a revision of its history
+    ///
+    /// The version of the revision must still be offered by the repository, it is deployed with
+    /// the values of the revision. No migration runs, the custom resources are expected to stay
+    /// readable by the previous versions.
+    #[tracing::instrument(skip(self))]
+    pub async fn rollback(
+        &self,
+        name: &str,
+        request: RollbackGameManager,
+    ) -> Result<DeployedRevision, GameManagersServiceError> {
+        let cluster = self.cluster()?;
+        let installation = self.find(name).await?;
+        let (repository_id, _) = catalog_reference(&installation)
+            .ok_or_else(|| GameManagersServiceError::NotFromCatalog(name.to_string()))?;
+
+        let revisions = cluster.list_revisions(name).await?;
+        let target =
+            match request.revision {
+                Some(number) => revisions
+                    .iter()
+                    .find(|revision| revision.revision == number)
+                    .ok_or_else(|| GameManagersServiceError::RevisionNotFound {
+                        name: name.to_string(),
+                        revision: number,
+                    })?,
+                None => revisions.iter().rev().nth(1).ok_or_else(|| {
+                    GameManagersServiceError::NoPreviousRevision(name.to_string())
+                })?,
+            };
+
+        let version = Version::parse(&target.version).map_err(|_| {
+            GameManagersServiceError::InvalidRevision {
+                name: name.to_string(),
+                revision: target.revision,
+                version: target.version.clone(),
+            }
+        })?;
+        let detail = self
+            .catalog
+            .detail(
+                &repository_id,
+                &installation.package,
+                Some(&version),
+                PackageChannel::Nightly,
+            )
+            .await?;
+        let package = detail.version.package.clone();
+        let image = Self::deployable(&detail.version)?;
+
+        let revision = cluster
+            .deploy_revision(
+                name,
+                NewGameManagerRevision {
+                    version: package.version.to_string(),
+                    image,
+                    values: target.values.clone(),
+                    permissions: package.permissions,
+                    game_servers: package.crds,
+                    cause: GameManagerRevisionCause::Rollback,
+                    created_by: request.user,
+                    rollout_deadline: None,
+                    replaces: None,
+                },
+            )
+            .await?;
+
+        Ok(DeployedRevision {
+            game_manager: self.get(name).await?,
+            revision,
+        })
+    }
+
+    /// Restore the previous revision of the game managers which did not become available by
+    /// their rollout deadline, returning the restored revisions
+    ///
+    /// The deadlines are stored along with the game managers, the rollouts interrupted by a
+    /// restart are checked as well. The deadline of an available game manager is cleared.
+    #[tracing::instrument(skip(self))]
+    pub async fn check_rollouts(
+        &self,
+    ) -> Result<Vec<GameManagerRevision>, GameManagersServiceError> {
+        let cluster = self.cluster()?;
+        let now = Utc::now();
+        let mut restored = vec![];
+
+        for game_manager in cluster.list_game_managers().await? {
+            let Some(deadline) = game_manager.rollout_deadline else {
+                continue;
+            };
+
+            let result = match game_manager.state.phase {
+                GameManagerPhase::Available => cluster
+                    .clear_rollout_deadline(&game_manager.name)
+                    .await
+                    .map(|_| None),
+                GameManagerPhase::Deleting => continue,
+                _ if now < deadline => continue,
+                _ => {
+                    restore_previous_revision(cluster.as_ref(), &game_manager.name, deadline).await
+                }
+            };
+            match result {
+                Ok(revision) => restored.extend(revision),
+                Err(e) => warn!(
+                    "Failed to check the rollout of game manager `{}`: {}",
+                    game_manager.name, e
+                ),
+            }
+        }
+
+        Ok(restored)
+    }
+
+    /// List the revisions of a game manager, oldest first
+    #[tracing::instrument(skip(self))]
+    pub async fn history(
+        &self,
+        name: &str,
+    ) -> Result<Vec<GameManagerRevision>, GameManagersServiceError> {
+        self.find(name).await?;
+
+        Ok(self.cluster()?.list_revisions(name).await?)
+    }
+
+    /// Check that a version of a package can be deployed, returning its image
+    fn deployable(version: &PackageVersion) -> Result<String, GameManagersServiceError> {
+        let package = &version.package;
+        if !version.compatible {
+            return Err(GameManagersServiceError::IncompatibleVersion {
+                name: package.name.clone(),
+                version: package.version.clone(),
+            });
+        }
+
+        let Some(image) = package.image.clone() else {
+            return Err(GameManagersServiceError::NotInstallable(
+                package.name.clone(),
+                "the package does not publish an image".to_string(),
+            ));
+        };
+        if let Some(rule) = package.permissions.iter().find(|rule| rule.cluster_wide) {
+            return Err(GameManagersServiceError::NotInstallable(
+                package.name.clone(),
+                format!("the cluster-wide permission `{}` is not supported", rule),
+            ));
+        }
+
+        Ok(image)
+    }
+
+    /// List the installed game managers
+    #[tracing::instrument(skip(self))]
+    pub async fn list(&self) -> Result<Vec<GameManagerDetail>, GameManagersServiceError> {
+        let installations = self.cluster()?.list_game_managers().await?;
+
+        let mut game_managers = Vec::with_capacity(installations.len());
+        for installation in installations {
+            game_managers.push(self.detail(installation).await?);
+        }
+
+        Ok(game_managers)
+    }
+
+    /// Get an installed game manager by its name
+    #[tracing::instrument(skip(self))]
+    pub async fn get(&self, name: &str) -> Result<GameManagerDetail, GameManagersServiceError> {
+        let installation = self.find(name).await?;
+
+        self.detail(installation).await
+    }
+
+    /// Uninstall a game manager
+    ///
+    /// A game manager still owning game servers is only uninstalled when the deletion is
+    /// cascaded, its game servers being deleted first.
+    #[tracing::instrument(skip(self))]
+    pub async fn uninstall(
+        &self,
+        name: &str,
+        cascade: bool,
+    ) -> Result<(), GameManagersServiceError> {
+        let cluster = self.cluster()?;
+        let installation = self.find(name).await?;
+
+        if cascade {
+            cluster.delete_game_servers(&installation).await?;
+        } else {
+            let count = cluster.count_game_servers(&installation).await?;
+            if count > 0 {
+                return Err(GameManagersServiceError::OwnsGameServers {
+                    name: name.to_string(),
+                    count,
+                });
+            }
+        }
+
+        cluster.delete_game_manager(name).await?;
+
+        Ok(())
+    }
+
+    async fn find(&self, name: &str) -> Result<GameManagerInstallation, GameManagersServiceError> {
+        self.cluster()?
+            .find_game_manager(name)
+            .await?
+            .ok_or_else(|| ClusterServiceError::NotFound(name.to_string()).into())
+    }
+
+    /// Look up the installed version of a game manager in the catalog
+    async fn detail(
+        &self,
+        installation: GameManagerInstallation,
+    ) -> Result<GameManagerDetail, GameManagersServiceError> {
+        // The resources created with `kubectl` may not reference a package of the catalog
+        let Some((repository_id, version)) = catalog_reference(&installation) else {
+            return Ok(GameManagerDetail {
+                installation,
+                package: None,
+            });
+        };
+
+        let package = match self
+            .catalog
+            .detail(
+                &repository_id,
+                &installation.package,
+                Some(&version),
+                PackageChannel::Nightly,
+            )
+            .await
+        {
+            Ok(detail) => Some(detail.version),
+            Err(
+                CatalogServiceError::RepositoryError(RepositoryRepoError::NotFound)
+                | CatalogServiceError::IndexUnavailable(_)
+                | CatalogServiceError::PackageNotFound(_)
+                | CatalogServiceError::VersionNotFound { .. },
+            ) => None,
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(GameManagerDetail {
+            installation,
+            package,
+        })
+    }
+
+    /// Check that the values are an object matching the schema of the package, if any
+    fn validate_values(
+        &self,
+        schema: Option<&serde_json::Value>,
+        values: &serde_json::Value,
+    ) -> Result<(), GameManagersServiceError> {
+        if !values.is_object() {
+            return Err(ValuesValidationError::InvalidValues(vec![ValueViolation {
+                path: String::new(),
+                message: "the values must be an object".to_string(),
+            }])
+            .into());
+        }
+
+        if let Some(schema) = schema {
+            self.validator.validate(schema, values)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Read the repository and the version of the package a game manager was installed from
+fn catalog_reference(installation: &GameManagerInstallation) -> Option<(RepositoryId, Version)> {
+    let repository_id = uuid::Uuid::parse_str(&installation.repository_id).ok()?;
+    let version = Version::parse(&installation.version).ok()?;
+
+    Some((RepositoryId::from(repository_id), version))
+}
+
+/// Restore the revision preceding the latest one of a game manager, which did not become
+/// available by its rollout deadline
+///
+/// Only the latest revision is replaced: nothing is restored when another revision was deployed
+/// in the meantime, it has its own deadline. Without previous revision, the deadline is cleared.
+async fn restore_previous_revision(
+    cluster: &dyn ClusterService,
+    name: &str,
+    deadline: DateTime<Utc>,
+) -> Result<Option<GameManagerRevision>, ClusterServiceError> {
+    let revisions = cluster.list_revisions(name).await?;
+    let (Some(latest), Some(previous)) = (revisions.last(), revisions.iter().rev().nth(1)) else {
+        cluster.clear_rollout_deadline(name).await?;
+        return Ok(None);
+    };
+
+    warn!(
+        "Game manager `{}` did not become available by {}, restoring revision {}",
+        name, deadline, previous.revision
+    );
+    let restored = cluster
+        .deploy_revision(
+            name,
+            NewGameManagerRevision {
+                version: previous.version.clone(),
+                image: previous.image.clone(),
+                values: previous.values.clone(),
+                permissions: previous.permissions.clone(),
+                game_servers: previous.game_servers.clone(),
+                cause: GameManagerRevisionCause::AutomaticRollback,
+                created_by: None,
+                rollout_deadline: None,
+                replaces: Some(latest.revision),
+            },
+        )
+        .await;
+
+    match restored {
+        Ok(revision) => Ok(Some(revision)),
+        Err(ClusterServiceError::Conflict(_)) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum GameManagersServiceError {
+    #[error(transparent)]
+    CatalogError(#[from] CatalogServiceError),
+    #[error(transparent)]
+    ClusterError(#[from] ClusterServiceError),
+    #[error(transparent)]
+    InvalidValues(#[from] ValuesValidationError),
+    #[error("No Kubernetes cluster is available to install game managers")]
+    ClusterUnavailable,
+    #[error(
+        "Invalid game manager name `{0}`, it must only contain lowercase letters, digits and \
+        dashes, start with a letter and be at most 63 characters long"
+    )]
+    InvalidName(String),
+    #[error("Version {version} of `{name}` is not compatible with the running Kubestro core")]
+    IncompatibleVersion { name: String, version: Version },
+    #[error("Package `{0}` cannot be installed: {1}")]
+    NotInstallable(String, String),
+    #[error("Game manager `{name}` still owns {count} game server(s)")]
+    OwnsGameServers { name: String, count: usize },
+    #[error("Game manager `{0}` was not installed from a package of the catalog")]
+    NotFromCatalog(String),
+    #[error("Cannot upgrade game manager `{name}` from version {from} to the older {to}, roll it back instead")]
+    Downgrade {
+        name: String,
+        from: Version,
+        to: Version,
+    },
+    #[error("Revision {revision} of game manager `{name}` not found")]
+    RevisionNotFound { name: String, revision: u32 },
+    #[error("Game manager `{0}` has no previous revision to roll back to")]
+    NoPreviousRevision(String),
+    #[error("Revision {revision} of game manager `{name}` has the invalid version `{version}`")]
+    InvalidRevision {
+        name: String,
+        revision: u32,
+        version: String,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::{
+        models::{
+            game_manager::{GameManagerPhase, GameManagerState},
+            index::{IndexPackage, PackageMigration, PermissionRule},
+        },
+        ports::{services::cluster_service::MockClusterService, validators::MockValuesValidator},
+        test_support::{package, versions_service},
+    };
+
+    use super::*;
+
+    fn installable(version: &str) -> IndexPackage {
+        IndexPackage {
+            image: Some(format!("ghcr.io/kubestro/minecraft:{}", version)),
+            values_schema: Some(json!({ "type": "object" })),
+            kubestro_version: Some(">=0.2".parse().unwrap()),
+            ..package("minecraft", version, "")
+        }
+    }
+
+    fn service(
+        packages: Vec<IndexPackage>,
+        validator: MockValuesValidator,
+        cluster: MockClusterService,
+    ) -> (GameManagersService, RepositoryId) {
+        let (catalog, id) = versions_service(packages);
+        let service = GameManagersService::new(Arc::new(catalog), Arc::new(validator))
+            .with_cluster(Arc::new(cluster));
+
+        (service, id)
+    }
+
+    fn request(id: &RepositoryId, version: &str, values: serde_json::Value) -> InstallGameManager {
+        InstallGameManager {
+            name: None,
+            repository_id: id.clone(),
+            package: "minecraft".to_string(),
+            version: Version::parse(version).unwrap(),
+            values: Some(values),
+            user: Some("admin".to_string()),
+        }
+    }
+
+    fn created(game_manager: NewGameManager) -> GameManagerInstallation {
+        GameManagerInstallation {
+            name: game_manager.name,
+            repository_id: game_manager.repository_id.to_string(),
+            package: game_manager.package,
+            version: game_manager.version.to_string(),
+            image: game_manager.image,
+            values: game_manager.values,
+            created_at: None,
+            game_servers: game_manager.game_servers,
+            rollout_deadline: None,
+            state: GameManagerState::new(vec![], false, false),
+            workload: None,
+        }
+    }
+
+    fn installed(id: &RepositoryId, package: &str, version: &str) -> GameManagerInstallation {
+        GameManagerInstallation {
+            name: package.to_string(),
+            repository_id: id.to_string(),
+            package: package.to_string(),
+            version: version.to_string(),
+            image: format!("ghcr.io/kubestro/{}:{}", package, version),
+            values: json!({}),
+            created_at: None,
+            game_servers: vec![],
+            rollout_deadline: None,
+            state: GameManagerState::new(vec![], true, false),
+            workload: None,
+        }
+    }
+
+    fn revision(number: u32, version: &str, values: serde_json::Value) -> GameManagerRevision {
+        GameManagerRevision {
+            revision: number,
+            version: version.to_string(),
+            image: format!("ghcr.io/kubestro/minecraft:{}", version),
+            values,
+            permissions: vec![],
+            game_servers: vec![],
+            cause: Some(GameManagerRevisionCause::Install),
+            created_at: None,
+            created_by: None,
+        }
+    }
+
+    fn deployed(number: u32, revision: NewGameManagerRevision) -> GameManagerRevision {
+        GameManagerRevision {
+            revision: number,
+            version: revision.version,
+            image: revision.image,
+            values: revision.values,
+            permissions: revision.permissions,
+            game_servers: revision.game_servers,
+            cause: Some(revision.cause),
+            created_at: None,
+            created_by: revision.created_by,
+        }
+    }
+
+    #[tokio::test]
+    async fn install_should_declare_the_game_manager() {
+        let mut validator = MockValuesValidator::new();
+        validator
+            .expect_validate()
+            .withf(|schema, values| {
+                schema == &json!({ "type": "object" }) && values == &json!({ "motd": "Hello" })
+            })
+            .returning(|_, _| Ok(()));
+        let mut cluster = MockClusterService::new();
+        cluster
+            .expect_create_game_manager()
+            .withf(|game_manager| {
+                game_manager.name == "minecraft"
+                    && game_manager.image == "ghcr.io/kubestro/minecraft:1.1.0"
+            })
+            .returning(|game_manager| Ok(created(game_manager)));
+        let (service, id) = service(
+            vec![installable("1.0.0"), installable("1.1.0")],
+            validator,
+            cluster,
+        );
+
+        let detail = service
+            .install(request(&id, "1.1.0", json!({ "motd": "Hello" })))
+            .await
+            .unwrap();
+
+        assert_eq!(detail.installation.version, "1.1.0");
+        assert_eq!(detail.installation.repository_id, id.to_string());
+        assert_eq!(detail.installation.state.phase, GameManagerPhase::Pending);
+        assert!(detail.package.is_some_and(|package| package.compatible));
+    }
+
+    #[tokio::test]
+    async fn install_should_reject_values_not_matching_the_schema() {
+        let mut validator = MockValuesValidator::new();
+        validator.expect_validate().returning(|_, _| {
+            Err(ValuesValidationError::InvalidValues(vec![ValueViolation {
+                path: "/motd".to_string(),
+                message: "42 is not of type \"string\"".to_string(),
+            }]))
+        });
+        let (service, id) = service(
+            vec![installable("1.0.0")],
+            validator,
+            MockClusterService::new(),
+        );
+
+        let result = service
+            .install(request(&id, "1.0.0", json!({ "motd": 42 })))
+            .await;
+        let not_object = service.install(request(&id, "1.0.0", json!([]))).await;
+
+        assert!(matches!(
+            result,
+            Err(GameManagersServiceError::InvalidValues(ValuesValidationError::InvalidValues(violations)))
+                if violations[0].path == "/motd"
+        ));
+        assert!(matches!(
+            not_object,
+            Err(GameManagersServiceError::InvalidValues(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn install_should_reject_packages_that_cannot_be_installed() {
+        let incompatible = IndexPackage {
+            kubestro_version: Some(">=0.3".parse().unwrap()),
+            ..installable("1.0.0")
+        };
+        let without_image = IndexPackage {
+            image: None,
+            ..installable("1.1.0")
+        };
+        let cluster_wide = IndexPackage {
+            permissions: vec![PermissionRule {
+                api_groups: vec![String::new()],
+                resources: vec!["nodes".to_string()],
+                verbs: vec!["list".to_string()],
+                cluster_wide: true,
+            }],
+            ..installable("1.2.0")
+        };
+        let (service, id) = service(
+            vec![incompatible, without_image, cluster_wide],
+            MockValuesValidator::new(),
+            MockClusterService::new(),
+        );
+
+        let incompatible = service.install(request(&id, "1.0.0", json!({}))).await;
+        let without_image = service.install(request(&id, "1.1.0", json!({}))).await;
+        let cluster_wide = service.install(request(&id, "1.2.0", json!({}))).await;
+        let missing = service.install(request(&id, "2.0.0", json!({}))).await;
+
+        assert!(matches!(
+            incompatible,
+            Err(GameManagersServiceError::IncompatibleVersion { .. })
+        ));
+        assert!(matches!(
+            without_image,
+            Err(GameManagersServiceError::NotInstallable(_, reason)) if reason.contains("image")
+        ));
+        assert!(matches!(
+            cluster_wide,
+            Err(GameManagersServiceError::NotInstallable(_, reason)) if reason.contains("cluster-wide")
+        ));
+        assert!(matches!(
+            missing,
+            Err(GameManagersServiceError::CatalogError(
+                CatalogServiceError::VersionNotFound { .. }
+            ))
+        ));
+    }
+
+    #[tokio::test]
+    async fn install_should_check_the_name_and_the_cluster() {
+        let (service, id) = service(
+            vec![installable("1.0.0")],
+            MockValuesValidator::new(),
+            MockClusterService::new(),
+        );
+        let (catalog, _) = versions_service(vec![]);
+        let without_cluster =
+            GameManagersService::new(Arc::new(catalog), Arc::new(MockValuesValidator::new()));
+
+        let invalid_name = service
+            .install(InstallGameManager {
+                name: Some("Minecraft Server".to_string()),
+                ..request(&id, "1.0.0", json!({}))
+            })
+            .await;
+        let unavailable = without_cluster
+            .install(request(&id, "1.0.0", json!({})))
+            .await;
+
+        assert!(matches!(
+            invalid_name,
+            Err(GameManagersServiceError::InvalidName(_))
+        ));
+        assert!(matches!(
+            unavailable,
+            Err(GameManagersServiceError::ClusterUnavailable)
+        ));
+    }
+
+    #[tokio::test]
+    async fn list_should_merge_the_catalog_packages() {
+        let (catalog, id) = versions_service(vec![installable("1.0.0")]);
+        let mut cluster = MockClusterService::new();
+        let installations = vec![
+            installed(&id, "minecraft", "1.0.0"),
+            installed(&id, "terraria", "1.0.0"),
+            GameManagerInstallation {
+                repository_id: "manual".to_string(),
+                ..installed(&id, "valheim", "1.0.0")
+            },
+        ];
+        cluster
+            .expect_list_game_managers()
+            .returning(move || Ok(installations.clone()));
+        let service =
+            GameManagersService::new(Arc::new(catalog), Arc::new(MockValuesValidator::new()))
+                .with_cluster(Arc::new(cluster));
+
+        let game_managers = service.list().await.unwrap();
+
+        assert_eq!(game_managers.len(), 3);
+        assert_eq!(
+            game_managers[0]
+                .package
+                .as_ref()
+                .map(|package| package.package.image.clone()),
+            Some(Some("ghcr.io/kubestro/minecraft:1.0.0".to_string()))
+        );
+        // Neither the unknown packages nor the resources created by hand are in the catalog
+        assert!(game_managers[1].package.is_none());
+        assert!(game_managers[2].package.is_none());
+    }
+
+    #[tokio::test]
+    async fn get_should_fail_for_unknown_game_managers() {
+        let mut cluster = MockClusterService::new();
+        cluster.expect_find_game_manager().returning(|_| Ok(None));
+        let (service, _) = service(vec![], MockValuesValidator::new(), cluster);
+
+        let result = service.get("minecraft").await;
+
+        assert!(matches!(
+            result,
+            Err(GameManagersServiceError::ClusterError(ClusterServiceError::NotFound(name)))
+                if name == "minecraft"
+        ));
+    }
+
+    #[tokio::test]
+    async fn uninstall_should_refuse_while_game_servers_remain() {
+        let (_, id) = versions_service(vec![]);
+        let installation = installed(&id, "minecraft", "1.0.0");
+        let mut cluster = MockClusterService::new();
+        cluster
+            .expect_find_game_manager()
+            .returning(move |_| Ok(Some(installation.clone())));
+        cluster.expect_count_game_servers().returning(|_| Ok(2));
+        cluster.expect_delete_game_manager().never();
+        let (service, _) = service(vec![], MockValuesValidator::new(), cluster);
+
+        let result = service.uninstall("minecraft", false).await;
+
+        assert!(matches!(
+            result,
+            Err(GameManagersServiceError::OwnsGameServers { count: 2, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn uninstall_should_cascade_to_the_game_servers() {
+        let (_, id) = versions_service(vec![]);
+        let installation = installed(&id, "minecraft", "1.0.0");
+        let mut cluster = MockClusterService::new();
+        let mut sequence = mockall::Sequence::new();
+        cluster
+            .expect_find_game_manager()
+            .returning(move |_| Ok(Some(installation.clone())));
+        cluster.expect_count_game_servers().never();
+        cluster
+            .expect_delete_game_servers()
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|_| Ok(()));
+        cluster
+            .expect_delete_game_manager()
+            .withf(|name| name == "minecraft")
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|_| Ok(()));
+        let (service, _) = service(vec![], MockValuesValidator::new(), cluster);
+
+        service.uninstall("minecraft", true).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn upgrade_should_migrate_the_resources_then_deploy() {
+        let (_, id) = versions_service(vec![]);
+        let installation = GameManagerInstallation {
+            values: json!({ "motd": "Hello" }),
+            ..installed(&id, "minecraft", "1.0.0")
+        };
+        let migrated = IndexPackage {
+            migration: Some(PackageMigration {
+                image: None,
+                command: vec!["/migrate".to_string()],
+                from: Some(">=1.0.0, <1.1.0".parse().unwrap()),
+            }),
+            ..installable("1.1.0")
+        };
+        let mut validator = MockValuesValidator::new();
+        validator.expect_validate().returning(|_, _| Ok(()));
+        let mut cluster = MockClusterService::new();
+        let mut sequence = mockall::Sequence::new();
+        cluster
+            .expect_find_game_manager()
+            .returning(move |_| Ok(Some(installation.clone())));
+        cluster
+            .expect_run_migration()
+            .withf(|name, migration, _| {
+                name == "minecraft"
+                    && migration.image == "ghcr.io/kubestro/minecraft:1.1.0"
+                    && migration.from_version == "1.0.0"
+                    && migration.to_version == Version::new(1, 1, 0)
+            })
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|_, _, _| Ok(()));
+        cluster
+            .expect_deploy_revision()
+            .withf(|_, revision| {
+                revision.version == "1.1.0"
+                    && revision.values == json!({ "motd": "Hello" })
+                    && revision.cause == GameManagerRevisionCause::Upgrade
+                    && revision.created_by.as_deref() == Some("admin")
+                    && revision
+                        .rollout_deadline
+                        .is_some_and(|deadline| deadline > Utc::now())
+            })
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|_, revision| Ok(deployed(2, revision)));
+        let (service, _) = service(vec![installable("1.0.0"), migrated], validator, cluster);
+
+        let deployed = service
+            .upgrade(
+                "minecraft",
+                UpgradeGameManager {
+                    version: Some(Version::new(1, 1, 0)),
+                    values: None,
+                    user: Some("admin".to_string()),
+                },
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(deployed.revision.revision, 2);
+        assert_eq!(deployed.revision.image, "ghcr.io/kubestro/minecraft:1.1.0");
+    }
+
+    #[tokio::test]
+    async fn upgrade_should_refuse_downgrades() {
+        let (_, id) = versions_service(vec![]);
+        let installation = installed(&id, "minecraft", "1.1.0");
+        let mut cluster = MockClusterService::new();
+        cluster
+            .expect_find_game_manager()
+            .returning(move |_| Ok(Some(installation.clone())));
+        cluster.expect_deploy_revision().never();
+        let (service, _) = service(
+            vec![installable("1.0.0"), installable("1.1.0")],
+            MockValuesValidator::new(),
+            cluster,
+        );
+
+        let result = service
+            .upgrade(
+                "minecraft",
+                UpgradeGameManager {
+                    version: Some(Version::new(1, 0, 0)),
+                    values: None,
+                    user: None,
+                },
+            )
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(GameManagersServiceError::Downgrade { from, to, .. })
+                if from == Version::new(1, 1, 0) && to == Version::new(1, 0, 0)
+        ));
+    }
+
+    #[tokio::test]
+    async fn rollback_should_restore_the_previous_revision() {
+        let (_, id) = versions_service(vec![]);
+        let installation = installed(&id, "minecraft", "1.1.0");
+        let revisions = vec![
+            revision(1, "1.0.0", json!({ "motd": "Hello" })),
+            revision(2, "1.1.0", json!({ "motd": "Hi" })),
+        ];
+        let mut cluster = MockClusterService::new();
+        cluster
+            .expect_find_game_manager()
+            .returning(move |_| Ok(Some(installation.clone())));
+        cluster
+            .expect_list_revisions()
+            .returning(move |_| Ok(revisions.clone()));
+        cluster
+            .expect_deploy_revision()
+            .withf(|_, revision| {
+                revision.version == "1.0.0"
+                    && revision.image == "ghcr.io/kubestro/minecraft:1.0.0"
+                    && revision.values == json!({ "motd": "Hello" })
+                    && revision.cause == GameManagerRevisionCause::Rollback
+            })
+            .times(1)
+            .returning(|_, revision| Ok(deployed(3, revision)));
+        let (service, _) = service(
+            vec![installable("1.0.0"), installable("1.1.0")],
+            MockValuesValidator::new(),
+            cluster,
+        );
+
+        let previous = service
+            .rollback(
+                "minecraft",
+                RollbackGameManager {
+                    revision: None,
+                    user: None,
+                },
+            )
+            .await
+            .unwrap();
+        let missing = service
+            .rollback(
+                "minecraft",
+                RollbackGameManager {
+                    revision: Some(5),
+                    user: None,
+                },
+            )
+            .await;
+
+        assert_eq!(previous.revision.revision, 3);
+        assert!(matches!(
+            missing,
+            Err(GameManagersServiceError::RevisionNotFound { revision: 5, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn rollback_should_need_a_previous_revision() {
+        let (_, id) = versions_service(vec![]);
+        let installation = installed(&id, "minecraft", "1.0.0");
+        let mut cluster = MockClusterService::new();
+        cluster
+            .expect_find_game_manager()
+            .returning(move |_| Ok(Some(installation.clone())));
+        cluster
+            .expect_list_revisions()
+            .returning(|_| Ok(vec![revision(1, "1.0.0", json!({}))]));
+        let (service, _) = service(
+            vec![installable("1.0.0")],
+            MockValuesValidator::new(),
+            cluster,
+        );
+
+        let result = service
+            .rollback(
+                "minecraft",
+                RollbackGameManager {
+                    revision: None,
+                    user: None,
+                },
+            )
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(GameManagersServiceError::NoPreviousRevision(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn rollback_should_report_a_revision_with_an_invalid_version() {
+        let (_, id) = versions_service(vec![]);
+        let installation = installed(&id, "minecraft", "1.1.0");
+        let mut cluster = MockClusterService::new();
+        cluster
+            .expect_find_game_manager()
+            .returning(move |_| Ok(Some(installation.clone())));
+        cluster.expect_list_revisions().returning(|_| {
+            Ok(vec![
+                revision(1, "latest", json!({})),
+                revision(2, "1.1.0", json!({})),
+            ])
+        });
+        let (service, _) = service(
+            vec![installable("1.1.0")],
+            MockValuesValidator::new(),
+            cluster,
+        );
+
+        let result = service
+            .rollback(
+                "minecraft",
+                RollbackGameManager {
+                    revision: None,
+                    user: None,
+                },
+            )
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(GameManagersServiceError::InvalidRevision { revision: 1, ref version, .. })
+                if version == "latest"
+        ));
+    }
+
+    fn upgrading(
+        id: &RepositoryId,
+        phase: GameManagerPhase,
+        deadline: DateTime<Utc>,
+    ) -> GameManagerInstallation {
+        GameManagerInstallation {
+            rollout_deadline: Some(deadline),
+            state: GameManagerState {
+                phase,
+                conditions: vec![],
+            },
+            ..installed(id, "minecraft", "1.1.0")
+        }
+    }
+
+    #[tokio::test]
+    async fn check_rollouts_should_restore_the_previous_revision_after_the_deadline() {
+        let (_, id) = versions_service(vec![]);
+        let game_managers = vec![
+            upgrading(
+                &id,
+                GameManagerPhase::Degraded,
+                Utc::now() - chrono::Duration::minutes(1),
+            ),
+            // Still in time, it is checked again later
+            GameManagerInstallation {
+                name: "terraria".to_string(),
+                ..upgrading(
+                    &id,
+                    GameManagerPhase::Progressing,
+                    Utc::now() + chrono::Duration::minutes(1),
+                )
+            },
+        ];
+        let revisions = vec![
+            revision(1, "1.0.0", json!({ "motd": "Hello" })),
+            revision(2, "1.1.0", json!({})),
+        ];
+        let mut cluster = MockClusterService::new();
+        cluster
+            .expect_list_game_managers()
+            .returning(move || Ok(game_managers.clone()));
+        cluster
+            .expect_list_revisions()
+            .withf(|name| name == "minecraft")
+            .returning(move |_| Ok(revisions.clone()));
+        cluster
+            .expect_deploy_revision()
+            .withf(|name, revision| {
+                name == "minecraft"
+                    && revision.version == "1.0.0"
+                    && revision.cause == GameManagerRevisionCause::AutomaticRollback
+                    && revision.created_by.is_none()
+                    && revision.rollout_deadline.is_none()
+                    && revision.replaces == Some(2)
+            })
+            .times(1)
+            .returning(|_, revision| Ok(deployed(3, revision)));
+        let (service, _) = service(vec![], MockValuesValidator::new(), cluster);
+
+        let restored = service.check_rollouts().await.unwrap();
+
+        assert_eq!(restored.len(), 1);
+        assert_eq!(restored[0].revision, 3);
+    }
+
+    #[tokio::test]
+    async fn check_rollouts_should_keep_a_revision_deployed_in_the_meantime() {
+        let (_, id) = versions_service(vec![]);
+        let game_manager = upgrading(
+            &id,
+            GameManagerPhase::Progressing,
+            Utc::now() - chrono::Duration::minutes(1),
+        );
+        let mut cluster = MockClusterService::new();
+        cluster
+            .expect_list_game_managers()
+            .returning(move || Ok(vec![game_manager.clone()]));
+        cluster.expect_list_revisions().returning(|_| {
+            Ok(vec![
+                revision(1, "1.0.0", json!({})),
+                revision(2, "1.1.0", json!({})),
+            ])
+        });
+        cluster
+            .expect_deploy_revision()
+            .times(1)
+            .returning(|name, _| Err(ClusterServiceError::Conflict(name.to_string())));
+        let (service, _) = service(vec![], MockValuesValidator::new(), cluster);
+
+        let restored = service.check_rollouts().await.unwrap();
+
+        assert!(restored.is_empty());
+    }
+
+    #[tokio::test]
+    async fn check_rollouts_should_clear_the_deadline_once_available() {
+        let (_, id) = versions_service(vec![]);
+        let game_manager = upgrading(
+            &id,
+            GameManagerPhase::Available,
+            Utc::now() - chrono::Duration::minutes(1),
+        );
+        let mut cluster = MockClusterService::new();
+        cluster
+            .expect_list_game_managers()
+            .returning(move || Ok(vec![game_manager.clone()]));
+        cluster
+            .expect_clear_rollout_deadline()
+            .withf(|name| name == "minecraft")
+            .times(1)
+            .returning(|_| Ok(()));
+        cluster.expect_list_revisions().never();
+        cluster.expect_deploy_revision().never();
+        let (service, _) = service(vec![], MockValuesValidator::new(), cluster);
+
+        let restored = service.check_rollouts().await.unwrap();
+
+        assert!(restored.is_empty());
+    }
+}
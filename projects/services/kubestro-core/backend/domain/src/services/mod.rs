@@ -1 +1,7 @@
 pub mod auth;
+pub mod catalog;
+pub mod game_managers;
+pub mod package_graph;
+pub mod refresh_jobs;
+pub mod repositories_import;
+pub mod repositories_refresh;
@@ -0,0 +1,527 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt::Display,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    models::{
+        index::{IndexPackage, PackageDependency},
+        package::{Repository, RepositoryId},
+    },
+    ports::repositories::packages_repository::CatalogEntry,
+};
+
+/// A package name published by a repository
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PackageOrigin {
+    pub repository_id: RepositoryId,
+    pub name: String,
+}
+
+/// A conflict between the packages of several repositories
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CatalogConflict {
+    /// Several repositories publish a package with the same name
+    DuplicateName {
+        name: String,
+        /// The publishing repositories, by priority: the first one wins
+        repositories: Vec<RepositoryId>,
+    },
+    /// Several packages install the same custom resource
+    Crd {
+        group: String,
+        kind: String,
+        packages: Vec<PackageOrigin>,
+    },
+    /// No version of a dependency matches the requirement of a package
+    UnresolvedDependency {
+        package: PackageOrigin,
+        dependency: PackageDependency,
+    },
+}
+
+impl CatalogConflict {
+    /// Check whether a repository takes part in the conflict
+    pub fn involves(&self, repository_id: &RepositoryId) -> bool {
+        match self {
+            CatalogConflict::DuplicateName { repositories, .. } => {
+                repositories.contains(repository_id)
+            }
+            CatalogConflict::Crd { packages, .. } => packages
+                .iter()
+                .any(|package| &package.repository_id == repository_id),
+            CatalogConflict::UnresolvedDependency { package, .. } => {
+                &package.repository_id == repository_id
+            }
+        }
+    }
+
+    /// The repositories taking part in the conflict, without duplicates
+    pub fn repositories(&self) -> Vec<RepositoryId> {
+        let ids = match self {
+            CatalogConflict::DuplicateName { repositories, .. } => repositories.iter().collect(),
+            CatalogConflict::Crd { packages, .. } => packages
+                .iter()
+                .map(|package| &package.repository_id)
+                .collect(),
+            CatalogConflict::UnresolvedDependency { package, .. } => vec![&package.repository_id],
+        };
+
+        let mut repositories = Vec::<RepositoryId>::new();
+        for id in ids {
+            if !repositories.contains(id) {
+                repositories.push(id.clone());
+            }
+        }
+
+        repositories
+    }
+}
+
+impl Display for CatalogConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CatalogConflict::DuplicateName { name, repositories } => write!(
+                f,
+                "Package `{}` is published by {} repositories, the one from repository {} is used",
+                name,
+                repositories.len(),
+                repositories[0]
+            ),
+            CatalogConflict::Crd {
+                group,
+                kind,
+                packages,
+            } => write!(
+                f,
+                "Custom resource {}/{} is installed by several packages: {}",
+                group,
+                kind,
+                packages
+                    .iter()
+                    .map(|package| format!("`{}`", package.name))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            CatalogConflict::UnresolvedDependency {
+                package,
+                dependency,
+            } => write!(
+                f,
+                "Package `{}` requires `{}`, which no repository provides",
+                package.name, dependency
+            ),
+        }
+    }
+}
+
+/// Dependency graph of the packages published by every repository
+///
+/// When several repositories publish the same package name, the one with the highest
+/// priority provides it to the graph, the others are reported as conflicting.
+#[derive(Debug, Clone, Default)]
+pub struct PackageGraph {
+    /// The versions of every package, from the repository providing it
+    packages: BTreeMap<String, (RepositoryId, Vec<IndexPackage>)>,
+    conflicts: Vec<CatalogConflict>,
+}
+
+impl PackageGraph {
+    /// Build the graph from the stored packages, grouped by the repository publishing them
+    ///
+    /// The packages of unknown repositories are left out.
+    pub fn from_entries(repositories: Vec<Repository>, entries: Vec<CatalogEntry>) -> Self {
+        let mut packages = HashMap::<RepositoryId, Vec<IndexPackage>>::new();
+        for entry in entries {
+            packages
+                .entry(entry.repository_id)
+                .or_default()
+                .push(entry.package);
+        }
+
+        Self::build(
+            repositories
+                .into_iter()
+                .filter_map(|repository| {
+                    let packages = packages.remove(&repository.id)?;
+                    Some((repository, packages))
+                })
+                .collect(),
+        )
+    }
+
+    /// Build the graph from the packages of every repository
+    pub fn build(mut indexes: Vec<(Repository, Vec<IndexPackage>)>) -> Self {
+        // Stable sort, the repositories sharing a priority keep their order
+        indexes.sort_by_key(|(repository, _)| std::cmp::Reverse(repository.priority));
+
+        let mut publishers = BTreeMap::<String, Vec<RepositoryId>>::new();
+        let mut packages = BTreeMap::<String, (RepositoryId, Vec<IndexPackage>)>::new();
+        let mut crds = BTreeMap::<(String, String), Vec<PackageOrigin>>::new();
+
+        for (repository, published) in indexes {
+            for package in published {
+                let origin = PackageOrigin {
+                    repository_id: repository.id.clone(),
+                    name: package.name.clone(),
+                };
+                for crd in &package.crds {
+                    let origins = crds
+                        .entry((crd.group.clone(), crd.kind.clone()))
+                        .or_default();
+                    if !origins.contains(&origin) {
+                        origins.push(origin.clone());
+                    }
+                }
+
+                let repositories = publishers.entry(package.name.clone()).or_default();
+                if !repositories.contains(&repository.id) {
+                    repositories.push(repository.id.clone());
+                }
+
+                let (provider, versions) = packages
+                    .entry(package.name.clone())
+                    .or_insert_with(|| (repository.id.clone(), vec![]));
+                if provider == &repository.id {
+                    versions.push(package);
+                }
+            }
+        }
+
+        let mut conflicts = publishers
+            .into_iter()
+            .filter(|(_, repositories)| repositories.len() > 1)
+            .map(|(name, repositories)| CatalogConflict::DuplicateName { name, repositories })
+            .collect::<Vec<_>>();
+
+        conflicts.extend(
+            crds.into_iter()
+                .filter(|(_, origins)| {
+                    let names = origins.iter().map(|origin| &origin.name);
+                    names.collect::<BTreeSet<_>>().len() > 1
+                })
+                .map(|((group, kind), origins)| CatalogConflict::Crd {
+                    group,
+                    kind,
+                    packages: origins,
+                }),
+        );
+
+        let mut graph = Self {
+            packages,
+            conflicts,
+        };
+
+        let mut unresolved = vec![];
+        for (repository_id, versions) in graph.packages.values() {
+            for package in versions {
+                for dependency in &package.dependencies {
+                    let conflict = CatalogConflict::UnresolvedDependency {
+                        package: PackageOrigin {
+                            repository_id: repository_id.clone(),
+                            name: package.name.clone(),
+                        },
+                        dependency: dependency.clone(),
+                    };
+                    if graph.find(dependency).is_none() && !unresolved.contains(&conflict) {
+                        unresolved.push(conflict);
+                    }
+                }
+            }
+        }
+        graph.conflicts.extend(unresolved);
+
+        graph
+    }
+
+    /// Every conflict found between the repositories
+    pub fn conflicts(&self) -> &[CatalogConflict] {
+        &self.conflicts
+    }
+
+    /// The conflicts a repository takes part in
+    pub fn conflicts_of<'a>(
+        &'a self,
+        repository_id: &'a RepositoryId,
+    ) -> impl Iterator<Item = &'a CatalogConflict> {
+        self.conflicts
+            .iter()
+            .filter(move |conflict| conflict.involves(repository_id))
+    }
+
+    /// The repository providing a package, the one with the highest priority publishing it
+    pub fn provider(&self, name: &str) -> Option<&RepositoryId> {
+        self.packages
+            .get(name)
+            .map(|(repository_id, _)| repository_id)
+    }
+
+    /// Find the newest version of the provided package matching a dependency
+    fn find(&self, dependency: &PackageDependency) -> Option<(&RepositoryId, &IndexPackage)> {
+        let (repository_id, versions) = self.packages.get(&dependency.name)?;
+
+        versions
+            .iter()
+            .filter(|package| dependency.version.matches(&package.version))
+            .max_by(|a, b| a.version.cmp(&b.version))
+            .map(|package| (repository_id, package))
+    }
+
+    /// Resolve the packages required by a package, in the order they must be installed
+    ///
+    /// Every dependency comes before the packages requiring it. The package itself is not
+    /// part of the result.
+    pub fn dependencies(
+        &self,
+        package: &IndexPackage,
+    ) -> Result<Vec<(RepositoryId, IndexPackage)>, PackageGraphError> {
+        let mut resolved = vec![];
+        let mut path = vec![package.name.clone()];
+
+        self.visit(package, &mut path, &mut resolved)?;
+
+        Ok(resolved)
+    }
+
+    fn visit(
+        &self,
+        package: &IndexPackage,
+        path: &mut Vec<String>,
+        resolved: &mut Vec<(RepositoryId, IndexPackage)>,
+    ) -> Result<(), PackageGraphError> {
+        for dependency in &package.dependencies {
+            if path.contains(&dependency.name) {
+                let mut cycle = path.clone();
+                cycle.push(dependency.name.clone());
+                return Err(PackageGraphError::Cycle(cycle));
+            }
+
+            // A package required twice must satisfy both requirements
+            if let Some((_, required)) = resolved
+                .iter()
+                .find(|(_, required)| required.name == dependency.name)
+            {
+                if !dependency.version.matches(&required.version) {
+                    return Err(PackageGraphError::Unresolved {
+                        package: package.name.clone(),
+                        dependency: dependency.clone(),
+                    });
+                }
+                continue;
+            }
+
+            let (repository_id, required) =
+                self.find(dependency)
+                    .ok_or_else(|| PackageGraphError::Unresolved {
+                        package: package.name.clone(),
+                        dependency: dependency.clone(),
+                    })?;
+
+            path.push(required.name.clone());
+            self.visit(required, path, resolved)?;
+            path.pop();
+
+            resolved.push((repository_id.clone(), required.clone()));
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum PackageGraphError {
+    #[error("Package `{package}` requires `{dependency}`, which cannot be resolved")]
+    Unresolved {
+        package: String,
+        dependency: PackageDependency,
+    },
+    #[error("Circular dependency: {}", .0.join(" -> "))]
+    Cycle(Vec<String>),
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        models::index::CrdReference,
+        test_support::{package, repository},
+    };
+
+    use super::*;
+
+    fn with_dependencies(mut package: IndexPackage, dependencies: &[(&str, &str)]) -> IndexPackage {
+        package.dependencies = dependencies
+            .iter()
+            .map(|(name, version)| PackageDependency {
+                name: name.to_string(),
+                version: version.parse().unwrap(),
+            })
+            .collect();
+        package
+    }
+
+    fn with_crd(mut package: IndexPackage, kind: &str) -> IndexPackage {
+        package.crds.push(CrdReference {
+            group: "games.kubestro.io".to_string(),
+            version: "v1".to_string(),
+            kind: kind.to_string(),
+        });
+        package
+    }
+
+    #[test]
+    fn highest_priority_repository_should_provide_duplicate_names() {
+        let low = repository("low");
+        let high = Repository {
+            priority: 10,
+            ..repository("high")
+        };
+
+        let graph = PackageGraph::build(vec![
+            (low.clone(), vec![package("minecraft", "2.0.0", "")]),
+            (high.clone(), vec![package("minecraft", "1.0.0", "")]),
+        ]);
+
+        assert_eq!(graph.provider("minecraft"), Some(&high.id));
+        assert_eq!(
+            graph.conflicts(),
+            &[CatalogConflict::DuplicateName {
+                name: "minecraft".to_string(),
+                repositories: vec![high.id.clone(), low.id.clone()],
+            }]
+        );
+        assert_eq!(graph.conflicts_of(&low.id).count(), 1);
+    }
+
+    #[test]
+    fn packages_installing_the_same_crd_should_conflict() {
+        let first = repository("first");
+        let second = repository("second");
+
+        let graph = PackageGraph::build(vec![
+            (
+                first.clone(),
+                vec![
+                    with_crd(package("minecraft", "1.0.0", ""), "GameServer"),
+                    with_crd(package("minecraft", "1.1.0", ""), "GameServer"),
+                ],
+            ),
+            (
+                second.clone(),
+                vec![
+                    with_crd(package("terraria", "1.0.0", ""), "GameServer"),
+                    with_crd(package("factorio", "1.0.0", ""), "FactorioServer"),
+                ],
+            ),
+        ]);
+
+        let [CatalogConflict::Crd { kind, packages, .. }] = graph.conflicts() else {
+            panic!(
+                "expected a single CRD conflict, got {:?}",
+                graph.conflicts()
+            );
+        };
+        assert_eq!(kind, "GameServer");
+        let mut names = packages
+            .iter()
+            .map(|package| package.name.as_str())
+            .collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, vec!["minecraft", "terraria"]);
+        let repositories = graph.conflicts()[0].repositories();
+        assert_eq!(repositories.len(), 2);
+        assert!(repositories.contains(&first.id) && repositories.contains(&second.id));
+    }
+
+    #[test]
+    fn stored_packages_should_be_grouped_by_repository() {
+        let low = repository("low");
+        let high = Repository {
+            priority: 10,
+            ..repository("high")
+        };
+        let entry = |repository: &Repository, version: &str| CatalogEntry {
+            repository_id: repository.id.clone(),
+            package: package("minecraft", version, ""),
+        };
+
+        let graph = PackageGraph::from_entries(
+            vec![low.clone(), high.clone()],
+            vec![
+                entry(&low, "2.0.0"),
+                entry(&high, "1.0.0"),
+                entry(&high, "1.1.0"),
+                entry(&repository("removed"), "3.0.0"),
+            ],
+        );
+
+        assert_eq!(graph.provider("minecraft"), Some(&high.id));
+        assert_eq!(
+            graph.conflicts()[0].repositories(),
+            vec![high.id.clone(), low.id.clone()]
+        );
+    }
+
+    #[test]
+    fn dependencies_should_be_resolved_in_installation_order() {
+        let repository = repository("demo");
+        let minecraft = with_dependencies(
+            package("minecraft", "1.0.0", ""),
+            &[("java", "^17"), ("crds", "*")],
+        );
+
+        let graph = PackageGraph::build(vec![(
+            repository.clone(),
+            vec![
+                minecraft.clone(),
+                with_dependencies(package("java", "17.0.2", ""), &[("crds", ">=1.0")]),
+                package("java", "21.0.0", ""),
+                package("crds", "1.2.0", ""),
+            ],
+        )]);
+
+        let dependencies = graph.dependencies(&minecraft).unwrap();
+
+        let resolved = dependencies
+            .iter()
+            .map(|(_, package)| format!("{}@{}", package.name, package.version))
+            .collect::<Vec<_>>();
+        assert_eq!(resolved, vec!["crds@1.2.0", "java@17.0.2"]);
+        assert!(graph.conflicts().is_empty());
+    }
+
+    #[test]
+    fn missing_and_circular_dependencies_should_be_reported() {
+        let repository = repository("demo");
+        let minecraft = with_dependencies(package("minecraft", "1.0.0", ""), &[("java", "^17")]);
+        let terraria = with_dependencies(package("terraria", "1.0.0", ""), &[("mono", "*")]);
+
+        let graph = PackageGraph::build(vec![(
+            repository.clone(),
+            vec![
+                minecraft.clone(),
+                terraria.clone(),
+                with_dependencies(package("java", "17.0.0", ""), &[("minecraft", "*")]),
+            ],
+        )]);
+
+        assert_eq!(
+            graph.dependencies(&minecraft),
+            Err(PackageGraphError::Cycle(vec![
+                "minecraft".to_string(),
+                "java".to_string(),
+                "minecraft".to_string(),
+            ]))
+        );
+        assert!(matches!(
+            graph.dependencies(&terraria),
+            Err(PackageGraphError::Unresolved { .. })
+        ));
+        assert!(matches!(
+            graph.conflicts(),
+            [CatalogConflict::UnresolvedDependency { package, .. }] if package.name == "terraria"
+        ));
+    }
+}
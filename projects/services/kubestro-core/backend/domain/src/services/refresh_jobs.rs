@@ -0,0 +1,299 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use tokio::{sync::watch, task::JoinSet};
+use tracing::warn;
+
+use crate::{
+    impl_entity_id,
+    models::{
+        package::{Repository, RepositoryId},
+        EntityId,
+    },
+    ports::{
+        repositories::repositories_repositories::{RepositoriesRepository, RepositoryRepoError},
+        services::repositories_service::RepositoriesService,
+    },
+};
+
+/// How long a completed job can still be retrieved
+const REFRESH_JOB_RETENTION: Duration = Duration::from_secs(3600);
+
+impl_entity_id!(
+    /// Refresh job Id
+    RefreshJobId
+);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RefreshJobStatus {
+    Running,
+    Completed,
+}
+
+/// A manual refresh of one or several repositories
+#[derive(Debug, Clone)]
+pub struct RefreshJob {
+    pub id: RefreshJobId,
+    pub status: RefreshJobStatus,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    /// The refreshed repositories, with their sync state as of the end of the job
+    pub repositories: Vec<Repository>,
+}
+
+/// Run forced repository refreshes in the background and keep track of them
+pub struct RefreshJobsService {
+    repositories_repo: Arc<dyn RepositoriesRepository>,
+    repositories_service: Arc<dyn RepositoriesService>,
+    jobs: Mutex<HashMap<RefreshJobId, watch::Receiver<RefreshJob>>>,
+}
+
+impl RefreshJobsService {
+    pub fn new(
+        repositories_repo: Arc<dyn RepositoriesRepository>,
+        repositories_service: Arc<dyn RepositoriesService>,
+    ) -> Self {
+        Self {
+            repositories_repo,
+            repositories_service,
+            jobs: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Start a forced refresh of a single repository
+    #[tracing::instrument(skip(self))]
+    pub async fn refresh(
+        &self,
+        repository_id: &RepositoryId,
+    ) -> Result<RefreshJob, RefreshJobsError> {
+        let repository = self
+            .repositories_repo
+            .find_one(repository_id)
+            .await?
+            .ok_or(RepositoryRepoError::NotFound)?;
+
+        self.start(vec![repository])
+    }
+
+    /// Start a forced refresh of every repository
+    #[tracing::instrument(skip(self))]
+    pub async fn refresh_all(&self) -> Result<RefreshJob, RefreshJobsError> {
+        let repositories = self.repositories_repo.find_all(None).await?;
+
+        self.start(repositories)
+    }
+
+    /// Get the current state of a job
+    pub fn get(&self, job_id: &RefreshJobId) -> Result<Option<RefreshJob>, RefreshJobsError> {
+        Ok(self
+            .receiver(job_id)?
+            .map(|receiver| receiver.borrow().clone()))
+    }
+
+    /// Wait for a job to complete, at most `timeout`
+    ///
+    /// The job is returned in its current state if it is still running once the timeout elapsed.
+    pub async fn wait(
+        &self,
+        job_id: &RefreshJobId,
+        timeout: Duration,
+    ) -> Result<Option<RefreshJob>, RefreshJobsError> {
+        let Some(mut receiver) = self.receiver(job_id)? else {
+            return Ok(None);
+        };
+
+        // Both a timeout and a dropped sender leave the latest state in the receiver
+        let _ = tokio::time::timeout(
+            timeout,
+            receiver.wait_for(|job| job.status == RefreshJobStatus::Completed),
+        )
+        .await;
+
+        let job = receiver.borrow().clone();
+        Ok(Some(job))
+    }
+
+    fn receiver(
+        &self,
+        job_id: &RefreshJobId,
+    ) -> Result<Option<watch::Receiver<RefreshJob>>, RefreshJobsError> {
+        let jobs = self
+            .jobs
+            .lock()
+            .map_err(|e| RefreshJobsError::JobsLock(e.to_string()))?;
+
+        Ok(jobs.get(job_id).cloned())
+    }
+
+    fn start(&self, repositories: Vec<Repository>) -> Result<RefreshJob, RefreshJobsError> {
+        let job = RefreshJob {
+            id: RefreshJobId::new(),
+            status: RefreshJobStatus::Running,
+            started_at: Utc::now(),
+            finished_at: None,
+            repositories,
+        };
+        let (sender, receiver) = watch::channel(job.clone());
+
+        {
+            let mut jobs = self
+                .jobs
+                .lock()
+                .map_err(|e| RefreshJobsError::JobsLock(e.to_string()))?;
+
+            // Forget the jobs completed for too long
+            let now = Utc::now();
+            jobs.retain(|_, receiver| {
+                receiver.borrow().finished_at.is_none_or(|finished_at| {
+                    (now - finished_at)
+                        .to_std()
+                        .is_ok_and(|elapsed| elapsed < REFRESH_JOB_RETENTION)
+                })
+            });
+
+            jobs.insert(job.id.clone(), receiver);
+        }
+
+        let repositories_repo = self.repositories_repo.clone();
+        let repositories_service = self.repositories_service.clone();
+        let repositories = job.repositories.clone();
+        tokio::spawn(async move {
+            let mut join_set = JoinSet::new();
+            for repository in repositories {
+                let repositories_service = repositories_service.clone();
+                join_set.spawn(async move {
+                    // Failures are recorded in the sync state of the repository
+                    let _ = repositories_service.refresh_cache(&repository, true).await;
+                    repository
+                });
+            }
+            let mut refreshed = join_set.join_all().await;
+            if let Err(e) = repositories_service.update_conflicts().await {
+                warn!("Failed to update the conflicts of the catalog: {}", e);
+            }
+
+            // Reload the repositories to get their new sync state
+            for repository in refreshed.iter_mut() {
+                match repositories_repo.find_one(&repository.id).await {
+                    Ok(Some(updated)) => *repository = updated,
+                    Ok(None) => {}
+                    Err(e) => warn!("Failed to reload repository {}: {}", repository.id, e),
+                }
+            }
+
+            sender.send_modify(|job| {
+                job.status = RefreshJobStatus::Completed;
+                job.finished_at = Some(Utc::now());
+                job.repositories = refreshed;
+            });
+        });
+
+        Ok(job)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RefreshJobsError {
+    #[error(transparent)]
+    RepositoryError(#[from] RepositoryRepoError),
+    #[error("Failed to acquire the refresh jobs lock: {0}")]
+    JobsLock(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        models::package::RepositorySyncStatus,
+        ports::{
+            repositories::repositories_repositories::MockRepositoriesRepository,
+            services::repositories_service::{MockRepositoriesService, RepositoriesServiceError},
+        },
+        test_support::repository,
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn refresh_job_should_complete_with_the_new_sync_state() {
+        let repository = repository("demo");
+
+        let mut repositories_repo = MockRepositoriesRepository::new();
+        let found = repository.clone();
+        let mut calls = 0;
+        repositories_repo.expect_find_one().returning(move |_| {
+            calls += 1;
+            let mut repository = found.clone();
+            // The second lookup happens once the refresh is done
+            if calls > 1 {
+                repository.sync = repository.sync.failed("timeout");
+            }
+            Ok(Some(repository))
+        });
+
+        let mut repositories_service = MockRepositoriesService::new();
+        repositories_service
+            .expect_refresh_cache()
+            .withf(|_, force| *force)
+            .times(1)
+            .returning(|_, _| Err(RepositoriesServiceError::RemoteDataError("timeout".into())));
+        repositories_service
+            .expect_update_conflicts()
+            .times(1)
+            .returning(|| Ok(()));
+
+        let service =
+            RefreshJobsService::new(Arc::new(repositories_repo), Arc::new(repositories_service));
+
+        let job = service.refresh(&repository.id).await.unwrap();
+        assert_eq!(job.status, RefreshJobStatus::Running);
+
+        let job = service
+            .wait(&job.id, Duration::from_secs(5))
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(job.status, RefreshJobStatus::Completed);
+        assert!(job.finished_at.is_some());
+        assert_eq!(
+            job.repositories[0].sync.status,
+            RepositorySyncStatus::Failed
+        );
+    }
+
+    #[tokio::test]
+    async fn refresh_of_unknown_repository_should_fail() {
+        let mut repositories_repo = MockRepositoriesRepository::new();
+        repositories_repo.expect_find_one().returning(|_| Ok(None));
+
+        let service = RefreshJobsService::new(
+            Arc::new(repositories_repo),
+            Arc::new(MockRepositoriesService::new()),
+        );
+
+        let result = service.refresh(&RepositoryId::new()).await;
+
+        assert!(matches!(
+            result,
+            Err(RefreshJobsError::RepositoryError(
+                RepositoryRepoError::NotFound
+            ))
+        ));
+    }
+
+    #[tokio::test]
+    async fn unknown_job_should_not_be_found() {
+        let service = RefreshJobsService::new(
+            Arc::new(MockRepositoriesRepository::new()),
+            Arc::new(MockRepositoriesService::new()),
+        );
+
+        let job = service.get(&RefreshJobId::new()).unwrap();
+
+        assert!(job.is_none());
+    }
+}
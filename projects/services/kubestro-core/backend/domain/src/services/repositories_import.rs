@@ -0,0 +1,579 @@
+use std::{collections::HashSet, sync::Arc};
+
+use tracing::warn;
+
+use crate::{
+    models::{
+        fields::trusted_key::TrustedKey,
+        package::{CreateRepository, Repository, RepositoryId, RepositoryKind, UpdateRepository},
+    },
+    ports::{
+        repositories::repositories_repositories::{RepositoriesRepository, RepositoryRepoError},
+        services::repositories_service::RepositoriesService,
+    },
+};
+
+/// A repository as declared in an import file, without its credentials
+///
+/// Repositories are identified by their url: importing the same file twice changes nothing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RepositoryDeclaration {
+    pub name: String,
+    pub kind: RepositoryKind,
+    pub url: String,
+    pub trusted_keys: Vec<TrustedKey>,
+    pub priority: i32,
+}
+
+impl From<&Repository> for RepositoryDeclaration {
+    fn from(repository: &Repository) -> Self {
+        Self {
+            name: repository.name.clone(),
+            kind: repository.kind.clone(),
+            url: repository.url.clone(),
+            trusted_keys: repository.trusted_keys.clone(),
+            priority: repository.priority,
+        }
+    }
+}
+
+/// A change needed to reach the declared repositories
+#[derive(Debug, Clone, PartialEq)]
+pub enum RepositoryChange {
+    /// The repository is not known yet
+    Create(RepositoryDeclaration),
+    /// The repository exists with different values
+    Update {
+        repository: Repository,
+        declaration: RepositoryDeclaration,
+        /// Names of the changed fields
+        fields: Vec<&'static str>,
+    },
+    /// The repository exists and is not declared, only when pruning
+    Delete(Repository),
+    /// The repository already matches its declaration
+    Unchanged(Repository),
+}
+
+impl RepositoryChange {
+    /// Name of the repository, as declared or as currently stored
+    pub fn name(&self) -> &str {
+        match self {
+            RepositoryChange::Create(declaration)
+            | RepositoryChange::Update { declaration, .. } => &declaration.name,
+            RepositoryChange::Delete(repository) | RepositoryChange::Unchanged(repository) => {
+                &repository.name
+            }
+        }
+    }
+
+    /// Url of the repository, its identity
+    pub fn url(&self) -> &str {
+        match self {
+            RepositoryChange::Create(declaration)
+            | RepositoryChange::Update { declaration, .. } => &declaration.url,
+            RepositoryChange::Delete(repository) | RepositoryChange::Unchanged(repository) => {
+                &repository.url
+            }
+        }
+    }
+}
+
+/// A planned change, along with the error raised when applying it
+#[derive(Debug)]
+pub struct AppliedChange {
+    pub change: RepositoryChange,
+    /// Id of the repository, once created
+    pub id: Option<RepositoryId>,
+    pub error: Option<String>,
+}
+
+/// Import repositories declarations, and export the current ones
+pub struct RepositoriesImportService {
+    repositories_repo: Arc<dyn RepositoriesRepository>,
+    repositories_service: Arc<dyn RepositoriesService>,
+}
+
+impl RepositoriesImportService {
+    pub fn new(
+        repositories_repo: Arc<dyn RepositoriesRepository>,
+        repositories_service: Arc<dyn RepositoriesService>,
+    ) -> Self {
+        Self {
+            repositories_repo,
+            repositories_service,
+        }
+    }
+
+    /// Declare every repository, without their credentials
+    #[tracing::instrument(skip(self))]
+    pub async fn export(&self) -> Result<Vec<RepositoryDeclaration>, RepositoriesImportError> {
+        let mut repositories = self.repositories_repo.find_all(None).await?;
+        repositories.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| a.url.cmp(&b.url)));
+
+        Ok(repositories.iter().map(Into::into).collect())
+    }
+
+    /// Compute the changes needed to reach the declared repositories, without applying them
+    ///
+    /// The repositories missing from the declarations are only deleted when `prune` is set.
+    #[tracing::instrument(skip(self, declarations))]
+    pub async fn plan(
+        &self,
+        declarations: Vec<RepositoryDeclaration>,
+        prune: bool,
+    ) -> Result<Vec<RepositoryChange>, RepositoriesImportError> {
+        let mut urls = HashSet::new();
+        if let Some(declaration) = declarations
+            .iter()
+            .find(|declaration| !urls.insert(declaration.url.as_str()))
+        {
+            return Err(RepositoriesImportError::DuplicateUrl(
+                declaration.url.clone(),
+            ));
+        }
+
+        let mut repositories = self.repositories_repo.find_all(None).await?;
+        let mut changes = Vec::with_capacity(declarations.len());
+
+        for declaration in declarations {
+            let Some(position) = repositories
+                .iter()
+                .position(|repository| repository.url == declaration.url)
+            else {
+                changes.push(RepositoryChange::Create(declaration));
+                continue;
+            };
+            let repository = repositories.swap_remove(position);
+
+            let fields = changed_fields(&repository, &declaration);
+            if fields.is_empty() {
+                changes.push(RepositoryChange::Unchanged(repository));
+            } else {
+                changes.push(RepositoryChange::Update {
+                    repository,
+                    declaration,
+                    fields,
+                });
+            }
+        }
+
+        if prune {
+            repositories.sort_by(|a, b| a.name.cmp(&b.name));
+            changes.extend(repositories.into_iter().map(RepositoryChange::Delete));
+        }
+
+        Ok(changes)
+    }
+
+    /// Apply the changes needed to reach the declared repositories
+    ///
+    /// Every change is attempted, the ones that fail, e.g. on a unique constraint, are
+    /// reported along with their error.
+    #[tracing::instrument(skip(self, declarations))]
+    pub async fn apply(
+        &self,
+        declarations: Vec<RepositoryDeclaration>,
+        prune: bool,
+    ) -> Result<Vec<AppliedChange>, RepositoriesImportError> {
+        let changes = self.plan(declarations, prune).await?;
+        let mut applied = Vec::with_capacity(changes.len());
+
+        for change in changes {
+            let result = match &change {
+                RepositoryChange::Create(declaration) => self
+                    .repositories_service
+                    .create(create_of(declaration, false))
+                    .await
+                    .map(|repository| Some(repository.id)),
+                RepositoryChange::Update {
+                    repository,
+                    declaration,
+                    fields,
+                } => self
+                    .repositories_service
+                    .update(&repository.id, update_of(declaration, fields))
+                    .await
+                    .map(|repository| Some(repository.id)),
+                RepositoryChange::Delete(repository) => self
+                    .repositories_service
+                    .delete(&repository.id)
+                    .await
+                    .map(|_| Some(repository.id.clone())),
+                RepositoryChange::Unchanged(repository) => Ok(Some(repository.id.clone())),
+            };
+
+            applied.push(match result {
+                Ok(id) => AppliedChange {
+                    change,
+                    id,
+                    error: None,
+                },
+                Err(e) => AppliedChange {
+                    change,
+                    id: None,
+                    error: Some(e.to_string()),
+                },
+            });
+        }
+
+        Ok(applied)
+    }
+
+    /// Reconcile the repositories seeded from the configuration
+    ///
+    /// The missing repositories are created, the existing ones are left untouched unless
+    /// `managed` is set: they are then kept in line with their declaration and cannot be
+    /// changed through the API anymore. The managed repositories which are no longer seeded
+    /// are released, only the applied changes are returned.
+    #[tracing::instrument(skip(self, declarations))]
+    pub async fn seed(
+        &self,
+        declarations: Vec<RepositoryDeclaration>,
+        managed: bool,
+    ) -> Result<Vec<AppliedChange>, RepositoriesImportError> {
+        let changes = self.plan(declarations, true).await?;
+        let mut applied = Vec::new();
+        let mut refreshed = false;
+
+        for change in changes {
+            let (repository, update) = match &change {
+                RepositoryChange::Create(declaration) => {
+                    let result = self
+                        .repositories_service
+                        .create(create_of(declaration, managed))
+                        .await;
+                    applied.push(AppliedChange {
+                        id: result.as_ref().ok().map(|repository| repository.id.clone()),
+                        error: result.err().map(|e| e.to_string()),
+                        change,
+                    });
+                    continue;
+                }
+                RepositoryChange::Update {
+                    repository,
+                    declaration,
+                    fields,
+                } if managed => (repository, update_of(declaration, fields)),
+                RepositoryChange::Update { repository, .. }
+                | RepositoryChange::Unchanged(repository) => {
+                    (repository, UpdateRepository::default())
+                }
+                RepositoryChange::Delete(repository) if repository.managed => {
+                    (repository, UpdateRepository::default())
+                }
+                RepositoryChange::Delete(_) => continue,
+            };
+
+            let is_managed = managed && !matches!(change, RepositoryChange::Delete(_));
+            if update == UpdateRepository::default() && repository.managed == is_managed {
+                continue;
+            }
+
+            let update = UpdateRepository {
+                managed: Some(is_managed),
+                ..update
+            };
+            let error = match self.update_seeded(repository, update).await {
+                Ok(seeded_refreshed) => {
+                    refreshed |= seeded_refreshed;
+                    None
+                }
+                Err(e) => Some(e.to_string()),
+            };
+
+            applied.push(AppliedChange {
+                id: Some(repository.id.clone()),
+                change,
+                error,
+            });
+        }
+
+        // Once for all the refreshed repositories, it loads every stored package
+        if refreshed {
+            if let Err(e) = self.repositories_service.update_conflicts().await {
+                warn!("Failed to update the conflicts of the catalog: {}", e);
+            }
+        }
+
+        Ok(applied)
+    }
+
+    /// Update a seeded repository, bypassing the protection of the managed repositories
+    ///
+    /// The index is fetched again when the kind or the trusted keys change, whether it was is
+    /// returned.
+    async fn update_seeded(
+        &self,
+        repository: &Repository,
+        update: UpdateRepository,
+    ) -> Result<bool, RepositoryRepoError> {
+        let updated = self
+            .repositories_repo
+            .update(&repository.id, update)
+            .await?;
+
+        if updated.kind != repository.kind || updated.trusted_keys != repository.trusted_keys {
+            if let Err(e) = self
+                .repositories_service
+                .refresh_cache(&updated, true)
+                .await
+            {
+                warn!("Failed to refresh repository {}: {}", updated.id, e);
+            }
+            return Ok(true);
+        }
+
+        Ok(false)
+    }
+}
+
+/// Names of the fields differing between a repository and its declaration
+fn changed_fields(
+    repository: &Repository,
+    declaration: &RepositoryDeclaration,
+) -> Vec<&'static str> {
+    let mut fields = vec![];
+    if repository.name != declaration.name {
+        fields.push("name");
+    }
+    if repository.kind != declaration.kind {
+        fields.push("kind");
+    }
+    if repository.trusted_keys != declaration.trusted_keys {
+        fields.push("trusted_keys");
+    }
+    if repository.priority != declaration.priority {
+        fields.push("priority");
+    }
+    fields
+}
+
+/// Create a declared repository, without credentials
+fn create_of(declaration: &RepositoryDeclaration, managed: bool) -> CreateRepository {
+    CreateRepository {
+        name: declaration.name.clone(),
+        kind: declaration.kind.clone(),
+        url: declaration.url.clone(),
+        trusted_keys: declaration.trusted_keys.clone(),
+        credentials: None,
+        priority: declaration.priority,
+        managed,
+    }
+}
+
+/// Only update the changed fields, leaving the credentials untouched
+fn update_of(declaration: &RepositoryDeclaration, fields: &[&'static str]) -> UpdateRepository {
+    let changed = |field| fields.contains(&field);
+
+    UpdateRepository {
+        name: changed("name").then(|| declaration.name.clone()),
+        kind: changed("kind").then(|| declaration.kind.clone()),
+        url: None,
+        trusted_keys: changed("trusted_keys").then(|| declaration.trusted_keys.clone()),
+        credentials: None,
+        priority: changed("priority").then_some(declaration.priority),
+        managed: None,
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RepositoriesImportError {
+    #[error(transparent)]
+    RepositoryError(#[from] RepositoryRepoError),
+    #[error("The repository `{0}` is declared more than once")]
+    DuplicateUrl(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ports::{
+            repositories::repositories_repositories::MockRepositoriesRepository,
+            services::repositories_service::{MockRepositoriesService, RepositoriesServiceError},
+        },
+        test_support,
+    };
+
+    use super::*;
+
+    fn repository(name: &str, url: &str) -> Repository {
+        Repository {
+            url: url.to_string(),
+            ..test_support::repository(name)
+        }
+    }
+
+    fn import_service(
+        repositories: Vec<Repository>,
+        repositories_service: MockRepositoriesService,
+    ) -> RepositoriesImportService {
+        let mut repositories_repo = MockRepositoriesRepository::new();
+        repositories_repo
+            .expect_find_all()
+            .returning(move |_| Ok(repositories.clone()));
+
+        RepositoriesImportService::new(Arc::new(repositories_repo), Arc::new(repositories_service))
+    }
+
+    #[tokio::test]
+    async fn plan_should_diff_the_declarations_by_url() {
+        let unchanged = repository("unchanged", "https://example.com/unchanged");
+        let updated = repository("updated", "https://example.com/updated");
+        let removed = repository("removed", "https://example.com/removed");
+        let service = import_service(
+            vec![unchanged.clone(), updated.clone(), removed.clone()],
+            MockRepositoriesService::new(),
+        );
+
+        let mut update = RepositoryDeclaration::from(&updated);
+        update.name = "renamed".to_string();
+        update.priority = 10;
+        let created = repository("created", "https://example.com/created");
+        let declarations = vec![
+            RepositoryDeclaration::from(&unchanged),
+            update.clone(),
+            RepositoryDeclaration::from(&created),
+        ];
+
+        let kept = service.plan(declarations.clone(), false).await.unwrap();
+        let pruned = service.plan(declarations, true).await.unwrap();
+
+        assert_eq!(
+            kept,
+            vec![
+                RepositoryChange::Unchanged(unchanged.clone()),
+                RepositoryChange::Update {
+                    repository: updated.clone(),
+                    declaration: update,
+                    fields: vec!["name", "priority"],
+                },
+                RepositoryChange::Create(RepositoryDeclaration::from(&created)),
+            ]
+        );
+        assert_eq!(pruned.len(), 4);
+        assert!(
+            matches!(&pruned[3], RepositoryChange::Delete(repository) if repository.id == removed.id)
+        );
+    }
+
+    #[tokio::test]
+    async fn duplicate_urls_should_be_rejected() {
+        let declared = repository("declared", "https://example.com/declared");
+        let service = import_service(vec![], MockRepositoriesService::new());
+
+        let plan = service
+            .plan(
+                vec![
+                    RepositoryDeclaration::from(&declared),
+                    RepositoryDeclaration::from(&declared),
+                ],
+                false,
+            )
+            .await;
+
+        assert!(matches!(
+            plan,
+            Err(RepositoriesImportError::DuplicateUrl(url)) if url == declared.url
+        ));
+    }
+
+    #[tokio::test]
+    async fn apply_should_report_the_failing_changes() {
+        let mut repositories_service = MockRepositoriesService::new();
+        repositories_service
+            .expect_create()
+            .returning(|repository| match repository.name.as_str() {
+                "taken" => Err(RepositoriesServiceError::RepositoryError(
+                    RepositoryRepoError::AlreadyExists,
+                )),
+                _ => Ok(Repository {
+                    priority: repository.priority,
+                    ..super::tests::repository(&repository.name, &repository.url)
+                }),
+            });
+        let service = import_service(vec![], repositories_service);
+
+        let applied = service
+            .apply(
+                vec![
+                    RepositoryDeclaration::from(&repository("created", "https://a.example.com")),
+                    RepositoryDeclaration::from(&repository("taken", "https://b.example.com")),
+                ],
+                false,
+            )
+            .await
+            .unwrap();
+
+        assert!(applied[0].id.is_some());
+        assert_eq!(applied[0].error, None);
+        assert_eq!(
+            applied[1].error,
+            Some("This repository already exists".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn seed_should_manage_the_seeded_repositories() {
+        let seeded = repository("seeded", "https://example.com/seeded");
+        let released = Repository {
+            managed: true,
+            ..repository("released", "https://example.com/released")
+        };
+        let untouched = repository("untouched", "https://example.com/untouched");
+
+        let mut repositories_repo = MockRepositoriesRepository::new();
+        let repositories = vec![seeded.clone(), released.clone(), untouched.clone()];
+        repositories_repo
+            .expect_find_all()
+            .returning(move |_| Ok(repositories.clone()));
+        let (seeded_id, released_id) = (seeded.id.clone(), released.id.clone());
+        repositories_repo
+            .expect_update()
+            .withf(move |id, update| {
+                (*id == seeded_id && update.managed == Some(true))
+                    || (*id == released_id && update.managed == Some(false))
+            })
+            .times(2)
+            .returning(|id, update| {
+                Ok(Repository {
+                    id: id.clone(),
+                    managed: update.managed.unwrap(),
+                    ..repository("updated", "https://example.com/updated")
+                })
+            });
+        let mut repositories_service = MockRepositoriesService::new();
+        repositories_service
+            .expect_create()
+            .withf(|repository| repository.managed)
+            .times(1)
+            .returning(|repository| {
+                Ok(Repository {
+                    managed: repository.managed,
+                    ..super::tests::repository(&repository.name, &repository.url)
+                })
+            });
+        let service = RepositoriesImportService::new(
+            Arc::new(repositories_repo),
+            Arc::new(repositories_service),
+        );
+
+        let created = repository("created", "https://example.com/created");
+        let applied = service
+            .seed(
+                vec![
+                    RepositoryDeclaration::from(&seeded),
+                    RepositoryDeclaration::from(&created),
+                ],
+                true,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(applied.len(), 3);
+        assert!(applied.iter().all(|change| change.error.is_none()));
+        assert!(
+            matches!(&applied[2].change, RepositoryChange::Delete(repository) if repository.id == released.id)
+        );
+    }
+}
@@ -0,0 +1,534 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use tokio::task::JoinSet;
+use tracing::warn;
+
+use crate::{
+    models::package::{Repository, RepositoryId, RepositorySyncStatus},
+    ports::{
+        repositories::repositories_repositories::{RepositoriesRepository, RepositoryRepoError},
+        services::repositories_service::RepositoriesService,
+    },
+};
+
+/// Upper bound of every delay of the refresh schedule, longer delays are capped to it
+pub const MAX_REFRESH_DELAY: Duration = Duration::from_secs(30 * 24 * 3600);
+
+/// Configuration of the periodic repositories refresh
+#[derive(Debug, Clone, PartialEq)]
+pub struct RefreshSchedulerConfig {
+    /// Delay between two successful refreshes of a repository
+    pub interval: Duration,
+    /// Maximum random delay added to each refresh, to spread the load on the remotes
+    pub jitter: Duration,
+    /// Delay before retrying a repository after its first failure
+    pub backoff_base: Duration,
+    /// Upper bound of the retry delay of a failing repository
+    pub backoff_max: Duration,
+}
+
+impl Default for RefreshSchedulerConfig {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(3600),
+            jitter: Duration::from_secs(300),
+            backoff_base: Duration::from_secs(30),
+            backoff_max: Duration::from_secs(3600),
+        }
+    }
+}
+
+impl RefreshSchedulerConfig {
+    /// Replace the values the schedule cannot run with
+    ///
+    /// A zero interval or backoff base would refresh the repositories in a loop, they fall back
+    /// to the default. The maximum backoff is raised to the base one when lower.
+    pub fn normalized(self) -> Self {
+        let default = Self::default();
+        let mut config = self;
+
+        if config.interval.is_zero() {
+            warn!("The refresh interval cannot be 0, using the default interval");
+            config.interval = default.interval;
+        }
+        if config.backoff_base.is_zero() {
+            warn!("The refresh backoff base cannot be 0, using the default backoff base");
+            config.backoff_base = default.backoff_base;
+        }
+        if config.backoff_max < config.backoff_base {
+            warn!("The refresh backoff maximum is lower than its base, using the base");
+            config.backoff_max = config.backoff_base;
+        }
+
+        config
+    }
+
+    /// Delay before the next refresh of a repository after `failures` consecutive failures
+    fn delay(&self, failures: u32) -> Duration {
+        if failures == 0 {
+            return self.interval;
+        }
+
+        let factor = 2u32.saturating_pow(failures - 1);
+        self.backoff_base
+            .saturating_mul(factor)
+            .min(self.backoff_max)
+    }
+
+    fn random_jitter(&self) -> Duration {
+        let max = self.jitter.min(MAX_REFRESH_DELAY).as_millis() as u64;
+        Duration::from_millis(rand::rng().random_range(0..=max))
+    }
+}
+
+/// Refresh state of a single repository
+#[derive(Debug, Clone, PartialEq)]
+pub struct RepositoryRefreshState {
+    /// Date of the last successful refresh
+    pub last_success: Option<DateTime<Utc>>,
+    /// Error of the last refresh, cleared on success
+    pub last_error: Option<String>,
+    /// Number of refreshes that failed in a row
+    pub consecutive_failures: u32,
+    /// Date of the next planned refresh
+    pub next_refresh: DateTime<Utc>,
+}
+
+/// Periodically refresh the cache of every repository
+///
+/// Each repository has its own schedule: successful repositories are refreshed every
+/// [`RefreshSchedulerConfig::interval`], failing ones are retried with an exponential backoff.
+pub struct RepositoriesRefreshScheduler {
+    config: RefreshSchedulerConfig,
+    repositories_repo: Arc<dyn RepositoriesRepository>,
+    repositories_service: Arc<dyn RepositoriesService>,
+    states: RwLock<HashMap<RepositoryId, RepositoryRefreshState>>,
+}
+
+impl RepositoriesRefreshScheduler {
+    pub fn new(
+        config: RefreshSchedulerConfig,
+        repositories_repo: Arc<dyn RepositoriesRepository>,
+        repositories_service: Arc<dyn RepositoriesService>,
+    ) -> Self {
+        Self {
+            config,
+            repositories_repo,
+            repositories_service,
+            states: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub fn config(&self) -> &RefreshSchedulerConfig {
+        &self.config
+    }
+
+    /// Get the refresh state of a repository, if it has been scheduled already
+    pub fn state(&self, repository_id: &RepositoryId) -> Option<RepositoryRefreshState> {
+        self.states
+            .read()
+            .ok()
+            .and_then(|states| states.get(repository_id).cloned())
+    }
+
+    /// Get the refresh state of every scheduled repository
+    pub fn states(&self) -> HashMap<RepositoryId, RepositoryRefreshState> {
+        self.states
+            .read()
+            .map(|states| states.clone())
+            .unwrap_or_default()
+    }
+
+    /// Restore the status of the repositories left syncing by a previous run
+    ///
+    /// A refresh in progress is dropped on shutdown, after its repository was marked as syncing.
+    /// It must run before any synchronization starts, and returns the number of repositories
+    /// restored.
+    #[tracing::instrument(skip(self))]
+    pub async fn reset_interrupted(&self) -> Result<usize, RepositoriesRefreshError> {
+        let interrupted = self
+            .repositories_repo
+            .find_all(None)
+            .await?
+            .into_iter()
+            .filter(|repository| repository.sync.status == RepositorySyncStatus::Syncing)
+            .collect::<Vec<_>>();
+
+        for repository in &interrupted {
+            self.repositories_repo
+                .update_sync(&repository.id, repository.sync.interrupted())
+                .await?;
+        }
+
+        Ok(interrupted.len())
+    }
+
+    /// Refresh every repository that is due and return how long to wait before the next one
+    pub async fn tick(&self) -> Result<Duration, RepositoriesRefreshError> {
+        self.tick_at(Utc::now()).await
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn tick_at(&self, now: DateTime<Utc>) -> Result<Duration, RepositoriesRefreshError> {
+        let repositories = self.repositories_repo.find_all(None).await?;
+
+        let due: Vec<Repository> = {
+            let mut states = self
+                .states
+                .write()
+                .map_err(|e| RepositoriesRefreshError::StateLock(e.to_string()))?;
+
+            // Forget the repositories that were deleted since the last tick
+            states.retain(|id, _| repositories.iter().any(|repository| &repository.id == id));
+
+            repositories
+                .into_iter()
+                .filter(|repository| {
+                    states
+                        .get(&repository.id)
+                        .is_none_or(|state| state.next_refresh <= now)
+                })
+                .collect()
+        };
+
+        let refreshed = !due.is_empty();
+        let mut join_set = JoinSet::new();
+        for repository in due {
+            let repositories_service = self.repositories_service.clone();
+            join_set.spawn(async move {
+                let result = repositories_service.refresh_cache(&repository, false).await;
+                (repository.id, result)
+            });
+        }
+
+        while let Some(joined) = join_set.join_next().await {
+            let Ok((repository_id, result)) = joined else {
+                continue;
+            };
+
+            let mut states = self
+                .states
+                .write()
+                .map_err(|e| RepositoriesRefreshError::StateLock(e.to_string()))?;
+            let previous = states.remove(&repository_id);
+
+            let state = match result {
+                Ok(()) => RepositoryRefreshState {
+                    last_success: Some(now),
+                    last_error: None,
+                    consecutive_failures: 0,
+                    next_refresh: self.next_refresh(now, 0),
+                },
+                Err(e) => {
+                    warn!("Failed to refresh repository {}: {}", repository_id, e);
+                    let consecutive_failures = previous
+                        .as_ref()
+                        .map_or(0, |state| state.consecutive_failures)
+                        .saturating_add(1);
+
+                    RepositoryRefreshState {
+                        last_success: previous.and_then(|state| state.last_success),
+                        last_error: Some(e.to_string()),
+                        consecutive_failures,
+                        next_refresh: self.next_refresh(now, consecutive_failures),
+                    }
+                }
+            };
+
+            states.insert(repository_id, state);
+        }
+
+        // Once for all the repositories of the tick, it loads every stored package
+        if refreshed {
+            if let Err(e) = self.repositories_service.update_conflicts().await {
+                warn!("Failed to update the conflicts of the catalog: {}", e);
+            }
+        }
+
+        let next_refresh = self
+            .states
+            .read()
+            .map_err(|e| RepositoriesRefreshError::StateLock(e.to_string()))?
+            .values()
+            .map(|state| state.next_refresh)
+            .min();
+
+        // Without anything scheduled, check again later for new repositories
+        let wait = next_refresh
+            .and_then(|next_refresh| (next_refresh - now).to_std().ok())
+            .unwrap_or(self.config.interval)
+            .min(self.config.interval);
+
+        Ok(wait)
+    }
+
+    fn next_refresh(&self, now: DateTime<Utc>, failures: u32) -> DateTime<Utc> {
+        let delay = self
+            .config
+            .delay(failures)
+            .saturating_add(self.config.random_jitter())
+            .min(MAX_REFRESH_DELAY);
+        chrono::Duration::from_std(delay)
+            .ok()
+            .and_then(|delay| now.checked_add_signed(delay))
+            .unwrap_or(DateTime::<Utc>::MAX_UTC)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RepositoriesRefreshError {
+    #[error(transparent)]
+    RepositoryError(#[from] RepositoryRepoError),
+    #[error("Failed to acquire the refresh state lock: {0}")]
+    StateLock(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        models::package::RepositorySync,
+        ports::{
+            repositories::repositories_repositories::MockRepositoriesRepository,
+            services::repositories_service::{MockRepositoriesService, RepositoriesServiceError},
+        },
+        test_support::repository,
+    };
+
+    use super::*;
+
+    fn config() -> RefreshSchedulerConfig {
+        RefreshSchedulerConfig {
+            interval: Duration::from_secs(600),
+            jitter: Duration::ZERO,
+            backoff_base: Duration::from_secs(10),
+            backoff_max: Duration::from_secs(60),
+        }
+    }
+
+    fn scheduler(
+        repository: Repository,
+        refresh_result: fn() -> Result<(), RepositoriesServiceError>,
+        expected_refreshes: usize,
+    ) -> RepositoriesRefreshScheduler {
+        let mut repositories_repo = MockRepositoriesRepository::new();
+        repositories_repo
+            .expect_find_all()
+            .returning(move |_| Ok(vec![repository.clone()]));
+
+        let mut repositories_service = MockRepositoriesService::new();
+        repositories_service
+            .expect_refresh_cache()
+            .times(expected_refreshes)
+            .returning(move |_, _| refresh_result());
+        repositories_service
+            .expect_update_conflicts()
+            .returning(|| Ok(()));
+
+        RepositoriesRefreshScheduler::new(
+            config(),
+            Arc::new(repositories_repo),
+            Arc::new(repositories_service),
+        )
+    }
+
+    #[test]
+    fn backoff_should_grow_exponentially_up_to_the_maximum() {
+        let config = config();
+
+        let delays = (0..6).map(|failures| config.delay(failures).as_secs());
+
+        assert_eq!(delays.collect::<Vec<_>>(), vec![600, 10, 20, 40, 60, 60]);
+    }
+
+    #[test]
+    fn zero_delays_should_fall_back_to_the_defaults() {
+        let config = RefreshSchedulerConfig {
+            interval: Duration::ZERO,
+            backoff_base: Duration::ZERO,
+            ..config()
+        }
+        .normalized();
+
+        assert_eq!(config.interval, RefreshSchedulerConfig::default().interval);
+        assert_eq!(
+            config.backoff_base,
+            RefreshSchedulerConfig::default().backoff_base
+        );
+        assert_eq!(config.backoff_max, Duration::from_secs(60));
+    }
+
+    #[test]
+    fn backoff_max_should_be_at_least_the_backoff_base() {
+        let config = RefreshSchedulerConfig {
+            backoff_base: Duration::from_secs(120),
+            ..config()
+        }
+        .normalized();
+
+        assert_eq!(config.backoff_max, Duration::from_secs(120));
+        assert_eq!(config.delay(1), Duration::from_secs(120));
+        assert_eq!(config.delay(3), Duration::from_secs(120));
+    }
+
+    #[test]
+    fn next_refresh_should_not_overflow() {
+        let scheduler = RepositoriesRefreshScheduler::new(
+            RefreshSchedulerConfig {
+                interval: Duration::MAX,
+                jitter: Duration::MAX,
+                ..config()
+            },
+            Arc::new(MockRepositoriesRepository::new()),
+            Arc::new(MockRepositoriesService::new()),
+        );
+        let now = Utc::now();
+
+        let next_refresh = scheduler.next_refresh(now, 0);
+
+        assert_eq!(
+            next_refresh,
+            now + chrono::Duration::from_std(MAX_REFRESH_DELAY).unwrap()
+        );
+        assert_eq!(
+            scheduler.next_refresh(DateTime::<Utc>::MAX_UTC, 0),
+            DateTime::<Utc>::MAX_UTC
+        );
+    }
+
+    #[tokio::test]
+    async fn interrupted_repositories_should_get_their_status_back() {
+        let synced_at = Utc::now();
+        let synced = Repository {
+            sync: RepositorySync {
+                last_synced_at: Some(synced_at),
+                ..Default::default()
+            }
+            .syncing(),
+            ..repository("synced")
+        };
+        let failed = Repository {
+            sync: RepositorySync::default().failed("timeout").syncing(),
+            ..repository("failed")
+        };
+        let idle = repository("idle");
+        let repositories = vec![synced.clone(), failed.clone(), idle];
+
+        let mut repositories_repo = MockRepositoriesRepository::new();
+        repositories_repo
+            .expect_find_all()
+            .returning(move |_| Ok(repositories.clone()));
+        let synced_id = synced.id.clone();
+        repositories_repo
+            .expect_update_sync()
+            .withf(move |id, sync| {
+                id == &synced_id
+                    && sync.status == RepositorySyncStatus::Ok
+                    && sync.last_synced_at == Some(synced_at)
+            })
+            .times(1)
+            .returning(|_, _| Ok(()));
+        let failed_id = failed.id.clone();
+        repositories_repo
+            .expect_update_sync()
+            .withf(move |id, sync| id == &failed_id && sync.status == RepositorySyncStatus::Failed)
+            .times(1)
+            .returning(|_, _| Ok(()));
+        let scheduler = RepositoriesRefreshScheduler::new(
+            config(),
+            Arc::new(repositories_repo),
+            Arc::new(MockRepositoriesService::new()),
+        );
+
+        assert_eq!(scheduler.reset_interrupted().await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn conflicts_should_be_updated_once_per_tick() {
+        let repositories = vec![repository("first"), repository("second")];
+
+        let mut repositories_repo = MockRepositoriesRepository::new();
+        repositories_repo
+            .expect_find_all()
+            .returning(move |_| Ok(repositories.clone()));
+        let mut repositories_service = MockRepositoriesService::new();
+        repositories_service
+            .expect_refresh_cache()
+            .times(2)
+            .returning(|_, _| Ok(()));
+        repositories_service
+            .expect_update_conflicts()
+            .times(1)
+            .returning(|| Ok(()));
+        let scheduler = RepositoriesRefreshScheduler::new(
+            config(),
+            Arc::new(repositories_repo),
+            Arc::new(repositories_service),
+        );
+        let now = Utc::now();
+
+        scheduler.tick_at(now).await.unwrap();
+        // Nothing is due anymore, the conflicts are left as they are
+        scheduler.tick_at(now).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn successful_refresh_should_be_recorded() {
+        let repository = repository("demo");
+        let scheduler = scheduler(repository.clone(), || Ok(()), 1);
+        let now = Utc::now();
+
+        let wait = scheduler.tick_at(now).await.unwrap();
+
+        let state = scheduler.state(&repository.id).unwrap();
+        assert_eq!(state.last_success, Some(now));
+        assert_eq!(state.last_error, None);
+        assert_eq!(wait, Duration::from_secs(600));
+    }
+
+    #[tokio::test]
+    async fn failing_refresh_should_be_retried_with_backoff() {
+        let repository = repository("demo");
+        let scheduler = scheduler(
+            repository.clone(),
+            || Err(RepositoriesServiceError::RemoteDataError("timeout".into())),
+            2,
+        );
+        let now = Utc::now();
+
+        scheduler.tick_at(now).await.unwrap();
+        let wait = scheduler
+            .tick_at(now + chrono::Duration::seconds(10))
+            .await
+            .unwrap();
+
+        let state = scheduler.state(&repository.id).unwrap();
+        assert_eq!(state.consecutive_failures, 2);
+        assert_eq!(state.last_success, None);
+        assert_eq!(
+            state.last_error,
+            Some("Failed to fetch remote data: timeout".to_string())
+        );
+        assert_eq!(wait, Duration::from_secs(20));
+    }
+
+    #[tokio::test]
+    async fn repository_should_not_be_refreshed_before_it_is_due() {
+        let repository = repository("demo");
+        let scheduler = scheduler(repository.clone(), || Ok(()), 1);
+        let now = Utc::now();
+
+        scheduler.tick_at(now).await.unwrap();
+        let wait = scheduler
+            .tick_at(now + chrono::Duration::seconds(60))
+            .await
+            .unwrap();
+
+        assert_eq!(wait, Duration::from_secs(540));
+    }
+}
@@ -0,0 +1,90 @@
+//! Values shared by the tests of the services
+
+use std::sync::Arc;
+
+use chrono::Utc;
+use semver::Version;
+
+use crate::{
+    models::{
+        index::{IndexPackage, IndexVersion, PackageChannel, PackageSource},
+        package::{Repository, RepositoryId, RepositoryKind, RepositorySync},
+        EntityId,
+    },
+    ports::repositories::{
+        packages_repository::MockPackagesRepository,
+        repositories_repositories::MockRepositoriesRepository,
+    },
+    services::catalog::CatalogService,
+};
+
+/// A synchronized repository, served from `https://example.com/<name>`
+pub fn repository(name: &str) -> Repository {
+    Repository {
+        id: RepositoryId::new(),
+        name: name.to_string(),
+        kind: RepositoryKind::Http,
+        url: format!("https://example.com/{}", name),
+        trusted_keys: vec![],
+        credentials: None,
+        priority: 0,
+        managed: false,
+        sync: RepositorySync::synced(Utc::now(), 1, IndexVersion { major: 1, minor: 0 }, None),
+    }
+}
+
+/// A stable package installed from a chart, without any optional field
+pub fn package(name: &str, version: &str, description: &str) -> IndexPackage {
+    IndexPackage {
+        name: name.to_string(),
+        version: Version::parse(version).unwrap(),
+        channel: PackageChannel::Stable,
+        kubestro_version: None,
+        description: description.to_string(),
+        game: None,
+        tags: vec![],
+        readme: None,
+        changelog: None,
+        icon: None,
+        screenshots: vec![],
+        maintainers: vec![],
+        license: None,
+        source_url: None,
+        permissions: vec![],
+        crds: vec![],
+        dependencies: vec![],
+        image: None,
+        values_schema: None,
+        migration: None,
+        source: PackageSource::Chart {
+            url: "https://example.com/chart.tgz".to_string(),
+        },
+    }
+}
+
+/// A catalog publishing the given packages from a single repository, along with its id
+pub fn versions_service(packages: Vec<IndexPackage>) -> (CatalogService, RepositoryId) {
+    let repository = repository("demo");
+    let id = repository.id.clone();
+
+    let mut repositories_repo = MockRepositoriesRepository::new();
+    repositories_repo
+        .expect_find_one()
+        .returning(move |_| Ok(Some(repository.clone())));
+
+    let mut packages_repo = MockPackagesRepository::new();
+    packages_repo
+        .expect_find_versions()
+        .returning(move |_, name| {
+            Ok(packages
+                .iter()
+                .filter(|package| package.name == name)
+                .cloned()
+                .collect())
+        });
+
+    let service = CatalogService::new(Arc::new(repositories_repo), Arc::new(packages_repo))
+        .with_core_version(Version::new(0, 2, 0));
+
+    (service, id)
+}
@@ -1,5 +1,7 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.4
 
+pub mod catalog_conflict;
+pub mod package;
 pub mod repository;
 pub mod sea_orm_active_enums;
 pub mod user;
@@ -1,5 +1,6 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.4
 
+use super::sea_orm_active_enums::{RepositoryKind, RepositorySyncStatus};
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
@@ -11,6 +12,23 @@ pub struct Model {
     pub id: Uuid,
     pub name: String,
     pub url: String,
+    pub sync_status: RepositorySyncStatus,
+    pub last_synced_at: Option<DateTimeWithTimeZone>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub last_sync_error: Option<String>,
+    pub package_count: Option<i32>,
+    pub index_version: Option<String>,
+    pub trusted_keys: Vec<String>,
+    #[sea_orm(column_type = "VarBinary(StringLen::None)", nullable)]
+    pub credentials: Option<Vec<u8>>,
+    pub kind: RepositoryKind,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub git_reference: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub git_path: Option<String>,
+    pub sync_revision: Option<String>,
+    pub priority: i32,
+    pub managed: bool,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
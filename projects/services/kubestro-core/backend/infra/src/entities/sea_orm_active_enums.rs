@@ -3,6 +3,34 @@
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
+#[derive(Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "repository_kind")]
+pub enum RepositoryKind {
+    #[sea_orm(string_value = "bundle")]
+    Bundle,
+    #[sea_orm(string_value = "git")]
+    Git,
+    #[sea_orm(string_value = "http")]
+    Http,
+    #[sea_orm(string_value = "oci")]
+    Oci,
+}
+#[derive(Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(
+    rs_type = "String",
+    db_type = "Enum",
+    enum_name = "repository_sync_status"
+)]
+pub enum RepositorySyncStatus {
+    #[sea_orm(string_value = "failed")]
+    Failed,
+    #[sea_orm(string_value = "ok")]
+    Ok,
+    #[sea_orm(string_value = "pending")]
+    Pending,
+    #[sea_orm(string_value = "syncing")]
+    Syncing,
+}
 #[derive(Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
 #[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "user_provider")]
 pub enum UserProvider {
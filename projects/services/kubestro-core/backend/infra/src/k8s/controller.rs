@@ -0,0 +1,1015 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Debug,
+    future::Future,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use k8s_openapi::{
+    api::{
+        apps::v1::{Deployment, DeploymentSpec},
+        core::v1::{
+            Container, ContainerPort, EnvVar, EnvVarSource, ObjectFieldSelector, PodSpec,
+            PodTemplateSpec, Service, ServiceAccount, ServicePort, ServiceSpec,
+        },
+        rbac::v1::{PolicyRule, Role, RoleBinding, RoleRef, Subject},
+    },
+    apimachinery::pkg::{
+        apis::meta::v1::{LabelSelector, ObjectMeta, OwnerReference},
+        util::intstr::IntOrString,
+    },
+    NamespaceResourceScope,
+};
+use kube::{
+    api::{DeleteParams, Patch, PatchParams},
+    runtime::{
+        controller::Action,
+        finalizer::{finalizer, Event},
+        watcher, Controller,
+    },
+    Api, Client, Resource, ResourceExt,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use tracing::{debug, warn};
+
+use super::game_manager::{
+    GameManager, GameManagerStatus, CONDITION_AVAILABLE, CONDITION_DEGRADED, CONDITION_PROGRESSING,
+    FIELD_MANAGER,
+};
+
+/// Finalizer removing the objects of a game manager before the resource is deleted
+pub const FINALIZER: &str = "kubestro.io/game-manager";
+/// Label set on every object deployed by Kubestro Core
+pub const MANAGED_BY_LABEL: &str = "app.kubernetes.io/managed-by";
+/// Label holding the name of the game manager an object belongs to
+pub const GAME_MANAGER_LABEL: &str = "kubestro.io/game-manager";
+/// Environment variable holding the configuration values of a game manager, as JSON
+pub const VALUES_ENV: &str = "KUBESTRO_VALUES";
+/// Environment variable holding the namespace of a game manager
+pub const NAMESPACE_ENV: &str = "KUBESTRO_NAMESPACE";
+/// Environment variable holding the name of a game manager, to label its game servers with
+pub const NAME_ENV: &str = "KUBESTRO_GAME_MANAGER";
+/// Name of the game manager container
+pub const CONTAINER_NAME: &str = "game-manager";
+/// Name of the port exposed by the game manager container
+const PORT_NAME: &str = "http";
+
+/// Delay before checking again an available game manager
+const AVAILABLE_REQUEUE: Duration = Duration::from_secs(5 * 60);
+/// Delay before checking again a game manager being rolled out
+const PROGRESSING_REQUEUE: Duration = Duration::from_secs(15);
+
+/// Delays between the retries of a failing reconciliation
+///
+/// The delay doubles after each failure of a game manager, until the maximum, and is reset
+/// once the game manager is reconciled.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ControllerBackoff {
+    /// First retry delay
+    pub base: Duration,
+    /// Maximum retry delay
+    pub max: Duration,
+}
+
+impl Default for ControllerBackoff {
+    fn default() -> Self {
+        Self {
+            base: Duration::from_secs(5),
+            max: Duration::from_secs(5 * 60),
+        }
+    }
+}
+
+/// State shared by the reconciliations of the game managers
+pub struct ControllerContext {
+    client: Client,
+    backoff: ControllerBackoff,
+    /// Number of consecutive failures of each game manager, by `namespace/name`
+    failures: Mutex<HashMap<String, u32>>,
+}
+
+impl ControllerContext {
+    pub fn new(client: Client) -> Self {
+        Self {
+            client,
+            backoff: ControllerBackoff::default(),
+            failures: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Set the delays between the retries of a failing reconciliation
+    pub fn with_backoff(mut self, backoff: ControllerBackoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Count a failure of a game manager, and compute the delay before retrying it
+    fn failure_delay(&self, key: &str) -> Duration {
+        let mut failures = self.failures.lock().unwrap_or_else(|e| e.into_inner());
+        let count = failures.entry(key.to_string()).or_default();
+        *count = count.saturating_add(1);
+
+        self.backoff
+            .base
+            .saturating_mul(2u32.saturating_pow(*count - 1))
+            .min(self.backoff.max)
+    }
+
+    fn reset_failures(&self, key: &str) {
+        self.failures
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(key);
+    }
+}
+
+/// Watch the game managers of every namespace and deploy them, until the shutdown future
+/// completes
+///
+/// The deployments and services of the game managers are watched as well, so a game manager
+/// is reconciled again as soon as one of its objects changes.
+pub async fn run(
+    ctx: ControllerContext,
+    shutdown: impl Future<Output = ()> + Send + Sync + 'static,
+) {
+    let client = ctx.client.clone();
+    let managed =
+        watcher::Config::default().labels(&format!("{}={}", MANAGED_BY_LABEL, FIELD_MANAGER));
+
+    Controller::new(
+        Api::<GameManager>::all(client.clone()),
+        watcher::Config::default(),
+    )
+    .owns(Api::<Deployment>::all(client.clone()), managed.clone())
+    .owns(Api::<Service>::all(client), managed)
+    .graceful_shutdown_on(shutdown)
+    .run(reconcile, error_policy, Arc::new(ctx))
+    .for_each(|result| async move {
+        match result {
+            Ok((object, _)) => debug!("Reconciled game manager {}", object),
+            Err(e) => debug!("Failed to reconcile a game manager: {}", e),
+        }
+    })
+    .await;
+}
+
+/// Deploy a game manager, or remove its objects when it is being deleted
+pub async fn reconcile(
+    game_manager: Arc<GameManager>,
+    ctx: Arc<ControllerContext>,
+) -> Result<Action, ControllerError> {
+    let namespace = game_manager
+        .namespace()
+        .ok_or(ControllerError::InvalidGameManager("no namespace"))?;
+    let key = format!("{}/{}", namespace, game_manager.name_any());
+    let api = Api::<GameManager>::namespaced(ctx.client.clone(), &namespace);
+
+    let action = finalizer(&api, FINALIZER, game_manager, |event| async {
+        match event {
+            Event::Apply(game_manager) => apply(&api, &game_manager, &ctx).await,
+            Event::Cleanup(game_manager) => cleanup(&game_manager, &ctx).await,
+        }
+    })
+    .await
+    .map_err(|e| ControllerError::FinalizerError(Box::new(e)))?;
+
+    ctx.reset_failures(&key);
+    Ok(action)
+}
+
+/// Retry a failing game manager with an exponential backoff
+pub fn error_policy(
+    game_manager: Arc<GameManager>,
+    error: &ControllerError,
+    ctx: Arc<ControllerContext>,
+) -> Action {
+    let key = format!(
+        "{}/{}",
+        game_manager.namespace().unwrap_or_default(),
+        game_manager.name_any()
+    );
+    let delay = ctx.failure_delay(&key);
+    warn!(
+        "Failed to reconcile game manager `{}`, retrying in {}s: {}",
+        key,
+        delay.as_secs(),
+        error
+    );
+
+    Action::requeue(delay)
+}
+
+/// Apply the objects of a game manager, and report their state in its status
+async fn apply(
+    api: &Api<GameManager>,
+    game_manager: &GameManager,
+    ctx: &ControllerContext,
+) -> Result<Action, ControllerError> {
+    let objects = GameManagerObjects::new(game_manager)?;
+
+    let deployment = match objects.apply(&ctx.client).await {
+        Ok(deployment) => deployment,
+        Err(e) => {
+            // Report the failure before retrying, the previous status would be misleading
+            let mut status = game_manager.status.clone().unwrap_or_default();
+            let generation = game_manager.metadata.generation;
+            let message = e.to_string();
+            let now = Utc::now();
+            status.observed_generation = generation;
+            status.set_condition(
+                CONDITION_PROGRESSING,
+                false,
+                "ApplyFailed",
+                &message,
+                generation,
+                now,
+            );
+            status.set_condition(
+                CONDITION_DEGRADED,
+                true,
+                "ApplyFailed",
+                &message,
+                generation,
+                now,
+            );
+            if let Err(status_error) = patch_status(api, game_manager, status).await {
+                warn!("{}", status_error);
+            }
+            return Err(ControllerError::ApplyError(e));
+        }
+    };
+
+    let status = deployment_status(game_manager, &deployment, Utc::now());
+    let available = status.is(CONDITION_AVAILABLE);
+    patch_status(api, game_manager, status).await?;
+
+    Ok(match available {
+        true => Action::requeue(AVAILABLE_REQUEUE),
+        false => Action::requeue(PROGRESSING_REQUEUE),
+    })
+}
+
+/// Delete the objects of a game manager
+///
+/// The objects are owned by the game manager and would be collected anyway, they are deleted
+/// first so the game manager is gone once its resource is.
+async fn cleanup(
+    game_manager: &GameManager,
+    ctx: &ControllerContext,
+) -> Result<Action, ControllerError> {
+    let namespace = game_manager
+        .namespace()
+        .ok_or(ControllerError::InvalidGameManager("no namespace"))?;
+    let name = game_manager.name_any();
+    debug!(
+        "Deleting the objects of game manager `{}/{}`...",
+        namespace, name
+    );
+
+    delete_object::<Deployment>(&ctx.client, &namespace, &name).await?;
+    delete_object::<Service>(&ctx.client, &namespace, &name).await?;
+    delete_object::<RoleBinding>(&ctx.client, &namespace, &name).await?;
+    delete_object::<Role>(&ctx.client, &namespace, &name).await?;
+    delete_object::<ServiceAccount>(&ctx.client, &namespace, &name).await?;
+
+    Ok(Action::await_change())
+}
+
+/// Update the status of a game manager, unless it did not change
+async fn patch_status(
+    api: &Api<GameManager>,
+    game_manager: &GameManager,
+    status: GameManagerStatus,
+) -> Result<(), ControllerError> {
+    if game_manager.status.as_ref() == Some(&status) {
+        return Ok(());
+    }
+
+    api.patch_status(
+        &game_manager.name_any(),
+        &PatchParams::default(),
+        &Patch::Merge(serde_json::json!({ "status": status })),
+    )
+    .await
+    .map_err(ControllerError::StatusError)?;
+
+    Ok(())
+}
+
+/// Compute the status of a game manager from the state of its deployment
+pub fn deployment_status(
+    game_manager: &GameManager,
+    deployment: &Deployment,
+    now: DateTime<Utc>,
+) -> GameManagerStatus {
+    let generation = game_manager.metadata.generation;
+    let mut status = game_manager.status.clone().unwrap_or_default();
+    status.observed_generation = generation;
+
+    let replicas = deployment
+        .spec
+        .as_ref()
+        .and_then(|spec| spec.replicas)
+        .unwrap_or(1);
+    let deployment_status = deployment.status.clone().unwrap_or_default();
+    // Every replica must run the latest template, the previous ones may still be available
+    let rolled_out = deployment_status.observed_generation >= deployment.metadata.generation
+        && deployment_status.updated_replicas.unwrap_or(0) >= replicas
+        && deployment_status.available_replicas.unwrap_or(0) >= replicas;
+    let failure = deployment_status
+        .conditions
+        .iter()
+        .flatten()
+        .find(|condition| {
+            condition.type_ == "Progressing"
+                && condition.status == "False"
+                && condition.reason.as_deref() == Some("ProgressDeadlineExceeded")
+        });
+
+    match (rolled_out, failure) {
+        (true, _) => {
+            let message = "The game manager is available";
+            status.set_condition(
+                CONDITION_AVAILABLE,
+                true,
+                "Deployed",
+                message,
+                generation,
+                now,
+            );
+            status.set_condition(
+                CONDITION_PROGRESSING,
+                false,
+                "Deployed",
+                message,
+                generation,
+                now,
+            );
+            status.set_condition(
+                CONDITION_DEGRADED,
+                false,
+                "Deployed",
+                message,
+                generation,
+                now,
+            );
+        }
+        (false, Some(failure)) => {
+            let message = failure
+                .message
+                .clone()
+                .unwrap_or_else(|| "The deployment did not progress in time".to_string());
+            let reason = "ProgressDeadlineExceeded";
+            status.set_condition(
+                CONDITION_AVAILABLE,
+                false,
+                reason,
+                &message,
+                generation,
+                now,
+            );
+            status.set_condition(
+                CONDITION_PROGRESSING,
+                false,
+                reason,
+                &message,
+                generation,
+                now,
+            );
+            status.set_condition(CONDITION_DEGRADED, true, reason, &message, generation, now);
+        }
+        (false, None) => {
+            let message = format!(
+                "{}/{} replica(s) updated and available",
+                deployment_status
+                    .available_replicas
+                    .unwrap_or(0)
+                    .min(deployment_status.updated_replicas.unwrap_or(0)),
+                replicas
+            );
+            status.set_condition(
+                CONDITION_AVAILABLE,
+                false,
+                "Deploying",
+                &message,
+                generation,
+                now,
+            );
+            status.set_condition(
+                CONDITION_PROGRESSING,
+                true,
+                "Deploying",
+                &message,
+                generation,
+                now,
+            );
+            status.set_condition(
+                CONDITION_DEGRADED,
+                false,
+                "Deploying",
+                &message,
+                generation,
+                now,
+            );
+        }
+    }
+
+    status
+}
+
+/// Objects deployed for a game manager, all named after it
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameManagerObjects {
+    pub service_account: ServiceAccount,
+    pub role: Role,
+    pub role_binding: RoleBinding,
+    pub deployment: Deployment,
+    pub service: Service,
+}
+
+impl GameManagerObjects {
+    /// Build the objects of a game manager, owned by its resource
+    pub fn new(game_manager: &GameManager) -> Result<Self, ControllerError> {
+        let namespace = game_manager
+            .namespace()
+            .ok_or(ControllerError::InvalidGameManager("no namespace"))?;
+        let owner = game_manager
+            .controller_owner_ref(&())
+            .ok_or(ControllerError::InvalidGameManager("no uid"))?;
+        let name = game_manager.name_any();
+        let spec = &game_manager.spec;
+
+        let selector = BTreeMap::from([(GAME_MANAGER_LABEL.to_string(), name.clone())]);
+        let mut labels = selector.clone();
+        labels.insert(MANAGED_BY_LABEL.to_string(), FIELD_MANAGER.to_string());
+        labels.insert(
+            "app.kubernetes.io/name".to_string(),
+            spec.package.name.clone(),
+        );
+        labels.insert("app.kubernetes.io/instance".to_string(), name.clone());
+        labels.insert(
+            "app.kubernetes.io/version".to_string(),
+            spec.version.clone(),
+        );
+        let metadata = object_metadata(&name, &namespace, &labels, owner);
+
+        let service_account = ServiceAccount {
+            metadata: metadata.clone(),
+            ..Default::default()
+        };
+
+        let role = Role {
+            metadata: metadata.clone(),
+            rules: Some(
+                spec.permissions
+                    .iter()
+                    .map(|permission| PolicyRule {
+                        api_groups: Some(permission.api_groups.clone()),
+                        resources: Some(permission.resources.clone()),
+                        verbs: permission.verbs.clone(),
+                        ..Default::default()
+                    })
+                    .collect(),
+            ),
+        };
+
+        let role_binding = RoleBinding {
+            metadata: metadata.clone(),
+            role_ref: RoleRef {
+                api_group: "rbac.authorization.k8s.io".to_string(),
+                kind: "Role".to_string(),
+                name: name.clone(),
+            },
+            subjects: Some(vec![Subject {
+                kind: "ServiceAccount".to_string(),
+                name: name.clone(),
+                namespace: Some(namespace.clone()),
+                ..Default::default()
+            }]),
+        };
+
+        let deployment = Deployment {
+            metadata: metadata.clone(),
+            spec: Some(DeploymentSpec {
+                replicas: Some(1),
+                selector: LabelSelector {
+                    match_labels: Some(selector.clone()),
+                    ..Default::default()
+                },
+                template: PodTemplateSpec {
+                    metadata: Some(ObjectMeta {
+                        labels: Some(labels.clone()),
+                        ..Default::default()
+                    }),
+                    spec: Some(PodSpec {
+                        service_account_name: Some(name.clone()),
+                        containers: vec![Container {
+                            name: CONTAINER_NAME.to_string(),
+                            image: Some(spec.image.clone()),
+                            ports: Some(vec![ContainerPort {
+                                name: Some(PORT_NAME.to_string()),
+                                container_port: spec.port,
+                                ..Default::default()
+                            }]),
+                            env: Some(vec![
+                                EnvVar {
+                                    name: VALUES_ENV.to_string(),
+                                    value: Some(spec.values.to_string()),
+                                    ..Default::default()
+                                },
+                                EnvVar {
+                                    name: NAME_ENV.to_string(),
+                                    value: Some(name.clone()),
+                                    ..Default::default()
+                                },
+                                EnvVar {
+                                    name: NAMESPACE_ENV.to_string(),
+                                    value_from: Some(EnvVarSource {
+                                        field_ref: Some(ObjectFieldSelector {
+                                            field_path: "metadata.namespace".to_string(),
+                                            ..Default::default()
+                                        }),
+                                        ..Default::default()
+                                    }),
+                                    ..Default::default()
+                                },
+                            ]),
+                            ..Default::default()
+                        }],
+                        ..Default::default()
+                    }),
+                },
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let service = Service {
+            metadata,
+            spec: Some(ServiceSpec {
+                selector: Some(selector),
+                ports: Some(vec![ServicePort {
+                    name: Some(PORT_NAME.to_string()),
+                    port: spec.port,
+                    target_port: Some(IntOrString::String(PORT_NAME.to_string())),
+                    ..Default::default()
+                }]),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        Ok(Self {
+            service_account,
+            role,
+            role_binding,
+            deployment,
+            service,
+        })
+    }
+
+    /// Apply every object with server-side apply, returning the deployment as stored
+    async fn apply(&self, client: &Client) -> Result<Deployment, kube::Error> {
+        apply_object(client, &self.service_account).await?;
+        apply_object(client, &self.role).await?;
+        apply_object(client, &self.role_binding).await?;
+        let deployment = apply_object(client, &self.deployment).await?;
+        apply_object(client, &self.service).await?;
+
+        Ok(deployment)
+    }
+}
+
+fn object_metadata(
+    name: &str,
+    namespace: &str,
+    labels: &BTreeMap<String, String>,
+    owner: OwnerReference,
+) -> ObjectMeta {
+    ObjectMeta {
+        name: Some(name.to_string()),
+        namespace: Some(namespace.to_string()),
+        labels: Some(labels.clone()),
+        owner_references: Some(vec![owner]),
+        ..Default::default()
+    }
+}
+
+async fn apply_object<K>(client: &Client, object: &K) -> Result<K, kube::Error>
+where
+    K: Resource<Scope = NamespaceResourceScope, DynamicType = ()>
+        + Clone
+        + Debug
+        + Serialize
+        + DeserializeOwned,
+{
+    let api = Api::<K>::namespaced(client.clone(), &object.namespace().unwrap_or_default());
+    api.patch(
+        &object.name_any(),
+        &PatchParams::apply(FIELD_MANAGER).force(),
+        &Patch::Apply(object),
+    )
+    .await
+}
+
+/// Delete an object, ignoring the objects already gone
+async fn delete_object<K>(
+    client: &Client,
+    namespace: &str,
+    name: &str,
+) -> Result<(), ControllerError>
+where
+    K: Resource<Scope = NamespaceResourceScope, DynamicType = ()>
+        + Clone
+        + Debug
+        + DeserializeOwned,
+{
+    let api = Api::<K>::namespaced(client.clone(), namespace);
+    match api.delete(name, &DeleteParams::background()).await {
+        Ok(_) => Ok(()),
+        Err(kube::Error::Api(e)) if e.code == 404 => Ok(()),
+        Err(e) => Err(ControllerError::CleanupError(e)),
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ControllerError {
+    #[error("Invalid game manager: {0}")]
+    InvalidGameManager(&'static str),
+    #[error("Failed to apply the objects of the game manager: {0}")]
+    ApplyError(#[source] kube::Error),
+    #[error("Failed to delete the objects of the game manager: {0}")]
+    CleanupError(#[source] kube::Error),
+    #[error("Failed to update the status of the game manager: {0}")]
+    StatusError(#[source] kube::Error),
+    #[error(transparent)]
+    FinalizerError(#[from] Box<kube::runtime::finalizer::Error<ControllerError>>),
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use http::{Method, Request, Response, StatusCode};
+    use kube::client::Body;
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::k8s::game_manager::{
+        GameManagerPackage, GameManagerPermission, GameManagerSpec, GameServerKind,
+    };
+
+    pub(crate) type ApiServerHandle = tower_test::mock::Handle<Request<Body>, Response<Body>>;
+
+    const GAME_MANAGERS_PATH: &str = "/apis/kubestro.io/v1alpha1/namespaces/games/gamemanagers";
+
+    fn mock_context() -> (Arc<ControllerContext>, ApiServerHandle) {
+        let (service, handle) = tower_test::mock::pair::<Request<Body>, Response<Body>>();
+        let client = Client::new(service, "default");
+        (Arc::new(ControllerContext::new(client)), handle)
+    }
+
+    pub(crate) fn game_manager(finalizers: Vec<String>) -> GameManager {
+        let mut game_manager = GameManager::new(
+            "minecraft",
+            GameManagerSpec {
+                package: GameManagerPackage {
+                    repository: "0195ab3c-4b7e-7a3c-9f0e-2c1d5e6f7a8b".to_string(),
+                    name: "minecraft".to_string(),
+                },
+                version: "1.2.0".to_string(),
+                image: "ghcr.io/kubestro/minecraft:1.2.0".to_string(),
+                values: json!({ "motd": "Hello" }),
+                port: 8080,
+                permissions: vec![GameManagerPermission {
+                    api_groups: vec!["apps".to_string()],
+                    resources: vec!["statefulsets".to_string()],
+                    verbs: vec!["get".to_string(), "create".to_string()],
+                }],
+                game_servers: vec![GameServerKind {
+                    group: "minecraft.kubestro.io".to_string(),
+                    version: "v1".to_string(),
+                    kind: "MinecraftServer".to_string(),
+                }],
+            },
+        );
+        game_manager.metadata.namespace = Some("games".to_string());
+        game_manager.metadata.uid = Some("6f1b1bbf-9e1c-4bb8-8d4f-0c3c3c0f1a2b".to_string());
+        game_manager.metadata.generation = Some(1);
+        game_manager.metadata.finalizers = Some(finalizers);
+        game_manager
+    }
+
+    /// Answer the next request sent to the API server, after checking its method and path
+    ///
+    /// Returns the body of the request.
+    pub(crate) async fn expect_request(
+        handle: &mut ApiServerHandle,
+        method: Method,
+        path: &str,
+        respond: impl FnOnce(&Value) -> (StatusCode, Value),
+    ) -> Value {
+        let (request, send) = handle
+            .next_request()
+            .await
+            .expect("the controller did not send a request");
+        assert_eq!(request.method(), method);
+        assert_eq!(request.uri().path(), path);
+
+        let body = request.into_body().collect_bytes().await.unwrap();
+        let body = serde_json::from_slice(&body).unwrap_or(Value::Null);
+        let (status, response) = respond(&body);
+        send.send_response(
+            Response::builder()
+                .status(status)
+                .body(Body::from(serde_json::to_vec(&response).unwrap()))
+                .unwrap(),
+        );
+
+        body
+    }
+
+    pub(crate) fn echo(body: &Value) -> (StatusCode, Value) {
+        (StatusCode::OK, body.clone())
+    }
+
+    pub(crate) fn api_status(code: StatusCode) -> (StatusCode, Value) {
+        (
+            code,
+            json!({
+                "kind": "Status",
+                "apiVersion": "v1",
+                "status": if code.is_success() { "Success" } else { "Failure" },
+                "message": code.canonical_reason(),
+                // The reasons are written in CamelCase by the API server, e.g. `NotFound`
+                "reason": code.canonical_reason().map(|reason| reason.replace(' ', "")),
+                "code": code.as_u16()
+            }),
+        )
+    }
+
+    /// Respond to a status patch with the patched game manager
+    fn patched_status(game_manager: &GameManager, body: &Value) -> (StatusCode, Value) {
+        let mut game_manager = serde_json::to_value(game_manager).unwrap();
+        game_manager["status"] = body["status"].clone();
+        (StatusCode::OK, game_manager)
+    }
+
+    fn available_deployment(body: &Value) -> (StatusCode, Value) {
+        let mut deployment = body.clone();
+        deployment["metadata"]["generation"] = json!(1);
+        deployment["status"] = json!({
+            "observedGeneration": 1,
+            "replicas": 1,
+            "updatedReplicas": 1,
+            "availableReplicas": 1
+        });
+        (StatusCode::OK, deployment)
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_adds_finalizer() {
+        let (ctx, mut handle) = mock_context();
+        let game_manager = game_manager(vec![]);
+
+        let reconciling = tokio::spawn(reconcile(Arc::new(game_manager.clone()), ctx));
+        let patch = expect_request(
+            &mut handle,
+            Method::PATCH,
+            &format!("{}/minecraft", GAME_MANAGERS_PATH),
+            |_| {
+                let mut game_manager = game_manager.clone();
+                game_manager.metadata.finalizers = Some(vec![FINALIZER.to_string()]);
+                (StatusCode::OK, serde_json::to_value(game_manager).unwrap())
+            },
+        )
+        .await;
+
+        assert_eq!(patch[1]["op"], "add");
+        assert_eq!(patch[1]["value"], json!([FINALIZER]));
+        assert_eq!(reconciling.await.unwrap().unwrap(), Action::await_change());
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_applies_objects() {
+        let (ctx, mut handle) = mock_context();
+        let game_manager = game_manager(vec![FINALIZER.to_string()]);
+
+        let reconciling = tokio::spawn(reconcile(Arc::new(game_manager.clone()), ctx));
+        expect_request(
+            &mut handle,
+            Method::PATCH,
+            "/api/v1/namespaces/games/serviceaccounts/minecraft",
+            echo,
+        )
+        .await;
+        let role = expect_request(
+            &mut handle,
+            Method::PATCH,
+            "/apis/rbac.authorization.k8s.io/v1/namespaces/games/roles/minecraft",
+            echo,
+        )
+        .await;
+        expect_request(
+            &mut handle,
+            Method::PATCH,
+            "/apis/rbac.authorization.k8s.io/v1/namespaces/games/rolebindings/minecraft",
+            echo,
+        )
+        .await;
+        let deployment = expect_request(
+            &mut handle,
+            Method::PATCH,
+            "/apis/apps/v1/namespaces/games/deployments/minecraft",
+            available_deployment,
+        )
+        .await;
+        expect_request(
+            &mut handle,
+            Method::PATCH,
+            "/api/v1/namespaces/games/services/minecraft",
+            echo,
+        )
+        .await;
+        let status = expect_request(
+            &mut handle,
+            Method::PATCH,
+            &format!("{}/minecraft/status", GAME_MANAGERS_PATH),
+            |body| patched_status(&game_manager, body),
+        )
+        .await;
+
+        assert_eq!(role["rules"][0]["resources"], json!(["statefulsets"]));
+        let container = &deployment["spec"]["template"]["spec"]["containers"][0];
+        assert_eq!(container["image"], "ghcr.io/kubestro/minecraft:1.2.0");
+        assert_eq!(container["env"][0]["name"], VALUES_ENV);
+        assert_eq!(container["env"][0]["value"], r#"{"motd":"Hello"}"#);
+        assert_eq!(container["env"][1]["name"], NAME_ENV);
+        assert_eq!(container["env"][1]["value"], "minecraft");
+        assert_eq!(
+            deployment["metadata"]["ownerReferences"][0]["kind"],
+            "GameManager"
+        );
+        let status = serde_json::from_value::<GameManagerStatus>(status["status"].clone()).unwrap();
+        assert!(status.is(CONDITION_AVAILABLE));
+        assert!(!status.is(CONDITION_DEGRADED));
+        assert_eq!(status.observed_generation, Some(1));
+        assert_eq!(
+            reconciling.await.unwrap().unwrap(),
+            Action::requeue(AVAILABLE_REQUEUE)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_reports_failures() {
+        let (ctx, mut handle) = mock_context();
+        let game_manager = game_manager(vec![FINALIZER.to_string()]);
+
+        let reconciling = tokio::spawn(reconcile(Arc::new(game_manager.clone()), ctx.clone()));
+        expect_request(
+            &mut handle,
+            Method::PATCH,
+            "/api/v1/namespaces/games/serviceaccounts/minecraft",
+            |_| api_status(StatusCode::FORBIDDEN),
+        )
+        .await;
+        let status = expect_request(
+            &mut handle,
+            Method::PATCH,
+            &format!("{}/minecraft/status", GAME_MANAGERS_PATH),
+            |body| patched_status(&game_manager, body),
+        )
+        .await;
+
+        let status = serde_json::from_value::<GameManagerStatus>(status["status"].clone()).unwrap();
+        assert!(status.is(CONDITION_DEGRADED));
+        assert_eq!(
+            status
+                .condition(CONDITION_DEGRADED)
+                .unwrap()
+                .reason
+                .as_deref(),
+            Some("ApplyFailed")
+        );
+        let error = reconciling.await.unwrap().unwrap_err();
+        assert!(matches!(
+            *error_source(&error),
+            kube::runtime::finalizer::Error::ApplyFailed(ControllerError::ApplyError(_))
+        ));
+        assert_eq!(
+            error_policy(Arc::new(game_manager), &error, ctx),
+            Action::requeue(Duration::from_secs(5))
+        );
+    }
+
+    fn error_source(error: &ControllerError) -> &kube::runtime::finalizer::Error<ControllerError> {
+        match error {
+            ControllerError::FinalizerError(e) => e,
+            e => panic!("unexpected error: {}", e),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_cleans_up() {
+        let (ctx, mut handle) = mock_context();
+        let mut game_manager = game_manager(vec![FINALIZER.to_string()]);
+        game_manager.metadata.deletion_timestamp = Some(
+            k8s_openapi::apimachinery::pkg::apis::meta::v1::Time(Utc::now()),
+        );
+
+        let reconciling = tokio::spawn(reconcile(Arc::new(game_manager.clone()), ctx));
+        for path in [
+            "/apis/apps/v1/namespaces/games/deployments/minecraft",
+            "/api/v1/namespaces/games/services/minecraft",
+            "/apis/rbac.authorization.k8s.io/v1/namespaces/games/rolebindings/minecraft",
+            "/apis/rbac.authorization.k8s.io/v1/namespaces/games/roles/minecraft",
+            "/api/v1/namespaces/games/serviceaccounts/minecraft",
+        ] {
+            // A missing object is already cleaned up
+            let status = match path.contains("services/") {
+                true => StatusCode::NOT_FOUND,
+                false => StatusCode::OK,
+            };
+            expect_request(&mut handle, Method::DELETE, path, |_| api_status(status)).await;
+        }
+        let patch = expect_request(
+            &mut handle,
+            Method::PATCH,
+            &format!("{}/minecraft", GAME_MANAGERS_PATH),
+            |_| (StatusCode::OK, serde_json::to_value(&game_manager).unwrap()),
+        )
+        .await;
+
+        assert_eq!(patch[1]["op"], "remove");
+        assert_eq!(patch[1]["path"], "/metadata/finalizers/0");
+        assert_eq!(reconciling.await.unwrap().unwrap(), Action::await_change());
+    }
+
+    #[tokio::test]
+    async fn test_failure_backoff() {
+        let (service, _) = tower_test::mock::pair::<Request<Body>, Response<Body>>();
+        let ctx = ControllerContext::new(Client::new(service, "default")).with_backoff(
+            ControllerBackoff {
+                base: Duration::from_secs(1),
+                max: Duration::from_secs(10),
+            },
+        );
+
+        let delays = (0..6)
+            .map(|_| ctx.failure_delay("games/minecraft").as_secs())
+            .collect::<Vec<_>>();
+        assert_eq!(delays, vec![1, 2, 4, 8, 10, 10]);
+        assert_eq!(ctx.failure_delay("games/other"), Duration::from_secs(1));
+
+        ctx.reset_failures("games/minecraft");
+        assert_eq!(ctx.failure_delay("games/minecraft"), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_deployment_status() {
+        let game_manager = game_manager(vec![]);
+        let now = Utc::now();
+        let mut deployment = GameManagerObjects::new(&game_manager).unwrap().deployment;
+        deployment.metadata.generation = Some(2);
+        deployment.status = Some(
+            serde_json::from_value(json!({
+                "observedGeneration": 2,
+                "updatedReplicas": 0,
+                "availableReplicas": 1
+            }))
+            .unwrap(),
+        );
+
+        // The previous replica is still available, but not rolled out
+        let status = deployment_status(&game_manager, &deployment, now);
+        assert!(status.is(CONDITION_PROGRESSING));
+        assert!(!status.is(CONDITION_AVAILABLE));
+
+        deployment.status = Some(
+            serde_json::from_value(json!({
+                "observedGeneration": 2,
+                "updatedReplicas": 1,
+                "availableReplicas": 0,
+                "conditions": [{
+                    "type": "Progressing",
+                    "status": "False",
+                    "reason": "ProgressDeadlineExceeded",
+                    "message": "ReplicaSet \"minecraft-5d4f\" has timed out progressing."
+                }]
+            }))
+            .unwrap(),
+        );
+        let status = deployment_status(&game_manager, &deployment, now);
+        assert!(status.is(CONDITION_DEGRADED));
+        assert!(!status.is(CONDITION_PROGRESSING));
+        assert_eq!(
+            status
+                .condition(CONDITION_DEGRADED)
+                .unwrap()
+                .message
+                .as_deref(),
+            Some("ReplicaSet \"minecraft-5d4f\" has timed out progressing.")
+        );
+    }
+}
@@ -0,0 +1,318 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use k8s_openapi::apiextensions_apiserver::pkg::apis::apiextensions::v1::CustomResourceDefinition;
+use kube::{CustomResource, CustomResourceExt};
+use schemars::{
+    gen::SchemaGenerator,
+    schema::{InstanceType, Schema, SchemaObject},
+    JsonSchema,
+};
+use serde::{Deserialize, Serialize};
+
+/// Name of the field manager used by Kubestro Core for server-side apply
+pub const FIELD_MANAGER: &str = "kubestro-core";
+/// Default port the game managers listen on
+pub const DEFAULT_PORT: i32 = 8080;
+
+/// The objects of the game manager are deployed and up to date
+pub const CONDITION_AVAILABLE: &str = "Available";
+/// The objects of the game manager are being created or rolled out
+pub const CONDITION_PROGRESSING: &str = "Progressing";
+/// The game manager failed to be deployed, or stopped working
+pub const CONDITION_DEGRADED: &str = "Degraded";
+
+/// A game manager installed on the cluster
+///
+/// Kubestro Core installs a game manager by creating this resource, and deploys the objects
+/// it describes. The resources can also be created with `kubectl`, they are handled the same
+/// way as the ones created from the dashboard.
+#[derive(CustomResource, Deserialize, Serialize, Clone, Debug, PartialEq, JsonSchema)]
+#[kube(
+    group = "kubestro.io",
+    version = "v1alpha1",
+    kind = "GameManager",
+    namespaced,
+    status = "GameManagerStatus",
+    shortname = "gm",
+    category = "kubestro",
+    printcolumn = r#"{"name":"Package","type":"string","jsonPath":".spec.package.name"}"#,
+    printcolumn = r#"{"name":"Version","type":"string","jsonPath":".spec.version"}"#,
+    printcolumn = r#"{"name":"Available","type":"string","jsonPath":".status.conditions[?(@.type==\"Available\")].status"}"#,
+    printcolumn = r#"{"name":"Age","type":"date","jsonPath":".metadata.creationTimestamp"}"#
+)]
+#[serde(rename_all = "camelCase")]
+pub struct GameManagerSpec {
+    /// Catalog package the game manager is installed from
+    pub package: GameManagerPackage,
+    /// Version of the package, as published in the repository index
+    pub version: String,
+    /// Container image of the game manager
+    pub image: String,
+    /// Configuration values given to the game manager
+    #[serde(default = "empty_values")]
+    #[schemars(schema_with = "values_schema")]
+    pub values: serde_json::Value,
+    /// Port the game manager listens on, exposed by its service
+    #[serde(default = "default_port")]
+    pub port: i32,
+    /// Permissions granted to the game manager in its namespace
+    #[serde(default)]
+    pub permissions: Vec<GameManagerPermission>,
+    /// Kinds of the game servers managed by the game manager
+    ///
+    /// The game servers are labelled with the name of their game manager, which cannot be
+    /// uninstalled while it still owns some.
+    #[serde(default)]
+    pub game_servers: Vec<GameServerKind>,
+}
+
+/// Reference to a package of the catalog
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GameManagerPackage {
+    /// Id of the repository publishing the package
+    pub repository: String,
+    /// Name of the package in the repository index
+    pub name: String,
+}
+
+/// A permission granted to a game manager, as a rule of its role
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GameManagerPermission {
+    /// API groups of the resources, the core group being `""`
+    #[serde(default)]
+    pub api_groups: Vec<String>,
+    pub resources: Vec<String>,
+    pub verbs: Vec<String>,
+}
+
+/// Kind of custom resource describing a game server
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GameServerKind {
+    /// API group of the resource
+    pub group: String,
+    /// API version of the resource
+    pub version: String,
+    pub kind: String,
+}
+
+/// Observed state of a game manager
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GameManagerStatus {
+    /// Generation of the resource the status was computed from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub observed_generation: Option<i64>,
+    /// Latest observations of the game manager state
+    #[serde(default)]
+    pub conditions: Vec<GameManagerCondition>,
+}
+
+/// An observation of the state of a game manager, following the Kubernetes conventions
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GameManagerCondition {
+    /// Type of the condition, such as `Available`
+    #[serde(rename = "type")]
+    pub type_: String,
+    /// Either `True`, `False` or `Unknown`
+    pub status: String,
+    /// Machine-readable reason of the last transition, in `CamelCase`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// Human-readable details about the last transition
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// Last time the status changed, formatted as RFC 3339
+    pub last_transition_time: String,
+    /// Generation of the resource the condition was computed from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub observed_generation: Option<i64>,
+}
+
+impl GameManagerStatus {
+    /// Find a condition by its type
+    pub fn condition(&self, type_: &str) -> Option<&GameManagerCondition> {
+        self.conditions
+            .iter()
+            .find(|condition| condition.type_ == type_)
+    }
+
+    /// Check whether a condition is set to `True`
+    pub fn is(&self, type_: &str) -> bool {
+        self.condition(type_)
+            .is_some_and(|condition| condition.status == "True")
+    }
+
+    /// Set a condition, keeping its transition time when its status did not change
+    pub fn set_condition(
+        &mut self,
+        type_: &str,
+        status: bool,
+        reason: &str,
+        message: impl Into<String>,
+        generation: Option<i64>,
+        now: DateTime<Utc>,
+    ) {
+        let status = match status {
+            true => "True",
+            false => "False",
+        };
+        let last_transition_time = match self.condition(type_) {
+            Some(condition) if condition.status == status => condition.last_transition_time.clone(),
+            _ => now.to_rfc3339_opts(SecondsFormat::Secs, true),
+        };
+        let condition = GameManagerCondition {
+            type_: type_.to_string(),
+            status: status.to_string(),
+            reason: Some(reason.to_string()),
+            message: Some(message.into()),
+            last_transition_time,
+            observed_generation: generation,
+        };
+
+        match self
+            .conditions
+            .iter_mut()
+            .find(|condition| condition.type_ == type_)
+        {
+            Some(existing) => *existing = condition,
+            None => self.conditions.push(condition),
+        }
+    }
+}
+
+fn default_port() -> i32 {
+    DEFAULT_PORT
+}
+
+fn empty_values() -> serde_json::Value {
+    serde_json::Value::Object(Default::default())
+}
+
+/// Schema of the configuration values, an object of any shape
+///
+/// The values are validated against the schema of the package before being applied, the
+/// API server keeps them as they are.
+fn values_schema(_: &mut SchemaGenerator) -> Schema {
+    let mut schema = SchemaObject {
+        instance_type: Some(InstanceType::Object.into()),
+        ..Default::default()
+    };
+    schema.extensions.insert(
+        "x-kubernetes-preserve-unknown-fields".to_string(),
+        serde_json::Value::Bool(true),
+    );
+    Schema::Object(schema)
+}
+
+/// Every custom resource definition owned by Kubestro Core
+pub fn crds() -> Vec<CustomResourceDefinition> {
+    vec![GameManager::crd()]
+}
+
+/// Render the custom resource definitions owned by Kubestro Core as a YAML manifest
+pub fn crds_manifest() -> Result<String, serde_yaml::Error> {
+    crds()
+        .iter()
+        .map(|crd| serde_yaml::to_string(crd).map(|document| format!("---\n{}", document)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crd_names() {
+        let crd = GameManager::crd();
+
+        assert_eq!(GameManager::crd_name(), "gamemanagers.kubestro.io");
+        assert_eq!(crd.spec.group, "kubestro.io");
+        assert_eq!(crd.spec.scope, "Namespaced");
+        assert_eq!(crd.spec.names.kind, "GameManager");
+        assert_eq!(crd.spec.names.short_names, Some(vec!["gm".to_string()]));
+        assert_eq!(crd.spec.versions.len(), 1);
+        assert_eq!(crd.spec.versions[0].name, "v1alpha1");
+        assert!(crd.spec.versions[0]
+            .subresources
+            .as_ref()
+            .is_some_and(|subresources| subresources.status.is_some()));
+    }
+
+    #[test]
+    fn test_crd_schema() {
+        let crd = serde_json::to_value(GameManager::crd()).unwrap();
+        let spec = &crd["spec"]["versions"][0]["schema"]["openAPIV3Schema"]["properties"]["spec"];
+
+        let mut required = spec["required"].as_array().unwrap().clone();
+        required.sort_by_key(|field| field.to_string());
+        assert_eq!(
+            required,
+            vec![
+                serde_json::json!("image"),
+                serde_json::json!("package"),
+                serde_json::json!("version")
+            ]
+        );
+        assert_eq!(
+            spec["properties"]["values"],
+            serde_json::json!({
+                "default": {},
+                "description": "Configuration values given to the game manager",
+                "type": "object",
+                "x-kubernetes-preserve-unknown-fields": true
+            })
+        );
+    }
+
+    #[test]
+    fn test_spec_values_default() {
+        let spec = serde_json::from_value::<GameManagerSpec>(serde_json::json!({
+            "package": { "repository": "0195ab3c-4b7e-7a3c-9f0e-2c1d5e6f7a8b", "name": "minecraft" },
+            "version": "1.2.0",
+            "image": "ghcr.io/kubestro/minecraft:1.2.0"
+        }))
+        .unwrap();
+
+        assert_eq!(spec.values, serde_json::json!({}));
+        assert_eq!(spec.port, DEFAULT_PORT);
+        assert!(spec.permissions.is_empty());
+        assert!(spec.game_servers.is_empty());
+        assert_eq!(spec.package.name, "minecraft");
+    }
+
+    #[test]
+    fn test_status_set_condition() {
+        let start = "2025-05-18T10:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let later = "2025-05-18T10:05:00Z".parse::<DateTime<Utc>>().unwrap();
+        let mut status = GameManagerStatus::default();
+
+        status.set_condition(CONDITION_AVAILABLE, false, "Deploying", "", Some(1), start);
+        status.set_condition(CONDITION_AVAILABLE, false, "Deploying", "", Some(2), later);
+        assert_eq!(status.conditions.len(), 1);
+        assert_eq!(
+            status.conditions[0].last_transition_time,
+            "2025-05-18T10:00:00Z"
+        );
+        assert_eq!(status.conditions[0].observed_generation, Some(2));
+        assert!(!status.is(CONDITION_AVAILABLE));
+
+        status.set_condition(CONDITION_AVAILABLE, true, "Deployed", "", Some(2), later);
+        assert_eq!(
+            status.conditions[0].last_transition_time,
+            "2025-05-18T10:05:00Z"
+        );
+        assert!(status.is(CONDITION_AVAILABLE));
+        assert!(!status.is(CONDITION_DEGRADED));
+    }
+
+    #[test]
+    fn test_crds_manifest() {
+        let manifest = crds_manifest().unwrap();
+
+        assert!(manifest.starts_with("---\n"));
+        assert!(manifest.contains("name: gamemanagers.kubestro.io"));
+    }
+}
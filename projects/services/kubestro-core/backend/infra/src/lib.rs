@@ -1,3 +1,4 @@
 mod entities;
+pub mod k8s;
 pub mod repositories;
 pub mod services;
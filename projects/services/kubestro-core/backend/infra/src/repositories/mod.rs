@@ -1,3 +1,4 @@
 pub mod db;
+pub mod packages_repo;
 pub mod repositories_repo;
 pub mod user_repo;
@@ -0,0 +1,578 @@
+use std::{collections::HashSet, sync::Arc};
+
+use kubestro_core_domain::{
+    models::{index::IndexPackage, package::RepositoryId, EntityId},
+    ports::repositories::packages_repository::{
+        CatalogEntry, CatalogFacets, CatalogSort, FacetCount, PackageRepoError, PackageSearch,
+        PackageSearchResult, PackagesRepository, SortOrder,
+    },
+    services::package_graph::CatalogConflict,
+};
+use sea_orm::{
+    prelude::Uuid,
+    sea_query::{Alias, Expr, Query, SelectStatement, SimpleExpr},
+    ActiveValue, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, FromQueryResult, Order,
+    PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, QueryTrait, Select, TransactionTrait,
+};
+
+use crate::entities::{
+    catalog_conflict,
+    package::{ActiveModel, Column, Entity, Model},
+};
+
+use super::db::DbProvider;
+
+/// Number of packages inserted by a single statement, far below the bind parameters limit
+const INSERT_BATCH_SIZE: usize = 500;
+/// Number of words of a search that are matched, the following ones are ignored
+const MAX_SEARCH_WORDS: usize = 8;
+/// Number of values returned by facet
+const MAX_FACET_VALUES: u64 = 50;
+/// Text matched by trigrams to tolerate typos, must follow the `idx_package_search_trigram` index
+const TRIGRAM_TEXT: &str = r#"("package"."name" || ' ' || "package"."keywords")"#;
+
+fn map_db_error(err: DbErr) -> PackageRepoError {
+    PackageRepoError::DatabaseError(err.to_string())
+}
+
+/// Split a package into its indexed columns, the whole package being kept as JSON
+fn to_active_model(
+    repository_id: &RepositoryId,
+    package: IndexPackage,
+) -> Result<ActiveModel, PackageRepoError> {
+    let data = serde_json::to_value(&package)
+        .map_err(|e| PackageRepoError::InvalidPackage(e.to_string()))?;
+    let keywords = package
+        .game
+        .iter()
+        .chain(&package.tags)
+        .cloned()
+        .collect::<Vec<_>>()
+        .join(" ");
+    let version = package.version;
+
+    Ok(ActiveModel {
+        id: ActiveValue::Set(Uuid::new_v4()),
+        repository_id: ActiveValue::Set(repository_id.value()),
+        name: ActiveValue::Set(package.name),
+        version: ActiveValue::Set(version.to_string()),
+        version_major: ActiveValue::Set(version.major.try_into().unwrap_or(i64::MAX)),
+        version_minor: ActiveValue::Set(version.minor.try_into().unwrap_or(i64::MAX)),
+        version_patch: ActiveValue::Set(version.patch.try_into().unwrap_or(i64::MAX)),
+        version_pre: ActiveValue::Set(version.pre.to_string()),
+        channel: ActiveValue::Set(package.channel.to_string()),
+        description: ActiveValue::Set(package.description),
+        game: ActiveValue::Set(package.game),
+        tags: ActiveValue::Set(package.tags),
+        keywords: ActiveValue::Set(keywords),
+        data: ActiveValue::Set(data),
+    })
+}
+
+impl TryFrom<Model> for CatalogEntry {
+    type Error = PackageRepoError;
+
+    fn try_from(value: Model) -> Result<Self, Self::Error> {
+        let package = serde_json::from_value::<IndexPackage>(value.data).map_err(|e| {
+            PackageRepoError::InvalidPackage(format!("{} {}: {}", value.name, value.version, e))
+        })?;
+
+        Ok(CatalogEntry {
+            repository_id: RepositoryId::from(value.repository_id),
+            package,
+        })
+    }
+}
+
+/// Split a search into lowercase words, the only characters trusted in a text search query
+///
+/// The words keep the order they were typed in, without duplicates.
+fn search_words(search: &str) -> Vec<String> {
+    let mut seen = HashSet::new();
+    search
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .filter(|word| seen.insert(word.clone()))
+        .take(MAX_SEARCH_WORDS)
+        .collect()
+}
+
+/// Select the packages matching a search, in no particular order
+///
+/// Each word of the search must match a word of the package as a prefix, or be similar enough
+/// to a word of its name or keywords to forgive a typo.
+fn filter_packages(search: &PackageSearch) -> Select<Entity> {
+    let words = search
+        .search
+        .as_deref()
+        .map(search_words)
+        .unwrap_or_default();
+
+    words
+        .into_iter()
+        .fold(Entity::find(), |query, word| {
+            query.filter(Expr::cust_with_values(
+                format!(
+                    r#"("package"."search_document" @@ to_tsquery('simple', $1) OR $2 <% {})"#,
+                    TRIGRAM_TEXT
+                ),
+                [format!("{}:*", word), word],
+            ))
+        })
+        .apply_if(search.repository_id.as_ref(), |query, id| {
+            query.filter(Column::RepositoryId.eq(id.value()))
+        })
+        .apply_if(search.game.as_ref(), |query, game| {
+            query.filter(Column::Game.eq(game))
+        })
+        .apply_if(search.channel, |query, channel| {
+            query.filter(Column::Channel.eq(channel.to_string()))
+        })
+        .apply_if(
+            (!search.tags.is_empty()).then(|| search.tags.clone()),
+            |query, tags| query.filter(Expr::cust_with_values(r#""package"."tags" @> $1"#, [tags])),
+        )
+}
+
+/// Score a package against the words of a search, the matches of its name and keywords
+/// weighing more than the ones of its description
+fn relevance(words: &[String]) -> SimpleExpr {
+    let any_word = words
+        .iter()
+        .map(|word| format!("{}:*", word))
+        .collect::<Vec<_>>()
+        .join(" | ");
+
+    Expr::cust_with_values(
+        format!(
+            r#"ts_rank("package"."search_document", to_tsquery('simple', $1)) + word_similarity($2, {})"#,
+            TRIGRAM_TEXT
+        ),
+        [any_word, words.join(" ")],
+    )
+}
+
+/// Sort the packages, the versions follow the semantic versioning precedence except for the
+/// numeric identifiers of the pre-releases which are compared as text
+///
+/// The most relevant packages always come first, the order only applies to the packages
+/// ranked equally.
+fn sort_packages(query: Select<Entity>, search: &PackageSearch) -> Select<Entity> {
+    let order = match search.order {
+        SortOrder::Asc => Order::Asc,
+        SortOrder::Desc => Order::Desc,
+    };
+
+    let by_version = |query: Select<Entity>| {
+        query
+            .order_by(Column::VersionMajor, order.clone())
+            .order_by(Column::VersionMinor, order.clone())
+            .order_by(Column::VersionPatch, order.clone())
+            // A release comes after its pre-releases
+            .order_by(
+                Expr::col((Entity, Column::VersionPre)).eq(""),
+                order.clone(),
+            )
+            .order_by(Column::VersionPre, order.clone())
+    };
+
+    let query = match search.sort {
+        CatalogSort::Relevance => {
+            let words = search
+                .search
+                .as_deref()
+                .map(search_words)
+                .unwrap_or_default();
+            let query = if words.is_empty() {
+                query
+            } else {
+                query.order_by(relevance(&words), Order::Desc)
+            };
+
+            by_version(query.order_by(Column::Name, order.clone()))
+        }
+        CatalogSort::Name => by_version(query.order_by(Column::Name, order.clone())),
+        CatalogSort::Version => by_version(query).order_by(Column::Name, order.clone()),
+    };
+
+    // Keep the pages stable when several repositories publish the same version
+    query.order_by(Column::RepositoryId, order)
+}
+
+/// Select the stored conflicts in their order, only the ones involving a repository when given
+fn filter_conflicts(repository_id: Option<&RepositoryId>) -> Select<catalog_conflict::Entity> {
+    catalog_conflict::Entity::find()
+        .apply_if(repository_id, |query, id| {
+            query.filter(Expr::cust_with_values(
+                r#""catalog_conflict"."repository_ids" @> $1"#,
+                [vec![id.value()]],
+            ))
+        })
+        .order_by_asc(catalog_conflict::Column::Position)
+}
+
+#[derive(Debug, FromQueryResult)]
+struct FacetRow {
+    value: String,
+    count: i64,
+}
+
+/// Count the packages matching a search by value of an expression, most frequent first
+fn facet_statement(search: &PackageSearch, value: SimpleExpr) -> SelectStatement {
+    let values = filter_packages(search)
+        .select_only()
+        .column_as(value, "value")
+        .into_query();
+
+    Query::select()
+        .column(Alias::new("value"))
+        .expr_as(Expr::col(Alias::new("value")).count(), Alias::new("count"))
+        .from_subquery(values, Alias::new("facet"))
+        .and_where(Expr::col(Alias::new("value")).is_not_null())
+        .group_by_col(Alias::new("value"))
+        .order_by(Alias::new("count"), Order::Desc)
+        .order_by(Alias::new("value"), Order::Asc)
+        .limit(MAX_FACET_VALUES)
+        .to_owned()
+}
+
+/// Postgres package store
+///
+/// The packages of each repository index are stored as rows, indexed by repository, name, game
+/// and tags, along with their whole definition. The search runs on a text search document and
+/// on trigrams of the name and keywords of the packages.
+///
+/// The conflicts between the packages of every repository are stored apart, along with the
+/// repositories taking part in them.
+#[derive(Clone)]
+pub struct PackagesPgRepo {
+    db: Arc<DbProvider>,
+}
+
+impl PackagesPgRepo {
+    pub fn new(db: Arc<DbProvider>) -> Self
+    where
+        Self: Sized,
+    {
+        Self { db }
+    }
+
+    async fn facet(
+        &self,
+        search: &PackageSearch,
+        value: SimpleExpr,
+    ) -> Result<Vec<FacetCount>, PackageRepoError> {
+        let statement = self
+            .db
+            .pool()
+            .get_database_backend()
+            .build(&facet_statement(search, value));
+
+        Ok(FacetRow::find_by_statement(statement)
+            .all(self.db.pool())
+            .await
+            .map_err(map_db_error)?
+            .into_iter()
+            .map(|row| FacetCount {
+                value: row.value,
+                count: row.count.try_into().unwrap_or_default(),
+            })
+            .collect())
+    }
+}
+
+#[async_trait::async_trait]
+impl PackagesRepository for PackagesPgRepo {
+    #[tracing::instrument(skip(self, packages))]
+    async fn replace(
+        &self,
+        repository_id: &RepositoryId,
+        packages: Vec<IndexPackage>,
+    ) -> Result<(), PackageRepoError> {
+        let models = packages
+            .into_iter()
+            .map(|package| to_active_model(repository_id, package))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let txn = self.db.pool().begin().await.map_err(map_db_error)?;
+
+        Entity::delete_many()
+            .filter(Column::RepositoryId.eq(repository_id.value()))
+            .exec(&txn)
+            .await
+            .map_err(map_db_error)?;
+
+        for batch in models.chunks(INSERT_BATCH_SIZE) {
+            Entity::insert_many(batch.to_vec())
+                .exec(&txn)
+                .await
+                .map_err(map_db_error)?;
+        }
+
+        txn.commit().await.map_err(map_db_error)
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn delete(&self, repository_id: &RepositoryId) -> Result<(), PackageRepoError> {
+        Entity::delete_many()
+            .filter(Column::RepositoryId.eq(repository_id.value()))
+            .exec(self.db.pool())
+            .await
+            .map_err(map_db_error)?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn search(&self, search: PackageSearch) -> Result<PackageSearchResult, PackageRepoError> {
+        let query = filter_packages(&search);
+
+        let total = query
+            .clone()
+            .count(self.db.pool())
+            .await
+            .map_err(map_db_error)?;
+
+        let packages = sort_packages(query, &search)
+            .offset(search.offset)
+            .limit(search.limit)
+            .all(self.db.pool())
+            .await
+            .map_err(map_db_error)?
+            .into_iter()
+            .map(CatalogEntry::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let (games, tags, repositories, channels) = futures::try_join!(
+            self.facet(&search, Expr::col((Entity, Column::Game)).into()),
+            self.facet(&search, Expr::cust(r#"unnest("package"."tags")"#)),
+            self.facet(
+                &search,
+                Expr::col((Entity, Column::RepositoryId)).cast_as(Alias::new("text"))
+            ),
+            self.facet(&search, Expr::col((Entity, Column::Channel)).into()),
+        )?;
+
+        Ok(PackageSearchResult {
+            packages,
+            total,
+            facets: CatalogFacets {
+                games,
+                tags,
+                repositories,
+                channels,
+            },
+        })
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn find_versions(
+        &self,
+        repository_id: &RepositoryId,
+        name: &str,
+    ) -> Result<Vec<IndexPackage>, PackageRepoError> {
+        Entity::find()
+            .filter(Column::RepositoryId.eq(repository_id.value()))
+            .filter(Column::Name.eq(name))
+            .all(self.db.pool())
+            .await
+            .map_err(map_db_error)?
+            .into_iter()
+            .map(|model| CatalogEntry::try_from(model).map(|entry| entry.package))
+            .collect()
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn find_all(&self) -> Result<Vec<CatalogEntry>, PackageRepoError> {
+        Entity::find()
+            .all(self.db.pool())
+            .await
+            .map_err(map_db_error)?
+            .into_iter()
+            .map(CatalogEntry::try_from)
+            .collect()
+    }
+
+    #[tracing::instrument(skip(self, conflicts))]
+    async fn replace_conflicts(
+        &self,
+        conflicts: Vec<CatalogConflict>,
+    ) -> Result<(), PackageRepoError> {
+        let models = conflicts
+            .iter()
+            .enumerate()
+            .map(|(position, conflict)| {
+                let data = serde_json::to_value(conflict)
+                    .map_err(|e| PackageRepoError::InvalidPackage(e.to_string()))?;
+
+                Ok(catalog_conflict::ActiveModel {
+                    id: ActiveValue::Set(Uuid::new_v4()),
+                    position: ActiveValue::Set(position.try_into().unwrap_or(i32::MAX)),
+                    repository_ids: ActiveValue::Set(
+                        conflict
+                            .repositories()
+                            .iter()
+                            .map(EntityId::value)
+                            .collect(),
+                    ),
+                    data: ActiveValue::Set(data),
+                })
+            })
+            .collect::<Result<Vec<_>, PackageRepoError>>()?;
+
+        let txn = self.db.pool().begin().await.map_err(map_db_error)?;
+
+        catalog_conflict::Entity::delete_many()
+            .exec(&txn)
+            .await
+            .map_err(map_db_error)?;
+
+        for batch in models.chunks(INSERT_BATCH_SIZE) {
+            catalog_conflict::Entity::insert_many(batch.to_vec())
+                .exec(&txn)
+                .await
+                .map_err(map_db_error)?;
+        }
+
+        txn.commit().await.map_err(map_db_error)
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn find_conflicts(
+        &self,
+        repository_id: Option<RepositoryId>,
+    ) -> Result<Vec<CatalogConflict>, PackageRepoError> {
+        filter_conflicts(repository_id.as_ref())
+            .all(self.db.pool())
+            .await
+            .map_err(map_db_error)?
+            .into_iter()
+            .map(|model| {
+                serde_json::from_value::<CatalogConflict>(model.data).map_err(|e| {
+                    PackageRepoError::InvalidPackage(format!("conflict {}: {}", model.id, e))
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kubestro_core_domain::models::index::PackageChannel;
+    use sea_orm::{sea_query::PostgresQueryBuilder, DbBackend};
+
+    use super::*;
+
+    fn sql(search: &PackageSearch) -> String {
+        sort_packages(filter_packages(search), search)
+            .build(DbBackend::Postgres)
+            .to_string()
+    }
+
+    #[test]
+    fn search_words_should_only_keep_alphanumeric_words() {
+        assert_eq!(
+            search_words("Minecraft  paper:* & minecraft!"),
+            vec!["minecraft".to_string(), "paper".to_string()]
+        );
+    }
+
+    #[test]
+    fn search_words_should_keep_the_first_words_typed() {
+        let words = search_words("zombie wolf vanilla survival paper modded forge creative arcade");
+
+        assert_eq!(words.len(), MAX_SEARCH_WORDS);
+        assert_eq!(words[0], "zombie");
+        assert_eq!(words[MAX_SEARCH_WORDS - 1], "creative");
+    }
+
+    #[test]
+    fn search_should_match_every_word_with_typos() {
+        let search = PackageSearch {
+            search: Some("Minecraft paper".to_string()),
+            ..Default::default()
+        };
+
+        let sql = sql(&search);
+
+        assert!(sql.contains(
+            r#"("package"."search_document" @@ to_tsquery('simple', 'minecraft:*') OR 'minecraft' <% ("package"."name" || ' ' || "package"."keywords"))"#
+        ));
+        assert!(sql.contains(
+            r#"("package"."search_document" @@ to_tsquery('simple', 'paper:*') OR 'paper' <% ("package"."name" || ' ' || "package"."keywords"))"#
+        ));
+        assert!(sql.contains(
+            r#"ORDER BY ts_rank("package"."search_document", to_tsquery('simple', 'minecraft:* | paper:*')) + word_similarity('minecraft paper', ("package"."name" || ' ' || "package"."keywords")) DESC, "package"."name" ASC"#
+        ));
+    }
+
+    #[test]
+    fn search_should_filter_in_the_query() {
+        let repository_id = RepositoryId::new();
+        let search = PackageSearch {
+            repository_id: Some(repository_id.clone()),
+            game: Some("minecraft".to_string()),
+            channel: Some(PackageChannel::Beta),
+            tags: vec!["sandbox".to_string()],
+            ..Default::default()
+        };
+
+        let sql = sql(&search);
+
+        assert!(sql.contains(&format!(
+            r#""package"."repository_id" = '{}'"#,
+            repository_id
+        )));
+        assert!(sql.contains(r#""package"."game" = 'minecraft'"#));
+        assert!(sql.contains(r#""package"."channel" = 'beta'"#));
+        assert!(sql.contains(r#""package"."tags" @> ARRAY ['sandbox']"#));
+        assert!(!sql.contains("ts_rank"));
+    }
+
+    #[test]
+    fn search_should_sort_by_semantic_version() {
+        let search = PackageSearch {
+            sort: CatalogSort::Version,
+            order: SortOrder::Desc,
+            ..Default::default()
+        };
+
+        let sql = sql(&search);
+
+        assert!(sql.ends_with(
+            r#"ORDER BY "package"."version_major" DESC, "package"."version_minor" DESC, "package"."version_patch" DESC, "package"."version_pre" = '' DESC, "package"."version_pre" DESC, "package"."name" DESC, "package"."repository_id" DESC"#
+        ));
+    }
+
+    #[test]
+    fn facets_should_count_the_matching_packages() {
+        let search = PackageSearch {
+            game: Some("minecraft".to_string()),
+            ..Default::default()
+        };
+
+        let sql = facet_statement(&search, Expr::cust(r#"unnest("package"."tags")"#))
+            .to_string(PostgresQueryBuilder);
+
+        assert!(sql.starts_with(
+            r#"SELECT "value", COUNT("value") AS "count" FROM (SELECT unnest("package"."tags") AS "value" FROM "package" WHERE "package"."game" = 'minecraft') AS "facet""#
+        ));
+        assert!(sql.ends_with(r#"GROUP BY "value" ORDER BY "count" DESC, "value" ASC LIMIT 50"#));
+    }
+
+    #[test]
+    fn conflicts_should_be_filtered_by_any_of_their_repositories() {
+        let id = RepositoryId::new();
+
+        let sql = filter_conflicts(Some(&id))
+            .build(DbBackend::Postgres)
+            .to_string();
+
+        assert!(sql.contains(&format!(
+            r#"WHERE "catalog_conflict"."repository_ids" @> ARRAY ['{}']"#,
+            id
+        )));
+        assert!(sql.ends_with(r#"ORDER BY "catalog_conflict"."position" ASC"#));
+    }
+}
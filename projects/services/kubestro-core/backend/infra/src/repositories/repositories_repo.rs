@@ -2,7 +2,11 @@ use std::sync::Arc;
 
 use kubestro_core_domain::{
     models::{
-        package::{CreateRepository, Repository, RepositoryId},
+        fields::trusted_key::TrustedKey,
+        package::{
+            CreateRepository, GitSource, Repository, RepositoryCredentials, RepositoryId,
+            RepositoryKind, RepositorySync, RepositorySyncStatus, UpdateRepository,
+        },
         EntityId,
     },
     ports::repositories::repositories_repositories::{RepositoriesRepository, RepositoryRepoError},
@@ -11,27 +15,143 @@ use sea_orm::{
     sqlx, ActiveModelTrait, ActiveValue, ColumnTrait, Condition, DbErr, EntityTrait, ModelTrait,
     QueryFilter, QueryTrait, RuntimeErr, TransactionTrait,
 };
-use tracing::trace;
+use tracing::{trace, warn};
 
-use crate::entities::{self};
+use crate::{
+    entities::{self, sea_orm_active_enums},
+    services::credentials_cipher::CredentialsCipher,
+};
 
 use super::db::DbProvider;
 
+impl From<sea_orm_active_enums::RepositorySyncStatus> for RepositorySyncStatus {
+    fn from(value: sea_orm_active_enums::RepositorySyncStatus) -> Self {
+        match value {
+            sea_orm_active_enums::RepositorySyncStatus::Pending => RepositorySyncStatus::Pending,
+            sea_orm_active_enums::RepositorySyncStatus::Syncing => RepositorySyncStatus::Syncing,
+            sea_orm_active_enums::RepositorySyncStatus::Ok => RepositorySyncStatus::Ok,
+            sea_orm_active_enums::RepositorySyncStatus::Failed => RepositorySyncStatus::Failed,
+        }
+    }
+}
+
+impl From<RepositorySyncStatus> for sea_orm_active_enums::RepositorySyncStatus {
+    fn from(value: RepositorySyncStatus) -> Self {
+        match value {
+            RepositorySyncStatus::Pending => sea_orm_active_enums::RepositorySyncStatus::Pending,
+            RepositorySyncStatus::Syncing => sea_orm_active_enums::RepositorySyncStatus::Syncing,
+            RepositorySyncStatus::Ok => sea_orm_active_enums::RepositorySyncStatus::Ok,
+            RepositorySyncStatus::Failed => sea_orm_active_enums::RepositorySyncStatus::Failed,
+        }
+    }
+}
+
+/// Split a repository kind into its database columns: the kind and the Git source
+fn to_kind_columns(
+    kind: RepositoryKind,
+) -> (
+    sea_orm_active_enums::RepositoryKind,
+    Option<String>,
+    Option<String>,
+) {
+    match kind {
+        RepositoryKind::Http => (sea_orm_active_enums::RepositoryKind::Http, None, None),
+        RepositoryKind::Oci => (sea_orm_active_enums::RepositoryKind::Oci, None, None),
+        RepositoryKind::Git(source) => (
+            sea_orm_active_enums::RepositoryKind::Git,
+            Some(source.reference),
+            Some(source.path),
+        ),
+        RepositoryKind::Bundle => (sea_orm_active_enums::RepositoryKind::Bundle, None, None),
+    }
+}
+
 impl TryFrom<entities::repository::Model> for Repository {
     type Error = String;
 
     fn try_from(value: entities::repository::Model) -> Result<Self, Self::Error> {
+        let package_count = value
+            .package_count
+            .map(u32::try_from)
+            .transpose()
+            .map_err(|e| format!("Invalid package count: {}", e))?;
+        let index_version = value
+            .index_version
+            .map(|version| version.parse())
+            .transpose()
+            .map_err(|e| format!("Invalid index version: {}", e))?;
+        let trusted_keys = value
+            .trusted_keys
+            .into_iter()
+            .map(TrustedKey::try_from)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Invalid trusted key: {}", e))?;
+
+        let kind = match value.kind {
+            sea_orm_active_enums::RepositoryKind::Http => RepositoryKind::Http,
+            sea_orm_active_enums::RepositoryKind::Oci => RepositoryKind::Oci,
+            sea_orm_active_enums::RepositoryKind::Git => {
+                let default = GitSource::default();
+                RepositoryKind::Git(GitSource {
+                    reference: value.git_reference.unwrap_or(default.reference),
+                    path: value.git_path.unwrap_or(default.path),
+                })
+            }
+            sea_orm_active_enums::RepositoryKind::Bundle => RepositoryKind::Bundle,
+        };
+
         Ok(Repository {
             id: RepositoryId::from(value.id),
             name: value.name,
+            kind,
             url: value.url,
+            trusted_keys,
+            // The credentials are decrypted by the repository, see `RepositoriesPgRepo`
+            credentials: None,
+            priority: value.priority,
+            managed: value.managed,
+            sync: RepositorySync {
+                status: value.sync_status.into(),
+                last_synced_at: value.last_synced_at.map(Into::into),
+                last_error: value.last_sync_error,
+                package_count,
+                index_version,
+                revision: value.sync_revision,
+            },
         })
     }
 }
 
+fn to_key_values(trusted_keys: Vec<TrustedKey>) -> Vec<String> {
+    trusted_keys
+        .iter()
+        .map(|key| key.value().to_string())
+        .collect()
+}
+
+/// Map an insert or update error, detecting the unique constraints violations
+fn map_write_error(err: DbErr) -> RepositoryRepoError {
+    match err {
+        DbErr::Query(RuntimeErr::SqlxError(sqlx::Error::Database(db_err))) => {
+            trace!("Database error: {:?}", db_err.to_string());
+            if db_err.is_unique_violation() {
+                RepositoryRepoError::AlreadyExists
+            } else {
+                RepositoryRepoError::DatabaseError(db_err.to_string())
+            }
+        }
+        e => RepositoryRepoError::UnexpectedError(e.to_string()),
+    }
+}
+
+/// Postgres repositories repository
+///
+/// The repositories credentials are encrypted with the [`CredentialsCipher`], no credentials
+/// can be stored without it.
 #[derive(Clone)]
 pub struct RepositoriesPgRepo {
     db: Arc<DbProvider>,
+    cipher: Option<CredentialsCipher>,
 }
 
 impl RepositoriesPgRepo {
@@ -39,7 +159,69 @@ impl RepositoriesPgRepo {
     where
         Self: Sized,
     {
-        Self { db }
+        Self { db, cipher: None }
+    }
+
+    /// Set the cipher used to encrypt the repositories credentials
+    pub fn with_credentials_cipher(mut self, cipher: CredentialsCipher) -> Self {
+        self.cipher = Some(cipher);
+        self
+    }
+
+    fn to_repository(
+        &self,
+        model: entities::repository::Model,
+    ) -> Result<Repository, RepositoryRepoError> {
+        let credentials = model.credentials.clone();
+        let mut repository =
+            Repository::try_from(model).map_err(RepositoryRepoError::UnexpectedError)?;
+
+        // Undecryptable credentials should not hide the repository, its next sync will
+        // fail and tell why
+        repository.credentials = credentials.and_then(|data| {
+            self.decrypt_credentials(&data)
+                .inspect_err(|e| {
+                    warn!(
+                        "Failed to read the credentials of repository {}: {}",
+                        repository.id, e
+                    )
+                })
+                .ok()
+        });
+
+        Ok(repository)
+    }
+
+    fn decrypt_credentials(&self, data: &[u8]) -> Result<RepositoryCredentials, String> {
+        let cipher = self
+            .cipher
+            .as_ref()
+            .ok_or("no encryption key is configured")?;
+        let data = cipher.decrypt(data).map_err(|e| e.to_string())?;
+
+        serde_json::from_slice(&data).map_err(|e| e.to_string())
+    }
+
+    fn encrypt_credentials(
+        &self,
+        credentials: Option<&RepositoryCredentials>,
+    ) -> Result<Option<Vec<u8>>, RepositoryRepoError> {
+        let Some(credentials) = credentials else {
+            return Ok(None);
+        };
+
+        let cipher = self.cipher.as_ref().ok_or_else(|| {
+            RepositoryRepoError::UnexpectedError(
+                "Credentials cannot be stored without an encryption key".to_string(),
+            )
+        })?;
+        let data = serde_json::to_vec(credentials)
+            .map_err(|e| RepositoryRepoError::UnexpectedError(e.to_string()))?;
+
+        cipher
+            .encrypt(&data)
+            .map(Some)
+            .map_err(|e| RepositoryRepoError::UnexpectedError(e.to_string()))
     }
 }
 
@@ -62,9 +244,8 @@ impl RepositoriesRepository for RepositoriesPgRepo {
             .await
             .map_err(|e| RepositoryRepoError::DatabaseError(e.to_string()))?
             .into_iter()
-            .map(Repository::try_from)
-            .collect::<Result<Vec<Repository>, String>>()
-            .map_err(|e| RepositoryRepoError::UnexpectedError(e.to_string()))?;
+            .map(|repo| self.to_repository(repo))
+            .collect::<Result<Vec<Repository>, _>>()?;
 
         Ok(repos)
     }
@@ -76,9 +257,8 @@ impl RepositoriesRepository for RepositoriesPgRepo {
             .one(self.db.pool())
             .await
             .map_err(|e| RepositoryRepoError::DatabaseError(e.to_string()))?
-            .map(Repository::try_from)
-            .transpose()
-            .map_err(|e| RepositoryRepoError::UnexpectedError(e.to_string()))?;
+            .map(|repo| self.to_repository(repo))
+            .transpose()?;
 
         Ok(repo)
     }
@@ -88,30 +268,88 @@ impl RepositoriesRepository for RepositoriesPgRepo {
         &self,
         repository_data: CreateRepository,
     ) -> Result<Repository, RepositoryRepoError> {
+        let (kind, git_reference, git_path) = to_kind_columns(repository_data.kind);
         let repository = entities::repository::ActiveModel {
             id: ActiveValue::Set(RepositoryId::new().value()),
             name: ActiveValue::Set(repository_data.name),
+            kind: ActiveValue::Set(kind),
+            git_reference: ActiveValue::Set(git_reference),
+            git_path: ActiveValue::Set(git_path),
             url: ActiveValue::Set(repository_data.url),
+            trusted_keys: ActiveValue::Set(to_key_values(repository_data.trusted_keys)),
+            credentials: ActiveValue::Set(
+                self.encrypt_credentials(repository_data.credentials.as_ref())?,
+            ),
+            priority: ActiveValue::Set(repository_data.priority),
+            managed: ActiveValue::Set(repository_data.managed),
+            ..Default::default()
         };
 
         repository
             .insert(self.db.pool())
             .await
-            .map_err(|err| match err {
-                DbErr::Query(RuntimeErr::SqlxError(sqlx::Error::Database(db_err))) => {
-                    trace!("Database error: {:?}", db_err.to_string());
-                    if db_err.is_unique_violation() {
-                        RepositoryRepoError::AlreadyExists
-                    } else {
-                        RepositoryRepoError::DatabaseError(db_err.to_string())
-                    }
-                }
-                e => RepositoryRepoError::UnexpectedError(e.to_string()),
-            })
-            .and_then(|repo| {
-                Repository::try_from(repo)
-                    .map_err(|e| RepositoryRepoError::UnexpectedError(e.to_string()))
-            })
+            .map_err(map_write_error)
+            .and_then(|repo| self.to_repository(repo))
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn update(
+        &self,
+        id: &RepositoryId,
+        repository_data: UpdateRepository,
+    ) -> Result<Repository, RepositoryRepoError> {
+        let txn = self
+            .db
+            .pool()
+            .begin()
+            .await
+            .map_err(|e| RepositoryRepoError::DatabaseError(e.to_string()))?;
+
+        let repo = entities::repository::Entity::find()
+            .filter(entities::repository::Column::Id.eq(id.value()))
+            .one(&txn)
+            .await
+            .map_err(|e| RepositoryRepoError::DatabaseError(e.to_string()))?
+            .ok_or(RepositoryRepoError::NotFound)?;
+
+        let mut repository: entities::repository::ActiveModel = repo.clone().into();
+        if let Some(name) = repository_data.name {
+            repository.name = ActiveValue::Set(name);
+        }
+        if let Some(kind) = repository_data.kind {
+            let (kind, git_reference, git_path) = to_kind_columns(kind);
+            repository.kind = ActiveValue::Set(kind);
+            repository.git_reference = ActiveValue::Set(git_reference);
+            repository.git_path = ActiveValue::Set(git_path);
+        }
+        if let Some(url) = repository_data.url {
+            repository.url = ActiveValue::Set(url);
+        }
+        if let Some(trusted_keys) = repository_data.trusted_keys {
+            repository.trusted_keys = ActiveValue::Set(to_key_values(trusted_keys));
+        }
+        if let Some(credentials) = repository_data.credentials {
+            repository.credentials =
+                ActiveValue::Set(self.encrypt_credentials(credentials.as_ref())?);
+        }
+        if let Some(priority) = repository_data.priority {
+            repository.priority = ActiveValue::Set(priority);
+        }
+        if let Some(managed) = repository_data.managed {
+            repository.managed = ActiveValue::Set(managed);
+        }
+
+        // Nothing to write when the fields are left unchanged
+        let repo = match repository.is_changed() {
+            true => repository.update(&txn).await.map_err(map_write_error)?,
+            false => repo,
+        };
+
+        txn.commit()
+            .await
+            .map_err(|e| RepositoryRepoError::DatabaseError(e.to_string()))?;
+
+        self.to_repository(repo)
     }
 
     #[tracing::instrument(skip(self))]
@@ -141,4 +379,38 @@ impl RepositoriesRepository for RepositoriesPgRepo {
 
         Ok(())
     }
+
+    #[tracing::instrument(skip(self))]
+    async fn update_sync(
+        &self,
+        id: &RepositoryId,
+        sync: RepositorySync,
+    ) -> Result<(), RepositoryRepoError> {
+        let package_count = sync
+            .package_count
+            .map(i32::try_from)
+            .transpose()
+            .map_err(|e| RepositoryRepoError::UnexpectedError(e.to_string()))?;
+
+        let repository = entities::repository::ActiveModel {
+            id: ActiveValue::Unchanged(id.value()),
+            sync_status: ActiveValue::Set(sync.status.into()),
+            last_synced_at: ActiveValue::Set(sync.last_synced_at.map(Into::into)),
+            last_sync_error: ActiveValue::Set(sync.last_error),
+            package_count: ActiveValue::Set(package_count),
+            index_version: ActiveValue::Set(sync.index_version.map(|version| version.to_string())),
+            sync_revision: ActiveValue::Set(sync.revision),
+            ..Default::default()
+        };
+
+        repository
+            .update(self.db.pool())
+            .await
+            .map_err(|err| match err {
+                DbErr::RecordNotUpdated => RepositoryRepoError::NotFound,
+                e => RepositoryRepoError::DatabaseError(e.to_string()),
+            })?;
+
+        Ok(())
+    }
 }
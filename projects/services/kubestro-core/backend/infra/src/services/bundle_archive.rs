@@ -0,0 +1,525 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::{Read, Write},
+};
+
+use chrono::Utc;
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use kubestro_core_domain::{
+    models::{fields::trusted_key::TrustedKey, index::RepositoryIndex},
+    ports::services::bundles_service::{BundledRepository, BundlesServiceError},
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::{
+    index_fetcher::IndexDocument,
+    index_signature::{verify_index_signature, SIGNATURE_SUFFIX},
+};
+
+/// Version of the bundle archive layout
+pub const BUNDLE_VERSION: u32 = 1;
+/// Prefix of the urls of the repositories served from a bundle
+pub const BUNDLE_URL_PREFIX: &str = "bundle://";
+
+const MANIFEST_PATH: &str = "bundle.json";
+/// Name of the index file of every bundled repository
+pub const INDEX_FILE: &str = "index.json";
+/// Maximum length of a repository name in a bundle
+const MAX_NAME_LENGTH: usize = 63;
+
+/// Repositories mirrored in a single archive, along with every file their packages reference
+///
+/// The archive is a gzipped tarball holding:
+///   - `bundle.json`: the manifest, listing the repositories and the files they reference
+///   - `repositories/<name>/index.json`: an index, as published by its repository
+///   - `repositories/<name>/index.json.minisig`: the detached signature of a signed index
+///   - `files/<sha256>`: the artifacts and icons, named by the digest of their content
+#[derive(Debug, Clone, Default)]
+pub struct Bundle {
+    pub repositories: Vec<BundleRepository>,
+    /// Content of the files, by digest
+    pub files: BTreeMap<String, Vec<u8>>,
+}
+
+/// A repository mirrored in a bundle
+#[derive(Debug, Clone)]
+pub struct BundleRepository {
+    /// Name of the repository in the bundle, see [`is_valid_name`]
+    pub name: String,
+    /// Url of the repository the bundle was exported from
+    pub source: String,
+    /// Keys the index signature is verified with when the bundle is uploaded
+    pub trusted_keys: Vec<TrustedKey>,
+    pub document: IndexDocument,
+    /// Digests of the files referenced by the index, by url
+    pub files: BTreeMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Manifest {
+    version: u32,
+    /// RFC 3339 date of the export
+    created_at: String,
+    repositories: Vec<ManifestRepository>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ManifestRepository {
+    name: String,
+    source: String,
+    #[serde(default)]
+    trusted_keys: Vec<String>,
+    #[serde(default)]
+    files: BTreeMap<String, String>,
+}
+
+/// A known entry of a bundle archive
+enum BundleEntry {
+    Manifest,
+    Index(String),
+    Signature(String),
+    File(String),
+}
+
+impl BundleEntry {
+    /// Recognize the path of an entry, any other path is rejected
+    fn parse(path: &str) -> Option<Self> {
+        let signature_file = format!("{}{}", INDEX_FILE, SIGNATURE_SUFFIX);
+
+        match path.trim_start_matches("./").split('/').collect::<Vec<_>>()[..] {
+            [MANIFEST_PATH] => Some(Self::Manifest),
+            ["repositories", name, INDEX_FILE] if is_valid_name(name) => {
+                Some(Self::Index(name.to_string()))
+            }
+            ["repositories", name, file] if is_valid_name(name) && file == signature_file => {
+                Some(Self::Signature(name.to_string()))
+            }
+            ["files", digest] if is_valid_digest(digest) => Some(Self::File(digest.to_string())),
+            _ => None,
+        }
+    }
+}
+
+impl Bundle {
+    /// Write the bundle as a gzipped tarball
+    pub fn pack(&self) -> Result<Vec<u8>, BundlesServiceError> {
+        let manifest = Manifest {
+            version: BUNDLE_VERSION,
+            created_at: Utc::now().to_rfc3339(),
+            repositories: self
+                .repositories
+                .iter()
+                .map(|repository| ManifestRepository {
+                    name: repository.name.clone(),
+                    source: repository.source.clone(),
+                    trusted_keys: repository
+                        .trusted_keys
+                        .iter()
+                        .map(|key| key.value().to_string())
+                        .collect(),
+                    files: repository.files.clone(),
+                })
+                .collect(),
+        };
+        let manifest = serde_json::to_vec_pretty(&manifest)
+            .map_err(|e| BundlesServiceError::UnexpectedError(e.to_string()))?;
+
+        let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+        let result = (|| {
+            append(&mut builder, MANIFEST_PATH, &manifest)?;
+            for repository in &self.repositories {
+                let index_path = format!("repositories/{}/{}", repository.name, INDEX_FILE);
+                append(&mut builder, &index_path, &repository.document.data)?;
+                if let Some(signature) = &repository.document.signature {
+                    let signature_path = format!("{}{}", index_path, SIGNATURE_SUFFIX);
+                    append(&mut builder, &signature_path, signature.as_bytes())?;
+                }
+            }
+            for (digest, data) in &self.files {
+                append(&mut builder, &format!("files/{}", digest), data)?;
+            }
+            builder.into_inner()?.finish()
+        })();
+
+        result.map_err(|e| BundlesServiceError::UnexpectedError(e.to_string()))
+    }
+
+    /// Read a bundle from a gzipped tarball
+    ///
+    /// Only the entries of the bundle layout are accepted, and the archive is rejected once
+    /// its unpacked content exceeds `max_size` bytes. The content itself is checked by
+    /// [`Bundle::verify`].
+    pub fn unpack(archive: &[u8], max_size: u64) -> Result<Self, BundlesServiceError> {
+        let invalid = |e: std::io::Error| BundlesServiceError::InvalidBundle(e.to_string());
+
+        let mut manifest = None;
+        let mut indexes = HashMap::new();
+        let mut signatures = HashMap::new();
+        let mut files = BTreeMap::new();
+        let mut size = 0u64;
+
+        let mut archive = tar::Archive::new(GzDecoder::new(archive));
+        for entry in archive.entries().map_err(invalid)? {
+            let mut entry = entry.map_err(invalid)?;
+            let path = entry
+                .path()
+                .map_err(invalid)?
+                .to_string_lossy()
+                .into_owned();
+
+            if entry.header().entry_type().is_dir() {
+                continue;
+            }
+            if !entry.header().entry_type().is_file() {
+                return Err(BundlesServiceError::InvalidBundle(format!(
+                    "`{}` is not a regular file",
+                    path
+                )));
+            }
+
+            size = size.saturating_add(entry.size());
+            if size > max_size {
+                return Err(BundlesServiceError::InvalidBundle(format!(
+                    "the unpacked bundle exceeds {} bytes",
+                    max_size
+                )));
+            }
+
+            let mut data = Vec::new();
+            entry.read_to_end(&mut data).map_err(invalid)?;
+
+            match BundleEntry::parse(&path) {
+                Some(BundleEntry::Manifest) => manifest = Some(data),
+                Some(BundleEntry::Index(name)) => {
+                    indexes.insert(name, data);
+                }
+                Some(BundleEntry::Signature(name)) => {
+                    let signature = String::from_utf8(data).map_err(|_| {
+                        BundlesServiceError::InvalidBundle(format!(
+                            "the signature of repository `{}` is not valid UTF-8",
+                            name
+                        ))
+                    })?;
+                    signatures.insert(name, signature);
+                }
+                Some(BundleEntry::File(digest)) => {
+                    files.insert(digest, data);
+                }
+                None => {
+                    return Err(BundlesServiceError::InvalidBundle(format!(
+                        "unexpected entry `{}`",
+                        path
+                    )))
+                }
+            }
+        }
+
+        let manifest = manifest.ok_or_else(|| {
+            BundlesServiceError::InvalidBundle(format!("`{}` is missing", MANIFEST_PATH))
+        })?;
+        let manifest = serde_json::from_slice::<Manifest>(&manifest)
+            .map_err(|e| BundlesServiceError::InvalidBundle(format!("{}: {}", MANIFEST_PATH, e)))?;
+        if manifest.version != BUNDLE_VERSION {
+            return Err(BundlesServiceError::InvalidBundle(format!(
+                "unsupported bundle version {}, expected {}",
+                manifest.version, BUNDLE_VERSION
+            )));
+        }
+
+        let mut repositories = Vec::with_capacity(manifest.repositories.len());
+        for repository in manifest.repositories {
+            let name = repository.name;
+            let data = indexes.remove(&name).ok_or_else(|| {
+                BundlesServiceError::InvalidBundle(format!(
+                    "the index of repository `{}` is missing",
+                    name
+                ))
+            })?;
+            let trusted_keys = repository
+                .trusted_keys
+                .into_iter()
+                .map(TrustedKey::try_from)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| {
+                    BundlesServiceError::InvalidBundle(format!("repository `{}`: {}", name, e))
+                })?;
+
+            repositories.push(BundleRepository {
+                source: repository.source,
+                trusted_keys,
+                document: IndexDocument {
+                    data,
+                    signature: signatures.remove(&name),
+                },
+                files: repository.files,
+                name,
+            });
+        }
+
+        // Every index has been taken by a repository of the manifest, or was listed twice
+        if let Some(name) = indexes.into_keys().next() {
+            return Err(BundlesServiceError::InvalidBundle(format!(
+                "repository `{}` is missing from the manifest, or listed twice",
+                name
+            )));
+        }
+
+        Ok(Self {
+            repositories,
+            files,
+        })
+    }
+
+    /// Check the content of the bundle and describe its repositories
+    ///
+    /// The files must match their digest, every referenced file must be bundled and every
+    /// index must be valid. The indexes of repositories with trusted keys must be signed by one
+    /// of them.
+    pub fn verify(&self) -> Result<Vec<BundledRepository>, BundlesServiceError> {
+        for (digest, data) in &self.files {
+            if file_digest(data) != *digest {
+                return Err(BundlesServiceError::InvalidBundle(format!(
+                    "the content of file `{}` does not match its digest",
+                    digest
+                )));
+            }
+        }
+
+        self.repositories
+            .iter()
+            .map(|repository| {
+                let invalid = |detail: String| {
+                    BundlesServiceError::InvalidBundle(format!(
+                        "repository `{}`: {}",
+                        repository.name, detail
+                    ))
+                };
+
+                if let Some(url) = repository
+                    .files
+                    .iter()
+                    .find(|(_, digest)| !self.files.contains_key(*digest))
+                    .map(|(url, _)| url)
+                {
+                    return Err(invalid(format!("the file of `{}` is missing", url)));
+                }
+
+                let document = &repository.document;
+                let signed = match (&document.signature, repository.trusted_keys.is_empty()) {
+                    (_, true) => false,
+                    (None, false) => return Err(invalid("the index is not signed".to_string())),
+                    (Some(signature), false) => {
+                        verify_index_signature(&document.data, signature, &repository.trusted_keys)
+                            .map_err(|e| invalid(e.to_string()))?;
+                        true
+                    }
+                };
+
+                let index = RepositoryIndex::from_slice(&document.data)
+                    .map_err(|e| invalid(e.to_string()))?;
+
+                Ok(BundledRepository {
+                    name: repository.name.clone(),
+                    url: bundle_url(&repository.name),
+                    source: repository.source.clone(),
+                    package_count: index.packages.len().try_into().unwrap_or(u32::MAX),
+                    index_version: index.version.to_string(),
+                    signed,
+                    file_count: repository.files.len().try_into().unwrap_or(u32::MAX),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Append a file to a tarball
+fn append<W: Write>(builder: &mut tar::Builder<W>, path: &str, data: &[u8]) -> std::io::Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o644);
+    builder.append_data(&mut header, path, data)
+}
+
+/// Digest a bundled file is named by
+pub fn file_digest(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
+fn is_valid_digest(digest: &str) -> bool {
+    digest.len() == 64
+        && digest
+            .chars()
+            .all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c))
+}
+
+/// Whether a name can identify a repository in a bundle
+///
+/// Names are made of lowercase letters, digits and dashes, so they are safe to use as paths.
+pub fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= MAX_NAME_LENGTH
+        && !name.starts_with('-')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+
+/// Derive the name of a repository in a bundle from its display name
+pub fn bundle_name(name: &str) -> String {
+    let mut bundle_name = String::new();
+    for c in name.chars() {
+        match c.is_ascii_alphanumeric() {
+            true => bundle_name.push(c.to_ascii_lowercase()),
+            false if !bundle_name.is_empty() && !bundle_name.ends_with('-') => {
+                bundle_name.push('-')
+            }
+            false => {}
+        }
+    }
+    bundle_name.truncate(MAX_NAME_LENGTH);
+
+    match bundle_name.trim_end_matches('-') {
+        "" => "repository".to_string(),
+        name => name.to_string(),
+    }
+}
+
+/// Url of the `bundle` repositories serving a repository of a bundle
+pub fn bundle_url(name: &str) -> String {
+    format!("{}{}", BUNDLE_URL_PREFIX, name)
+}
+
+/// Read the name of the bundled repository a `bundle://<name>` url refers to
+pub fn parse_bundle_url(url: &str) -> Option<&str> {
+    url.strip_prefix(BUNDLE_URL_PREFIX)
+        .map(|name| name.trim_end_matches('/'))
+        .filter(|name| is_valid_name(name))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::services::index_signature::tests::{
+        INDEX_SIGNATURE, OTHER_KEY, SIGNED_INDEX, SIGNING_KEY,
+    };
+
+    pub(crate) fn bundle(signature: Option<&str>, trusted_keys: &[&str]) -> Bundle {
+        let icon = b"icon".to_vec();
+        let digest = file_digest(&icon);
+
+        Bundle {
+            repositories: vec![BundleRepository {
+                name: "stub".to_string(),
+                source: "https://example.com/index.json".to_string(),
+                trusted_keys: trusted_keys
+                    .iter()
+                    .map(|key| TrustedKey::try_from(*key).unwrap())
+                    .collect(),
+                document: IndexDocument {
+                    data: SIGNED_INDEX.as_bytes().to_vec(),
+                    signature: signature.map(String::from),
+                },
+                files: BTreeMap::from([(
+                    "https://example.com/icon.png".to_string(),
+                    digest.clone(),
+                )]),
+            }],
+            files: BTreeMap::from([(digest, icon)]),
+        }
+    }
+
+    /// Pack arbitrary entries in a gzipped tarball
+    fn archive(entries: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+        for (path, data) in entries {
+            append(&mut builder, path, data).unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap()
+    }
+
+    #[test]
+    fn unpack_should_read_a_packed_bundle() {
+        let archive = bundle(Some(INDEX_SIGNATURE), &[SIGNING_KEY])
+            .pack()
+            .unwrap();
+
+        let bundle = Bundle::unpack(&archive, u64::MAX).unwrap();
+
+        let repositories = bundle.verify().unwrap();
+        assert_eq!(
+            repositories,
+            vec![BundledRepository {
+                name: "stub".to_string(),
+                url: "bundle://stub".to_string(),
+                source: "https://example.com/index.json".to_string(),
+                package_count: 0,
+                index_version: "1.0".to_string(),
+                signed: true,
+                file_count: 1,
+            }]
+        );
+        assert_eq!(
+            bundle.repositories[0].document.signature.as_deref(),
+            Some(INDEX_SIGNATURE)
+        );
+    }
+
+    #[test]
+    fn unpack_should_reject_unexpected_entries() {
+        let archive = archive(&[("scripts/install.sh", b"")]);
+
+        let result = Bundle::unpack(&archive, u64::MAX);
+
+        assert!(matches!(result, Err(BundlesServiceError::InvalidBundle(_))));
+        assert!(BundleEntry::parse("repositories/../index.json").is_none());
+        assert!(BundleEntry::parse("files/../../etc/passwd").is_none());
+    }
+
+    #[test]
+    fn unpack_should_reject_oversized_bundles() {
+        let archive = bundle(None, &[]).pack().unwrap();
+
+        let result = Bundle::unpack(&archive, 16);
+
+        assert!(matches!(result, Err(BundlesServiceError::InvalidBundle(_))));
+    }
+
+    #[test]
+    fn verify_should_reject_altered_files() {
+        let mut bundle = bundle(None, &[]);
+        for data in bundle.files.values_mut() {
+            *data = b"altered".to_vec();
+        }
+
+        assert!(matches!(
+            bundle.verify(),
+            Err(BundlesServiceError::InvalidBundle(_))
+        ));
+    }
+
+    #[test]
+    fn verify_should_check_the_index_signature() {
+        let unsigned = bundle(None, &[SIGNING_KEY]);
+        let untrusted = bundle(Some(INDEX_SIGNATURE), &[OTHER_KEY]);
+
+        assert!(matches!(
+            unsigned.verify(),
+            Err(BundlesServiceError::InvalidBundle(_))
+        ));
+        assert!(matches!(
+            untrusted.verify(),
+            Err(BundlesServiceError::InvalidBundle(_))
+        ));
+    }
+
+    #[test]
+    fn bundle_name_should_only_keep_safe_characters() {
+        assert_eq!(bundle_name("Kubestro Official!"), "kubestro-official");
+        assert_eq!(bundle_name("--Ünïcode--"), "n-code");
+        assert_eq!(bundle_name("!!!"), "repository");
+        assert!(is_valid_name(&bundle_name(&"a-".repeat(40))));
+    }
+}
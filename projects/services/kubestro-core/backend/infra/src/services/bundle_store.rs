@@ -0,0 +1,397 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use kubestro_core_domain::{
+    models::{index::RepositoryIndex, package::Repository},
+    ports::services::{
+        bundles_service::BundlesServiceError, repositories_service::RepositoriesServiceError,
+    },
+};
+use tokio::sync::RwLock;
+
+use super::{
+    bundle_archive::{file_digest, parse_bundle_url, Bundle, BUNDLE_URL_PREFIX, INDEX_FILE},
+    index_fetcher::{CacheValidators, FetchOutcome, IndexDocument},
+    index_signature::{verify_index_signature, SIGNATURE_SUFFIX},
+};
+
+/// Name of the file holding the digests of the files referenced by a stored index, by url
+const FILES_MAP: &str = "files.json";
+
+/// Store of the uploaded bundles, serving the indexes of the `bundle` repositories
+///
+/// The repositories of the bundles are kept by name in the bundles directory, so a newer
+/// bundle replaces them:
+///   - `repositories/<name>/index.json`, along with its signature
+///   - `repositories/<name>/files.json`: the digests of the files the index references, by url
+///   - `files/<sha256>`: the files, shared by every repository
+///
+/// The digest of an index is its cache validator and its revision. Like the indexes of the
+/// other sources, it must be signed by one of the trusted keys of the repository serving it.
+#[derive(Clone)]
+pub struct BundleStore {
+    dir: PathBuf,
+    allow_unsigned: bool,
+    /// Bundles are stored while nothing is read from the directory
+    lock: Arc<RwLock<()>>,
+}
+
+impl Default for BundleStore {
+    fn default() -> Self {
+        Self {
+            dir: std::env::temp_dir().join("kubestro-bundles"),
+            allow_unsigned: false,
+            lock: Arc::new(RwLock::new(())),
+        }
+    }
+}
+
+impl BundleStore {
+    /// Set the directory holding the uploaded bundles
+    pub fn with_dir(mut self, dir: PathBuf) -> Self {
+        self.dir = dir;
+        self
+    }
+
+    /// Accept the indexes of repositories without trusted keys
+    pub fn with_unsigned_indexes(mut self, allow_unsigned: bool) -> Self {
+        self.allow_unsigned = allow_unsigned;
+        self
+    }
+
+    /// Read the index of a `bundle` repository
+    ///
+    /// When `validators` hold the digest of the stored index, [`FetchOutcome::NotModified`] is
+    /// returned.
+    #[tracing::instrument(skip(self, repository), fields(url = %repository.url))]
+    pub async fn fetch(
+        &self,
+        repository: &Repository,
+        validators: Option<&CacheValidators>,
+    ) -> Result<FetchOutcome, RepositoriesServiceError> {
+        let name = bundle_repository_name(&repository.url)?;
+        let trusted_keys = &repository.trusted_keys;
+
+        if trusted_keys.is_empty() && !self.allow_unsigned {
+            return Err(RepositoriesServiceError::InvalidSignature(
+                "no trusted key is configured for this repository".to_string(),
+            ));
+        }
+
+        let _guard = self.lock.read().await;
+        let repository_dir = self.repository_dir(name);
+
+        let data = match tokio::fs::read(repository_dir.join(INDEX_FILE)).await {
+            Ok(data) => data,
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                return Err(RepositoriesServiceError::RemoteDataError(format!(
+                    "No uploaded bundle holds the repository `{}`",
+                    name
+                )))
+            }
+            Err(e) => return Err(RepositoriesServiceError::UnexpectedError(e.to_string())),
+        };
+        let digest = file_digest(&data);
+
+        if validators.and_then(|validators| validators.etag.as_ref()) == Some(&digest) {
+            return Ok(FetchOutcome::NotModified);
+        }
+
+        let signature = match trusted_keys.is_empty() {
+            true => None,
+            false => {
+                let signature_path =
+                    repository_dir.join(format!("{}{}", INDEX_FILE, SIGNATURE_SUFFIX));
+                let signature = tokio::fs::read_to_string(signature_path)
+                    .await
+                    .map_err(|e| {
+                        RepositoriesServiceError::InvalidSignature(format!(
+                            "failed to read the index signature: {}",
+                            e
+                        ))
+                    })?;
+
+                verify_index_signature(&data, &signature, trusted_keys)?;
+                Some(signature)
+            }
+        };
+
+        // Parse the index, any invalid entry will reject the whole index
+        let index = Box::new(RepositoryIndex::from_slice(&data)?);
+
+        Ok(FetchOutcome::Fetched {
+            index,
+            validators: CacheValidators {
+                etag: Some(digest.clone()),
+                last_modified: None,
+            },
+            revision: Some(digest),
+            document: IndexDocument { data, signature },
+        })
+    }
+
+    /// Read a file referenced by the index of a `bundle` repository
+    pub async fn read_file(
+        &self,
+        repository: &Repository,
+        url: &str,
+    ) -> Result<Vec<u8>, RepositoriesServiceError> {
+        let name = bundle_repository_name(&repository.url)?;
+        let missing = || {
+            RepositoriesServiceError::RemoteDataError(format!(
+                "The bundle of repository `{}` does not hold `{}`",
+                name, url
+            ))
+        };
+
+        let _guard = self.lock.read().await;
+        let files = read_files_map(&self.repository_dir(name).join(FILES_MAP))
+            .await
+            .map_err(|_| missing())?;
+        let digest = files.get(url).ok_or_else(missing)?;
+
+        tokio::fs::read(self.dir.join("files").join(digest))
+            .await
+            .map_err(|_| missing())
+    }
+
+    /// Store the repositories of a verified bundle
+    ///
+    /// The repositories of the same names are replaced, the files no repository references
+    /// anymore are removed.
+    pub async fn store(&self, bundle: &Bundle) -> Result<(), BundlesServiceError> {
+        let _guard = self.lock.write().await;
+
+        let files_dir = self.dir.join("files");
+        tokio::fs::create_dir_all(&files_dir)
+            .await
+            .map_err(storage_error)?;
+        for (digest, data) in &bundle.files {
+            let path = files_dir.join(digest);
+            if !tokio::fs::try_exists(&path).await.map_err(storage_error)? {
+                write_file(&path, data).await?;
+            }
+        }
+
+        let repositories_dir = self.dir.join("repositories");
+        tokio::fs::create_dir_all(&repositories_dir)
+            .await
+            .map_err(storage_error)?;
+        for repository in &bundle.repositories {
+            // Every repository is written aside, then swapped with the previous one
+            let staging_dir = repositories_dir.join(format!(".{}.staging", repository.name));
+            remove_dir(&staging_dir).await?;
+            tokio::fs::create_dir_all(&staging_dir)
+                .await
+                .map_err(storage_error)?;
+
+            let document = &repository.document;
+            write_file(&staging_dir.join(INDEX_FILE), &document.data).await?;
+            if let Some(signature) = &document.signature {
+                let signature_file = format!("{}{}", INDEX_FILE, SIGNATURE_SUFFIX);
+                write_file(&staging_dir.join(signature_file), signature.as_bytes()).await?;
+            }
+            let files = serde_json::to_vec(&repository.files)
+                .map_err(|e| BundlesServiceError::UnexpectedError(e.to_string()))?;
+            write_file(&staging_dir.join(FILES_MAP), &files).await?;
+
+            let repository_dir = self.repository_dir(&repository.name);
+            remove_dir(&repository_dir).await?;
+            tokio::fs::rename(&staging_dir, &repository_dir)
+                .await
+                .map_err(storage_error)?;
+        }
+
+        self.prune_files().await
+    }
+
+    /// Remove the files no stored repository references
+    async fn prune_files(&self) -> Result<(), BundlesServiceError> {
+        let mut referenced = HashSet::new();
+        let mut repositories = tokio::fs::read_dir(self.dir.join("repositories"))
+            .await
+            .map_err(storage_error)?;
+        while let Some(entry) = repositories.next_entry().await.map_err(storage_error)? {
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            let files = read_files_map(&entry.path().join(FILES_MAP))
+                .await
+                .map_err(storage_error)?;
+            referenced.extend(files.into_values());
+        }
+
+        let mut files = tokio::fs::read_dir(self.dir.join("files"))
+            .await
+            .map_err(storage_error)?;
+        while let Some(entry) = files.next_entry().await.map_err(storage_error)? {
+            if !referenced.contains(entry.file_name().to_string_lossy().as_ref()) {
+                tokio::fs::remove_file(entry.path())
+                    .await
+                    .map_err(storage_error)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn repository_dir(&self, name: &str) -> PathBuf {
+        self.dir.join("repositories").join(name)
+    }
+}
+
+/// Read the name of the bundled repository served by a `bundle` repository
+pub fn bundle_repository_name(url: &str) -> Result<&str, RepositoriesServiceError> {
+    parse_bundle_url(url).ok_or_else(|| {
+        RepositoriesServiceError::UrlNotAllowed(format!(
+            "expected a `{}<name>` url, the name being made of lowercase letters, digits and \
+                dashes",
+            BUNDLE_URL_PREFIX
+        ))
+    })
+}
+
+async fn read_files_map(path: &Path) -> std::io::Result<BTreeMap<String, String>> {
+    let files = tokio::fs::read(path).await?;
+    serde_json::from_slice(&files).map_err(std::io::Error::other)
+}
+
+/// Write a file at once, readers never see it partially written
+async fn write_file(path: &Path, data: &[u8]) -> Result<(), BundlesServiceError> {
+    let partial = path.with_extension("partial");
+    tokio::fs::write(&partial, data)
+        .await
+        .map_err(storage_error)?;
+    tokio::fs::rename(&partial, path)
+        .await
+        .map_err(storage_error)
+}
+
+async fn remove_dir(path: &Path) -> Result<(), BundlesServiceError> {
+    match tokio::fs::remove_dir_all(path).await {
+        Err(e) if e.kind() != ErrorKind::NotFound => Err(storage_error(e)),
+        _ => Ok(()),
+    }
+}
+
+fn storage_error(e: std::io::Error) -> BundlesServiceError {
+    BundlesServiceError::StorageError(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use kubestro_core_domain::models::{
+        package::{RepositoryId, RepositoryKind},
+        EntityId,
+    };
+
+    use super::*;
+    use crate::services::{
+        bundle_archive::tests::bundle,
+        index_fetcher::tests::repository,
+        index_signature::tests::{INDEX_SIGNATURE, SIGNING_KEY},
+    };
+
+    fn store() -> BundleStore {
+        BundleStore::default().with_dir(
+            std::env::temp_dir().join(format!("kubestro-bundles-test-{}", RepositoryId::new())),
+        )
+    }
+
+    fn bundle_repository(trusted_keys: &[&str]) -> Repository {
+        Repository {
+            kind: RepositoryKind::Bundle,
+            ..repository("bundle://stub", trusted_keys)
+        }
+    }
+
+    #[tokio::test]
+    async fn fetch_should_read_the_stored_index_and_pin_its_digest() {
+        let store = store();
+        store
+            .store(&bundle(Some(INDEX_SIGNATURE), &[SIGNING_KEY]))
+            .await
+            .unwrap();
+        let repository = bundle_repository(&[SIGNING_KEY]);
+
+        let outcome = store.fetch(&repository, None).await.unwrap();
+
+        let FetchOutcome::Fetched {
+            index,
+            validators,
+            revision,
+            document,
+        } = outcome
+        else {
+            panic!("the index should be read");
+        };
+        assert_eq!(index.repository.name, "Stub");
+        assert_eq!(validators.etag, revision);
+        assert_eq!(document.signature.as_deref(), Some(INDEX_SIGNATURE));
+
+        let outcome = store.fetch(&repository, Some(&validators)).await.unwrap();
+        assert!(matches!(outcome, FetchOutcome::NotModified));
+    }
+
+    #[tokio::test]
+    async fn fetch_should_require_a_signature_from_the_repository_keys() {
+        let store = store();
+        store.store(&bundle(None, &[])).await.unwrap();
+
+        let result = store.fetch(&bundle_repository(&[SIGNING_KEY]), None).await;
+
+        assert!(matches!(
+            result,
+            Err(RepositoriesServiceError::InvalidSignature(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn fetch_should_reject_repositories_not_uploaded_or_invalid() {
+        let store = store().with_unsigned_indexes(true);
+
+        let missing = store.fetch(&bundle_repository(&[]), None).await;
+        let invalid = store
+            .fetch(&repository("bundle://../stub", &[]), None)
+            .await;
+
+        assert!(matches!(
+            missing,
+            Err(RepositoriesServiceError::RemoteDataError(_))
+        ));
+        assert!(matches!(
+            invalid,
+            Err(RepositoriesServiceError::UrlNotAllowed(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn store_should_replace_the_repository_and_prune_its_files() {
+        let store = store();
+        let repository = bundle_repository(&[]);
+        store.store(&bundle(None, &[])).await.unwrap();
+        assert_eq!(
+            store
+                .read_file(&repository, "https://example.com/icon.png")
+                .await
+                .unwrap(),
+            b"icon"
+        );
+
+        let mut newer = bundle(None, &[]);
+        newer.repositories[0].files.clear();
+        newer.files.clear();
+        store.store(&newer).await.unwrap();
+
+        assert!(store
+            .read_file(&repository, "https://example.com/icon.png")
+            .await
+            .is_err());
+        let mut files = tokio::fs::read_dir(store.dir.join("files")).await.unwrap();
+        assert!(files.next_entry().await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,157 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use kubestro_core_domain::{
+    models::package::RepositoryId,
+    ports::{
+        repositories::repositories_repositories::{RepositoriesRepository, RepositoryRepoError},
+        services::bundles_service::{BundledRepository, BundlesService, BundlesServiceError},
+    },
+};
+use tracing::debug;
+
+use super::{
+    bundle_archive::{bundle_name, file_digest, Bundle, BundleRepository},
+    bundle_store::BundleStore,
+    repositories_service::InfraRepositoriesService,
+};
+
+/// Default maximum size of an unpacked bundle, in bytes
+pub const BUNDLE_MAX_SIZE: u64 = 1024 * 1024 * 1024;
+
+/// Export repositories in bundles, and serve the uploaded bundles through the `bundle`
+/// repositories
+///
+/// The bundles are built and read in memory, their size is bounded by the maximum size.
+#[derive(Clone)]
+pub struct InfraBundlesService {
+    repositories_repository: Arc<dyn RepositoriesRepository>,
+    repositories_service: InfraRepositoriesService,
+    store: BundleStore,
+    max_size: u64,
+}
+
+impl InfraBundlesService {
+    pub fn new(
+        repositories_repository: Arc<dyn RepositoriesRepository>,
+        repositories_service: InfraRepositoriesService,
+        store: BundleStore,
+    ) -> Self {
+        Self {
+            repositories_repository,
+            repositories_service,
+            store,
+            max_size: BUNDLE_MAX_SIZE,
+        }
+    }
+
+    /// Set the maximum size of an unpacked bundle, in bytes
+    pub fn with_max_size(mut self, max_size: u64) -> Self {
+        self.max_size = max_size;
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl BundlesService for InfraBundlesService {
+    /// Export repositories in a single bundle archive
+    ///
+    /// Every repository is named after its display name in the bundle. The package artifacts
+    /// and icons are downloaded once, even when several packages share them.
+    #[tracing::instrument(skip(self))]
+    async fn export(&self, repositories: &[RepositoryId]) -> Result<Vec<u8>, BundlesServiceError> {
+        let mut bundle = Bundle::default();
+
+        for repository_id in repositories {
+            let repository = self
+                .repositories_repository
+                .find_one(repository_id)
+                .await?
+                .ok_or(RepositoryRepoError::NotFound)?;
+
+            let (index, document) = self
+                .repositories_service
+                .fetch_document(&repository)
+                .await?;
+
+            let urls = index
+                .packages
+                .iter()
+                .flat_map(|package| {
+                    std::iter::once(package.source.url()).chain(package.icon.as_deref())
+                })
+                .collect::<Vec<_>>();
+            let mut files = BTreeMap::new();
+            for url in urls {
+                if files.contains_key(url) {
+                    continue;
+                }
+                let data = self
+                    .repositories_service
+                    .fetch_file(&repository, url)
+                    .await?;
+                let digest = file_digest(&data);
+                bundle.files.insert(digest.clone(), data);
+                files.insert(url.to_string(), digest);
+            }
+
+            // Repositories with the same display name get a numbered name
+            let name = bundle_name(&repository.name);
+            let mut unique_name = name.clone();
+            let mut suffix = 1;
+            while bundle
+                .repositories
+                .iter()
+                .any(|bundled| bundled.name == unique_name)
+            {
+                suffix += 1;
+                unique_name = format!("{}-{}", name, suffix);
+            }
+
+            debug!(
+                "Bundled repository {} as `{}` with {} file(s)",
+                repository.id,
+                unique_name,
+                files.len()
+            );
+            bundle.repositories.push(BundleRepository {
+                name: unique_name,
+                source: repository.url,
+                trusted_keys: repository.trusted_keys,
+                document,
+                files,
+            });
+        }
+
+        tokio::task::spawn_blocking(move || bundle.pack())
+            .await
+            .map_err(|e| BundlesServiceError::UnexpectedError(e.to_string()))?
+    }
+
+    /// Verify an uploaded bundle archive and store its repositories
+    ///
+    /// Nothing is stored unless the whole bundle is valid.
+    #[tracing::instrument(skip(self, archive))]
+    async fn import(
+        &self,
+        archive: Vec<u8>,
+    ) -> Result<Vec<BundledRepository>, BundlesServiceError> {
+        let max_size = self.max_size;
+        let (bundle, repositories) = tokio::task::spawn_blocking(move || {
+            let bundle = Bundle::unpack(&archive, max_size)?;
+            let repositories = bundle.verify()?;
+            Ok::<_, BundlesServiceError>((bundle, repositories))
+        })
+        .await
+        .map_err(|e| BundlesServiceError::UnexpectedError(e.to_string()))??;
+
+        self.store.store(&bundle).await?;
+
+        let names = repositories
+            .iter()
+            .map(|repository| repository.name.clone())
+            .collect::<Vec<_>>();
+        self.repositories_service.refresh_bundled(&names).await?;
+
+        Ok(repositories)
+    }
+}
@@ -0,0 +1,1330 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    time::Duration,
+};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use k8s_openapi::{
+    api::{
+        apps::v1::{ControllerRevision, Deployment},
+        batch::v1::{Job, JobSpec},
+        core::v1::{
+            Container, EnvVar, EnvVarSource, ObjectFieldSelector, Pod, PodSpec, PodTemplateSpec,
+        },
+    },
+    apimachinery::pkg::{apis::meta::v1::ObjectMeta, runtime::RawExtension},
+};
+use kube::{
+    api::{
+        DeleteParams, DynamicObject, GroupVersionKind, ListParams, Patch, PatchParams, PostParams,
+    },
+    discovery,
+    error::DiscoveryError,
+    Api, Client, Resource, ResourceExt,
+};
+use kubestro_core_domain::{
+    models::{
+        game_manager::{
+            GameManagerCondition, GameManagerInstallation, GameManagerMigration,
+            GameManagerRevision, GameManagerRevisionCause, GameManagerState, GameManagerWorkload,
+            NewGameManager, NewGameManagerRevision,
+        },
+        index::{CrdReference, PermissionRule},
+    },
+    ports::services::cluster_service::{ClusterService, ClusterServiceError},
+};
+use serde::{Deserialize, Serialize};
+use tokio::time::Instant;
+use tracing::warn;
+
+use crate::k8s::{
+    controller::{CONTAINER_NAME, GAME_MANAGER_LABEL, MANAGED_BY_LABEL, NAMESPACE_ENV, NAME_ENV},
+    game_manager::{
+        GameManager, GameManagerPackage, GameManagerPermission, GameManagerSpec, GameServerKind,
+        DEFAULT_PORT, FIELD_MANAGER,
+    },
+};
+
+use super::k8s_client::K8sClient;
+
+/// Annotation of the revisions telling why they were deployed
+const CAUSE_ANNOTATION: &str = "kubestro.io/cause";
+/// Annotation of the revisions naming the user who deployed them
+const CREATED_BY_ANNOTATION: &str = "kubestro.io/created-by";
+/// Annotation of the game managers holding the time their latest revision must become available
+/// by, formatted as RFC 3339
+const ROLLOUT_DEADLINE_ANNOTATION: &str = "kubestro.io/rollout-deadline";
+/// Version the migration jobs migrate from
+const FROM_VERSION_ENV: &str = "KUBESTRO_FROM_VERSION";
+/// Version the migration jobs migrate to
+const TO_VERSION_ENV: &str = "KUBESTRO_TO_VERSION";
+/// Name of the container of the migration jobs
+const MIGRATION_CONTAINER_NAME: &str = "migration";
+/// Delay before the finished migration jobs are deleted, keeping their logs around for a while
+const MIGRATION_TTL: i32 = 24 * 60 * 60;
+/// Delay between two checks of the state of a migration job
+const MIGRATION_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Part of the spec of a game manager recorded in each revision, as the data of a
+/// `ControllerRevision`
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct RevisionData {
+    version: String,
+    image: String,
+    values: serde_json::Value,
+    #[serde(default)]
+    permissions: Vec<GameManagerPermission>,
+    #[serde(default)]
+    game_servers: Vec<GameServerKind>,
+}
+
+impl From<&GameManagerSpec> for RevisionData {
+    fn from(spec: &GameManagerSpec) -> Self {
+        Self {
+            version: spec.version.clone(),
+            image: spec.image.clone(),
+            values: spec.values.clone(),
+            permissions: spec.permissions.clone(),
+            game_servers: spec.game_servers.clone(),
+        }
+    }
+}
+
+/// Declare the game managers as `GameManager` resources, in a single namespace
+#[derive(Clone)]
+pub struct K8sClusterService {
+    client: Client,
+    namespace: String,
+}
+
+impl K8sClusterService {
+    pub fn new(client: &K8sClient, namespace: String) -> Self {
+        Self {
+            client: client.client(),
+            namespace,
+        }
+    }
+
+    fn api(&self) -> Api<GameManager> {
+        Api::namespaced(self.client.clone(), &self.namespace)
+    }
+
+    fn revisions_api(&self) -> Api<ControllerRevision> {
+        Api::namespaced(self.client.clone(), &self.namespace)
+    }
+
+    /// Get the resource of a game manager, failing when it does not exist
+    async fn resource(&self, name: &str) -> Result<GameManager, ClusterServiceError> {
+        self.api()
+            .get(name)
+            .await
+            .map_err(|e| map_api_error(e, name))
+    }
+
+    /// Record the current spec of a game manager as a revision of its history
+    ///
+    /// The revisions are owned by the game manager, and deleted along with it.
+    async fn record_revision(
+        &self,
+        game_manager: &GameManager,
+        number: u32,
+        cause: Option<GameManagerRevisionCause>,
+        created_by: Option<String>,
+    ) -> Result<GameManagerRevision, ClusterServiceError> {
+        let name = game_manager.name_any();
+        let mut annotations = BTreeMap::new();
+        if let Some(cause) = cause {
+            annotations.insert(CAUSE_ANNOTATION.to_string(), cause.to_string());
+        }
+        if let Some(created_by) = created_by {
+            annotations.insert(CREATED_BY_ANNOTATION.to_string(), created_by);
+        }
+        let data = serde_json::to_value(RevisionData::from(&game_manager.spec))
+            .map_err(|e| ClusterServiceError::ApiError(e.to_string()))?;
+
+        let record = ControllerRevision {
+            metadata: ObjectMeta {
+                name: Some(format!("{}-{}", name, number)),
+                namespace: Some(self.namespace.clone()),
+                labels: Some(labels(&name)),
+                annotations: Some(annotations),
+                owner_references: game_manager
+                    .controller_owner_ref(&())
+                    .map(|owner| vec![owner]),
+                ..Default::default()
+            },
+            data: Some(RawExtension(data)),
+            revision: number.into(),
+        };
+
+        let created = self
+            .revisions_api()
+            .create(&post_params(), &record)
+            .await
+            .map_err(|e| ClusterServiceError::ApiError(e.to_string()))?;
+
+        revision(created).ok_or_else(|| {
+            ClusterServiceError::ApiError(format!("invalid revision {} of `{}`", number, name))
+        })
+    }
+
+    /// List the pods of the game managers matching a label selector, by game manager
+    async fn pods(&self, selector: &str) -> Result<HashMap<String, Vec<Pod>>, ClusterServiceError> {
+        let pods = Api::<Pod>::namespaced(self.client.clone(), &self.namespace)
+            .list(&ListParams::default().labels(selector))
+            .await
+            .map_err(|e| ClusterServiceError::ApiError(e.to_string()))?;
+
+        let mut by_game_manager = HashMap::<String, Vec<Pod>>::new();
+        for pod in pods {
+            if let Some(name) = pod.labels().get(GAME_MANAGER_LABEL) {
+                by_game_manager.entry(name.clone()).or_default().push(pod);
+            }
+        }
+
+        Ok(by_game_manager)
+    }
+
+    /// Get the APIs of the game servers kinds of a game manager installed on the cluster
+    async fn game_servers_apis(
+        &self,
+        game_manager: &GameManagerInstallation,
+    ) -> Result<Vec<Api<DynamicObject>>, ClusterServiceError> {
+        let mut apis = Vec::with_capacity(game_manager.game_servers.len());
+        for kind in &game_manager.game_servers {
+            let gvk = GroupVersionKind::gvk(&kind.group, &kind.version, &kind.kind);
+            match discovery::pinned_kind(&self.client, &gvk).await {
+                Ok((resource, _)) => apis.push(Api::namespaced_with(
+                    self.client.clone(),
+                    &self.namespace,
+                    &resource,
+                )),
+                // Without its definition, no game server of the kind can exist
+                Err(kube::Error::Api(e)) if e.code == 404 => {}
+                Err(kube::Error::Discovery(DiscoveryError::MissingKind(_))) => {}
+                Err(e) => return Err(ClusterServiceError::ApiError(e.to_string())),
+            }
+        }
+
+        Ok(apis)
+    }
+}
+
+/// Select the game servers of a game manager
+fn game_servers_params(game_manager: &GameManagerInstallation) -> ListParams {
+    ListParams::default().labels(&format!("{}={}", GAME_MANAGER_LABEL, game_manager.name))
+}
+
+#[async_trait::async_trait]
+impl ClusterService for K8sClusterService {
+    #[tracing::instrument(skip(self))]
+    async fn create_game_manager(
+        &self,
+        game_manager: NewGameManager,
+    ) -> Result<GameManagerInstallation, ClusterServiceError> {
+        let resource = GameManager::new(
+            &game_manager.name,
+            GameManagerSpec {
+                package: GameManagerPackage {
+                    repository: game_manager.repository_id.to_string(),
+                    name: game_manager.package,
+                },
+                version: game_manager.version.to_string(),
+                image: game_manager.image,
+                values: game_manager.values,
+                port: DEFAULT_PORT,
+                permissions: spec_permissions(game_manager.permissions),
+                game_servers: spec_game_servers(game_manager.game_servers),
+            },
+        );
+
+        let created = self
+            .api()
+            .create(&post_params(), &resource)
+            .await
+            .map_err(|e| map_api_error(e, &game_manager.name))?;
+
+        // The game manager is installed even without its history, which is then started by
+        // its first upgrade
+        if let Err(e) = self
+            .record_revision(
+                &created,
+                1,
+                Some(GameManagerRevisionCause::Install),
+                game_manager.created_by,
+            )
+            .await
+        {
+            warn!(
+                "Failed to record the first revision of game manager `{}`: {}",
+                game_manager.name, e
+            );
+        }
+
+        Ok(installation(created))
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn list_game_managers(
+        &self,
+    ) -> Result<Vec<GameManagerInstallation>, ClusterServiceError> {
+        let resources = self
+            .api()
+            .list(&ListParams::default())
+            .await
+            .map_err(|e| ClusterServiceError::ApiError(e.to_string()))?;
+
+        let selector = format!("{}={}", MANAGED_BY_LABEL, FIELD_MANAGER);
+        let mut deployments = Api::<Deployment>::namespaced(self.client.clone(), &self.namespace)
+            .list(&ListParams::default().labels(&selector))
+            .await
+            .map_err(|e| ClusterServiceError::ApiError(e.to_string()))?
+            .into_iter()
+            .map(|deployment| (deployment.name_any(), deployment))
+            .collect::<HashMap<_, _>>();
+        let mut pods = self.pods(&selector).await?;
+
+        Ok(resources
+            .into_iter()
+            .map(|resource| {
+                let name = resource.name_any();
+                let deployment = deployments.remove(&name);
+                let pods = pods.remove(&name).unwrap_or_default();
+
+                GameManagerInstallation {
+                    workload: deployment.map(|deployment| workload(&deployment, &pods)),
+                    ..installation(resource)
+                }
+            })
+            .collect())
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn find_game_manager(
+        &self,
+        name: &str,
+    ) -> Result<Option<GameManagerInstallation>, ClusterServiceError> {
+        let Some(resource) = self
+            .api()
+            .get_opt(name)
+            .await
+            .map_err(|e| map_api_error(e, name))?
+        else {
+            return Ok(None);
+        };
+
+        let deployment = Api::<Deployment>::namespaced(self.client.clone(), &self.namespace)
+            .get_opt(name)
+            .await
+            .map_err(|e| ClusterServiceError::ApiError(e.to_string()))?;
+        let pods = self
+            .pods(&format!("{}={}", GAME_MANAGER_LABEL, name))
+            .await?
+            .remove(name)
+            .unwrap_or_default();
+
+        Ok(Some(GameManagerInstallation {
+            workload: deployment.map(|deployment| workload(&deployment, &pods)),
+            ..installation(resource)
+        }))
+    }
+
+    #[tracing::instrument(skip(self, game_manager), fields(name = %game_manager.name))]
+    async fn count_game_servers(
+        &self,
+        game_manager: &GameManagerInstallation,
+    ) -> Result<usize, ClusterServiceError> {
+        let params = game_servers_params(game_manager);
+
+        let mut count = 0;
+        for api in self.game_servers_apis(game_manager).await? {
+            count += api
+                .list_metadata(&params)
+                .await
+                .map_err(|e| ClusterServiceError::ApiError(e.to_string()))?
+                .items
+                .len();
+        }
+
+        Ok(count)
+    }
+
+    #[tracing::instrument(skip(self, game_manager), fields(name = %game_manager.name))]
+    async fn delete_game_servers(
+        &self,
+        game_manager: &GameManagerInstallation,
+    ) -> Result<(), ClusterServiceError> {
+        let params = game_servers_params(game_manager);
+
+        for api in self.game_servers_apis(game_manager).await? {
+            api.delete_collection(&DeleteParams::background(), &params)
+                .await
+                .map_err(|e| ClusterServiceError::ApiError(e.to_string()))?;
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn delete_game_manager(&self, name: &str) -> Result<(), ClusterServiceError> {
+        self.api()
+            .delete(name, &DeleteParams::background())
+            .await
+            .map_err(|e| map_api_error(e, name))?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn list_revisions(
+        &self,
+        name: &str,
+    ) -> Result<Vec<GameManagerRevision>, ClusterServiceError> {
+        let mut revisions = self
+            .revisions_api()
+            .list(&ListParams::default().labels(&format!("{}={}", GAME_MANAGER_LABEL, name)))
+            .await
+            .map_err(|e| ClusterServiceError::ApiError(e.to_string()))?
+            .into_iter()
+            .filter_map(revision)
+            .collect::<Vec<_>>();
+        revisions.sort_by_key(|revision| revision.revision);
+
+        Ok(revisions)
+    }
+
+    #[tracing::instrument(skip(self, revision))]
+    async fn deploy_revision(
+        &self,
+        name: &str,
+        revision: NewGameManagerRevision,
+    ) -> Result<GameManagerRevision, ClusterServiceError> {
+        let mut resource = self.resource(name).await?;
+
+        let revisions = self.list_revisions(name).await?;
+        if let Some(replaces) = revision.replaces {
+            if revisions
+                .last()
+                .is_none_or(|latest| latest.revision != replaces)
+            {
+                return Err(ClusterServiceError::Conflict(name.to_string()));
+            }
+        }
+
+        // The game managers installed before the history existed start it with their current
+        // state, which they can then be rolled back to
+        let number = match revisions.last() {
+            Some(latest) => latest.revision + 1,
+            None => {
+                self.record_revision(&resource, 1, None, None).await?;
+                2
+            }
+        };
+
+        resource.spec.version = revision.version;
+        resource.spec.image = revision.image;
+        resource.spec.values = revision.values;
+        resource.spec.permissions = spec_permissions(revision.permissions);
+        resource.spec.game_servers = spec_game_servers(revision.game_servers);
+        let annotations = resource.annotations_mut();
+        match revision.rollout_deadline {
+            Some(deadline) => annotations.insert(
+                ROLLOUT_DEADLINE_ANNOTATION.to_string(),
+                deadline.to_rfc3339_opts(SecondsFormat::Secs, true),
+            ),
+            None => annotations.remove(ROLLOUT_DEADLINE_ANNOTATION),
+        };
+        // The resource version of the resource read above rejects concurrent changes
+        let replaced = self
+            .api()
+            .replace(name, &post_params(), &resource)
+            .await
+            .map_err(|e| match e {
+                kube::Error::Api(e) if e.code == 409 => {
+                    ClusterServiceError::Conflict(name.to_string())
+                }
+                e => map_api_error(e, name),
+            })?;
+
+        self.record_revision(&replaced, number, Some(revision.cause), revision.created_by)
+            .await
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn clear_rollout_deadline(&self, name: &str) -> Result<(), ClusterServiceError> {
+        let patch = serde_json::json!({
+            "metadata": { "annotations": { ROLLOUT_DEADLINE_ANNOTATION: null } }
+        });
+        self.api()
+            .patch(name, &PatchParams::default(), &Patch::Merge(patch))
+            .await
+            .map_err(|e| map_api_error(e, name))?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self, migration))]
+    async fn run_migration(
+        &self,
+        name: &str,
+        migration: GameManagerMigration,
+        timeout: Duration,
+    ) -> Result<(), ClusterServiceError> {
+        let resource = self.resource(name).await?;
+        let job = migration_job(&resource, &self.namespace, migration, timeout);
+
+        let api = Api::<Job>::namespaced(self.client.clone(), &self.namespace);
+        let mut job = api
+            .create(&post_params(), &job)
+            .await
+            .map_err(|e| ClusterServiceError::ApiError(e.to_string()))?;
+        let job_name = job.name_any();
+
+        let deadline = Instant::now() + timeout;
+        loop {
+            let status = job.status.unwrap_or_default();
+            if status.succeeded.unwrap_or_default() > 0 {
+                return Ok(());
+            }
+            if status.failed.unwrap_or_default() > 0 {
+                let reason = status
+                    .conditions
+                    .unwrap_or_default()
+                    .into_iter()
+                    .find(|condition| condition.type_ == "Failed")
+                    .and_then(|condition| condition.message)
+                    .unwrap_or_else(|| "the job failed".to_string());
+                return Err(ClusterServiceError::MigrationFailed(
+                    name.to_string(),
+                    format!("{} (job `{}`)", reason, job_name),
+                ));
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(ClusterServiceError::MigrationFailed(
+                    name.to_string(),
+                    format!(
+                        "the job `{}` did not succeed in {}s",
+                        job_name,
+                        timeout.as_secs()
+                    ),
+                ));
+            }
+            tokio::time::sleep((deadline - now).min(MIGRATION_POLL_INTERVAL)).await;
+
+            job = api
+                .get(&job_name)
+                .await
+                .map_err(|e| ClusterServiceError::ApiError(e.to_string()))?;
+        }
+    }
+}
+
+fn post_params() -> PostParams {
+    PostParams {
+        field_manager: Some(FIELD_MANAGER.to_string()),
+        ..Default::default()
+    }
+}
+
+/// Labels of the objects created for a game manager, besides the ones of its controller
+fn labels(name: &str) -> BTreeMap<String, String> {
+    BTreeMap::from([
+        (GAME_MANAGER_LABEL.to_string(), name.to_string()),
+        (MANAGED_BY_LABEL.to_string(), FIELD_MANAGER.to_string()),
+    ])
+}
+
+fn spec_permissions(permissions: Vec<PermissionRule>) -> Vec<GameManagerPermission> {
+    permissions
+        .into_iter()
+        .map(|rule| GameManagerPermission {
+            api_groups: rule.api_groups,
+            resources: rule.resources,
+            verbs: rule.verbs,
+        })
+        .collect()
+}
+
+fn spec_game_servers(game_servers: Vec<CrdReference>) -> Vec<GameServerKind> {
+    game_servers
+        .into_iter()
+        .map(|crd| GameServerKind {
+            group: crd.group,
+            version: crd.version,
+            kind: crd.kind,
+        })
+        .collect()
+}
+
+fn crd_references(game_servers: Vec<GameServerKind>) -> Vec<CrdReference> {
+    game_servers
+        .into_iter()
+        .map(|kind| CrdReference {
+            group: kind.group,
+            version: kind.version,
+            kind: kind.kind,
+        })
+        .collect()
+}
+
+/// Build the job migrating the custom resources of a game manager
+///
+/// The job runs once with the service account of the game manager, and is stopped by the
+/// cluster when it outlives the timeout.
+fn migration_job(
+    game_manager: &GameManager,
+    namespace: &str,
+    migration: GameManagerMigration,
+    timeout: Duration,
+) -> Job {
+    let name = game_manager.name_any();
+    let labels = labels(&name);
+    let env = |name: &str, value: String| EnvVar {
+        name: name.to_string(),
+        value: Some(value),
+        ..Default::default()
+    };
+
+    Job {
+        metadata: ObjectMeta {
+            generate_name: Some(format!("{}-migration-", name)),
+            namespace: Some(namespace.to_string()),
+            labels: Some(labels.clone()),
+            owner_references: game_manager
+                .controller_owner_ref(&())
+                .map(|owner| vec![owner]),
+            ..Default::default()
+        },
+        spec: Some(JobSpec {
+            backoff_limit: Some(0),
+            active_deadline_seconds: Some(timeout.as_secs().max(1) as i64),
+            ttl_seconds_after_finished: Some(MIGRATION_TTL),
+            template: PodTemplateSpec {
+                metadata: Some(ObjectMeta {
+                    labels: Some(labels),
+                    ..Default::default()
+                }),
+                spec: Some(PodSpec {
+                    service_account_name: Some(name.clone()),
+                    restart_policy: Some("Never".to_string()),
+                    containers: vec![Container {
+                        name: MIGRATION_CONTAINER_NAME.to_string(),
+                        image: Some(migration.image),
+                        command: Some(migration.command),
+                        env: Some(vec![
+                            env(FROM_VERSION_ENV, migration.from_version),
+                            env(TO_VERSION_ENV, migration.to_version.to_string()),
+                            env(NAME_ENV, name),
+                            EnvVar {
+                                name: NAMESPACE_ENV.to_string(),
+                                value_from: Some(EnvVarSource {
+                                    field_ref: Some(ObjectFieldSelector {
+                                        field_path: "metadata.namespace".to_string(),
+                                        ..Default::default()
+                                    }),
+                                    ..Default::default()
+                                }),
+                                ..Default::default()
+                            },
+                        ]),
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                }),
+            },
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+}
+
+/// Read a revision of a game manager from its record, `None` when the record is not valid
+fn revision(record: ControllerRevision) -> Option<GameManagerRevision> {
+    let data = serde_json::from_value::<RevisionData>(record.data?.0).ok()?;
+    let annotations = record.metadata.annotations.unwrap_or_default();
+
+    Some(GameManagerRevision {
+        revision: u32::try_from(record.revision).ok()?,
+        version: data.version,
+        image: data.image,
+        values: data.values,
+        permissions: data
+            .permissions
+            .into_iter()
+            .map(|permission| PermissionRule {
+                api_groups: permission.api_groups,
+                resources: permission.resources,
+                verbs: permission.verbs,
+                cluster_wide: false,
+            })
+            .collect(),
+        game_servers: crd_references(data.game_servers),
+        cause: annotations
+            .get(CAUSE_ANNOTATION)
+            .and_then(|cause| cause.parse().ok()),
+        created_at: record.metadata.creation_timestamp.map(|time| time.0),
+        created_by: annotations.get(CREATED_BY_ANNOTATION).cloned(),
+    })
+}
+
+fn map_api_error(error: kube::Error, name: &str) -> ClusterServiceError {
+    match error {
+        kube::Error::Api(e) if e.code == 409 => {
+            ClusterServiceError::AlreadyExists(name.to_string())
+        }
+        kube::Error::Api(e) if e.code == 404 => ClusterServiceError::NotFound(name.to_string()),
+        e => ClusterServiceError::ApiError(e.to_string()),
+    }
+}
+
+/// Read the installation of a game manager from its resource, without its pods
+pub(crate) fn installation(resource: GameManager) -> GameManagerInstallation {
+    let status = resource.status.unwrap_or_default();
+    let up_to_date = status.observed_generation >= resource.metadata.generation;
+    let conditions = status
+        .conditions
+        .into_iter()
+        .map(|condition| GameManagerCondition {
+            status: match condition.status.as_str() {
+                "True" => Some(true),
+                "False" => Some(false),
+                _ => None,
+            },
+            last_transition_time: DateTime::parse_from_rfc3339(&condition.last_transition_time)
+                .ok()
+                .map(|time| time.with_timezone(&Utc)),
+            type_: condition.type_,
+            reason: condition.reason,
+            message: condition.message,
+        })
+        .collect();
+    let rollout_deadline = resource
+        .metadata
+        .annotations
+        .as_ref()
+        .and_then(|annotations| annotations.get(ROLLOUT_DEADLINE_ANNOTATION))
+        .and_then(|deadline| DateTime::parse_from_rfc3339(deadline).ok())
+        .map(|deadline| deadline.with_timezone(&Utc));
+
+    GameManagerInstallation {
+        name: resource.metadata.name.unwrap_or_default(),
+        repository_id: resource.spec.package.repository,
+        package: resource.spec.package.name,
+        version: resource.spec.version,
+        image: resource.spec.image,
+        values: resource.spec.values,
+        created_at: resource.metadata.creation_timestamp.map(|time| time.0),
+        game_servers: crd_references(resource.spec.game_servers),
+        rollout_deadline,
+        state: GameManagerState::new(
+            conditions,
+            up_to_date,
+            resource.metadata.deletion_timestamp.is_some(),
+        ),
+        workload: None,
+    }
+}
+
+/// Summarize the pods of a game manager
+///
+/// The image is the one run by the pods, which can still be the previous one during a rollout.
+pub(crate) fn workload(deployment: &Deployment, pods: &[Pod]) -> GameManagerWorkload {
+    let status = deployment.status.clone().unwrap_or_default();
+    let containers = pods
+        .iter()
+        .filter_map(|pod| pod.status.as_ref()?.container_statuses.as_ref())
+        .flatten()
+        .filter(|container| container.name == CONTAINER_NAME)
+        .collect::<Vec<_>>();
+
+    GameManagerWorkload {
+        replicas: status.replicas.unwrap_or_default().max(0) as u32,
+        ready_replicas: status.ready_replicas.unwrap_or_default().max(0) as u32,
+        image: containers
+            .iter()
+            .find(|container| container.ready)
+            .or(containers.first())
+            .map(|container| container.image.clone()),
+        restarts: containers
+            .iter()
+            .map(|container| container.restart_count.max(0) as u32)
+            .sum(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use http::{Method, Request, Response, StatusCode};
+    use kube::client::Body;
+    use kubestro_core_domain::models::{
+        game_manager::GameManagerPhase, index::PermissionRule, package::RepositoryId, EntityId,
+    };
+    use semver::Version;
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::k8s::{
+        controller::tests::{api_status, echo, expect_request, game_manager},
+        game_manager::{GameManagerStatus, CONDITION_AVAILABLE},
+    };
+
+    const GAME_MANAGERS_PATH: &str = "/apis/kubestro.io/v1alpha1/namespaces/games/gamemanagers";
+    const DEPLOYMENTS_PATH: &str = "/apis/apps/v1/namespaces/games/deployments";
+    const PODS_PATH: &str = "/api/v1/namespaces/games/pods";
+    const MINECRAFT_API_PATH: &str = "/apis/minecraft.kubestro.io/v1";
+    const MINECRAFT_SERVERS_PATH: &str =
+        "/apis/minecraft.kubestro.io/v1/namespaces/games/minecraftservers";
+    const REVISIONS_PATH: &str = "/apis/apps/v1/namespaces/games/controllerrevisions";
+    const JOBS_PATH: &str = "/apis/batch/v1/namespaces/games/jobs";
+
+    fn cluster_service() -> (
+        K8sClusterService,
+        crate::k8s::controller::tests::ApiServerHandle,
+    ) {
+        let (service, handle) = tower_test::mock::pair::<Request<Body>, Response<Body>>();
+        let cluster = K8sClusterService {
+            client: Client::new(service, "default"),
+            namespace: "games".to_string(),
+        };
+
+        (cluster, handle)
+    }
+
+    fn new_game_manager() -> NewGameManager {
+        NewGameManager {
+            name: "minecraft".to_string(),
+            repository_id: RepositoryId::new(),
+            package: "minecraft".to_string(),
+            version: Version::new(1, 2, 0),
+            image: "ghcr.io/kubestro/minecraft:1.2.0".to_string(),
+            values: json!({ "motd": "Hello" }),
+            permissions: vec![PermissionRule {
+                api_groups: vec!["apps".to_string()],
+                resources: vec!["statefulsets".to_string()],
+                verbs: vec!["get".to_string()],
+                cluster_wide: false,
+            }],
+            game_servers: vec![CrdReference {
+                group: "minecraft.kubestro.io".to_string(),
+                version: "v1".to_string(),
+                kind: "MinecraftServer".to_string(),
+            }],
+            created_by: Some("admin".to_string()),
+        }
+    }
+
+    fn list(kind: &str, items: Vec<Value>) -> (StatusCode, Value) {
+        (
+            StatusCode::OK,
+            json!({ "kind": kind, "apiVersion": "v1", "metadata": {}, "items": items }),
+        )
+    }
+
+    fn deployment(ready_replicas: i32) -> Value {
+        json!({
+            "metadata": {
+                "name": "minecraft",
+                "namespace": "games",
+                "labels": { GAME_MANAGER_LABEL: "minecraft" }
+            },
+            "status": { "replicas": 2, "readyReplicas": ready_replicas }
+        })
+    }
+
+    fn pod(name: &str, image: &str, ready: bool, restarts: i32) -> Value {
+        json!({
+            "metadata": {
+                "name": name,
+                "namespace": "games",
+                "labels": { GAME_MANAGER_LABEL: "minecraft" }
+            },
+            "status": {
+                "containerStatuses": [{
+                    "name": CONTAINER_NAME,
+                    "image": image,
+                    "imageID": "",
+                    "ready": ready,
+                    "restartCount": restarts
+                }]
+            }
+        })
+    }
+
+    fn minecraft_api() -> (StatusCode, Value) {
+        (
+            StatusCode::OK,
+            json!({
+                "kind": "APIResourceList",
+                "apiVersion": "v1",
+                "groupVersion": "minecraft.kubestro.io/v1",
+                "resources": [{
+                    "name": "minecraftservers",
+                    "singularName": "minecraftserver",
+                    "namespaced": true,
+                    "kind": "MinecraftServer",
+                    "verbs": ["get", "list", "delete", "deletecollection"]
+                }]
+            }),
+        )
+    }
+
+    fn installed() -> GameManagerInstallation {
+        installation(game_manager(vec![]))
+    }
+
+    fn revision_record(number: i64, version: &str, cause: Option<&str>) -> Value {
+        json!({
+            "metadata": {
+                "name": format!("minecraft-{}", number),
+                "namespace": "games",
+                "annotations": match cause {
+                    Some(cause) => json!({ CAUSE_ANNOTATION: cause }),
+                    None => json!({}),
+                },
+                "creationTimestamp": "2025-05-18T10:00:00Z"
+            },
+            "data": {
+                "version": version,
+                "image": format!("ghcr.io/kubestro/minecraft:{}", version),
+                "values": { "motd": "Hello" }
+            },
+            "revision": number
+        })
+    }
+
+    fn migration() -> GameManagerMigration {
+        GameManagerMigration {
+            image: "ghcr.io/kubestro/minecraft:1.3.0".to_string(),
+            command: vec!["/migrate".to_string()],
+            from_version: "1.2.0".to_string(),
+            to_version: Version::new(1, 3, 0),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_game_manager() {
+        let (cluster, mut handle) = cluster_service();
+        let game_manager = new_game_manager();
+        let repository_id = game_manager.repository_id.to_string();
+
+        let creating = tokio::spawn(async move { cluster.create_game_manager(game_manager).await });
+        let body = expect_request(&mut handle, Method::POST, GAME_MANAGERS_PATH, echo).await;
+        let revision = expect_request(&mut handle, Method::POST, REVISIONS_PATH, echo).await;
+
+        assert_eq!(body["metadata"]["name"], "minecraft");
+        assert_eq!(body["spec"]["package"]["repository"], repository_id);
+        assert_eq!(body["spec"]["version"], "1.2.0");
+        assert_eq!(body["spec"]["permissions"][0]["apiGroups"], json!(["apps"]));
+        assert_eq!(body["spec"]["gameServers"][0]["kind"], "MinecraftServer");
+        assert_eq!(revision["metadata"]["name"], "minecraft-1");
+        assert_eq!(
+            revision["metadata"]["annotations"][CAUSE_ANNOTATION],
+            "install"
+        );
+        assert_eq!(
+            revision["metadata"]["annotations"][CREATED_BY_ANNOTATION],
+            "admin"
+        );
+        assert_eq!(revision["data"]["version"], "1.2.0");
+        assert_eq!(revision["revision"], 1);
+        let installation = creating.await.unwrap().unwrap();
+        assert_eq!(installation.image, "ghcr.io/kubestro/minecraft:1.2.0");
+        assert_eq!(installation.state.phase, GameManagerPhase::Pending);
+    }
+
+    #[tokio::test]
+    async fn test_create_existing_game_manager() {
+        let (cluster, mut handle) = cluster_service();
+
+        let creating =
+            tokio::spawn(async move { cluster.create_game_manager(new_game_manager()).await });
+        expect_request(&mut handle, Method::POST, GAME_MANAGERS_PATH, |_| {
+            api_status(StatusCode::CONFLICT)
+        })
+        .await;
+
+        assert!(matches!(
+            creating.await.unwrap(),
+            Err(ClusterServiceError::AlreadyExists(name)) if name == "minecraft"
+        ));
+    }
+
+    #[test]
+    fn test_installation() {
+        let mut resource = game_manager(vec![]);
+        resource.metadata.generation = Some(2);
+        resource.annotations_mut().insert(
+            ROLLOUT_DEADLINE_ANNOTATION.to_string(),
+            "2025-05-18T10:05:00Z".to_string(),
+        );
+        let mut status = GameManagerStatus {
+            observed_generation: Some(1),
+            ..Default::default()
+        };
+        let now = "2025-05-18T10:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        status.set_condition(CONDITION_AVAILABLE, true, "Deployed", "", Some(1), now);
+        resource.status = Some(status.clone());
+
+        let outdated = installation(resource.clone());
+        status.observed_generation = Some(2);
+        resource.status = Some(status);
+        let installation = installation(resource);
+
+        // The status of the previous generation does not tell the new one is available
+        assert_eq!(outdated.state.phase, GameManagerPhase::Progressing);
+        assert_eq!(installation.state.phase, GameManagerPhase::Available);
+        assert_eq!(installation.state.conditions[0].status, Some(true));
+        assert_eq!(
+            installation.state.conditions[0].last_transition_time,
+            Some(now)
+        );
+        assert_eq!(installation.values, json!({ "motd": "Hello" }));
+        assert_eq!(
+            installation.rollout_deadline,
+            Some("2025-05-18T10:05:00Z".parse().unwrap())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_game_managers() {
+        let (cluster, mut handle) = cluster_service();
+
+        let listing = tokio::spawn(async move { cluster.list_game_managers().await });
+        expect_request(&mut handle, Method::GET, GAME_MANAGERS_PATH, |_| {
+            list(
+                "GameManagerList",
+                vec![serde_json::to_value(game_manager(vec![])).unwrap()],
+            )
+        })
+        .await;
+        expect_request(&mut handle, Method::GET, DEPLOYMENTS_PATH, |_| {
+            list("DeploymentList", vec![deployment(1)])
+        })
+        .await;
+        expect_request(&mut handle, Method::GET, PODS_PATH, |_| {
+            list(
+                "PodList",
+                vec![
+                    pod("minecraft-1", "ghcr.io/kubestro/minecraft:1.1.0", false, 3),
+                    pod("minecraft-2", "ghcr.io/kubestro/minecraft:1.2.0", true, 1),
+                ],
+            )
+        })
+        .await;
+
+        let game_managers = listing.await.unwrap().unwrap();
+        assert_eq!(game_managers.len(), 1);
+        assert_eq!(
+            game_managers[0].workload,
+            Some(GameManagerWorkload {
+                replicas: 2,
+                ready_replicas: 1,
+                image: Some("ghcr.io/kubestro/minecraft:1.2.0".to_string()),
+                restarts: 4,
+            })
+        );
+        assert_eq!(game_managers[0].game_servers[0].kind, "MinecraftServer");
+    }
+
+    #[tokio::test]
+    async fn test_find_missing_game_manager() {
+        let (cluster, mut handle) = cluster_service();
+
+        let finding = tokio::spawn(async move { cluster.find_game_manager("minecraft").await });
+        expect_request(
+            &mut handle,
+            Method::GET,
+            &format!("{}/minecraft", GAME_MANAGERS_PATH),
+            |_| api_status(StatusCode::NOT_FOUND),
+        )
+        .await;
+
+        assert_eq!(finding.await.unwrap().unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_count_game_servers() {
+        let (cluster, mut handle) = cluster_service();
+
+        let counting = tokio::spawn(async move { cluster.count_game_servers(&installed()).await });
+        expect_request(&mut handle, Method::GET, MINECRAFT_API_PATH, |_| {
+            minecraft_api()
+        })
+        .await;
+        expect_request(&mut handle, Method::GET, MINECRAFT_SERVERS_PATH, |_| {
+            list(
+                "PartialObjectMetadataList",
+                vec![
+                    json!({ "metadata": { "name": "survival" } }),
+                    json!({ "metadata": { "name": "creative" } }),
+                ],
+            )
+        })
+        .await;
+
+        assert_eq!(counting.await.unwrap().unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_count_game_servers_without_definition() {
+        let (cluster, mut handle) = cluster_service();
+
+        let counting = tokio::spawn(async move { cluster.count_game_servers(&installed()).await });
+        expect_request(&mut handle, Method::GET, MINECRAFT_API_PATH, |_| {
+            api_status(StatusCode::NOT_FOUND)
+        })
+        .await;
+
+        assert_eq!(counting.await.unwrap().unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_delete_game_servers_and_game_manager() {
+        let (cluster, mut handle) = cluster_service();
+
+        let deleting = tokio::spawn(async move {
+            cluster.delete_game_servers(&installed()).await?;
+            cluster.delete_game_manager("minecraft").await
+        });
+        expect_request(&mut handle, Method::GET, MINECRAFT_API_PATH, |_| {
+            minecraft_api()
+        })
+        .await;
+        expect_request(&mut handle, Method::DELETE, MINECRAFT_SERVERS_PATH, |_| {
+            list("MinecraftServerList", vec![])
+        })
+        .await;
+        expect_request(
+            &mut handle,
+            Method::DELETE,
+            &format!("{}/minecraft", GAME_MANAGERS_PATH),
+            |_| api_status(StatusCode::NOT_FOUND),
+        )
+        .await;
+
+        assert!(matches!(
+            deleting.await.unwrap(),
+            Err(ClusterServiceError::NotFound(name)) if name == "minecraft"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_list_revisions() {
+        let (cluster, mut handle) = cluster_service();
+
+        let listing = tokio::spawn(async move { cluster.list_revisions("minecraft").await });
+        expect_request(&mut handle, Method::GET, REVISIONS_PATH, |_| {
+            list(
+                "ControllerRevisionList",
+                vec![
+                    revision_record(2, "1.3.0", Some("upgrade")),
+                    revision_record(1, "1.2.0", None),
+                    // Records not written by Kubestro are skipped
+                    json!({ "metadata": { "name": "minecraft-3" }, "revision": 3 }),
+                ],
+            )
+        })
+        .await;
+
+        let revisions = listing.await.unwrap().unwrap();
+        assert_eq!(revisions.len(), 2);
+        assert_eq!(revisions[0].revision, 1);
+        assert_eq!(revisions[0].cause, None);
+        assert_eq!(revisions[1].version, "1.3.0");
+        assert_eq!(revisions[1].cause, Some(GameManagerRevisionCause::Upgrade));
+        assert_eq!(revisions[1].values, json!({ "motd": "Hello" }));
+    }
+
+    #[tokio::test]
+    async fn test_deploy_revision_without_history() {
+        let (cluster, mut handle) = cluster_service();
+        let revision = NewGameManagerRevision {
+            version: "1.3.0".to_string(),
+            image: "ghcr.io/kubestro/minecraft:1.3.0".to_string(),
+            values: json!({ "motd": "Hi" }),
+            permissions: vec![],
+            game_servers: vec![],
+            cause: GameManagerRevisionCause::Upgrade,
+            created_by: Some("admin".to_string()),
+            rollout_deadline: Some("2025-05-18T10:05:00Z".parse().unwrap()),
+            replaces: None,
+        };
+
+        let deploying =
+            tokio::spawn(async move { cluster.deploy_revision("minecraft", revision).await });
+        let game_manager_path = format!("{}/minecraft", GAME_MANAGERS_PATH);
+        expect_request(&mut handle, Method::GET, &game_manager_path, |_| {
+            (
+                StatusCode::OK,
+                serde_json::to_value(game_manager(vec![])).unwrap(),
+            )
+        })
+        .await;
+        expect_request(&mut handle, Method::GET, REVISIONS_PATH, |_| {
+            list("ControllerRevisionList", vec![])
+        })
+        .await;
+        let current = expect_request(&mut handle, Method::POST, REVISIONS_PATH, echo).await;
+        let replaced = expect_request(&mut handle, Method::PUT, &game_manager_path, echo).await;
+        let deployed = expect_request(&mut handle, Method::POST, REVISIONS_PATH, echo).await;
+
+        // The state before the first upgrade is recorded so it can be rolled back to
+        assert_eq!(current["revision"], 1);
+        assert_eq!(current["data"]["version"], "1.2.0");
+        assert_eq!(
+            current["metadata"]["ownerReferences"][0]["kind"],
+            "GameManager"
+        );
+        assert_eq!(
+            replaced["spec"]["image"],
+            "ghcr.io/kubestro/minecraft:1.3.0"
+        );
+        assert_eq!(replaced["spec"]["values"], json!({ "motd": "Hi" }));
+        assert_eq!(replaced["spec"]["permissions"], json!([]));
+        assert_eq!(
+            replaced["metadata"]["annotations"][ROLLOUT_DEADLINE_ANNOTATION],
+            "2025-05-18T10:05:00Z"
+        );
+        assert_eq!(deployed["metadata"]["name"], "minecraft-2");
+        let revision = deploying.await.unwrap().unwrap();
+        assert_eq!(revision.revision, 2);
+        assert_eq!(revision.version, "1.3.0");
+        assert_eq!(revision.created_by.as_deref(), Some("admin"));
+    }
+
+    #[tokio::test]
+    async fn test_deploy_revision_replacing_an_older_revision() {
+        let (cluster, mut handle) = cluster_service();
+        let revision = NewGameManagerRevision {
+            version: "1.2.0".to_string(),
+            image: "ghcr.io/kubestro/minecraft:1.2.0".to_string(),
+            values: json!({ "motd": "Hello" }),
+            permissions: vec![],
+            game_servers: vec![],
+            cause: GameManagerRevisionCause::AutomaticRollback,
+            created_by: None,
+            rollout_deadline: None,
+            replaces: Some(2),
+        };
+
+        let deploying =
+            tokio::spawn(async move { cluster.deploy_revision("minecraft", revision).await });
+        let game_manager_path = format!("{}/minecraft", GAME_MANAGERS_PATH);
+        expect_request(&mut handle, Method::GET, &game_manager_path, |_| {
+            (
+                StatusCode::OK,
+                serde_json::to_value(game_manager(vec![])).unwrap(),
+            )
+        })
+        .await;
+        expect_request(&mut handle, Method::GET, REVISIONS_PATH, |_| {
+            list(
+                "ControllerRevisionList",
+                vec![
+                    revision_record(1, "1.2.0", None),
+                    revision_record(2, "1.3.0", Some("upgrade")),
+                    // Deployed since the revision to replace was read
+                    revision_record(3, "1.3.1", Some("upgrade")),
+                ],
+            )
+        })
+        .await;
+
+        // Nothing is replaced nor recorded
+        assert!(matches!(
+            deploying.await.unwrap(),
+            Err(ClusterServiceError::Conflict(name)) if name == "minecraft"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_run_migration() {
+        let (cluster, mut handle) = cluster_service();
+
+        let migrating = tokio::spawn(async move {
+            cluster
+                .run_migration("minecraft", migration(), Duration::from_secs(60))
+                .await
+        });
+        expect_request(
+            &mut handle,
+            Method::GET,
+            &format!("{}/minecraft", GAME_MANAGERS_PATH),
+            |_| {
+                (
+                    StatusCode::OK,
+                    serde_json::to_value(game_manager(vec![])).unwrap(),
+                )
+            },
+        )
+        .await;
+        let job = expect_request(&mut handle, Method::POST, JOBS_PATH, |body| {
+            let mut job = body.clone();
+            job["metadata"]["name"] = json!("minecraft-migration-x7k2p");
+            job["status"] = json!({ "succeeded": 1 });
+            (StatusCode::CREATED, job)
+        })
+        .await;
+
+        assert_eq!(job["metadata"]["generateName"], "minecraft-migration-");
+        assert_eq!(job["spec"]["backoffLimit"], 0);
+        assert_eq!(job["spec"]["activeDeadlineSeconds"], 60);
+        let pod = &job["spec"]["template"]["spec"];
+        assert_eq!(pod["serviceAccountName"], "minecraft");
+        assert_eq!(pod["restartPolicy"], "Never");
+        let container = &pod["containers"][0];
+        assert_eq!(container["image"], "ghcr.io/kubestro/minecraft:1.3.0");
+        assert_eq!(container["command"], json!(["/migrate"]));
+        assert_eq!(container["env"][0]["name"], FROM_VERSION_ENV);
+        assert_eq!(container["env"][0]["value"], "1.2.0");
+        assert_eq!(container["env"][1]["value"], "1.3.0");
+        migrating.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_run_failing_migration() {
+        let (cluster, mut handle) = cluster_service();
+
+        let migrating = tokio::spawn(async move {
+            cluster
+                .run_migration("minecraft", migration(), Duration::from_secs(60))
+                .await
+        });
+        expect_request(
+            &mut handle,
+            Method::GET,
+            &format!("{}/minecraft", GAME_MANAGERS_PATH),
+            |_| {
+                (
+                    StatusCode::OK,
+                    serde_json::to_value(game_manager(vec![])).unwrap(),
+                )
+            },
+        )
+        .await;
+        expect_request(&mut handle, Method::POST, JOBS_PATH, |body| {
+            let mut job = body.clone();
+            job["metadata"]["name"] = json!("minecraft-migration-x7k2p");
+            job["status"] = json!({
+                "failed": 1,
+                "conditions": [{
+                    "type": "Failed",
+                    "status": "True",
+                    "reason": "BackoffLimitExceeded",
+                    "message": "Job has reached the specified backoff limit"
+                }]
+            });
+            (StatusCode::CREATED, job)
+        })
+        .await;
+
+        assert!(matches!(
+            migrating.await.unwrap(),
+            Err(ClusterServiceError::MigrationFailed(name, reason))
+                if name == "minecraft" && reason.contains("backoff limit")
+        ));
+    }
+}
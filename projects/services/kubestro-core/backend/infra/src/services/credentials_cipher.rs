@@ -0,0 +1,104 @@
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    Aes256Gcm, Key, Nonce,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+/// Size of the AES-GCM nonce prepended to every ciphertext
+const NONCE_SIZE: usize = 12;
+
+/// Encrypt secrets before storing them in the database
+///
+/// Secrets are encrypted with AES-256-GCM, a random nonce is generated for every value and
+/// stored in front of the ciphertext.
+#[derive(Clone)]
+pub struct CredentialsCipher {
+    cipher: Aes256Gcm,
+}
+
+impl CredentialsCipher {
+    /// Create a cipher from a base64 encoded 256-bit key
+    pub fn from_base64(key: &str) -> Result<Self, CredentialsCipherError> {
+        let key = STANDARD
+            .decode(key.trim())
+            .map_err(|_| CredentialsCipherError::InvalidKey)?;
+
+        if key.len() != 32 {
+            return Err(CredentialsCipherError::InvalidKey);
+        }
+
+        Ok(Self {
+            cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key)),
+        })
+    }
+
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, CredentialsCipherError> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, plaintext)
+            .map_err(|_| CredentialsCipherError::Encryption)?;
+
+        Ok([nonce.as_slice(), &ciphertext].concat())
+    }
+
+    pub fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>, CredentialsCipherError> {
+        if data.len() < NONCE_SIZE {
+            return Err(CredentialsCipherError::Decryption);
+        }
+
+        let (nonce, ciphertext) = data.split_at(NONCE_SIZE);
+        self.cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| CredentialsCipherError::Decryption)
+    }
+}
+
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum CredentialsCipherError {
+    #[error("The encryption key must be a base64 encoded 256-bit key")]
+    InvalidKey,
+    #[error("Failed to encrypt the credentials")]
+    Encryption,
+    #[error("Failed to decrypt the credentials, the encryption key may have changed")]
+    Decryption,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &str = "MDEyMzQ1Njc4OWFiY2RlZjAxMjM0NTY3ODlhYmNkZWY=";
+    const OTHER_KEY: &str = "ZmVkY2JhOTg3NjU0MzIxMGZlZGNiYTk4NzY1NDMyMTA=";
+
+    #[test]
+    fn encrypted_data_should_be_decrypted() {
+        let cipher = CredentialsCipher::from_base64(KEY).unwrap();
+
+        let encrypted = cipher.encrypt(b"secret").unwrap();
+
+        assert_ne!(&encrypted[NONCE_SIZE..], b"secret");
+        assert_eq!(cipher.decrypt(&encrypted).unwrap(), b"secret");
+    }
+
+    #[test]
+    fn decryption_with_another_key_should_fail() {
+        let encrypted = CredentialsCipher::from_base64(KEY)
+            .unwrap()
+            .encrypt(b"secret")
+            .unwrap();
+
+        let decrypted = CredentialsCipher::from_base64(OTHER_KEY)
+            .unwrap()
+            .decrypt(&encrypted);
+
+        assert_eq!(decrypted, Err(CredentialsCipherError::Decryption));
+    }
+
+    #[test]
+    fn short_key_should_be_rejected() {
+        let cipher = CredentialsCipher::from_base64("c2hvcnQ=");
+
+        assert!(matches!(cipher, Err(CredentialsCipherError::InvalidKey)));
+    }
+}
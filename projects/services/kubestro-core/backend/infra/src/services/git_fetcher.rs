@@ -0,0 +1,482 @@
+use std::{
+    path::{Path, PathBuf},
+    process::Stdio,
+    time::Duration,
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use kubestro_core_domain::{
+    models::{
+        index::RepositoryIndex,
+        package::{Repository, RepositoryCredentials, RepositoryId, RepositoryKind},
+    },
+    ports::services::repositories_service::RepositoriesServiceError,
+};
+use reqwest::Url;
+use tokio::process::Command;
+
+use super::{
+    index_fetcher::{CacheValidators, FetchOutcome, IndexDocument},
+    index_signature::{verify_index_signature, SIGNATURE_SUFFIX},
+};
+
+/// Transports allowed for Git remotes, the ones credentials can be sent with
+const ALLOWED_PROTOCOLS: [&str; 3] = ["http", "https", "file"];
+/// Default maximum duration of a Git command
+const GIT_COMMAND_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Read repository indexes committed in Git repositories
+///
+/// Every repository is fetched in its own bare clone, kept in the work directory, so only the
+/// new commits are downloaded. The commit the reference resolves to is the cache validator:
+/// the index is only read again when the reference moves. The detached signature of the index
+/// is read next to it, at the index path suffixed by [`SIGNATURE_SUFFIX`].
+///
+/// The `git` executable must be available.
+#[derive(Clone)]
+pub struct GitIndexFetcher {
+    work_dir: PathBuf,
+    timeout: Duration,
+    allow_unsigned: bool,
+}
+
+impl Default for GitIndexFetcher {
+    fn default() -> Self {
+        Self {
+            work_dir: std::env::temp_dir().join("kubestro-git"),
+            timeout: GIT_COMMAND_TIMEOUT,
+            allow_unsigned: false,
+        }
+    }
+}
+
+impl GitIndexFetcher {
+    /// Set the directory holding the clones of the repositories
+    pub fn with_work_dir(mut self, work_dir: PathBuf) -> Self {
+        self.work_dir = work_dir;
+        self
+    }
+
+    /// Set the maximum duration of a Git command
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Accept the indexes of repositories without trusted keys
+    pub fn with_unsigned_indexes(mut self, allow_unsigned: bool) -> Self {
+        self.allow_unsigned = allow_unsigned;
+        self
+    }
+
+    /// Fetch the reference of a repository and read its index
+    ///
+    /// When `validators` hold the commit the reference still resolves to, the index is not read
+    /// again and [`FetchOutcome::NotModified`] is returned.
+    #[tracing::instrument(skip(self, repository), fields(url = %repository.url))]
+    pub async fn fetch(
+        &self,
+        repository: &Repository,
+        validators: Option<&CacheValidators>,
+    ) -> Result<FetchOutcome, RepositoriesServiceError> {
+        let RepositoryKind::Git(source) = &repository.kind else {
+            return Err(RepositoriesServiceError::UnexpectedError(
+                "Not a Git repository".to_string(),
+            ));
+        };
+        let trusted_keys = &repository.trusted_keys;
+
+        if trusted_keys.is_empty() && !self.allow_unsigned {
+            return Err(RepositoriesServiceError::InvalidSignature(
+                "no trusted key is configured for this repository".to_string(),
+            ));
+        }
+
+        let remote = Url::parse(&repository.url)
+            .map_err(|e| RepositoriesServiceError::RemoteDataError(e.to_string()))?;
+        if !ALLOWED_PROTOCOLS.contains(&remote.scheme()) {
+            return Err(RepositoriesServiceError::RemoteDataError(format!(
+                "Unsupported Git remote `{}`, expected one of {}",
+                remote.scheme(),
+                ALLOWED_PROTOCOLS.join(", ")
+            )));
+        }
+        // Anything starting with a dash would be read as an option by git
+        if source.reference.is_empty() || source.reference.starts_with('-') {
+            return Err(RepositoriesServiceError::RemoteDataError(format!(
+                "Invalid Git reference `{}`",
+                source.reference
+            )));
+        }
+
+        let clone_dir = self.clone_dir(&repository.id);
+        if !clone_dir.exists() {
+            tokio::fs::create_dir_all(&clone_dir)
+                .await
+                .map_err(|e| RepositoriesServiceError::UnexpectedError(e.to_string()))?;
+            self.git(&clone_dir, None, &["init", "--bare", "--quiet"])
+                .await?;
+        }
+
+        self.git(
+            &clone_dir,
+            repository.credentials.as_ref(),
+            &[
+                "fetch",
+                "--quiet",
+                "--no-tags",
+                "--depth",
+                "1",
+                repository.url.as_str(),
+                source.reference.as_str(),
+            ],
+        )
+        .await?;
+        let commit = self
+            .git(
+                &clone_dir,
+                None,
+                &["rev-parse", "--verify", "FETCH_HEAD^{commit}"],
+            )
+            .await?;
+        let commit = String::from_utf8_lossy(&commit).trim().to_string();
+
+        if validators.and_then(|validators| validators.etag.as_ref()) == Some(&commit) {
+            return Ok(FetchOutcome::NotModified);
+        }
+
+        let data = self
+            .git(
+                &clone_dir,
+                None,
+                &["show", &format!("{}:{}", commit, source.path)],
+            )
+            .await?;
+
+        let signature = match trusted_keys.is_empty() {
+            true => None,
+            false => {
+                let signature = self
+                    .git(
+                        &clone_dir,
+                        None,
+                        &[
+                            "show",
+                            &format!("{}:{}{}", commit, source.path, SIGNATURE_SUFFIX),
+                        ],
+                    )
+                    .await
+                    .map_err(|e| {
+                        RepositoriesServiceError::InvalidSignature(format!(
+                            "failed to read the index signature: {}",
+                            e
+                        ))
+                    })?;
+                let signature = String::from_utf8(signature)
+                    .map_err(|e| RepositoriesServiceError::InvalidSignature(e.to_string()))?;
+
+                verify_index_signature(&data, &signature, trusted_keys)?;
+                Some(signature)
+            }
+        };
+
+        // Parse the index, any invalid entry will reject the whole index
+        let index = Box::new(RepositoryIndex::from_slice(&data)?);
+
+        Ok(FetchOutcome::Fetched {
+            index,
+            validators: CacheValidators {
+                etag: Some(commit.clone()),
+                last_modified: None,
+            },
+            revision: Some(commit),
+            document: IndexDocument { data, signature },
+        })
+    }
+
+    /// Remove the clone of a repository
+    pub async fn remove(&self, repository_id: &RepositoryId) -> std::io::Result<()> {
+        match tokio::fs::remove_dir_all(self.clone_dir(repository_id)).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    fn clone_dir(&self, repository_id: &RepositoryId) -> PathBuf {
+        self.work_dir.join(repository_id.to_string())
+    }
+
+    /// Run a git command in `dir` and return its output
+    ///
+    /// The credentials are sent as HTTP headers, they are given through the environment so
+    /// they never show in the processes list.
+    async fn git(
+        &self,
+        dir: &Path,
+        credentials: Option<&RepositoryCredentials>,
+        args: &[&str],
+    ) -> Result<Vec<u8>, RepositoriesServiceError> {
+        let headers = credentials.map(credentials_headers).unwrap_or_default();
+
+        let mut command = Command::new("git");
+        command
+            .arg("-C")
+            .arg(dir)
+            .args(args)
+            .env("GIT_TERMINAL_PROMPT", "0")
+            .env("GIT_ALLOW_PROTOCOL", ALLOWED_PROTOCOLS.join(":"))
+            .env("GIT_CONFIG_COUNT", headers.len().to_string())
+            .stdin(Stdio::null())
+            .kill_on_drop(true);
+        for (i, header) in headers.iter().enumerate() {
+            command
+                .env(format!("GIT_CONFIG_KEY_{}", i), "http.extraHeader")
+                .env(format!("GIT_CONFIG_VALUE_{}", i), header);
+        }
+
+        let output = tokio::time::timeout(self.timeout, command.output())
+            .await
+            .map_err(|_| {
+                RepositoriesServiceError::RemoteDataError(format!("`git {}` timed out", args[0]))
+            })?
+            .map_err(|e| {
+                RepositoriesServiceError::RemoteDataError(format!("Failed to run git: {}", e))
+            })?;
+
+        if !output.status.success() {
+            return Err(RepositoriesServiceError::RemoteDataError(format!(
+                "`git {}` failed: {}",
+                args[0],
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        Ok(output.stdout)
+    }
+}
+
+/// HTTP headers carrying the repository credentials
+fn credentials_headers(credentials: &RepositoryCredentials) -> Vec<String> {
+    match credentials {
+        RepositoryCredentials::Basic { username, password } => vec![format!(
+            "Authorization: Basic {}",
+            STANDARD.encode(format!("{}:{}", username, password))
+        )],
+        RepositoryCredentials::Bearer { token } => vec![format!("Authorization: Bearer {}", token)],
+        RepositoryCredentials::Headers { headers } => headers
+            .iter()
+            .map(|(name, value)| format!("{}: {}", name, value))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kubestro_core_domain::models::{package::GitSource, EntityId};
+
+    use crate::services::{
+        index_fetcher::tests::repository,
+        index_signature::tests::{INDEX_SIGNATURE, SIGNED_INDEX, SIGNING_KEY},
+    };
+
+    use super::*;
+
+    /// A bare Git remote, with a working copy to push commits to it
+    struct Remote {
+        dir: PathBuf,
+    }
+
+    impl Remote {
+        async fn new() -> Self {
+            let dir =
+                std::env::temp_dir().join(format!("kubestro-git-test-{}", RepositoryId::new()));
+            tokio::fs::create_dir_all(&dir).await.unwrap();
+
+            let remote = Self { dir };
+            remote
+                .run(&[
+                    "init",
+                    "--quiet",
+                    "--bare",
+                    "--initial-branch",
+                    "main",
+                    "remote.git",
+                ])
+                .await;
+            remote
+                .run(&["init", "--quiet", "--initial-branch", "main", "work"])
+                .await;
+            remote
+        }
+
+        fn url(&self) -> String {
+            format!("file://{}", self.dir.join("remote.git").display())
+        }
+
+        async fn run(&self, args: &[&str]) -> String {
+            let output = Command::new("git")
+                .arg("-C")
+                .arg(&self.dir)
+                .args(args)
+                .output()
+                .await
+                .unwrap();
+            assert!(
+                output.status.success(),
+                "{}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+
+            String::from_utf8_lossy(&output.stdout).trim().to_string()
+        }
+
+        /// Commit the files and push them, returns the commit SHA
+        async fn commit(&self, files: &[(&str, &str)], tag: Option<&str>) -> String {
+            for (path, content) in files {
+                let path = self.dir.join("work").join(path);
+                tokio::fs::create_dir_all(path.parent().unwrap())
+                    .await
+                    .unwrap();
+                tokio::fs::write(path, content).await.unwrap();
+            }
+
+            let work = self.dir.join("work").display().to_string();
+            self.run(&["-C", &work, "add", "--all"]).await;
+            self.run(&[
+                "-C",
+                &work,
+                "-c",
+                "user.name=Kubestro",
+                "-c",
+                "user.email=test@kubestro.io",
+                "commit",
+                "--quiet",
+                "--message",
+                "Update index",
+            ])
+            .await;
+            if let Some(tag) = tag {
+                self.run(&["-C", &work, "tag", tag]).await;
+            }
+
+            let remote = self.dir.join("remote.git").display().to_string();
+            self.run(&["-C", &work, "push", "--quiet", "--tags", &remote, "main"])
+                .await;
+            self.run(&["-C", &work, "rev-parse", "HEAD"]).await
+        }
+    }
+
+    impl Drop for Remote {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    fn git_repository(remote: &Remote, reference: &str, trusted_keys: &[&str]) -> Repository {
+        let mut repository = repository(&remote.url(), trusted_keys);
+        repository.kind = RepositoryKind::Git(GitSource {
+            reference: reference.to_string(),
+            path: "indexes/index.json".to_string(),
+        });
+        repository
+    }
+
+    fn fetcher(remote: &Remote) -> GitIndexFetcher {
+        GitIndexFetcher::default().with_work_dir(remote.dir.join("clones"))
+    }
+
+    fn index(name: &str) -> String {
+        format!(
+            r#"{{"version":"1.0","repository":{{"name":"{}"}},"packages":[]}}"#,
+            name
+        )
+    }
+
+    #[tokio::test]
+    async fn fetch_should_read_the_signed_index_and_pin_the_commit() {
+        let remote = Remote::new().await;
+        let commit = remote
+            .commit(
+                &[
+                    ("indexes/index.json", SIGNED_INDEX),
+                    ("indexes/index.json.minisig", INDEX_SIGNATURE),
+                ],
+                None,
+            )
+            .await;
+
+        let repository = git_repository(&remote, "main", &[SIGNING_KEY]);
+        let outcome = fetcher(&remote).fetch(&repository, None).await.unwrap();
+
+        let FetchOutcome::Fetched {
+            index,
+            validators,
+            revision,
+            document,
+        } = outcome
+        else {
+            panic!("Expected a fetched index");
+        };
+        assert_eq!(index.repository.name, "Stub");
+        assert_eq!(document.signature.as_deref(), Some(INDEX_SIGNATURE));
+        assert_eq!(validators.etag, Some(commit.clone()));
+        assert_eq!(revision, Some(commit));
+    }
+
+    #[tokio::test]
+    async fn index_should_only_be_read_again_when_the_commit_changes() {
+        let remote = Remote::new().await;
+        let first = remote
+            .commit(&[("indexes/index.json", &index("First"))], None)
+            .await;
+        let repository = git_repository(&remote, "HEAD", &[]);
+        let fetcher = fetcher(&remote).with_unsigned_indexes(true);
+        let validators = CacheValidators {
+            etag: Some(first),
+            last_modified: None,
+        };
+
+        let unchanged = fetcher.fetch(&repository, Some(&validators)).await.unwrap();
+        let second = remote
+            .commit(&[("indexes/index.json", &index("Second"))], None)
+            .await;
+        let changed = fetcher.fetch(&repository, Some(&validators)).await.unwrap();
+
+        assert!(matches!(unchanged, FetchOutcome::NotModified));
+        let FetchOutcome::Fetched {
+            index, revision, ..
+        } = changed
+        else {
+            panic!("Expected a fetched index");
+        };
+        assert_eq!(index.repository.name, "Second");
+        assert_eq!(revision, Some(second));
+    }
+
+    #[tokio::test]
+    async fn fetch_should_read_the_index_of_the_tag() {
+        let remote = Remote::new().await;
+        let tagged = remote
+            .commit(&[("indexes/index.json", &index("Tagged"))], Some("v1.0"))
+            .await;
+        remote
+            .commit(&[("indexes/index.json", &index("Unreleased"))], None)
+            .await;
+        let repository = git_repository(&remote, "v1.0", &[]);
+
+        let outcome = fetcher(&remote)
+            .with_unsigned_indexes(true)
+            .fetch(&repository, None)
+            .await
+            .unwrap();
+
+        let FetchOutcome::Fetched {
+            index, revision, ..
+        } = outcome
+        else {
+            panic!("Expected a fetched index");
+        };
+        assert_eq!(index.repository.name, "Tagged");
+        assert_eq!(revision, Some(tagged));
+    }
+}
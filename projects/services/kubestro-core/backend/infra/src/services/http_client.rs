@@ -0,0 +1,120 @@
+use std::{path::PathBuf, time::Duration};
+
+use reqwest::{Certificate, Client, ClientBuilder, Proxy};
+
+use super::outbound_policy::OutboundPolicy;
+
+/// Default `User-Agent` header sent by Kubestro
+pub const DEFAULT_USER_AGENT: &str = concat!("kubestro/", env!("CARGO_PKG_VERSION"));
+
+/// Configuration of the HTTP client shared by every outgoing request
+#[derive(Debug, Clone, PartialEq)]
+pub struct HttpClientConfig {
+    /// Maximum duration of a whole request
+    pub timeout: Duration,
+    /// Maximum duration to establish a connection
+    pub connect_timeout: Duration,
+    pub user_agent: String,
+    /// Proxy used for every request, the system proxy is used otherwise
+    pub proxy: Option<String>,
+    /// PEM bundle of additional trusted certificate authorities
+    pub ca_bundle: Option<PathBuf>,
+}
+
+impl Default for HttpClientConfig {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(30),
+            connect_timeout: Duration::from_secs(10),
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            proxy: None,
+            ca_bundle: None,
+        }
+    }
+}
+
+/// Build the HTTP client described by the configuration, restricted by the outbound policy
+///
+/// Behind a proxy, the hosts are resolved by the proxy: the addresses are only checked before
+/// the requests.
+pub fn build_http_client(
+    config: &HttpClientConfig,
+    policy: &OutboundPolicy,
+) -> Result<Client, HttpClientError> {
+    build_client(config, policy.restrict_client(Client::builder()))
+}
+
+/// Build the HTTP client sending the credentials headers of the repositories
+///
+/// It follows the configuration and the outbound policy as well, but never follows a redirect
+/// to another host. See [`OutboundPolicy::restrict_credentials_client`].
+pub fn build_credentials_http_client(
+    config: &HttpClientConfig,
+    policy: &OutboundPolicy,
+) -> Result<Client, HttpClientError> {
+    build_client(
+        config,
+        policy.restrict_credentials_client(Client::builder()),
+    )
+}
+
+fn build_client(
+    config: &HttpClientConfig,
+    builder: ClientBuilder,
+) -> Result<Client, HttpClientError> {
+    let mut builder = builder
+        .timeout(config.timeout)
+        .connect_timeout(config.connect_timeout)
+        .user_agent(&config.user_agent);
+
+    if let Some(proxy) = &config.proxy {
+        builder = builder.proxy(Proxy::all(proxy).map_err(HttpClientError::InvalidProxy)?);
+    }
+
+    if let Some(ca_bundle) = &config.ca_bundle {
+        let pem = std::fs::read(ca_bundle)
+            .map_err(|e| HttpClientError::CaBundle(ca_bundle.clone(), e.to_string()))?;
+        let certificates = Certificate::from_pem_bundle(&pem)
+            .map_err(|e| HttpClientError::CaBundle(ca_bundle.clone(), e.to_string()))?;
+
+        for certificate in certificates {
+            builder = builder.add_root_certificate(certificate);
+        }
+    }
+
+    builder.build().map_err(HttpClientError::Build)
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum HttpClientError {
+    #[error("Invalid proxy: {0}")]
+    InvalidProxy(reqwest::Error),
+    #[error("Failed to load the CA bundle `{0}`: {1}")]
+    CaBundle(PathBuf, String),
+    #[error("Failed to build the HTTP client: {0}")]
+    Build(reqwest::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_configuration_should_build_a_client() {
+        let client = build_http_client(&HttpClientConfig::default(), &OutboundPolicy::default());
+
+        assert!(client.is_ok());
+    }
+
+    #[test]
+    fn missing_ca_bundle_should_be_reported() {
+        let config = HttpClientConfig {
+            ca_bundle: Some(PathBuf::from("/nonexistent/ca.pem")),
+            ..Default::default()
+        };
+
+        let client = build_http_client(&config, &OutboundPolicy::default());
+
+        assert!(matches!(client, Err(HttpClientError::CaBundle(_, _))));
+    }
+}
@@ -0,0 +1,642 @@
+use kubestro_core_domain::{
+    models::{
+        index::RepositoryIndex,
+        package::{Repository, RepositoryCredentials},
+    },
+    ports::services::repositories_service::RepositoriesServiceError,
+};
+use reqwest::{
+    header::{
+        HeaderMap, HeaderName, HeaderValue, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED,
+    },
+    RequestBuilder, StatusCode, Url,
+};
+
+use super::{
+    index_signature::{verify_index_signature, SIGNATURE_SUFFIX},
+    outbound_policy::OutboundPolicy,
+};
+
+/// Content types of the indexes served over HTTP
+const INDEX_CONTENT_TYPES: [&str; 1] = ["application/json"];
+
+/// HTTP validators sent back by a server along with an index
+///
+/// They are replayed on the next fetch so the server can answer with `304 Not Modified`
+/// instead of sending the whole index again.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CacheValidators {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+impl CacheValidators {
+    fn from_headers(headers: &HeaderMap) -> Self {
+        let header = |name| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(String::from)
+        };
+
+        Self {
+            etag: header(ETAG),
+            last_modified: header(LAST_MODIFIED),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.etag.is_none() && self.last_modified.is_none()
+    }
+}
+
+/// An index as published by its source, along with its detached signature
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexDocument {
+    pub data: Vec<u8>,
+    /// The signature is only read for the repositories with trusted keys
+    pub signature: Option<String>,
+}
+
+/// Result of an index fetch
+#[derive(Debug)]
+pub enum FetchOutcome {
+    /// The server confirmed the cached index is still up to date
+    NotModified,
+    /// A new index has been downloaded
+    Fetched {
+        index: Box<RepositoryIndex>,
+        validators: CacheValidators,
+        /// Revision of the source the index was read from, when the source has one
+        revision: Option<String>,
+        /// The verified document the index was parsed from
+        document: IndexDocument,
+    },
+}
+
+/// Download repository indexes over HTTP
+///
+/// Every index must come with a detached minisign signature, located at the index url
+/// suffixed by [`SIGNATURE_SUFFIX`], made by one of the repository trusted keys. Unsigned
+/// indexes are only accepted from repositories without trusted keys, and only when
+/// explicitly allowed.
+///
+/// The size and the content type of the responses are checked against the outbound policy.
+#[derive(Clone)]
+pub struct HttpIndexFetcher {
+    client: reqwest::Client,
+    /// Client sending the credentials headers, see [`credentials_client`]
+    credentials_client: reqwest::Client,
+    policy: OutboundPolicy,
+    allow_unsigned: bool,
+}
+
+impl Default for HttpIndexFetcher {
+    fn default() -> Self {
+        Self::new(reqwest::Client::default())
+    }
+}
+
+impl HttpIndexFetcher {
+    pub fn new(client: reqwest::Client) -> Self {
+        Self {
+            client,
+            credentials_client: default_credentials_client(),
+            policy: OutboundPolicy::default(),
+            allow_unsigned: false,
+        }
+    }
+
+    /// Set the outbound policy the responses are checked against
+    pub fn with_outbound_policy(mut self, policy: OutboundPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Replace the HTTP client used to fetch the indexes
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Replace the HTTP client used to fetch the indexes with credentials headers, it must
+    /// refuse the redirects to other hosts
+    pub fn with_credentials_client(mut self, client: reqwest::Client) -> Self {
+        self.credentials_client = client;
+        self
+    }
+
+    /// Accept the indexes of repositories without trusted keys
+    pub fn with_unsigned_indexes(mut self, allow_unsigned: bool) -> Self {
+        self.allow_unsigned = allow_unsigned;
+        self
+    }
+
+    /// Fetch the index of a repository
+    ///
+    /// When `validators` are given, the request is made conditional and the server may
+    /// answer with [`FetchOutcome::NotModified`].
+    #[tracing::instrument(skip(self, repository), fields(url = %repository.url))]
+    pub async fn fetch(
+        &self,
+        repository: &Repository,
+        validators: Option<&CacheValidators>,
+    ) -> Result<FetchOutcome, RepositoriesServiceError> {
+        let url = repository.url.as_str();
+        let trusted_keys = &repository.trusted_keys;
+
+        if trusted_keys.is_empty() && !self.allow_unsigned {
+            return Err(RepositoriesServiceError::InvalidSignature(
+                "no trusted key is configured for this repository".to_string(),
+            ));
+        }
+
+        let credentials = repository.credentials.as_ref();
+        let client = credentials_client(&self.client, &self.credentials_client, credentials);
+        let mut request = authenticate(client.get(url), credentials)?;
+
+        if let Some(validators) = validators {
+            if let Some(etag) = &validators.etag {
+                request = request.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &validators.last_modified {
+                request = request.header(IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| RepositoriesServiceError::RemoteDataError(e.to_string()))?;
+
+        if response.status() == StatusCode::NOT_MODIFIED {
+            return Ok(FetchOutcome::NotModified);
+        }
+
+        let response = response
+            .error_for_status()
+            .map_err(|e| RepositoriesServiceError::RemoteDataError(e.to_string()))?;
+
+        let validators = CacheValidators::from_headers(response.headers());
+
+        self.policy
+            .check_content_type(&response, &INDEX_CONTENT_TYPES)?;
+        let data = self.policy.read_body(response).await?;
+
+        let signature = match trusted_keys.is_empty() {
+            true => None,
+            false => {
+                let signature = self.fetch_signature(repository).await?;
+                verify_index_signature(&data, &signature, trusted_keys)?;
+                Some(signature)
+            }
+        };
+
+        // Parse the index, any invalid entry will reject the whole index
+        let index = Box::new(RepositoryIndex::from_slice(&data)?);
+
+        Ok(FetchOutcome::Fetched {
+            index,
+            validators,
+            revision: None,
+            document: IndexDocument { data, signature },
+        })
+    }
+
+    /// Download a file referenced by an index, e.g. a package artifact or an icon
+    ///
+    /// The files may be hosted anywhere, the repository credentials are never sent along.
+    pub async fn download(&self, url: &str) -> Result<Vec<u8>, RepositoriesServiceError> {
+        self.policy.check_url(url).await?;
+
+        let response = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| RepositoriesServiceError::RemoteDataError(e.to_string()))?;
+
+        Ok(self.policy.read_body(response).await?)
+    }
+
+    /// Fetch the detached signature of the index of a repository
+    async fn fetch_signature(
+        &self,
+        repository: &Repository,
+    ) -> Result<String, RepositoriesServiceError> {
+        let mut signature_url = Url::parse(&repository.url)
+            .map_err(|e| RepositoriesServiceError::RemoteDataError(e.to_string()))?;
+        signature_url.set_path(&format!("{}{}", signature_url.path(), SIGNATURE_SUFFIX));
+
+        let credentials = repository.credentials.as_ref();
+        let client = credentials_client(&self.client, &self.credentials_client, credentials);
+        let response = authenticate(client.get(signature_url), credentials)?
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| {
+                RepositoriesServiceError::InvalidSignature(format!(
+                    "failed to fetch the index signature: {}",
+                    e
+                ))
+            })?;
+
+        let signature = self.policy.read_body(response).await?;
+
+        String::from_utf8(signature)
+            .map_err(|e| RepositoriesServiceError::InvalidSignature(e.to_string()))
+    }
+}
+
+/// Pick the client to send the repository credentials with
+///
+/// The credentials headers are kept by the redirects, unlike `Authorization`: they are sent by
+/// a client refusing the redirects to other hosts.
+pub(super) fn credentials_client<'a>(
+    client: &'a reqwest::Client,
+    credentials_client: &'a reqwest::Client,
+    credentials: Option<&RepositoryCredentials>,
+) -> &'a reqwest::Client {
+    match credentials {
+        Some(RepositoryCredentials::Headers { .. }) => credentials_client,
+        _ => client,
+    }
+}
+
+/// Client sending the credentials headers when none is given, it only refuses the redirects to
+/// other hosts
+pub(super) fn default_credentials_client() -> reqwest::Client {
+    OutboundPolicy {
+        allow_private_networks: true,
+        ..Default::default()
+    }
+    .restrict_credentials_client(reqwest::Client::builder())
+    .build()
+    .expect("the default credentials client should build")
+}
+
+/// Add the repository credentials to a request
+pub(super) fn authenticate(
+    request: RequestBuilder,
+    credentials: Option<&RepositoryCredentials>,
+) -> Result<RequestBuilder, RepositoriesServiceError> {
+    let request = match credentials {
+        None => request,
+        Some(RepositoryCredentials::Basic { username, password }) => {
+            request.basic_auth(username, Some(password))
+        }
+        Some(RepositoryCredentials::Bearer { token }) => request.bearer_auth(token),
+        Some(RepositoryCredentials::Headers { headers }) => {
+            let mut header_map = HeaderMap::new();
+            for (name, value) in headers {
+                let name = HeaderName::from_bytes(name.as_bytes()).map_err(|_| {
+                    RepositoriesServiceError::RemoteDataError(format!(
+                        "Invalid credentials header name `{}`",
+                        name
+                    ))
+                })?;
+                let mut value = HeaderValue::from_str(value).map_err(|_| {
+                    RepositoriesServiceError::RemoteDataError(format!(
+                        "Invalid value for the credentials header `{}`",
+                        name
+                    ))
+                })?;
+                value.set_sensitive(true);
+                header_map.insert(name, value);
+            }
+            request.headers(header_map)
+        }
+    };
+
+    Ok(request)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use serde_json::json;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    use std::collections::BTreeMap;
+
+    use kubestro_core_domain::models::{
+        fields::trusted_key::TrustedKey,
+        package::{RepositoryId, RepositoryKind},
+        EntityId,
+    };
+
+    use crate::services::index_signature::tests::{
+        INDEX_SIGNATURE, OTHER_KEY, SIGNED_INDEX, SIGNING_KEY,
+    };
+
+    use super::*;
+
+    pub(crate) fn repository(url: &str, trusted_keys: &[&str]) -> Repository {
+        Repository {
+            id: RepositoryId::new(),
+            name: "stub".to_string(),
+            kind: RepositoryKind::Http,
+            url: url.to_string(),
+            trusted_keys: trusted_keys
+                .iter()
+                .map(|key| TrustedKey::try_from(*key).unwrap())
+                .collect(),
+            credentials: None,
+            priority: 0,
+            managed: false,
+            sync: Default::default(),
+        }
+    }
+
+    const ETAG_VALUE: &str = "\"index-v1\"";
+    const LAST_MODIFIED_VALUE: &str = "Wed, 21 Oct 2015 07:28:00 GMT";
+
+    /// Start a minimal HTTP server answering every request with the given handler
+    ///
+    /// Returns the server URL and the raw requests it received.
+    pub(crate) async fn start_stub_server(
+        handler: fn(&str) -> String,
+    ) -> (String, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/index.json", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let received = requests.clone();
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buffer = vec![0; 4096];
+                let read = socket.read(&mut buffer).await.unwrap();
+                let request = String::from_utf8_lossy(&buffer[..read]).to_lowercase();
+
+                let response = handler(&request);
+                received.lock().unwrap().push(request);
+                socket.write_all(response.as_bytes()).await.unwrap();
+                socket.shutdown().await.unwrap();
+            }
+        });
+
+        (url, requests)
+    }
+
+    fn index_response(request: &str) -> String {
+        if request.contains(&format!("if-none-match: {}", ETAG_VALUE)) {
+            return "HTTP/1.1 304 Not Modified\r\nconnection: close\r\n\r\n".to_string();
+        }
+
+        let body = json!({
+            "version": "1.0",
+            "repository": { "name": "Stub" },
+            "packages": []
+        })
+        .to_string();
+
+        format!(
+            "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\netag: {}\r\nlast-modified: {}\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+            ETAG_VALUE,
+            LAST_MODIFIED_VALUE,
+            body.len(),
+            body
+        )
+    }
+
+    #[tokio::test]
+    async fn fetch_should_return_the_index_and_its_validators() {
+        let (url, _) = start_stub_server(index_response).await;
+        let fetcher = HttpIndexFetcher::default().with_unsigned_indexes(true);
+
+        let outcome = fetcher.fetch(&repository(&url, &[]), None).await.unwrap();
+
+        let FetchOutcome::Fetched {
+            index, validators, ..
+        } = outcome
+        else {
+            panic!("the index should be fetched");
+        };
+        assert_eq!(index.repository.name, "Stub");
+        assert_eq!(
+            validators,
+            CacheValidators {
+                etag: Some(ETAG_VALUE.to_string()),
+                last_modified: Some(LAST_MODIFIED_VALUE.to_string()),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn fetch_with_validators_should_send_conditional_headers() {
+        let (url, requests) = start_stub_server(index_response).await;
+        let fetcher = HttpIndexFetcher::default().with_unsigned_indexes(true);
+        let validators = CacheValidators {
+            etag: Some(ETAG_VALUE.to_string()),
+            last_modified: Some(LAST_MODIFIED_VALUE.to_string()),
+        };
+
+        let outcome = fetcher
+            .fetch(&repository(&url, &[]), Some(&validators))
+            .await
+            .unwrap();
+
+        assert!(matches!(outcome, FetchOutcome::NotModified));
+        let request = requests.lock().unwrap()[0].clone();
+        assert!(request.contains(&format!("if-none-match: {}", ETAG_VALUE)));
+        assert!(request.contains(&format!(
+            "if-modified-since: {}",
+            LAST_MODIFIED_VALUE.to_lowercase()
+        )));
+    }
+
+    #[tokio::test]
+    async fn fetch_should_fail_on_server_error() {
+        let (url, _) = start_stub_server(|_| {
+            "HTTP/1.1 500 Internal Server Error\r\ncontent-length: 0\r\nconnection: close\r\n\r\n"
+                .to_string()
+        })
+        .await;
+        let fetcher = HttpIndexFetcher::default().with_unsigned_indexes(true);
+
+        let outcome = fetcher.fetch(&repository(&url, &[]), None).await;
+
+        assert!(matches!(
+            outcome,
+            Err(RepositoriesServiceError::RemoteDataError(_))
+        ));
+    }
+
+    fn signed_index_response(request: &str) -> String {
+        let (content_type, body) = match request.starts_with("get /index.json.minisig ") {
+            true => ("text/plain", INDEX_SIGNATURE),
+            false => ("application/json", SIGNED_INDEX),
+        };
+
+        format!(
+            "HTTP/1.1 200 OK\r\ncontent-type: {}\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+            content_type,
+            body.len(),
+            body
+        )
+    }
+
+    #[tokio::test]
+    async fn fetch_should_check_the_index_content_type() {
+        let (url, _) = start_stub_server(|_| {
+            "HTTP/1.1 200 OK\r\ncontent-type: text/html\r\ncontent-length: 0\r\nconnection: close\r\n\r\n"
+                .to_string()
+        })
+        .await;
+        let fetcher = HttpIndexFetcher::default().with_unsigned_indexes(true);
+
+        let outcome = fetcher.fetch(&repository(&url, &[]), None).await;
+
+        assert!(matches!(
+            outcome,
+            Err(RepositoriesServiceError::RemoteDataError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn fetch_should_verify_the_index_signature() {
+        let (url, requests) = start_stub_server(signed_index_response).await;
+        let fetcher = HttpIndexFetcher::default();
+        let repository = repository(&url, &[SIGNING_KEY]);
+
+        let outcome = fetcher.fetch(&repository, None).await.unwrap();
+
+        assert!(matches!(outcome, FetchOutcome::Fetched { .. }));
+        assert_eq!(requests.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn fetch_should_reject_an_index_signed_by_an_untrusted_key() {
+        let (url, _) = start_stub_server(signed_index_response).await;
+        let fetcher = HttpIndexFetcher::default();
+        let repository = repository(&url, &[OTHER_KEY]);
+
+        let outcome = fetcher.fetch(&repository, None).await;
+
+        assert!(matches!(
+            outcome,
+            Err(RepositoriesServiceError::InvalidSignature(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn fetch_should_reject_unsigned_repositories_by_default() {
+        let fetcher = HttpIndexFetcher::default();
+
+        let outcome = fetcher
+            .fetch(&repository("http://127.0.0.1:1/index.json", &[]), None)
+            .await;
+
+        assert!(matches!(
+            outcome,
+            Err(RepositoriesServiceError::InvalidSignature(_))
+        ));
+    }
+
+    fn authenticated_response(request: &str) -> String {
+        let status = match request.contains("authorization: bearer s3cr3t")
+            && request.contains("x-api-key: k3y")
+        {
+            true => "200 OK",
+            false => "401 Unauthorized",
+        };
+
+        format!(
+            "HTTP/1.1 {}\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+            status,
+            SIGNED_INDEX.len(),
+            SIGNED_INDEX
+        )
+    }
+
+    #[tokio::test]
+    async fn fetch_should_send_the_repository_credentials() {
+        let (url, _) = start_stub_server(authenticated_response).await;
+        let fetcher = HttpIndexFetcher::default().with_unsigned_indexes(true);
+        let mut repository = repository(&url, &[]);
+
+        let unauthenticated = fetcher.fetch(&repository, None).await;
+        repository.credentials = Some(RepositoryCredentials::Headers {
+            headers: BTreeMap::from([
+                ("Authorization".to_string(), "Bearer s3cr3t".to_string()),
+                ("X-Api-Key".to_string(), "k3y".to_string()),
+            ]),
+        });
+        let authenticated = fetcher.fetch(&repository, None).await;
+
+        assert!(matches!(
+            unauthenticated,
+            Err(RepositoriesServiceError::RemoteDataError(_))
+        ));
+        assert!(matches!(authenticated, Ok(FetchOutcome::Fetched { .. })));
+    }
+
+    /// Redirect the requests to `127.0.0.1` to the same server reached as `localhost`, another
+    /// host for the client
+    fn cross_host_redirect_response(request: &str) -> String {
+        let Some(port) = request
+            .lines()
+            .find_map(|line| line.strip_prefix("host: 127.0.0.1:"))
+        else {
+            return authenticated_response(request);
+        };
+
+        format!(
+            "HTTP/1.1 302 Found\r\nlocation: http://localhost:{}/index.json\r\ncontent-length: 0\r\nconnection: close\r\n\r\n",
+            port.trim()
+        )
+    }
+
+    #[tokio::test]
+    async fn credentials_headers_should_not_follow_a_redirect_to_another_host() {
+        let (url, requests) = start_stub_server(cross_host_redirect_response).await;
+        let fetcher = HttpIndexFetcher::default().with_unsigned_indexes(true);
+        let mut repository = repository(&url, &[]);
+        repository.credentials = Some(RepositoryCredentials::Headers {
+            headers: BTreeMap::from([
+                ("Authorization".to_string(), "Bearer s3cr3t".to_string()),
+                ("X-Api-Key".to_string(), "k3y".to_string()),
+            ]),
+        });
+
+        let outcome = fetcher.fetch(&repository, None).await;
+
+        assert!(matches!(
+            outcome,
+            Err(RepositoriesServiceError::RemoteDataError(_))
+        ));
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 1);
+        assert!(requests[0].contains("host: 127.0.0.1:"));
+    }
+
+    #[tokio::test]
+    async fn credentials_headers_should_follow_a_redirect_on_the_same_host() {
+        let (url, requests) = start_stub_server(|request| {
+            match request.starts_with("get /index.json ") {
+                true => "HTTP/1.1 302 Found\r\nlocation: /moved.json\r\ncontent-length: 0\r\nconnection: close\r\n\r\n".to_string(),
+                false => authenticated_response(request),
+            }
+        })
+        .await;
+        let fetcher = HttpIndexFetcher::default().with_unsigned_indexes(true);
+        let mut repository = repository(&url, &[]);
+        repository.credentials = Some(RepositoryCredentials::Headers {
+            headers: BTreeMap::from([
+                ("Authorization".to_string(), "Bearer s3cr3t".to_string()),
+                ("X-Api-Key".to_string(), "k3y".to_string()),
+            ]),
+        });
+
+        let outcome = fetcher.fetch(&repository, None).await;
+
+        assert!(matches!(outcome, Ok(FetchOutcome::Fetched { .. })));
+        assert_eq!(requests.lock().unwrap().len(), 2);
+    }
+}
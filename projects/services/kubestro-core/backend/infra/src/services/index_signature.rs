@@ -0,0 +1,92 @@
+use kubestro_core_domain::{
+    models::fields::trusted_key::TrustedKey,
+    ports::services::repositories_service::RepositoriesServiceError,
+};
+use minisign_verify::Signature;
+
+/// Suffix appended to an index url to locate its detached signature
+pub const SIGNATURE_SUFFIX: &str = ".minisig";
+
+/// Verify the detached minisign signature of an index
+///
+/// The signature must be made by one of the `trusted_keys`. Only the prehashed signatures
+/// produced by current minisign versions are accepted.
+pub fn verify_index_signature(
+    data: &[u8],
+    signature: &str,
+    trusted_keys: &[TrustedKey],
+) -> Result<(), RepositoriesServiceError> {
+    let signature = Signature::decode(signature)
+        .map_err(|e| RepositoriesServiceError::InvalidSignature(e.to_string()))?;
+
+    let verified = trusted_keys
+        .iter()
+        .any(|key| key.public_key().verify(data, &signature, false).is_ok());
+
+    if !verified {
+        return Err(RepositoriesServiceError::InvalidSignature(
+            "the index is not signed by any of the trusted keys".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    pub(crate) const SIGNED_INDEX: &str =
+        r#"{"version":"1.0","repository":{"name":"Stub"},"packages":[]}"#;
+    pub(crate) const SIGNING_KEY: &str = "RWQkCmdL7FRgDeMAxTlNADXgk1COUFMnKJJqWT2o78dcrYFRW/OEkD4s";
+    pub(crate) const OTHER_KEY: &str = "RWS4Kp70ZamsaO7fWISE84CgALbwwZpHXoqSlFM2Q0xVkIw8MmYeVVZP";
+    pub(crate) const INDEX_SIGNATURE: &str = "untrusted comment: signature from kubestro test key
+RUQkCmdL7FRgDYpFpcJVKd+uGoiHi0rclMAI6ED0a/ln4nuB49s5CKvKu/rKsa2kYoR5mkqgQQgx8iKI8gIW3jJmzqWaHlmQKAs=
+trusted comment: kubestro test
+8Qy+yw8puOIdhpBb0wxlVuCp6MoCFYaQtW7K8TWEM7DRIuHce9Y2GU867m5pZtKvvSI/xXUjha56A1kbRY7BAw==
+";
+
+    fn keys(keys: &[&str]) -> Vec<TrustedKey> {
+        keys.iter()
+            .map(|key| TrustedKey::try_from(*key).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn signature_from_a_trusted_key_should_be_accepted() {
+        let result = verify_index_signature(
+            SIGNED_INDEX.as_bytes(),
+            INDEX_SIGNATURE,
+            &keys(&[OTHER_KEY, SIGNING_KEY]),
+        );
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn signature_from_an_untrusted_key_should_be_rejected() {
+        let result = verify_index_signature(
+            SIGNED_INDEX.as_bytes(),
+            INDEX_SIGNATURE,
+            &keys(&[OTHER_KEY]),
+        );
+
+        assert!(matches!(
+            result,
+            Err(RepositoriesServiceError::InvalidSignature(_))
+        ));
+    }
+
+    #[test]
+    fn tampered_index_should_be_rejected() {
+        let tampered = SIGNED_INDEX.replace("Stub", "Evil");
+
+        let result =
+            verify_index_signature(tampered.as_bytes(), INDEX_SIGNATURE, &keys(&[SIGNING_KEY]));
+
+        assert!(matches!(
+            result,
+            Err(RepositoriesServiceError::InvalidSignature(_))
+        ));
+    }
+}
@@ -1,4 +1,17 @@
-use kube::Client;
+use std::time::Duration;
+
+use k8s_openapi::apiextensions_apiserver::pkg::apis::apiextensions::v1::CustomResourceDefinition;
+use kube::{
+    api::{Patch, PatchParams},
+    runtime::wait::{await_condition, conditions},
+    Api, Client, ResourceExt,
+};
+use tracing::debug;
+
+use crate::k8s::game_manager::{crds, FIELD_MANAGER};
+
+/// Time given to the API server to serve a custom resource definition once applied
+const CRD_ESTABLISHED_TIMEOUT: Duration = Duration::from_secs(30);
 
 #[derive(Clone)]
 pub struct K8sClient {
@@ -15,10 +28,38 @@ impl K8sClient {
     pub fn client(&self) -> Client {
         self.client.clone()
     }
+
+    /// Apply the custom resource definitions owned by Kubestro Core, and wait for the API
+    /// server to serve them
+    ///
+    /// The definitions are applied with server-side apply, so the fields changed by another
+    /// manager are taken over and a definition from an older release is upgraded in place.
+    pub async fn apply_crds(&self) -> Result<(), K8sClientError> {
+        let api = Api::<CustomResourceDefinition>::all(self.client());
+        let params = PatchParams::apply(FIELD_MANAGER).force();
+
+        for crd in crds() {
+            let name = crd.name_any();
+            debug!("Applying custom resource definition `{}`...", name);
+            api.patch(&name, &params, &Patch::Apply(&crd)).await?;
+
+            let established = await_condition(api.clone(), &name, conditions::is_crd_established());
+            tokio::time::timeout(CRD_ESTABLISHED_TIMEOUT, established)
+                .await
+                .map_err(|_| K8sClientError::CrdNotEstablished(name.clone()))?
+                .map_err(|e| K8sClientError::WaitError(e.to_string()))?;
+        }
+
+        Ok(())
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
 pub enum K8sClientError {
     #[error("An error occurred while creating the Kubernetes client: {0}")]
     ClientError(#[from] kube::error::Error),
+    #[error("The custom resource definition `{0}` was not established in time")]
+    CrdNotEstablished(String),
+    #[error("An error occurred while waiting for a Kubernetes resource: {0}")]
+    WaitError(String),
 }
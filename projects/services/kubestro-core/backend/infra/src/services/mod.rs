@@ -1,5 +1,17 @@
 pub mod argon_hasher;
+pub mod bundle_archive;
+pub mod bundle_store;
+pub mod bundles_service;
+pub mod cluster_service;
+pub mod credentials_cipher;
+pub mod git_fetcher;
+pub mod http_client;
+pub mod index_fetcher;
+pub mod index_signature;
 pub mod k8s_client;
+pub mod oci_fetcher;
 pub mod oidc;
+pub mod outbound_policy;
 pub mod password_validator;
 pub mod repositories_service;
+pub mod values_validator;
@@ -0,0 +1,737 @@
+use std::net::IpAddr;
+
+use kubestro_core_domain::{
+    models::{
+        index::RepositoryIndex,
+        package::{Repository, RepositoryCredentials},
+    },
+    ports::services::repositories_service::RepositoriesServiceError,
+};
+use reqwest::{
+    header::{ACCEPT, WWW_AUTHENTICATE},
+    Response, StatusCode, Url,
+};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use super::{
+    index_fetcher::{
+        authenticate, credentials_client, default_credentials_client, CacheValidators,
+        FetchOutcome, IndexDocument,
+    },
+    index_signature::verify_index_signature,
+    outbound_policy::OutboundPolicy,
+};
+
+/// Media type of the manifests pulled from the registries
+const MANIFEST_MEDIA_TYPE: &str = "application/vnd.oci.image.manifest.v1+json";
+/// Media type of the artifact layer holding the index
+pub const INDEX_MEDIA_TYPE: &str = "application/vnd.kubestro.index.v1+json";
+/// Media type of the artifact layer holding the detached minisign signature of the index
+pub const SIGNATURE_MEDIA_TYPE: &str = "application/vnd.kubestro.index.signature.v1+minisign";
+/// Tag pulled when the reference does not pin one, if the registry has it
+const LATEST_TAG: &str = "latest";
+
+/// Location of an index artifact in an OCI registry
+#[derive(Debug, Clone, PartialEq)]
+struct OciReference {
+    /// Base url of the registry
+    registry: Url,
+    /// Name of the artifact in the registry, e.g. `kubestro/index`
+    name: String,
+    tag: Option<String>,
+}
+
+impl OciReference {
+    /// Parse an `oci://<registry>/<name>[:<tag>]` reference
+    ///
+    /// Registries are reached over HTTPS, except the local ones which are reached over HTTP.
+    fn parse(reference: &str) -> Result<Self, RepositoriesServiceError> {
+        let invalid = |reason: &str| {
+            RepositoriesServiceError::RemoteDataError(format!(
+                "Invalid OCI reference `{}`: {}",
+                reference, reason
+            ))
+        };
+
+        let url = Url::parse(reference).map_err(|e| invalid(&e.to_string()))?;
+        if url.scheme() != "oci" {
+            return Err(invalid("expected an `oci://` reference"));
+        }
+
+        let host = url.host_str().ok_or_else(|| invalid("missing registry"))?;
+        let local = host == "localhost"
+            || host
+                .trim_matches(['[', ']'])
+                .parse::<IpAddr>()
+                .is_ok_and(|ip| ip.is_loopback());
+        let registry = match url.port() {
+            Some(port) => format!("{}:{}", host, port),
+            None => host.to_string(),
+        };
+        let scheme = if local { "http" } else { "https" };
+        let registry = Url::parse(&format!("{}://{}", scheme, registry))
+            .map_err(|e| invalid(&e.to_string()))?;
+
+        let path = url.path().trim_start_matches('/');
+        let (name, tag) = match path.rsplit_once(':') {
+            // A colon is only a tag separator in the last segment of the name
+            Some((name, tag)) if !tag.contains('/') => (name, Some(tag.to_string())),
+            _ => (path, None),
+        };
+        if name.is_empty() {
+            return Err(invalid("missing artifact name"));
+        }
+
+        Ok(Self {
+            registry,
+            name: name.to_string(),
+            tag,
+        })
+    }
+
+    /// Url of an endpoint of the registry API for this artifact
+    fn endpoint(&self, path: &str) -> String {
+        format!("{}v2/{}/{}", self.registry, self.name, path)
+    }
+}
+
+#[derive(Deserialize)]
+struct TagList {
+    #[serde(default)]
+    tags: Option<Vec<String>>,
+}
+
+#[derive(Deserialize)]
+struct Manifest {
+    layers: Vec<Descriptor>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Descriptor {
+    media_type: String,
+    digest: String,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    token: Option<String>,
+    access_token: Option<String>,
+}
+
+/// Pull repository indexes stored as artifacts in OCI registries
+///
+/// The artifact manifest must have an [`INDEX_MEDIA_TYPE`] layer, and a
+/// [`SIGNATURE_MEDIA_TYPE`] layer made by one of the repository trusted keys. The manifest
+/// digest is used as the cache validator and the revision: an unchanged manifest is not
+/// pulled again.
+///
+/// The size of the responses, the content type of the manifests and the token realms given by
+/// the registries are checked against the outbound policy.
+#[derive(Clone)]
+pub struct OciIndexFetcher {
+    client: reqwest::Client,
+    /// Client sending the credentials headers, see [`credentials_client`]
+    credentials_client: reqwest::Client,
+    policy: OutboundPolicy,
+    allow_unsigned: bool,
+}
+
+impl Default for OciIndexFetcher {
+    fn default() -> Self {
+        Self::new(reqwest::Client::default())
+    }
+}
+
+impl OciIndexFetcher {
+    pub fn new(client: reqwest::Client) -> Self {
+        Self {
+            client,
+            credentials_client: default_credentials_client(),
+            policy: OutboundPolicy::default(),
+            allow_unsigned: false,
+        }
+    }
+
+    /// Set the outbound policy the responses are checked against
+    pub fn with_outbound_policy(mut self, policy: OutboundPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Replace the HTTP client used to reach the registries
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Replace the HTTP client used to reach the registries with credentials headers, it must
+    /// refuse the redirects to other hosts
+    pub fn with_credentials_client(mut self, client: reqwest::Client) -> Self {
+        self.credentials_client = client;
+        self
+    }
+
+    /// Accept the indexes of repositories without trusted keys
+    pub fn with_unsigned_indexes(mut self, allow_unsigned: bool) -> Self {
+        self.allow_unsigned = allow_unsigned;
+        self
+    }
+
+    /// Pull the index artifact of a repository
+    ///
+    /// When `validators` hold the digest of the current manifest, the layers are not pulled
+    /// again and [`FetchOutcome::NotModified`] is returned.
+    #[tracing::instrument(skip(self, repository), fields(url = %repository.url))]
+    pub async fn fetch(
+        &self,
+        repository: &Repository,
+        validators: Option<&CacheValidators>,
+    ) -> Result<FetchOutcome, RepositoriesServiceError> {
+        let trusted_keys = &repository.trusted_keys;
+
+        if trusted_keys.is_empty() && !self.allow_unsigned {
+            return Err(RepositoriesServiceError::InvalidSignature(
+                "no trusted key is configured for this repository".to_string(),
+            ));
+        }
+
+        let reference = OciReference::parse(&repository.url)?;
+        let mut session = RegistrySession {
+            client: &self.client,
+            credentials_client: &self.credentials_client,
+            policy: &self.policy,
+            credentials: repository.credentials.as_ref(),
+            token: None,
+        };
+
+        let tag = match &reference.tag {
+            Some(tag) => tag.clone(),
+            None => {
+                let tags = session
+                    .get_json::<TagList>(&reference.endpoint("tags/list"), None)
+                    .await?;
+                select_tag(tags.tags.unwrap_or_default()).ok_or_else(|| {
+                    RepositoriesServiceError::RemoteDataError(format!(
+                        "No tag found for `{}`",
+                        reference.name
+                    ))
+                })?
+            }
+        };
+
+        let manifest = session
+            .get_bytes(
+                &reference.endpoint(&format!("manifests/{}", tag)),
+                Some(MANIFEST_MEDIA_TYPE),
+            )
+            .await?;
+
+        // The manifest digest identifies the whole artifact
+        let digest = sha256_digest(&manifest);
+        if validators.and_then(|validators| validators.etag.as_ref()) == Some(&digest) {
+            return Ok(FetchOutcome::NotModified);
+        }
+
+        let manifest: Manifest = serde_json::from_slice(&manifest).map_err(|e| {
+            RepositoriesServiceError::RemoteDataError(format!("Invalid manifest: {}", e))
+        })?;
+        let layer = |media_type: &str| {
+            manifest
+                .layers
+                .iter()
+                .find(|layer| layer.media_type == media_type)
+        };
+
+        let index_layer = layer(INDEX_MEDIA_TYPE).ok_or_else(|| {
+            RepositoriesServiceError::RemoteDataError(format!(
+                "The artifact has no `{}` layer",
+                INDEX_MEDIA_TYPE
+            ))
+        })?;
+        let data = session.pull_blob(&reference, index_layer).await?;
+
+        let signature = match trusted_keys.is_empty() {
+            true => None,
+            false => {
+                let signature_layer = layer(SIGNATURE_MEDIA_TYPE).ok_or_else(|| {
+                    RepositoriesServiceError::InvalidSignature(
+                        "the artifact has no signature layer".to_string(),
+                    )
+                })?;
+                let signature = session.pull_blob(&reference, signature_layer).await?;
+                let signature = String::from_utf8(signature)
+                    .map_err(|e| RepositoriesServiceError::InvalidSignature(e.to_string()))?;
+
+                verify_index_signature(&data, &signature, trusted_keys)?;
+                Some(signature)
+            }
+        };
+
+        // Parse the index, any invalid entry will reject the whole index
+        let index = Box::new(RepositoryIndex::from_slice(&data)?);
+
+        Ok(FetchOutcome::Fetched {
+            index,
+            validators: CacheValidators {
+                etag: Some(digest.clone()),
+                last_modified: None,
+            },
+            revision: Some(digest),
+            document: IndexDocument { data, signature },
+        })
+    }
+}
+
+/// Pick the tag to pull: `latest` when present, the highest version otherwise
+fn select_tag(tags: Vec<String>) -> Option<String> {
+    if tags.iter().any(|tag| tag == LATEST_TAG) {
+        return Some(LATEST_TAG.to_string());
+    }
+
+    // Releases rank above pre-releases, which rank above the tags that are not versions
+    tags.into_iter().max_by_key(|tag| {
+        let (version, pre_release) = match tag.trim_start_matches('v').split_once('-') {
+            Some((version, _)) => (version, true),
+            None => (tag.trim_start_matches('v'), false),
+        };
+        let version = version
+            .split('.')
+            .map(|part| part.parse::<u64>().ok())
+            .collect::<Option<Vec<_>>>();
+
+        (version.is_some() && !pre_release, version, tag.clone())
+    })
+}
+
+fn sha256_digest(data: &[u8]) -> String {
+    format!("sha256:{:x}", Sha256::digest(data))
+}
+
+/// Requests made to a registry during a single fetch
+///
+/// The registry token obtained after the first challenge is reused by the next requests.
+struct RegistrySession<'a> {
+    client: &'a reqwest::Client,
+    credentials_client: &'a reqwest::Client,
+    policy: &'a OutboundPolicy,
+    credentials: Option<&'a RepositoryCredentials>,
+    token: Option<String>,
+}
+
+impl RegistrySession<'_> {
+    async fn get_json<T: serde::de::DeserializeOwned>(
+        &mut self,
+        url: &str,
+        accept: Option<&str>,
+    ) -> Result<T, RepositoriesServiceError> {
+        let data = self.get_bytes(url, accept).await?;
+
+        serde_json::from_slice(&data)
+            .map_err(|e| RepositoriesServiceError::RemoteDataError(e.to_string()))
+    }
+
+    async fn get_bytes(
+        &mut self,
+        url: &str,
+        accept: Option<&str>,
+    ) -> Result<Vec<u8>, RepositoriesServiceError> {
+        let response = self
+            .get(url, accept)
+            .await?
+            .error_for_status()
+            .map_err(|e| RepositoriesServiceError::RemoteDataError(e.to_string()))?;
+
+        if let Some(accept) = accept {
+            self.policy.check_content_type(&response, &[accept])?;
+        }
+
+        Ok(self.policy.read_body(response).await?)
+    }
+
+    /// Pull a blob and check it matches its digest
+    async fn pull_blob(
+        &mut self,
+        reference: &OciReference,
+        descriptor: &Descriptor,
+    ) -> Result<Vec<u8>, RepositoriesServiceError> {
+        if !descriptor.digest.starts_with("sha256:") {
+            return Err(RepositoriesServiceError::RemoteDataError(format!(
+                "Unsupported digest `{}`",
+                descriptor.digest
+            )));
+        }
+
+        let data = self
+            .get_bytes(
+                &reference.endpoint(&format!("blobs/{}", descriptor.digest)),
+                None,
+            )
+            .await?;
+
+        if sha256_digest(&data) != descriptor.digest {
+            return Err(RepositoriesServiceError::RemoteDataError(format!(
+                "The blob does not match its digest `{}`",
+                descriptor.digest
+            )));
+        }
+
+        Ok(data)
+    }
+
+    /// Send a GET request, requesting a registry token when the registry asks for one
+    async fn get(
+        &mut self,
+        url: &str,
+        accept: Option<&str>,
+    ) -> Result<Response, RepositoriesServiceError> {
+        let response = self.send(url, accept).await?;
+        if response.status() != StatusCode::UNAUTHORIZED || self.token.is_some() {
+            return Ok(response);
+        }
+
+        let Some((realm, params)) = response
+            .headers()
+            .get(WWW_AUTHENTICATE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(parse_bearer_challenge)
+        else {
+            return Ok(response);
+        };
+
+        self.token = Some(self.request_token(&realm, &params).await?);
+        self.send(url, accept).await
+    }
+
+    async fn send(
+        &self,
+        url: &str,
+        accept: Option<&str>,
+    ) -> Result<Response, RepositoriesServiceError> {
+        let credentials = self.token.is_none().then_some(self.credentials).flatten();
+        let client = credentials_client(self.client, self.credentials_client, credentials);
+        let mut request = client.get(url);
+        if let Some(accept) = accept {
+            request = request.header(ACCEPT, accept);
+        }
+        let request = match &self.token {
+            Some(token) => request.bearer_auth(token),
+            None => authenticate(request, credentials)?,
+        };
+
+        request
+            .send()
+            .await
+            .map_err(|e| RepositoriesServiceError::RemoteDataError(e.to_string()))
+    }
+
+    /// Exchange the repository credentials for a registry token, anonymously without them
+    async fn request_token(
+        &self,
+        realm: &str,
+        params: &[(String, String)],
+    ) -> Result<String, RepositoriesServiceError> {
+        // The realm is chosen by the registry, it must follow the policy as well
+        let realm = Url::parse(realm).map_err(|e| {
+            RepositoriesServiceError::RemoteDataError(format!("Invalid token realm: {}", e))
+        })?;
+        self.policy.check_target(&realm)?;
+
+        let client = credentials_client(self.client, self.credentials_client, self.credentials);
+        let request = authenticate(client.get(realm).query(params), self.credentials)?;
+
+        let response = request
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| {
+                RepositoriesServiceError::RemoteDataError(format!(
+                    "Failed to get a registry token: {}",
+                    e
+                ))
+            })?;
+        let response: TokenResponse =
+            serde_json::from_slice(&self.policy.read_body(response).await?)
+                .map_err(|e| RepositoriesServiceError::RemoteDataError(e.to_string()))?;
+
+        response.token.or(response.access_token).ok_or_else(|| {
+            RepositoriesServiceError::RemoteDataError(
+                "The registry did not return any token".to_string(),
+            )
+        })
+    }
+}
+
+/// Parse a `WWW-Authenticate: Bearer realm="...",service="...",scope="..."` challenge
+///
+/// Returns the realm and the other parameters, to send along the token request.
+fn parse_bearer_challenge(header: &str) -> Option<(String, Vec<(String, String)>)> {
+    let (scheme, mut rest) = header.trim().split_once(' ')?;
+    if !scheme.eq_ignore_ascii_case("bearer") {
+        return None;
+    }
+
+    let mut realm = None;
+    let mut params = Vec::new();
+    while !rest.is_empty() {
+        let (key, value) = rest.split_once('=')?;
+        let (value, remaining) = match value.strip_prefix('"') {
+            Some(quoted) => {
+                let end = quoted.find('"')?;
+                (&quoted[..end], &quoted[end + 1..])
+            }
+            None => value.split_once(',').unwrap_or((value, "")),
+        };
+        rest = remaining.trim_start_matches([',', ' ']);
+
+        match key.trim().to_ascii_lowercase().as_str() {
+            "realm" => realm = Some(value.to_string()),
+            key => params.push((key.to_string(), value.to_string())),
+        }
+    }
+
+    Some((realm?, params))
+}
+
+#[cfg(test)]
+mod tests {
+    use kubestro_core_domain::models::package::RepositoryKind;
+    use serde_json::json;
+
+    use crate::services::{
+        index_fetcher::tests::{repository, start_stub_server},
+        index_signature::tests::{INDEX_SIGNATURE, SIGNED_INDEX, SIGNING_KEY},
+    };
+
+    use super::*;
+
+    fn response(status: &str, headers: &str, body: &str) -> String {
+        format!(
+            "HTTP/1.1 {}\r\n{}content-length: {}\r\nconnection: close\r\n\r\n{}",
+            status,
+            headers,
+            body.len(),
+            body
+        )
+    }
+
+    fn manifest() -> String {
+        json!({
+            "schemaVersion": 2,
+            "mediaType": MANIFEST_MEDIA_TYPE,
+            "config": {
+                "mediaType": "application/vnd.oci.empty.v1+json",
+                "digest": sha256_digest(b"{}"),
+                "size": 2
+            },
+            "layers": [
+                {
+                    "mediaType": INDEX_MEDIA_TYPE,
+                    "digest": sha256_digest(SIGNED_INDEX.as_bytes()),
+                    "size": SIGNED_INDEX.len()
+                },
+                {
+                    "mediaType": SIGNATURE_MEDIA_TYPE,
+                    "digest": sha256_digest(INDEX_SIGNATURE.as_bytes()),
+                    "size": INDEX_SIGNATURE.len()
+                }
+            ]
+        })
+        .to_string()
+    }
+
+    /// Registry stand-in serving the `kubestro/index` artifact under the `1.9.0` tag
+    fn registry_response(request: &str) -> String {
+        let path = request.split_whitespace().nth(1).unwrap_or_default();
+        let blob = |data: &str| {
+            format!(
+                "/v2/kubestro/index/blobs/{}",
+                sha256_digest(data.as_bytes())
+            )
+        };
+
+        match path {
+            "/v2/kubestro/index/tags/list" => response(
+                "200 OK",
+                "",
+                &json!({ "name": "kubestro/index", "tags": ["1.2.0", "1.10.0-rc.1", "1.9.0"] })
+                    .to_string(),
+            ),
+            "/v2/kubestro/index/manifests/1.9.0" => response(
+                "200 OK",
+                &format!("content-type: {}\r\n", MANIFEST_MEDIA_TYPE),
+                &manifest(),
+            ),
+            path if path == blob(SIGNED_INDEX) => response("200 OK", "", SIGNED_INDEX),
+            path if path == blob(INDEX_SIGNATURE) => response("200 OK", "", INDEX_SIGNATURE),
+            _ => response("404 Not Found", "", ""),
+        }
+    }
+
+    /// Registry stand-in only accepting the token delivered to the `admin` user
+    fn protected_registry_response(request: &str) -> String {
+        let path = request.split_whitespace().nth(1).unwrap_or_default();
+
+        if path.starts_with("/token?") {
+            let scoped = path.contains("scope=repository%3akubestro%2findex%3apull");
+            // admin:secret
+            let authenticated = request.contains("authorization: basic ywrtaw46c2vjcmv0");
+            return match scoped && authenticated {
+                true => response("200 OK", "", r#"{"token":"registry-token"}"#),
+                false => response("401 Unauthorized", "", ""),
+            };
+        }
+
+        if !request.contains("authorization: bearer registry-token") {
+            let host = request
+                .lines()
+                .find_map(|line| line.strip_prefix("host: "))
+                .unwrap_or_default()
+                .trim();
+            let challenge = format!(
+                "www-authenticate: Bearer realm=\"http://{}/token\",service=\"stub\",scope=\"repository:kubestro/index:pull\"\r\n",
+                host
+            );
+            return response("401 Unauthorized", &challenge, "");
+        }
+
+        registry_response(request)
+    }
+
+    async fn oci_repository(handler: fn(&str) -> String) -> Repository {
+        let (url, _) = start_stub_server(handler).await;
+        let registry = Url::parse(&url).unwrap();
+
+        let mut repository = repository(
+            &format!(
+                "oci://{}:{}/kubestro/index",
+                registry.host_str().unwrap(),
+                registry.port().unwrap()
+            ),
+            &[SIGNING_KEY],
+        );
+        repository.kind = RepositoryKind::Oci;
+        repository
+    }
+
+    #[test]
+    fn reference_should_be_parsed() {
+        let reference = OciReference::parse("oci://ghcr.io/kubestro/index:1.0").unwrap();
+        let local = OciReference::parse("oci://localhost:5000/kubestro/index").unwrap();
+
+        assert_eq!(
+            reference.endpoint("manifests/1.0"),
+            "https://ghcr.io/v2/kubestro/index/manifests/1.0"
+        );
+        assert_eq!(reference.tag, Some("1.0".to_string()));
+        assert_eq!(local.registry.as_str(), "http://localhost:5000/");
+        assert_eq!(local.tag, None);
+        assert!(OciReference::parse("https://ghcr.io/kubestro/index").is_err());
+    }
+
+    #[test]
+    fn latest_tag_should_be_preferred_over_versions() {
+        let tags = |tags: &[&str]| tags.iter().map(|tag| tag.to_string()).collect();
+
+        assert_eq!(
+            select_tag(tags(&["1.2.0", "dev", "1.10.0", "1.10.0-rc.1"])),
+            Some("1.10.0".to_string())
+        );
+        assert_eq!(
+            select_tag(tags(&["1.2.0", "latest"])),
+            Some("latest".to_string())
+        );
+        assert_eq!(select_tag(vec![]), None);
+    }
+
+    #[test]
+    fn bearer_challenge_should_be_parsed() {
+        let challenge = parse_bearer_challenge(
+            r#"Bearer realm="https://auth.example.com/token",service="registry",scope="repository:a/b:pull,push""#,
+        );
+
+        assert_eq!(
+            challenge,
+            Some((
+                "https://auth.example.com/token".to_string(),
+                vec![
+                    ("service".to_string(), "registry".to_string()),
+                    ("scope".to_string(), "repository:a/b:pull,push".to_string()),
+                ]
+            ))
+        );
+        assert_eq!(parse_bearer_challenge("Basic realm=\"registry\""), None);
+    }
+
+    #[tokio::test]
+    async fn fetch_should_pull_the_signed_index_of_the_highest_tag() {
+        let repository = oci_repository(registry_response).await;
+        let fetcher = OciIndexFetcher::default();
+
+        let outcome = fetcher.fetch(&repository, None).await.unwrap();
+
+        let FetchOutcome::Fetched {
+            index, validators, ..
+        } = outcome
+        else {
+            panic!("Expected a fetched index");
+        };
+        assert_eq!(index.repository.name, "Stub");
+        assert_eq!(validators.etag, Some(sha256_digest(manifest().as_bytes())));
+    }
+
+    #[tokio::test]
+    async fn fetch_should_not_pull_an_unchanged_artifact() {
+        let repository = oci_repository(registry_response).await;
+        let fetcher = OciIndexFetcher::default();
+        let validators = CacheValidators {
+            etag: Some(sha256_digest(manifest().as_bytes())),
+            last_modified: None,
+        };
+
+        let outcome = fetcher.fetch(&repository, Some(&validators)).await.unwrap();
+
+        assert!(matches!(outcome, FetchOutcome::NotModified));
+    }
+
+    #[tokio::test]
+    async fn fetch_should_not_request_a_token_from_a_private_realm() {
+        let mut repository = oci_repository(protected_registry_response).await;
+        repository.credentials = Some(RepositoryCredentials::Basic {
+            username: "admin".to_string(),
+            password: "secret".to_string(),
+        });
+        let fetcher = OciIndexFetcher::default();
+
+        let outcome = fetcher.fetch(&repository, None).await;
+
+        assert!(matches!(
+            outcome,
+            Err(RepositoriesServiceError::UrlNotAllowed(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn fetch_should_exchange_the_credentials_for_a_registry_token() {
+        let mut repository = oci_repository(protected_registry_response).await;
+        // The stub registry serves its tokens from a loopback address
+        let fetcher = OciIndexFetcher::default().with_outbound_policy(OutboundPolicy {
+            allow_private_networks: true,
+            ..Default::default()
+        });
+
+        let anonymous = fetcher.fetch(&repository, None).await;
+        repository.credentials = Some(RepositoryCredentials::Basic {
+            username: "admin".to_string(),
+            password: "secret".to_string(),
+        });
+        let authenticated = fetcher.fetch(&repository, None).await;
+
+        assert!(matches!(
+            anonymous,
+            Err(RepositoriesServiceError::RemoteDataError(_))
+        ));
+        assert!(matches!(authenticated, Ok(FetchOutcome::Fetched { .. })));
+    }
+}
@@ -0,0 +1,560 @@
+use std::{
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    sync::Arc,
+};
+
+use kubestro_core_domain::ports::services::repositories_service::RepositoriesServiceError;
+use reqwest::{
+    dns::{Addrs, Name, Resolve, Resolving},
+    header::CONTENT_TYPE,
+    redirect::Policy,
+    ClientBuilder, Response, Url,
+};
+
+/// Default maximum size of a response, in bytes
+const DEFAULT_MAX_RESPONSE_SIZE: u64 = 16 * 1024 * 1024;
+/// Default maximum number of redirects followed by a request
+const DEFAULT_MAX_REDIRECTS: usize = 5;
+
+/// Rules restricting the requests made to the repositories
+///
+/// The repositories urls are given by the administrators, while the requests are sent from
+/// inside the cluster: without restrictions they could reach the internal services, such as
+/// the cloud metadata endpoints, the Kubernetes API or Redis.
+///
+/// The urls are checked when a repository is created or updated, and before every fetch. The
+/// HTTP client built with [`OutboundPolicy::restrict_client`] also checks the addresses of
+/// every connection and the target of every redirect, so a host cannot resolve to a private
+/// address once the url is accepted. Git resolves the hosts on its own: only the checks made
+/// before the fetch apply to the Git remotes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutboundPolicy {
+    /// Url schemes the repositories can use
+    pub allowed_schemes: Vec<String>,
+    /// Whether the loopback, private, link-local and other non-public addresses can be reached
+    pub allow_private_networks: bool,
+    /// Hosts the repositories can use, any host when empty
+    ///
+    /// A `*.` prefix allows every subdomain of a domain.
+    pub allowed_hosts: Vec<String>,
+    /// Maximum size of a response, in bytes
+    pub max_response_size: u64,
+    /// Maximum number of redirects followed by a request
+    pub max_redirects: usize,
+    /// Whether the responses must announce the content type of the document they hold
+    pub strict_content_type: bool,
+}
+
+impl Default for OutboundPolicy {
+    fn default() -> Self {
+        Self {
+            allowed_schemes: vec!["https".to_string(), "http".to_string(), "oci".to_string()],
+            allow_private_networks: false,
+            allowed_hosts: vec![],
+            max_response_size: DEFAULT_MAX_RESPONSE_SIZE,
+            max_redirects: DEFAULT_MAX_REDIRECTS,
+            strict_content_type: true,
+        }
+    }
+}
+
+impl OutboundPolicy {
+    /// Check a repository url, resolving its host
+    ///
+    /// Hosts that cannot be resolved yet are accepted, their addresses are checked again on
+    /// every connection.
+    pub async fn check_url(&self, url: &str) -> Result<(), OutboundPolicyError> {
+        let url = Url::parse(url).map_err(|e| OutboundPolicyError::InvalidUrl(e.to_string()))?;
+        self.check_target(&url)?;
+
+        if self.allow_private_networks {
+            return Ok(());
+        }
+
+        let host = url.host_str().unwrap_or_default();
+        if host.trim_matches(['[', ']']).parse::<IpAddr>().is_ok() {
+            return Ok(());
+        }
+
+        let port = url.port_or_known_default().unwrap_or(443);
+        let Ok(addresses) = tokio::net::lookup_host((host, port)).await else {
+            return Ok(());
+        };
+        for address in addresses {
+            if !is_public_address(address.ip()) {
+                return Err(OutboundPolicyError::AddressNotAllowed {
+                    host: host.to_string(),
+                    address: address.ip(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check the scheme and the host of a url, without resolving the host
+    pub fn check_target(&self, url: &Url) -> Result<(), OutboundPolicyError> {
+        if !self
+            .allowed_schemes
+            .iter()
+            .any(|scheme| scheme == url.scheme())
+        {
+            return Err(OutboundPolicyError::SchemeNotAllowed(
+                url.scheme().to_string(),
+            ));
+        }
+
+        let host = url
+            .host_str()
+            .filter(|host| !host.is_empty())
+            .ok_or_else(|| OutboundPolicyError::InvalidUrl("missing host".to_string()))?;
+        if !self.is_host_allowed(host) {
+            return Err(OutboundPolicyError::HostNotAllowed(host.to_string()));
+        }
+
+        match host.trim_matches(['[', ']']).parse::<IpAddr>() {
+            Ok(address) if !self.allow_private_networks && !is_public_address(address) => {
+                Err(OutboundPolicyError::AddressNotAllowed {
+                    host: host.to_string(),
+                    address,
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn is_host_allowed(&self, host: &str) -> bool {
+        let host = host.to_lowercase();
+
+        self.allowed_hosts.is_empty()
+            || self.allowed_hosts.iter().any(|allowed| {
+                let allowed = allowed.to_lowercase();
+                match allowed.strip_prefix("*.") {
+                    Some(domain) => host
+                        .strip_suffix(domain)
+                        .is_some_and(|subdomain| subdomain.ends_with('.')),
+                    None => host == allowed,
+                }
+            })
+    }
+
+    /// Enforce the policy on every connection and redirect of a client
+    pub fn restrict_client(&self, builder: ClientBuilder) -> ClientBuilder {
+        self.restrict(builder, false)
+    }
+
+    /// Enforce the policy on a client sending credentials headers, the redirects to another
+    /// host are refused as well
+    ///
+    /// A redirect to another host drops the `Authorization` header, but keeps the custom
+    /// headers: they would be sent to whatever host the server redirects to.
+    pub fn restrict_credentials_client(&self, builder: ClientBuilder) -> ClientBuilder {
+        self.restrict(builder, true)
+    }
+
+    fn restrict(&self, builder: ClientBuilder, same_host: bool) -> ClientBuilder {
+        let policy = self.clone();
+        let builder = builder.redirect(Policy::custom(move |attempt| {
+            if attempt.previous().len() > policy.max_redirects {
+                return attempt.error(OutboundPolicyError::TooManyRedirects(policy.max_redirects));
+            }
+
+            if same_host {
+                let previous = attempt.previous().last();
+                let url = attempt.url();
+                if previous.is_some_and(|previous| {
+                    previous.host_str() != url.host_str()
+                        || previous.port_or_known_default() != url.port_or_known_default()
+                }) {
+                    let host = url.host_str().unwrap_or_default().to_string();
+                    return attempt.error(OutboundPolicyError::CrossHostRedirect(host));
+                }
+            }
+
+            match policy.check_target(attempt.url()) {
+                Ok(()) => attempt.follow(),
+                Err(e) => attempt.error(e),
+            }
+        }));
+
+        match self.allow_private_networks {
+            true => builder,
+            false => builder.dns_resolver(Arc::new(PublicResolver)),
+        }
+    }
+
+    /// Read the body of a response, up to the maximum response size
+    pub async fn read_body(&self, mut response: Response) -> Result<Vec<u8>, OutboundPolicyError> {
+        let max_size = self.max_response_size;
+        if response
+            .content_length()
+            .is_some_and(|length| length > max_size)
+        {
+            return Err(OutboundPolicyError::ResponseTooLarge(max_size));
+        }
+
+        let mut data = Vec::new();
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| OutboundPolicyError::Read(e.to_string()))?
+        {
+            if (data.len() + chunk.len()) as u64 > max_size {
+                return Err(OutboundPolicyError::ResponseTooLarge(max_size));
+            }
+            data.extend_from_slice(&chunk);
+        }
+
+        Ok(data)
+    }
+
+    /// Check a response holds one of the expected content types, when they are strictly
+    /// checked
+    pub fn check_content_type(
+        &self,
+        response: &Response,
+        expected: &[&str],
+    ) -> Result<(), OutboundPolicyError> {
+        if !self.strict_content_type {
+            return Ok(());
+        }
+
+        let content_type = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(';').next())
+            .map(|value| value.trim().to_lowercase())
+            .unwrap_or_default();
+
+        match expected.contains(&content_type.as_str()) {
+            true => Ok(()),
+            false => Err(OutboundPolicyError::ContentType {
+                expected: expected.join(", "),
+                actual: content_type,
+            }),
+        }
+    }
+}
+
+/// Whether an address can be reached from the internet, the addresses of the loopback,
+/// private, link-local, shared, documentation and multicast ranges cannot
+pub fn is_public_address(address: IpAddr) -> bool {
+    match address {
+        IpAddr::V4(address) => is_public_ipv4(address),
+        IpAddr::V6(address) => match address.to_ipv4_mapped() {
+            Some(mapped) => is_public_ipv4(mapped),
+            None => is_public_ipv6(address),
+        },
+    }
+}
+
+fn is_public_ipv4(address: Ipv4Addr) -> bool {
+    let [first, second, third, _] = address.octets();
+
+    !(address.is_private()
+        || address.is_loopback()
+        || address.is_link_local()
+        || address.is_unspecified()
+        || address.is_broadcast()
+        || address.is_multicast()
+        || address.is_documentation()
+        // "This network", 0.0.0.0/8
+        || first == 0
+        // Shared address space of the carrier-grade NATs, 100.64.0.0/10
+        || (first == 100 && (second & 0xc0) == 64)
+        // IETF protocol assignments, 192.0.0.0/24
+        || (first == 192 && second == 0 && third == 0)
+        // Deprecated 6to4 relay anycast, 192.88.99.0/24
+        || (first == 192 && second == 88 && third == 99)
+        // Benchmarking, 198.18.0.0/15
+        || (first == 198 && (second & 0xfe) == 18)
+        // Reserved, 240.0.0.0/4, along with the broadcast address
+        || first >= 240)
+}
+
+fn is_public_ipv6(address: Ipv6Addr) -> bool {
+    // Translated or tunneled to an IPv4 address, which is the one reached
+    if let Some(embedded) = embedded_ipv4(address) {
+        return is_public_ipv4(embedded);
+    }
+
+    let [first, second, ..] = address.segments();
+
+    !(address.is_loopback()
+        || address.is_unspecified()
+        || address.is_multicast()
+        // Unique local addresses, fc00::/7
+        || (first & 0xfe00) == 0xfc00
+        // Link-local addresses, fe80::/10
+        || (first & 0xffc0) == 0xfe80
+        // Deprecated site-local addresses, fec0::/10
+        || (first & 0xffc0) == 0xfec0
+        // Documentation addresses, 2001:db8::/32
+        || (first == 0x2001 && second == 0x0db8))
+}
+
+/// The IPv4 address embedded in a NAT64 (64:ff9b::/96), 6to4 (2002::/16) or IPv4-compatible
+/// (::/96) address
+fn embedded_ipv4(address: Ipv6Addr) -> Option<Ipv4Addr> {
+    let segments = address.segments();
+    let ipv4 = |high: u16, low: u16| Ipv4Addr::from((u32::from(high) << 16) | u32::from(low));
+
+    match segments {
+        [0x0064, 0xff9b, 0, 0, 0, 0, high, low] | [0, 0, 0, 0, 0, 0, high, low] => {
+            Some(ipv4(high, low))
+        }
+        [0x2002, high, low, ..] => Some(ipv4(high, low)),
+        _ => None,
+    }
+}
+
+/// DNS resolver only returning the public addresses of a host
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let host = name.as_str().to_string();
+            let addresses = tokio::net::lookup_host((host.as_str(), 0))
+                .await?
+                .filter(|address| is_public_address(address.ip()))
+                .collect::<Vec<_>>();
+
+            if addresses.is_empty() {
+                return Err(OutboundPolicyError::NoPublicAddress(host).into());
+            }
+
+            let addresses: Addrs = Box::new(addresses.into_iter());
+            Ok(addresses)
+        })
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum OutboundPolicyError {
+    #[error("Invalid url: {0}")]
+    InvalidUrl(String),
+    #[error("The `{0}` scheme is not allowed")]
+    SchemeNotAllowed(String),
+    #[error("The host `{0}` is not allowed")]
+    HostNotAllowed(String),
+    #[error("The host `{host}` resolves to the non-public address {address}")]
+    AddressNotAllowed { host: String, address: IpAddr },
+    #[error("The host `{0}` has no public address")]
+    NoPublicAddress(String),
+    #[error("More than {0} redirects")]
+    TooManyRedirects(usize),
+    #[error("Redirect to the host `{0}` refused, the repository credentials would be sent along")]
+    CrossHostRedirect(String),
+    #[error("The response exceeds {0} bytes")]
+    ResponseTooLarge(u64),
+    #[error("Unexpected content type `{actual}`, expected {expected}")]
+    ContentType { expected: String, actual: String },
+    #[error("Failed to read the response: {0}")]
+    Read(String),
+}
+
+impl From<OutboundPolicyError> for RepositoriesServiceError {
+    fn from(value: OutboundPolicyError) -> Self {
+        match value {
+            OutboundPolicyError::InvalidUrl(_)
+            | OutboundPolicyError::SchemeNotAllowed(_)
+            | OutboundPolicyError::HostNotAllowed(_)
+            | OutboundPolicyError::AddressNotAllowed { .. }
+            | OutboundPolicyError::NoPublicAddress(_) => Self::UrlNotAllowed(value.to_string()),
+            OutboundPolicyError::TooManyRedirects(_)
+            | OutboundPolicyError::CrossHostRedirect(_)
+            | OutboundPolicyError::ResponseTooLarge(_)
+            | OutboundPolicyError::ContentType { .. }
+            | OutboundPolicyError::Read(_) => Self::RemoteDataError(value.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::services::index_fetcher::tests::start_stub_server;
+
+    use super::*;
+
+    fn target(policy: &OutboundPolicy, url: &str) -> Result<(), OutboundPolicyError> {
+        policy.check_target(&Url::parse(url).unwrap())
+    }
+
+    #[test]
+    fn only_public_addresses_should_be_public() {
+        for address in [
+            "93.184.215.14",
+            "198.20.0.1",
+            "192.0.1.1",
+            "2606:2800:21f:cb07:6820:80da:af6b:8b2c",
+        ] {
+            assert!(is_public_address(address.parse().unwrap()), "{}", address);
+        }
+
+        for address in [
+            "127.0.0.1",
+            "10.0.0.1",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:169.254.169.254",
+            "192.0.0.8",
+            "192.88.99.1",
+            "198.18.0.1",
+            "198.19.255.254",
+            "240.0.0.1",
+            "250.1.2.3",
+            "::ffff:198.18.0.1",
+        ] {
+            assert!(!is_public_address(address.parse().unwrap()), "{}", address);
+        }
+    }
+
+    #[test]
+    fn embedded_ipv4_addresses_should_be_checked() {
+        for address in [
+            // NAT64
+            "64:ff9b::5db8:d70e",
+            // 6to4
+            "2002:5db8:d70e::1",
+            // IPv4-compatible
+            "::5db8:d70e",
+        ] {
+            assert!(is_public_address(address.parse().unwrap()), "{}", address);
+        }
+
+        for address in [
+            "64:ff9b::7f00:1",
+            "64:ff9b::a9fe:a9fe",
+            "2002:a00:1::1",
+            "2002:c0a8:101::1",
+            "::7f00:1",
+            "::a00:1",
+            "2002:c000:8::1",
+            "2002:c058:6301::1",
+            "2002:c612:1::1",
+            "2002:f000:1::1",
+        ] {
+            assert!(!is_public_address(address.parse().unwrap()), "{}", address);
+        }
+    }
+
+    #[test]
+    fn documentation_and_site_local_ipv6_addresses_should_not_be_public() {
+        for address in ["2001:db8::1", "2001:db8:ffff::1", "fec0::1", "feff::1"] {
+            assert!(!is_public_address(address.parse().unwrap()), "{}", address);
+        }
+        assert!(is_public_address("2001:db9::1".parse().unwrap()));
+    }
+
+    #[test]
+    fn target_should_follow_the_schemes_hosts_and_addresses_rules() {
+        let policy = OutboundPolicy {
+            allowed_hosts: vec!["*.example.com".to_string(), "10.0.0.1".to_string()],
+            ..Default::default()
+        };
+
+        assert!(target(&policy, "https://repo.example.com/index.json").is_ok());
+        assert!(matches!(
+            target(&policy, "file:///etc/passwd"),
+            Err(OutboundPolicyError::SchemeNotAllowed(_))
+        ));
+        assert!(matches!(
+            target(&policy, "https://example.com/index.json"),
+            Err(OutboundPolicyError::HostNotAllowed(_))
+        ));
+        assert!(matches!(
+            target(&policy, "http://10.0.0.1/index.json"),
+            Err(OutboundPolicyError::AddressNotAllowed { .. })
+        ));
+
+        let private = OutboundPolicy {
+            allow_private_networks: true,
+            ..policy
+        };
+        assert!(target(&private, "http://10.0.0.1/index.json").is_ok());
+    }
+
+    #[tokio::test]
+    async fn url_resolving_to_a_private_address_should_be_rejected() {
+        let policy = OutboundPolicy::default();
+
+        let url = policy.check_url("http://localhost:8080/index.json").await;
+
+        assert!(matches!(
+            url,
+            Err(OutboundPolicyError::AddressNotAllowed { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn restricted_client_should_not_connect_to_private_addresses() {
+        let (url, requests) = start_stub_server(|_| {
+            "HTTP/1.1 200 OK\r\ncontent-length: 0\r\nconnection: close\r\n\r\n".to_string()
+        })
+        .await;
+        let url = url.replace("127.0.0.1", "localhost");
+        let client = OutboundPolicy::default()
+            .restrict_client(reqwest::Client::builder())
+            .build()
+            .unwrap();
+
+        let response = client.get(url).send().await;
+
+        assert!(response.is_err());
+        assert!(requests.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn redirects_to_private_addresses_should_not_be_followed() {
+        let (url, requests) = start_stub_server(|_| {
+            "HTTP/1.1 302 Found\r\nlocation: http://169.254.169.254/latest/meta-data\r\ncontent-length: 0\r\nconnection: close\r\n\r\n"
+                .to_string()
+        })
+        .await;
+        // The stub server address is a literal, only the redirect is checked
+        let client = OutboundPolicy::default()
+            .restrict_client(reqwest::Client::builder())
+            .build()
+            .unwrap();
+
+        let response = client.get(url).send().await;
+
+        assert!(response.is_err());
+        assert_eq!(requests.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn large_responses_should_be_rejected() {
+        let (url, _) = start_stub_server(|_| {
+            "HTTP/1.1 200 OK\r\ncontent-type: text/html\r\ncontent-length: 64\r\nconnection: close\r\n\r\n"
+                .to_string()
+                + &"a".repeat(64)
+        })
+        .await;
+        let policy = OutboundPolicy {
+            max_response_size: 32,
+            ..Default::default()
+        };
+
+        let response = reqwest::get(url).await.unwrap();
+        let content_type = policy.check_content_type(&response, &["application/json"]);
+        let body = policy.read_body(response).await;
+
+        assert!(matches!(
+            content_type,
+            Err(OutboundPolicyError::ContentType { .. })
+        ));
+        assert!(matches!(
+            body,
+            Err(OutboundPolicyError::ResponseTooLarge(32))
+        ));
+    }
+}
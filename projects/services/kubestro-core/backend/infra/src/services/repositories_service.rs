@@ -1,74 +1,268 @@
-use std::sync::Arc;
+use std::{
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 
+use chrono::Utc;
 use kubestro_core_domain::{
-    models::package::{CreateRepository, Repository, RepositoryId},
+    models::{
+        index::RepositoryIndex,
+        package::{
+            CreateRepository, Repository, RepositoryId, RepositoryKind, RepositorySync,
+            UpdateRepository,
+        },
+    },
     ports::{
-        repositories::repositories_repositories::RepositoriesRepository,
+        repositories::{
+            packages_repository::PackagesRepository,
+            repositories_repositories::{RepositoriesRepository, RepositoryRepoError},
+        },
         services::repositories_service::{RepositoriesService, RepositoriesServiceError},
     },
+    services::package_graph::PackageGraph,
 };
-use serde_json;
 
 use redis::AsyncCommands;
 use redis_pool::SingleRedisPool;
-use tokio::task::JoinSet;
-use tracing::debug;
+use tokio::{sync::Mutex, task::JoinSet};
+use tracing::{debug, warn};
+
+use super::{
+    bundle_archive::parse_bundle_url,
+    bundle_store::{bundle_repository_name, BundleStore},
+    git_fetcher::GitIndexFetcher,
+    index_fetcher::{CacheValidators, FetchOutcome, HttpIndexFetcher, IndexDocument},
+    oci_fetcher::OciIndexFetcher,
+    outbound_policy::OutboundPolicy,
+};
 
 #[derive(Clone)]
 pub struct InfraRepositoriesService {
     repositories_repository: Arc<dyn RepositoriesRepository>,
+    packages_repository: Arc<dyn PackagesRepository>,
     cache_service: SingleRedisPool,
+    index_fetcher: HttpIndexFetcher,
+    oci_fetcher: OciIndexFetcher,
+    git_fetcher: GitIndexFetcher,
+    bundle_store: BundleStore,
+    outbound_policy: OutboundPolicy,
+    /// How long the validators of a stored index are kept, in seconds
+    cache_ttl: i64,
+    /// Only one search of the conflicts runs at a time, the last one stores the latest packages
+    conflicts_lock: Arc<Mutex<()>>,
+    /// Whether the stored packages changed since the conflicts were last found
+    conflicts_stale: Arc<AtomicBool>,
 }
 
 impl InfraRepositoriesService {
     pub fn new(
         repositories_repository: Arc<dyn RepositoriesRepository>,
+        packages_repository: Arc<dyn PackagesRepository>,
         cache_service: SingleRedisPool,
     ) -> Self {
         Self {
             repositories_repository,
+            packages_repository,
             cache_service,
+            index_fetcher: HttpIndexFetcher::default(),
+            oci_fetcher: OciIndexFetcher::default(),
+            git_fetcher: GitIndexFetcher::default(),
+            bundle_store: BundleStore::default(),
+            outbound_policy: OutboundPolicy::default(),
+            cache_ttl: REPOSITORIES_CACHE_TTL,
+            conflicts_lock: Arc::default(),
+            // Found once at startup, the packages may have been stored by a previous version
+            conflicts_stale: Arc::new(AtomicBool::new(true)),
         }
     }
+
+    /// Set how long the validators of a stored index are kept before expiring, the index is
+    /// downloaded again without them
+    pub fn with_cache_ttl(mut self, cache_ttl: Duration) -> Self {
+        self.cache_ttl = cache_ttl.as_secs().try_into().unwrap_or(i64::MAX);
+        self
+    }
+
+    /// Accept the indexes of repositories without trusted keys
+    pub fn with_unsigned_indexes(mut self, allow_unsigned: bool) -> Self {
+        self.index_fetcher = self.index_fetcher.with_unsigned_indexes(allow_unsigned);
+        self.oci_fetcher = self.oci_fetcher.with_unsigned_indexes(allow_unsigned);
+        self.git_fetcher = self.git_fetcher.with_unsigned_indexes(allow_unsigned);
+        self.bundle_store = self.bundle_store.with_unsigned_indexes(allow_unsigned);
+        self
+    }
+
+    /// Set the directory holding the clones of the Git repositories
+    pub fn with_git_work_dir(mut self, work_dir: PathBuf) -> Self {
+        self.git_fetcher = self.git_fetcher.with_work_dir(work_dir);
+        self
+    }
+
+    /// Set the store of the uploaded bundles, serving the `bundle` repositories
+    ///
+    /// Call it before [`Self::with_unsigned_indexes`], which configures the store as well.
+    pub fn with_bundle_store(mut self, store: BundleStore) -> Self {
+        self.bundle_store = store;
+        self
+    }
+
+    /// Set the outbound policy the repositories urls and the fetched responses must follow
+    ///
+    /// The HTTP client should be restricted by the same policy.
+    pub fn with_outbound_policy(mut self, policy: OutboundPolicy) -> Self {
+        self.index_fetcher = self.index_fetcher.with_outbound_policy(policy.clone());
+        self.oci_fetcher = self.oci_fetcher.with_outbound_policy(policy.clone());
+        self.outbound_policy = policy;
+        self
+    }
+
+    /// Set the HTTP client used to fetch the repositories indexes, from servers or registries
+    pub fn with_http_client(mut self, client: reqwest::Client) -> Self {
+        self.index_fetcher = self.index_fetcher.with_client(client.clone());
+        self.oci_fetcher = self.oci_fetcher.with_client(client);
+        self
+    }
+
+    /// Set the HTTP client sending the credentials headers of the repositories, it must refuse
+    /// the redirects to other hosts
+    pub fn with_credentials_http_client(mut self, client: reqwest::Client) -> Self {
+        self.index_fetcher = self.index_fetcher.with_credentials_client(client.clone());
+        self.oci_fetcher = self.oci_fetcher.with_credentials_client(client);
+        self
+    }
 }
 
-const REPOSITORIES_CACHE_KEY: &str = "repositories_cache";
+const REPOSITORIES_VALIDATORS_KEY: &str = "repositories_cache_validators";
+/// Default lifetime of the validators of a stored index, in seconds
+const REPOSITORIES_CACHE_TTL: i64 = 3600;
 
 #[async_trait::async_trait]
 impl RepositoriesService for InfraRepositoriesService {
     /// Create a new repository for managers
     ///
-    /// This method proxy [`RepositoriesRepository::create`] and update the cache
+    /// This method checks the url against the outbound policy, proxy
+    /// [`RepositoriesRepository::create`] and stores the packages of its index
     async fn create(
         &self,
         repository: CreateRepository,
     ) -> Result<Repository, RepositoriesServiceError> {
+        self.check_source(&repository.kind, &repository.url).await?;
+
         let repository = self.repositories_repository.create(repository).await?;
 
         let self_clone = self.clone();
         let repository_clone = repository.clone();
-        tokio::spawn(async move { self_clone.fetch_and_cache(repository_clone).await });
+        tokio::spawn(async move {
+            let result = self_clone.fetch_and_cache(repository_clone, false).await;
+            self_clone.refresh_conflicts().await;
+            result
+        });
+
+        Ok(repository)
+    }
+
+    /// Update a repository
+    ///
+    /// This method checks the new url against the outbound policy and proxy
+    /// [`RepositoriesRepository::update`]. When the kind, the url, the trusted keys or the
+    /// credentials change, the stored packages are dropped and fetched again.
+    async fn update(
+        &self,
+        repository_id: &RepositoryId,
+        repository: UpdateRepository,
+    ) -> Result<Repository, RepositoriesServiceError> {
+        let previous = self
+            .repositories_repository
+            .find_one(repository_id)
+            .await?
+            .ok_or(RepositoryRepoError::NotFound)?;
+        if previous.managed {
+            return Err(RepositoriesServiceError::Managed(previous.name));
+        }
+        if repository.kind.is_some() || repository.url.is_some() {
+            self.check_source(
+                repository.kind.as_ref().unwrap_or(&previous.kind),
+                repository.url.as_ref().unwrap_or(&previous.url),
+            )
+            .await?;
+        }
+
+        let mut repository = self
+            .repositories_repository
+            .update(repository_id, repository)
+            .await?;
+
+        if repository.kind != previous.kind
+            || repository.url != previous.url
+            || repository.trusted_keys != previous.trusted_keys
+            || repository.credentials != previous.credentials
+        {
+            self.remove_cached_data(repository.id.clone()).await?;
+
+            // The sync state describes the previous index
+            repository.sync = RepositorySync::default();
+            self.repositories_repository
+                .update_sync(&repository.id, repository.sync.clone())
+                .await?;
+
+            let self_clone = self.clone();
+            let repository_clone = repository.clone();
+            tokio::spawn(async move {
+                let result = self_clone.fetch_and_cache(repository_clone, true).await;
+                self_clone.refresh_conflicts().await;
+                result
+            });
+        } else if repository.priority != previous.priority {
+            // The priorities decide which repository provides a package published by several
+            self.conflicts_stale.store(true, Ordering::Release);
+            let self_clone = self.clone();
+            tokio::spawn(async move { self_clone.refresh_conflicts().await });
+        }
 
         Ok(repository)
     }
 
     /// Delete a repository by its id
     ///
-    /// This method proxy [`RepositoriesRepository::delete`] and update the cache
+    /// This method proxy [`RepositoriesRepository::delete`] and removes its packages, the clone
+    /// of a Git repository is removed as well
     async fn delete(&self, repository_id: &RepositoryId) -> Result<(), RepositoriesServiceError> {
+        let repository = self
+            .repositories_repository
+            .find_one(repository_id)
+            .await?
+            .ok_or(RepositoryRepoError::NotFound)?;
+        if repository.managed {
+            return Err(RepositoriesServiceError::Managed(repository.name));
+        }
+
         self.repositories_repository.delete(repository_id).await?;
 
         let self_clone = self.clone();
         let repository_id = repository_id.clone();
-        tokio::spawn(async move { self_clone.remove_cached_data(repository_id).await });
+        tokio::spawn(async move {
+            if let Err(e) = self_clone.git_fetcher.remove(&repository_id).await {
+                warn!(
+                    "Failed to remove the clone of repository {}: {}",
+                    repository_id, e
+                );
+            }
+            let result = self_clone.remove_cached_data(repository_id).await;
+            self_clone.refresh_conflicts().await;
+            result
+        });
 
         Ok(())
     }
 
-    /// Update repositories cache
+    /// Update the packages of every repository
     ///
-    /// This method will fetch all the repositories remote data and cache them into
-    /// a KV-store (a Redis pool here).
+    /// This method will fetch all the repositories indexes and store their packages, unless
+    /// `force` is set the repositories already stored are skipped.
     async fn update_cache(&self, force: bool) -> Result<(), RepositoriesServiceError> {
         // Retrieve all repositories
         let repositories = self.repositories_repository.find_all(None).await?;
@@ -81,30 +275,283 @@ impl RepositoriesService for InfraRepositoriesService {
             let service_clone = self.clone();
             let repository = repository.clone();
             join_set.spawn(async move {
-                // Check if the repository is already cached
-                if !force && service_clone.check_if_cached(&repository.id).await? {
+                // Check if the repository is already stored
+                if !force && is_stored(&repository) {
                     return Ok(());
                 }
-                service_clone.fetch_and_cache(repository).await
+                service_clone.fetch_and_cache(repository, force).await
             });
         }
 
-        join_set
-            .join_all()
-            .await
-            .into_iter()
-            .collect::<Result<Vec<_>, _>>()?;
+        let results = join_set.join_all().await;
+        self.refresh_conflicts().await;
+        results.into_iter().collect::<Result<Vec<_>, _>>()?;
 
         Ok(())
     }
+
+    /// Refresh the packages of a single repository
+    ///
+    /// Unless `force` is set, the request is made conditional when the repository is already
+    /// stored, so an unchanged index only extends the lifetime of its validators.
+    async fn refresh_cache(
+        &self,
+        repository: &Repository,
+        force: bool,
+    ) -> Result<(), RepositoriesServiceError> {
+        self.fetch_and_cache(repository.clone(), force).await
+    }
+
+    /// Find the conflicts between the stored packages of every repository, and store them
+    ///
+    /// Nothing is done when no package changed since the last time. The changes made while the
+    /// conflicts are found mark them stale again, for the next call.
+    #[tracing::instrument(skip(self))]
+    async fn update_conflicts(&self) -> Result<(), RepositoriesServiceError> {
+        let _guard = self.conflicts_lock.lock().await;
+        if !self.conflicts_stale.swap(false, Ordering::AcqRel) {
+            return Ok(());
+        }
+
+        let result = self.store_conflicts().await;
+        if result.is_err() {
+            self.conflicts_stale.store(true, Ordering::Release);
+        }
+
+        result
+    }
 }
 
 impl InfraRepositoriesService {
+    /// Fetch the index of a repository, store its packages and record the synchronization
+    /// result
     #[tracing::instrument(skip(self))]
-    async fn check_if_cached(
+    async fn fetch_and_cache(
         &self,
-        repository_id: &RepositoryId,
-    ) -> Result<bool, RepositoriesServiceError> {
+        repository: Repository,
+        force: bool,
+    ) -> Result<(), RepositoriesServiceError> {
+        self.record_sync(&repository.id, repository.sync.syncing())
+            .await;
+
+        let (sync, result) = match self.sync_index(&repository, force).await {
+            Ok(Some(synced)) => (synced, Ok(())),
+            Ok(None) => (repository.sync.unchanged(Utc::now()), Ok(())),
+            Err(e) => (repository.sync.failed(&e), Err(e)),
+        };
+        self.record_sync(&repository.id, sync).await;
+
+        result
+    }
+
+    /// Fetch the index of a repository and store its packages
+    ///
+    /// Returns the synchronization state of the new index, or `None` when the stored index is
+    /// still up to date. A forced sync always downloads the index again.
+    async fn sync_index(
+        &self,
+        repository: &Repository,
+        force: bool,
+    ) -> Result<Option<RepositorySync>, RepositoriesServiceError> {
+        // Reuse the validators of the stored index to make the request conditional
+        let validators = match force {
+            true => None,
+            false => self.get_cached_validators(repository).await?,
+        };
+
+        let outcome = self.fetch_index(repository, validators.as_ref()).await?;
+
+        let outcome = match outcome {
+            FetchOutcome::NotModified => {
+                if self.refresh_cache_ttl(repository).await? {
+                    debug!(
+                        "Index of repository {} not modified, validators extended",
+                        repository.id
+                    );
+                    return Ok(None);
+                }
+
+                // The validators expired in the meantime, download the index again
+                self.fetch_index(repository, None).await?
+            }
+            outcome => outcome,
+        };
+
+        let FetchOutcome::Fetched {
+            index,
+            validators,
+            revision,
+            ..
+        } = outcome
+        else {
+            return Err(RepositoriesServiceError::RemoteDataError(
+                "The server answered `304 Not Modified` to an unconditional request".to_string(),
+            ));
+        };
+
+        debug!(
+            "Fetched index {} for repository {} with {} package(s)",
+            index.version,
+            repository.id,
+            index.packages.len()
+        );
+
+        let synced = RepositorySync::synced(
+            Utc::now(),
+            index.packages.len().try_into().unwrap_or(u32::MAX),
+            index.version,
+            revision,
+        );
+
+        // Store the packages of the index
+        self.cache_remote_data(&repository.id, *index, validators)
+            .await?;
+
+        Ok(Some(synced))
+    }
+
+    /// Fetch the index of a repository from its source
+    ///
+    /// The url is checked again, the policy may have changed or the host may resolve to
+    /// another address since the repository was created.
+    async fn fetch_index(
+        &self,
+        repository: &Repository,
+        validators: Option<&CacheValidators>,
+    ) -> Result<FetchOutcome, RepositoriesServiceError> {
+        self.check_source(&repository.kind, &repository.url).await?;
+
+        match repository.kind {
+            RepositoryKind::Http => self.index_fetcher.fetch(repository, validators).await,
+            RepositoryKind::Oci => self.oci_fetcher.fetch(repository, validators).await,
+            RepositoryKind::Git(_) => self.git_fetcher.fetch(repository, validators).await,
+            RepositoryKind::Bundle => self.bundle_store.fetch(repository, validators).await,
+        }
+    }
+
+    /// Check the url of a repository can be fetched
+    ///
+    /// The `bundle` repositories are read from the uploaded bundles, their url only needs to
+    /// name a bundled repository. The other urls must follow the outbound policy.
+    async fn check_source(
+        &self,
+        kind: &RepositoryKind,
+        url: &str,
+    ) -> Result<(), RepositoriesServiceError> {
+        match kind {
+            RepositoryKind::Bundle => bundle_repository_name(url).map(|_| ()),
+            _ => Ok(self.outbound_policy.check_url(url).await?),
+        }
+    }
+
+    /// Fetch the index of a repository as published, without storing its packages
+    pub(crate) async fn fetch_document(
+        &self,
+        repository: &Repository,
+    ) -> Result<(RepositoryIndex, IndexDocument), RepositoriesServiceError> {
+        match self.fetch_index(repository, None).await? {
+            FetchOutcome::Fetched {
+                index, document, ..
+            } => Ok((*index, document)),
+            FetchOutcome::NotModified => Err(RepositoriesServiceError::RemoteDataError(
+                "The server answered `304 Not Modified` to an unconditional request".to_string(),
+            )),
+        }
+    }
+
+    /// Download a file referenced by the index of a repository
+    ///
+    /// The files of the `bundle` repositories are read from their bundle.
+    pub(crate) async fn fetch_file(
+        &self,
+        repository: &Repository,
+        url: &str,
+    ) -> Result<Vec<u8>, RepositoriesServiceError> {
+        match repository.kind {
+            RepositoryKind::Bundle => self.bundle_store.read_file(repository, url).await,
+            _ => self.index_fetcher.download(url).await,
+        }
+    }
+
+    /// Refresh the `bundle` repositories serving the given bundled repositories, in the
+    /// background
+    pub(crate) async fn refresh_bundled(
+        &self,
+        names: &[String],
+    ) -> Result<(), RepositoriesServiceError> {
+        let repositories = self.repositories_repository.find_all(None).await?;
+
+        let mut join_set = JoinSet::new();
+        for repository in repositories {
+            let bundled = repository.kind == RepositoryKind::Bundle
+                && parse_bundle_url(&repository.url)
+                    .is_some_and(|name| names.iter().any(|n| n == name));
+            if bundled {
+                let self_clone = self.clone();
+                join_set.spawn(async move { self_clone.fetch_and_cache(repository, false).await });
+            }
+        }
+
+        let self_clone = self.clone();
+        tokio::spawn(async move {
+            // Failures are recorded in the sync state of the repositories
+            join_set.join_all().await;
+            self_clone.refresh_conflicts().await;
+        });
+
+        Ok(())
+    }
+
+    /// Build the graph of the stored packages of every repository, and store its conflicts
+    async fn store_conflicts(&self) -> Result<(), RepositoriesServiceError> {
+        let repositories = self.repositories_repository.find_all(None).await?;
+        let packages = self.packages_repository.find_all().await?;
+        let graph = PackageGraph::from_entries(repositories, packages);
+
+        self.packages_repository
+            .replace_conflicts(graph.conflicts().to_vec())
+            .await?;
+
+        Ok(())
+    }
+
+    /// Find the conflicts again after the stored packages changed
+    ///
+    /// Failures are only logged, the previous conflicts are kept until the next update.
+    async fn refresh_conflicts(&self) {
+        if let Err(e) = self.update_conflicts().await {
+            warn!("Failed to update the conflicts of the catalog: {}", e);
+        }
+    }
+
+    /// Persist the synchronization state of a repository
+    ///
+    /// Failures are only logged, they must not hide the result of the synchronization itself.
+    async fn record_sync(&self, repository_id: &RepositoryId, sync: RepositorySync) {
+        if let Err(e) = self
+            .repositories_repository
+            .update_sync(repository_id, sync)
+            .await
+        {
+            warn!(
+                "Failed to save the synchronization state of repository {}: {}",
+                repository_id, e
+            );
+        }
+    }
+
+    /// Get the validators of the stored index of a repository
+    ///
+    /// Returns `None` when there is no stored index to revalidate.
+    #[tracing::instrument(skip(self, repository), fields(repository_id = %repository.id))]
+    async fn get_cached_validators(
+        &self,
+        repository: &Repository,
+    ) -> Result<Option<CacheValidators>, RepositoriesServiceError> {
+        if !is_stored(repository) {
+            return Ok(None);
+        }
+
         // Get redis pool connection
         let mut con = self
             .cache_service
@@ -112,56 +559,60 @@ impl InfraRepositoriesService {
             .await
             .map_err(|e| RepositoriesServiceError::CachingError(e.to_string()))?;
 
-        let key = format!("{}:{}", REPOSITORIES_CACHE_KEY, repository_id);
+        let validators_key = format!("{}:{}", REPOSITORIES_VALIDATORS_KEY, repository.id);
 
-        // Check if the hashmap exists
-        let exists: u32 = con
-            .exists(key)
+        let (etag, last_modified): (Option<String>, Option<String>) = redis::pipe()
+            .hget(&validators_key, "etag")
+            .hget(&validators_key, "last_modified")
+            .query_async(&mut con)
             .await
             .map_err(|e| RepositoriesServiceError::CachingError(e.to_string()))?;
 
-        Ok(exists == 1)
-    }
+        let validators = CacheValidators {
+            etag,
+            last_modified,
+        };
 
-    #[tracing::instrument(skip(self))]
-    async fn fetch_and_cache(
-        &self,
-        repository: Repository,
-    ) -> Result<(), RepositoriesServiceError> {
-        // Fetch the remote data
-        let repo_data = self.fetch_remote_data(&repository).await?;
-        // Cache the remote data
-        self.cache_remote_data(&repository.id, repo_data).await?;
+        if validators.is_empty() {
+            return Ok(None);
+        }
 
-        Ok(())
+        Ok(Some(validators))
     }
 
-    #[tracing::instrument(skip(self))]
-    async fn fetch_remote_data(
+    /// Extend the lifetime of the validators of the stored index of a repository
+    ///
+    /// Returns `false` if there was no stored index or no validators to extend.
+    #[tracing::instrument(skip(self, repository), fields(repository_id = %repository.id))]
+    async fn refresh_cache_ttl(
         &self,
         repository: &Repository,
-    ) -> Result<serde_json::Value, RepositoriesServiceError> {
-        // Fetch the remote data
-        let data = reqwest::get(&repository.url)
-            .await
-            .map_err(|e| RepositoriesServiceError::RemoteDataError(e.to_string()))?
-            .json::<serde_json::Value>()
+    ) -> Result<bool, RepositoriesServiceError> {
+        if !is_stored(repository) {
+            return Ok(false);
+        }
+
+        // Get redis pool connection
+        let mut con = self
+            .cache_service
+            .acquire()
             .await
-            .map_err(|e| RepositoriesServiceError::RemoteDataError(e.to_string()))?;
+            .map_err(|e| RepositoriesServiceError::CachingError(e.to_string()))?;
 
-        debug!(
-            "Fetched remote data for repository {}: {}",
-            repository.id, data
-        );
+        let validators_key = format!("{}:{}", REPOSITORIES_VALIDATORS_KEY, repository.id);
 
-        Ok(data)
+        con.expire(validators_key, self.cache_ttl)
+            .await
+            .map_err(|e| RepositoriesServiceError::CachingError(e.to_string()))
     }
 
-    #[tracing::instrument(skip(self))]
+    /// Swap the stored packages of a repository with the ones of its new index
+    #[tracing::instrument(skip(self, index, validators))]
     async fn cache_remote_data(
         &self,
         repository_id: &RepositoryId,
-        repo_data: serde_json::Value,
+        index: RepositoryIndex,
+        validators: CacheValidators,
     ) -> Result<(), RepositoriesServiceError> {
         // Get redis pool connection
         let mut con = self
@@ -170,22 +621,40 @@ impl InfraRepositoriesService {
             .await
             .map_err(|e| RepositoriesServiceError::CachingError(e.to_string()))?;
 
-        // Check if the hashmap exists, if not create it
-        let stringified_data = serde_json::to_string(&repo_data)
-            .map_err(|e| RepositoriesServiceError::CachingError(e.to_string()))?;
-
-        let key = format!("{}:{}", REPOSITORIES_CACHE_KEY, repository_id);
+        let validators_key = format!("{}:{}", REPOSITORIES_VALIDATORS_KEY, repository_id);
 
-        let _: () = redis::pipe()
-            .atomic()
-            .set(&key, stringified_data)
-            .expire(key, 3600)
-            .ignore()
-            .query_async(&mut con)
+        // The previous validators are dropped first, so they can never be sent along with a
+        // newer index
+        let _: () = con
+            .del(&validators_key)
             .await
             .map_err(|e| RepositoriesServiceError::CachingError(e.to_string()))?;
 
-        // Cache the remote data
+        self.packages_repository
+            .replace(repository_id, index.packages)
+            .await?;
+        self.conflicts_stale.store(true, Ordering::Release);
+
+        let validator_fields = [
+            ("etag", validators.etag),
+            ("last_modified", validators.last_modified),
+        ]
+        .into_iter()
+        .filter_map(|(field, value)| value.map(|value| (field, value)))
+        .collect::<Vec<_>>();
+
+        if !validator_fields.is_empty() {
+            let _: () = redis::pipe()
+                .atomic()
+                .hset_multiple(&validators_key, &validator_fields)
+                .ignore()
+                .expire(&validators_key, self.cache_ttl)
+                .ignore()
+                .query_async(&mut con)
+                .await
+                .map_err(|e| RepositoriesServiceError::CachingError(e.to_string()))?;
+        }
+
         Ok(())
     }
 
@@ -201,17 +670,24 @@ impl InfraRepositoriesService {
             .await
             .map_err(|e| RepositoriesServiceError::CachingError(e.to_string()))?;
 
-        let key = format!("{}:{}", REPOSITORIES_CACHE_KEY, repository);
+        let validators_key = format!("{}:{}", REPOSITORIES_VALIDATORS_KEY, repository);
 
-        // Remove the cached data
-        let _: () = redis::pipe()
-            .atomic()
-            .del(key)
-            .ignore()
-            .query_async(&mut con)
+        let _: () = con
+            .del(validators_key)
             .await
             .map_err(|e| RepositoriesServiceError::CachingError(e.to_string()))?;
 
+        self.packages_repository.delete(&repository).await?;
+        self.conflicts_stale.store(true, Ordering::Release);
+
         Ok(())
     }
 }
+
+/// Whether the packages of the last synchronized index of a repository are stored
+///
+/// They are kept when a later synchronization fails, and dropped along with the
+/// synchronization state when the source of the repository changes.
+fn is_stored(repository: &Repository) -> bool {
+    repository.sync.package_count.is_some()
+}
@@ -0,0 +1,92 @@
+use kubestro_core_domain::ports::validators::{
+    ValueViolation, ValuesValidationError, ValuesValidator,
+};
+
+/// Validate the configuration values with JSON schemas
+///
+/// The schemas are compiled on every validation, they are only used when a game manager is
+/// installed or upgraded. The remote references are never resolved: a schema must be
+/// self-contained.
+#[derive(Default)]
+pub struct JsonSchemaValuesValidator {}
+
+impl ValuesValidator for JsonSchemaValuesValidator {
+    fn validate(
+        &self,
+        schema: &serde_json::Value,
+        values: &serde_json::Value,
+    ) -> Result<(), ValuesValidationError> {
+        let validator = jsonschema::validator_for(schema)
+            .map_err(|e| ValuesValidationError::InvalidSchema(e.to_string()))?;
+
+        let violations = validator
+            .iter_errors(values)
+            .map(|error| ValueViolation {
+                path: error.instance_path.to_string(),
+                message: error.to_string(),
+            })
+            .collect::<Vec<_>>();
+
+        match violations.is_empty() {
+            true => Ok(()),
+            false => Err(ValuesValidationError::InvalidValues(violations)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn schema() -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "motd": { "type": "string" },
+                "max_players": { "type": "integer", "minimum": 1 }
+            },
+            "required": ["motd"],
+            "additionalProperties": false
+        })
+    }
+
+    #[test]
+    fn test_valid_values() {
+        let validator = JsonSchemaValuesValidator::default();
+
+        let result = validator.validate(&schema(), &json!({ "motd": "Hello", "max_players": 10 }));
+
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn test_every_violation_is_reported() {
+        let validator = JsonSchemaValuesValidator::default();
+
+        let result = validator.validate(&schema(), &json!({ "motd": 42, "max_players": 0 }));
+
+        let Err(ValuesValidationError::InvalidValues(violations)) = result else {
+            panic!("the values should be rejected");
+        };
+        let mut paths = violations
+            .iter()
+            .map(|violation| violation.path.as_str())
+            .collect::<Vec<_>>();
+        paths.sort();
+        assert_eq!(paths, vec!["/max_players", "/motd"]);
+    }
+
+    #[test]
+    fn test_invalid_schema() {
+        let validator = JsonSchemaValuesValidator::default();
+
+        let result = validator.validate(&json!({ "type": "unknown" }), &json!({}));
+
+        assert!(matches!(
+            result,
+            Err(ValuesValidationError::InvalidSchema(_))
+        ));
+    }
+}
@@ -4,6 +4,17 @@ mod m20250201_204250_create_table_user;
 mod m20250220_082156_create_table_user_oidc;
 mod m20250223_124005_alter_table_user_oidc;
 mod m20250301_231759_create_table_repositories;
+mod m20250315_101203_alter_table_repositories_sync;
+mod m20250322_164512_alter_table_repositories_trusted_keys;
+mod m20250329_093547_alter_table_repositories_credentials;
+mod m20250405_141022_alter_table_repositories_kind;
+mod m20250412_103318_alter_table_repositories_git;
+mod m20250419_152847_alter_table_repositories_priority;
+mod m20250426_113402_alter_table_repositories_managed;
+mod m20250503_094715_create_table_packages;
+mod m20250510_142306_alter_table_packages_search;
+mod m20250517_101544_alter_table_repositories_bundle;
+mod m20250524_093012_create_table_catalog_conflicts;
 
 pub struct Migrator;
 
@@ -15,6 +26,17 @@ impl MigratorTrait for Migrator {
             Box::new(m20250220_082156_create_table_user_oidc::Migration),
             Box::new(m20250223_124005_alter_table_user_oidc::Migration),
             Box::new(m20250301_231759_create_table_repositories::Migration),
+            Box::new(m20250315_101203_alter_table_repositories_sync::Migration),
+            Box::new(m20250322_164512_alter_table_repositories_trusted_keys::Migration),
+            Box::new(m20250329_093547_alter_table_repositories_credentials::Migration),
+            Box::new(m20250405_141022_alter_table_repositories_kind::Migration),
+            Box::new(m20250412_103318_alter_table_repositories_git::Migration),
+            Box::new(m20250419_152847_alter_table_repositories_priority::Migration),
+            Box::new(m20250426_113402_alter_table_repositories_managed::Migration),
+            Box::new(m20250503_094715_create_table_packages::Migration),
+            Box::new(m20250510_142306_alter_table_packages_search::Migration),
+            Box::new(m20250517_101544_alter_table_repositories_bundle::Migration),
+            Box::new(m20250524_093012_create_table_catalog_conflicts::Migration),
         ]
     }
 }
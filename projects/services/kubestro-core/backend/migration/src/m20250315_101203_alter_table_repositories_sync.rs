@@ -0,0 +1,93 @@
+use sea_orm_migration::{
+    prelude::{extension::postgres::Type, *},
+    schema::*,
+};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(RepositorySyncStatus::Enum)
+                    .values([
+                        RepositorySyncStatus::Pending,
+                        RepositorySyncStatus::Syncing,
+                        RepositorySyncStatus::Ok,
+                        RepositorySyncStatus::Failed,
+                    ])
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Repository::Table)
+                    .add_column(
+                        ColumnDef::new(Repository::SyncStatus)
+                            .custom(RepositorySyncStatus::Enum)
+                            .not_null()
+                            .default(SimpleExpr::Custom(
+                                "'pending'::repository_sync_status".to_owned(),
+                            )),
+                    )
+                    .add_column(timestamp_with_time_zone_null(Repository::LastSyncedAt))
+                    .add_column(text_null(Repository::LastSyncError))
+                    .add_column(integer_null(Repository::PackageCount))
+                    .add_column(string_null(Repository::IndexVersion))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Repository::Table)
+                    .drop_column(Repository::SyncStatus)
+                    .drop_column(Repository::LastSyncedAt)
+                    .drop_column(Repository::LastSyncError)
+                    .drop_column(Repository::PackageCount)
+                    .drop_column(Repository::IndexVersion)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_type(Type::drop().name(RepositorySyncStatus::Enum).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Repository {
+    Table,
+    SyncStatus,
+    LastSyncedAt,
+    LastSyncError,
+    PackageCount,
+    IndexVersion,
+}
+
+#[derive(DeriveIden)]
+pub enum RepositorySyncStatus {
+    #[sea_orm(iden = "repository_sync_status")]
+    Enum,
+
+    #[sea_orm(iden = "pending")]
+    Pending,
+
+    #[sea_orm(iden = "syncing")]
+    Syncing,
+
+    #[sea_orm(iden = "ok")]
+    Ok,
+
+    #[sea_orm(iden = "failed")]
+    Failed,
+}
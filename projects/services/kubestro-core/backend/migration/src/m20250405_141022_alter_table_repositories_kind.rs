@@ -0,0 +1,66 @@
+use sea_orm_migration::prelude::{extension::postgres::Type, *};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(RepositoryKind::Enum)
+                    .values([RepositoryKind::Http, RepositoryKind::Oci])
+                    .to_owned(),
+            )
+            .await?;
+
+        // Existing repositories are all served over HTTP
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Repository::Table)
+                    .add_column(
+                        ColumnDef::new(Repository::Kind)
+                            .custom(RepositoryKind::Enum)
+                            .not_null()
+                            .default(SimpleExpr::Custom("'http'::repository_kind".to_owned())),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Repository::Table)
+                    .drop_column(Repository::Kind)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_type(Type::drop().name(RepositoryKind::Enum).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Repository {
+    Table,
+    Kind,
+}
+
+#[derive(DeriveIden)]
+pub enum RepositoryKind {
+    #[sea_orm(iden = "repository_kind")]
+    Enum,
+
+    #[sea_orm(iden = "http")]
+    Http,
+
+    #[sea_orm(iden = "oci")]
+    Oci,
+}
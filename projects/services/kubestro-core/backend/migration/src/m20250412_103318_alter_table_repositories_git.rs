@@ -0,0 +1,64 @@
+use sea_orm_migration::{
+    prelude::{extension::postgres::Type, *},
+    schema::*,
+};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_type(
+                Type::alter()
+                    .name(RepositoryKind::Enum)
+                    .add_value(RepositoryKind::Git)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Repository::Table)
+                    .add_column(text_null(Repository::GitReference))
+                    .add_column(text_null(Repository::GitPath))
+                    .add_column(string_null(Repository::SyncRevision))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Postgres cannot drop an enum value, the `git` kind is left in place
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Repository::Table)
+                    .drop_column(Repository::GitReference)
+                    .drop_column(Repository::GitPath)
+                    .drop_column(Repository::SyncRevision)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Repository {
+    Table,
+    GitReference,
+    GitPath,
+    SyncRevision,
+}
+
+#[derive(DeriveIden)]
+pub enum RepositoryKind {
+    #[sea_orm(iden = "repository_kind")]
+    Enum,
+
+    #[sea_orm(iden = "git")]
+    Git,
+}
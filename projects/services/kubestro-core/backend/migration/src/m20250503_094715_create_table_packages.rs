@@ -0,0 +1,105 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Package::Table)
+                    .if_not_exists()
+                    .col(pk_uuid(Package::Id))
+                    .col(uuid(Package::RepositoryId))
+                    .col(string(Package::Name))
+                    .col(string(Package::Version))
+                    .col(big_integer(Package::VersionMajor))
+                    .col(big_integer(Package::VersionMinor))
+                    .col(big_integer(Package::VersionPatch))
+                    .col(string(Package::VersionPre).default(""))
+                    .col(string(Package::Channel))
+                    .col(text(Package::Description))
+                    .col(
+                        array(Package::Tags, ColumnType::Text)
+                            .default(SimpleExpr::Custom("'{}'::text[]".to_owned())),
+                    )
+                    .col(json_binary(Package::Data))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_package_repository")
+                            .from(Package::Table, Package::RepositoryId)
+                            .to(Repository::Table, Repository::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_package_repository_name_version")
+                    .table(Package::Table)
+                    .col(Package::RepositoryId)
+                    .col(Package::Name)
+                    .col(Package::Version)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_package_name")
+                    .table(Package::Table)
+                    .col(Package::Name)
+                    .to_owned(),
+            )
+            .await?;
+
+        let db = manager.get_connection();
+        db.execute_unprepared("CREATE INDEX idx_package_tags ON package USING GIN (tags)")
+            .await?;
+
+        // The indexes were only cached in Redis: every repository is fetched again to fill
+        // the package store
+        db.execute_unprepared(
+            "UPDATE repository SET sync_status = 'pending', package_count = NULL, \
+                index_version = NULL, sync_revision = NULL",
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Package::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Package {
+    Table,
+    Id,
+    RepositoryId,
+    Name,
+    Version,
+    VersionMajor,
+    VersionMinor,
+    VersionPatch,
+    VersionPre,
+    Channel,
+    Description,
+    Tags,
+    Data,
+}
+
+#[derive(DeriveIden)]
+enum Repository {
+    Table,
+    Id,
+}
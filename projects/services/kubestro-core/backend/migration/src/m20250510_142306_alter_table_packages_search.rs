@@ -0,0 +1,79 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        // Trigram matching lets the search tolerate typos
+        db.execute_unprepared("CREATE EXTENSION IF NOT EXISTS pg_trgm")
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Package::Table)
+                    .add_column(string_null(Package::Game))
+                    .add_column(text(Package::Keywords).default(""))
+                    .to_owned(),
+            )
+            .await?;
+
+        // The packages were stored without a game, their keywords are only their tags
+        db.execute_unprepared("UPDATE package SET keywords = array_to_string(tags, ' ')")
+            .await?;
+
+        // The name and the keywords outrank the description
+        db.execute_unprepared(
+            "ALTER TABLE package ADD COLUMN search_document tsvector GENERATED ALWAYS AS (\
+                setweight(to_tsvector('simple', name), 'A') || \
+                setweight(to_tsvector('simple', keywords), 'B') || \
+                setweight(to_tsvector('simple', description), 'C')\
+            ) STORED",
+        )
+        .await?;
+        db.execute_unprepared(
+            "CREATE INDEX idx_package_search_document ON package USING GIN (search_document)",
+        )
+        .await?;
+        db.execute_unprepared(
+            "CREATE INDEX idx_package_search_trigram ON package \
+                USING GIN ((name || ' ' || keywords) gin_trgm_ops)",
+        )
+        .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_package_game")
+                    .table(Package::Table)
+                    .col(Package::Game)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Package::Table)
+                    .drop_column(Package::SearchDocument)
+                    .drop_column(Package::Keywords)
+                    .drop_column(Package::Game)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Package {
+    Table,
+    Game,
+    Keywords,
+    SearchDocument,
+}
@@ -0,0 +1,33 @@
+use sea_orm_migration::prelude::{extension::postgres::Type, *};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_type(
+                Type::alter()
+                    .name(RepositoryKind::Enum)
+                    .add_value(RepositoryKind::Bundle)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        // Postgres cannot drop an enum value, the `bundle` kind is left in place
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum RepositoryKind {
+    #[sea_orm(iden = "repository_kind")]
+    Enum,
+
+    #[sea_orm(iden = "bundle")]
+    Bundle,
+}
@@ -0,0 +1,47 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(CatalogConflict::Table)
+                    .if_not_exists()
+                    .col(pk_uuid(CatalogConflict::Id))
+                    .col(integer(CatalogConflict::Position))
+                    .col(array(CatalogConflict::RepositoryIds, ColumnType::Uuid))
+                    .col(json_binary(CatalogConflict::Data))
+                    .to_owned(),
+            )
+            .await?;
+
+        // Conflicts are looked up by any of the repositories taking part in them
+        let db = manager.get_connection();
+        db.execute_unprepared(
+            "CREATE INDEX idx_catalog_conflict_repository_ids ON catalog_conflict \
+                USING GIN (repository_ids)",
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(CatalogConflict::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum CatalogConflict {
+    Table,
+    Id,
+    Position,
+    RepositoryIds,
+    Data,
+}
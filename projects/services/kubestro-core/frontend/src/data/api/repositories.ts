@@ -1,5 +1,5 @@
 import ky from 'ky'
-import type { Repository, RepositoryWithPackages } from '../types/repositories'
+import type { Repository, RepositoryKind, RepositoryWithPackages } from '../types/repositories'
 
 export async function repositoriesGetAllApi({
   search
@@ -19,7 +19,9 @@ export async function repositoriesGetAllApi({
 export async function repositoriesCreateApi(
   repository: {
     name: string
+    kind: RepositoryKind
     url: string
+    trusted_keys: string[]
   }
 ): Promise<Repository> {
   return ky.post<{ repository: Repository }>('/api/v1.0/game-managers/repositories', { json: repository })
@@ -1,16 +1,110 @@
+export type RepositorySyncStatus = 'pending' | 'syncing' | 'ok' | 'failed'
+
+export interface RepositorySync {
+  status: RepositorySyncStatus
+  last_synced_at: string | null
+  last_error: string | null
+  package_count: number | null
+  index_version: string | null
+  revision: string | null
+  conflicts: CatalogConflict[]
+}
+
+export type CatalogConflictKind = 'duplicate_name' | 'crd' | 'unresolved_dependency'
+
+export interface CatalogConflict {
+  kind: CatalogConflictKind
+  detail: string
+  repositories: string[]
+  packages: string[]
+}
+
+export type RepositoryKind = 'http' | 'oci' | 'git' | 'bundle'
+
+export interface GitSource {
+  reference: string
+  path: string
+}
+
+export type RepositoryCredentialsKind = 'basic' | 'bearer' | 'headers'
+
 export interface Repository {
   id: string
   name: string
+  kind: RepositoryKind
+  git: GitSource | null
   url: string
+  trusted_keys: string[]
+  credentials: RepositoryCredentialsKind | null
+  priority: number
+  managed: boolean
+  sync: RepositorySync
 }
 
+export type RepositoryCatalogStatus = 'ok' | 'missing' | 'failed'
+
 export interface RepositoryWithPackages extends Repository {
+  status: RepositoryCatalogStatus
+  error?: string
   packages: Package[]
 }
 
+export type PackageChannel = 'stable' | 'beta' | 'nightly'
+
 export interface Package {
   id: string
   name: string
   version: string
+  channel: PackageChannel
+  description: string
+  game?: string
+  tags: string[]
+  icon?: string
+}
+
+export interface PackageMaintainer {
+  name: string
+  email: string | null
+  url: string | null
+}
+
+export interface PermissionRule {
+  api_groups: string[]
+  resources: string[]
+  verbs: string[]
+  cluster_wide: boolean
+}
+
+export interface CrdReference {
+  group: string
+  version: string
+  kind: string
+}
+
+export interface PackageChangelog {
+  version: string
+  channel: PackageChannel
+  changelog: string | null
+}
+
+export interface PackageDetail {
+  id: string
+  repository_id: string
+  name: string
+  version: string
+  channel: PackageChannel
+  kubestro_version: string | null
+  compatible: boolean
   description: string
+  game: string | null
+  tags: string[]
+  readme: string | null
+  icon: string | null
+  screenshots: string[]
+  maintainers: PackageMaintainer[]
+  license: string | null
+  source_url: string | null
+  permissions: PermissionRule[]
+  crds: CrdReference[]
+  changelog: PackageChangelog[]
 }
@@ -1,4 +1,4 @@
-import { Button, Dialog, DialogClose, DialogContent, DialogDescription, DialogFooter, DialogHeader, DialogTitle, DialogTrigger, FormMessage, Input, Label } from '@kubestro/design-system/components'
+import { Button, Dialog, DialogClose, DialogContent, DialogDescription, DialogFooter, DialogHeader, DialogTitle, DialogTrigger, FormMessage, Input, inputVariants, Label } from '@kubestro/design-system/components'
 import { BookPlusIcon } from 'lucide-react'
 import { href, useFetcher, useRevalidator } from 'react-router'
 import { useEffect, useState } from 'react'
@@ -59,6 +59,27 @@ export function AddRepository() {
               null}
           </div>
 
+          <div className="grid gap-2">
+            <Label htmlFor="kind">Source</Label>
+
+            <select
+              className={inputVariants()}
+              defaultValue="http"
+              disabled={submitting}
+              id="kind"
+              name="kind"
+            >
+              <option value="http">HTTP index</option>
+              <option value="oci">OCI registry</option>
+              <option value="git">Git repository</option>
+              <option value="bundle">Uploaded bundle</option>
+            </select>
+
+            {error?.kind ?
+              <FormMessage error={error.kind.detail} /> :
+              null}
+          </div>
+
           <div className="grid gap-2">
             <Label htmlFor="url">URL</Label>
 
@@ -66,7 +87,7 @@ export function AddRepository() {
               disabled={submitting}
               id="url"
               name="url"
-              placeholder="URL of the repository"
+              placeholder="URL of the repository, oci://<registry>/<name>[:<tag>] or bundle://<name>"
               required
               type="url"
             />
@@ -75,6 +96,22 @@ export function AddRepository() {
               <FormMessage error={error.url.detail} /> :
               null}
           </div>
+
+          <div className="grid gap-2">
+            <Label htmlFor="trusted_keys">Trusted keys</Label>
+
+            <textarea
+              className={inputVariants({ className: 'h-auto min-h-20 py-2' })}
+              disabled={submitting}
+              id="trusted_keys"
+              name="trusted_keys"
+              placeholder="Minisign public keys allowed to sign the repository index, one per line"
+            />
+
+            {error?.trusted_keys ?
+              <FormMessage error={error.trusted_keys.detail} /> :
+              null}
+          </div>
         </fetcher.Form>
 
         <DialogFooter className="gap-2">
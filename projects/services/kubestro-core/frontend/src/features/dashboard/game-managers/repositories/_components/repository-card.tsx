@@ -3,7 +3,35 @@ import { TrashIcon } from 'lucide-react'
 import { href, useFetcher, useRevalidator } from 'react-router'
 import { useEffect, useState } from 'react'
 import type { deleteRepositoryAction } from '../repository-action'
-import type { Repository } from '~/data/types/repositories'
+import type { Repository, RepositorySync } from '~/data/types/repositories'
+
+const SYNC_STATUS_LABELS: Record<RepositorySync['status'], string> = {
+  pending: 'Waiting for the first synchronization',
+  syncing: 'Synchronizing...',
+  ok: 'Synchronized',
+  failed: 'Synchronization failed',
+}
+
+function RepositorySyncSummary({ sync }: { readonly sync: RepositorySync }) {
+  const details = [
+    sync.package_count !== null ? `${sync.package_count} package(s)` : null,
+    sync.index_version !== null ? `index v${sync.index_version}` : null,
+    sync.last_synced_at !== null ? `last synchronized on ${new Date(sync.last_synced_at).toLocaleString()}` : null,
+  ].filter(Boolean)
+
+  return (
+    <div className="text-sm text-text-muted">
+      <p>
+        {SYNC_STATUS_LABELS[sync.status]}
+        {details.length > 0 ? ` - ${details.join(', ')}` : null}
+      </p>
+
+      {sync.status === 'failed' && sync.last_error !== null ? (
+        <p className="text-red-500">{sync.last_error}</p>
+      ) : null}
+    </div>
+  )
+}
 
 export interface RepositoryCardProps {
   readonly repository: Repository
@@ -37,6 +65,8 @@ export function RepositoryCard({ repository }: RepositoryCardProps) {
             {repository.url}
           </a>
         </CardDescription>
+
+        <RepositorySyncSummary sync={repository.sync} />
       </CardHeader>
 
       <CardFooter>
@@ -12,6 +12,7 @@ import { REPOSITORIES_GET_ALL_KEY, repositoriesGetAll } from '~/data/queries/rep
 import { repositoriesCreateApi } from '~/data/api/repositories'
 import type { ConflictError, ForbiddenError, ValidationError } from '~/data/api/generic-errors'
 import { transformErrors } from '~/data/api/transform-errors'
+import type { RepositoryKind } from '~/data/types/repositories'
 
 async function clientLoader({ request }: LoaderFunctionArgs) {
   const url = new URL(request.url)
@@ -90,16 +91,20 @@ function Repositories() {
 
 interface FormFields {
   name: string
+  kind: RepositoryKind
   url: string
+  trusted_keys: string
 }
 
 async function clientAction({ request }: ActionFunctionArgs) {
   const formData = await request.formData()
   // eslint-disable-next-line @typescript-eslint/no-unsafe-type-assertion -- I trust the form data
   const body = Object.fromEntries(formData) as unknown as FormFields
+  // One public key per line
+  const trustedKeys = body.trusted_keys.split('\n').map(key => key.trim()).filter(Boolean)
 
   try {
-    await repositoriesCreateApi(body)
+    await repositoriesCreateApi({ ...body, trusted_keys: trustedKeys })
     void queryClient.refetchQueries({ queryKey: REPOSITORIES_GET_ALL_KEY })
   }
   catch (error) {
@@ -110,6 +115,15 @@ async function clientAction({ request }: ActionFunctionArgs) {
         return { error: transformErrors(errorBody.errors) }
       }
 
+      // Bad Request, only raised for invalid trusted keys
+      if (error.response.status === 400) {
+        const errorBody = await error.response.json<ForbiddenError>()
+        const errors: Partial<Record<keyof FormFields, { detail: string }>> = {
+          trusted_keys: { detail: errorBody.detail }
+        }
+        return { error: errors }
+      }
+
       // Forbidden
       if (error.response.status === 403) {
         const errorBody = await error.response.json<ForbiddenError>()